@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+use std::path::{Component, Path};
+
+use crate::chunker::{Chunk, ChunkMetadata, ChunkType};
+use crate::context::VectorStore;
+
+/// Which aggregate embeddings `--aggregate` adds on top of the per-function chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggregateMode {
+    None,
+    File,
+    Dir,
+    Both,
+}
+
+impl AggregateMode {
+    pub const NAMES: [&'static str; 4] = ["none", "file", "dir", "both"];
+
+    fn files(self) -> bool {
+        matches!(self, AggregateMode::File | AggregateMode::Both)
+    }
+
+    fn dirs(self) -> bool {
+        matches!(self, AggregateMode::Dir | AggregateMode::Both)
+    }
+}
+
+impl std::str::FromStr for AggregateMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "none" => Ok(AggregateMode::None),
+            "file" => Ok(AggregateMode::File),
+            "dir" | "directory" => Ok(AggregateMode::Dir),
+            "both" => Ok(AggregateMode::Both),
+            _ => Err(format!(
+                "unknown aggregate mode '{}' (expected one of: {})",
+                s.trim(),
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// Build one chunk per file and/or top-level directory whose vector is the
+/// mean of the already embedded function, method and entry point vectors
+/// inside it. Nothing is re-embedded; chunks without a vector are skipped.
+pub fn aggregate_chunks(chunks: &[Chunk], vectors: &VectorStore, mode: AggregateMode) -> Vec<(Chunk, Vec<f32>)> {
+    let mut by_file: BTreeMap<&str, Vec<(&Chunk, &[f32])>> = BTreeMap::new();
+    for chunk in chunks {
+        if !matches!(chunk.chunk_type, ChunkType::Function | ChunkType::Method | ChunkType::EntryPoint) {
+            continue;
+        }
+        let (Some(file_path), Some(vector)) = (&chunk.metadata.file_path, vectors.get(&chunk.id).map(Vec::as_slice)) else {
+            continue;
+        };
+        by_file.entry(file_path.as_str()).or_default().push((chunk, vector));
+    }
+
+    let mut aggregates = Vec::new();
+
+    if mode.files() {
+        for (file_path, members) in &by_file {
+            let vecs: Vec<&[f32]> = members.iter().map(|(_, v)| *v).collect();
+            let language = members[0].0.metadata.language.clone();
+            let chunk = aggregate_chunk(
+                format!("file_agg:{}", file_path),
+                ChunkType::File,
+                file_path,
+                language,
+                members.iter().map(|(c, _)| c.metadata.name.as_str()),
+            );
+            aggregates.push((chunk, mean(&vecs)));
+        }
+    }
+
+    if mode.dirs() {
+        let mut by_dir: BTreeMap<String, Vec<(&Chunk, &[f32])>> = BTreeMap::new();
+        for (file_path, members) in &by_file {
+            by_dir.entry(top_level_dir(file_path)).or_default().extend(members.iter().copied());
+        }
+        for (dir, members) in &by_dir {
+            let vecs: Vec<&[f32]> = members.iter().map(|(_, v)| *v).collect();
+            let chunk = aggregate_chunk(
+                format!("dir:{}", dir),
+                ChunkType::Directory,
+                dir,
+                None,
+                members.iter().map(|(c, _)| c.metadata.name.as_str()),
+            );
+            aggregates.push((chunk, mean(&vecs)));
+        }
+    }
+
+    aggregates
+}
+
+fn aggregate_chunk<'a>(
+    id: String,
+    chunk_type: ChunkType,
+    path: &str,
+    language: Option<String>,
+    member_names: impl Iterator<Item = &'a str>,
+) -> Chunk {
+    let mut names: Vec<&str> = member_names.collect();
+    names.dedup();
+    let count = names.len();
+    let shown: Vec<&str> = names.into_iter().take(20).collect();
+    let more = if count > shown.len() { format!(" (+{} more)", count - shown.len()) } else { String::new() };
+    let kind = if chunk_type == ChunkType::Directory { "directory" } else { "file" };
+
+    Chunk {
+        id,
+        content: format!("{} {} ({} functions): {}{}", kind, path, count, shown.join(", "), more),
+        metadata: ChunkMetadata {
+            file_path: Some(path.to_string()),
+            language,
+            line_start: None,
+            line_end: None,
+            name: path.to_string(),
+            complexity: None,
+        },
+        tags: vec!["aggregate".to_string(), kind.to_string()],
+        importance_score: 0.5,
+        chunk_type,
+    }
+}
+
+/// First path component, or `.` for files at the project root
+fn top_level_dir(file_path: &str) -> String {
+    let mut components = Path::new(file_path)
+        .components()
+        .filter(|c| matches!(c, Component::Normal(_)));
+    match (components.next(), components.next()) {
+        (Some(first), Some(_)) => first.as_os_str().to_string_lossy().into_owned(),
+        _ => ".".to_string(),
+    }
+}
+
+/// Component-wise mean of equally sized vectors
+fn mean(vectors: &[&[f32]]) -> Vec<f32> {
+    let mut sum = vec![0.0f32; vectors.first().map_or(0, |v| v.len())];
+    for vector in vectors {
+        for (acc, x) in sum.iter_mut().zip(vector.iter()) {
+            *acc += x;
+        }
+    }
+    let n = vectors.len().max(1) as f32;
+    sum.iter_mut().for_each(|x| *x /= n);
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn function_chunk(id: &str, file_path: &str) -> Chunk {
+        Chunk {
+            id: id.to_string(),
+            chunk_type: ChunkType::Function,
+            content: String::new(),
+            metadata: ChunkMetadata {
+                file_path: Some(file_path.to_string()),
+                language: Some("python".to_string()),
+                line_start: Some(1),
+                line_end: Some(2),
+                name: id.to_string(),
+                complexity: Some(1),
+            },
+            tags: vec![],
+            importance_score: 0.5,
+        }
+    }
+
+    #[test]
+    fn test_file_aggregate_is_mean_of_members() {
+        let chunks = vec![
+            function_chunk("login", "auth/session.py"),
+            function_chunk("logout", "auth/session.py"),
+            function_chunk("hash", "auth/crypto.py"),
+            function_chunk("main", "main.py"),
+        ];
+        let mut vectors = VectorStore::new();
+        vectors.add("login".to_string(), vec![1.0, 0.0, 2.0]);
+        vectors.add("logout".to_string(), vec![3.0, 4.0, 0.0]);
+        vectors.add("hash".to_string(), vec![0.0, 2.0, 2.0]);
+        vectors.add("main".to_string(), vec![1.0, 1.0, 1.0]);
+
+        let aggregates = aggregate_chunks(&chunks, &vectors, AggregateMode::Both);
+        let find = |id: &str| aggregates.iter().find(|(c, _)| c.id == id).unwrap();
+
+        let (session, session_vec) = find("file_agg:auth/session.py");
+        assert_eq!(session.chunk_type, ChunkType::File);
+        assert_eq!(session_vec, &vec![2.0, 2.0, 1.0]);
+
+        let (auth, auth_vec) = find("dir:auth");
+        assert_eq!(auth.chunk_type, ChunkType::Directory);
+        let expected: Vec<f32> = vec![4.0 / 3.0, 2.0, 4.0 / 3.0];
+        assert_eq!(auth_vec, &expected);
+
+        let (_, root_vec) = find("dir:.");
+        assert_eq!(root_vec, &vec![1.0, 1.0, 1.0]);
+
+        assert!(aggregate_chunks(&chunks, &vectors, AggregateMode::None).is_empty());
+        assert_eq!(aggregate_chunks(&chunks, &vectors, AggregateMode::File).len(), 3);
+    }
+}
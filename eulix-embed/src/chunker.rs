@@ -1,5 +1,14 @@
-use crate::kb_loader::KnowledgeBase;
+use crate::kb_loader::{KnowledgeBase, MethodKind};
+use crate::tags::generate_tags;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tokenizers::Tokenizer;
+
+pub use crate::tags::ChunkType;
+
+/// Sequence length the embedding models truncate at, including [CLS]/[SEP]
+pub(crate) const MAX_MODEL_TOKENS: usize = 512;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Chunk {
@@ -11,16 +20,54 @@ pub struct Chunk {
     pub importance_score: f32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
-#[serde(rename_all = "lowercase")]
-pub enum ChunkType {
-    Function,
-    Class,
-    Method,
-    File,
-    EntryPoint,
-    #[serde(other)]
-    Other,
+/// How many `Calls:` and `Called by:` lines a function chunk lists before
+/// summarizing the rest as "... and N more". `None` lists them all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallLimits {
+    pub calls: Option<usize>,
+    pub called_by: Option<usize>,
+}
+
+impl Default for CallLimits {
+    fn default() -> Self {
+        Self { calls: Some(10), called_by: Some(5) }
+    }
+}
+
+/// `// Key: value` header lines whose values the chunk's metadata also holds
+const METADATA_HEADERS: [&str; 5] = ["// File:", "// Function:", "// Method:", "// Lines:", "// Complexity:"];
+
+/// Opt-in cleanup of the text handed to the model. The chunk that is stored
+/// and shown in search results keeps its full content either way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextCleanup {
+    /// Drop the `// File:`, `// Function:`/`// Method:`, `// Lines:` and
+    /// `// Complexity:` headers. `// Class:` and `// Description:` stay, as
+    /// nothing else carries them.
+    pub strip_headers: bool,
+    /// Replace every run of whitespace, newlines included, with one space
+    pub collapse_whitespace: bool,
+}
+
+impl TextCleanup {
+    /// Copy of `chunk` whose content is cleaned up for embedding
+    pub fn for_embedding(&self, chunk: &Chunk) -> Chunk {
+        let mut content = if self.strip_headers {
+            chunk.content
+                .lines()
+                .filter(|line| !METADATA_HEADERS.iter().any(|header| line.starts_with(header)))
+                .collect::<Vec<_>>()
+                .join("\n")
+                .trim_start()
+                .to_string()
+        } else {
+            chunk.content.clone()
+        };
+        if self.collapse_whitespace {
+            content = content.split_whitespace().collect::<Vec<_>>().join(" ");
+        }
+        Chunk { content, ..chunk.clone() }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -34,109 +81,153 @@ pub struct ChunkMetadata {
 }
 
 /// Convert KB to chunks with different granularity options
-pub fn chunk_knowledge_base(kb: &KnowledgeBase, max_size: usize) -> Vec<Chunk> {
-    let mut chunks = Vec::new();
-
-    // Chunk 1: Entry points (highest priority)
-    for entry_point in &kb.entry_points {
-        if let Some((file_path, func)) = kb.get_function(&entry_point.function) {
-            let content = format_function_with_context(func, file_path, kb);
-            chunks.push(Chunk {
-                id: entry_point.function.clone(),
-                chunk_type: ChunkType::EntryPoint,
-                content: truncate_content(&content, max_size),
-                metadata: ChunkMetadata {
-                    file_path: Some(file_path.clone()),
-                    language: Some(kb.structure[file_path].language.clone()),
-                    line_start: Some(func.line_start),
-                    line_end: Some(func.line_end),
-                    name: func.name.clone(),
-                    complexity: Some(func.complexity),
-                },
-                tags: generate_tags(func, &entry_point.entry_type),
-                importance_score: 1.0, // Entry points are most important
-            });
+///
+/// With the embedder's tokenizer each chunk is cut at the model's real token
+/// limit; without one the ~4 chars per token estimate is used.
+///
+/// With `overlap`, oversized function and method chunks are split into
+/// windows (`func_foo#0`, `func_foo#1`, ...) instead of being cut off. Each
+/// window holds up to `max_size` new characters plus the last `overlap`
+/// characters of the window before it.
+///
+/// `call_limits` caps the call lists in function and method chunks; lifting
+/// them only makes the chunk longer, it is still cut at `max_size`.
+pub fn chunk_knowledge_base(
+    kb: &KnowledgeBase,
+    max_size: usize,
+    overlap: Option<usize>,
+    tokenizer: Option<&Tokenizer>,
+    call_limits: CallLimits,
+) -> Vec<Chunk> {
+    let truncate = |content: &str, max_size: usize| match tokenizer {
+        Some(tokenizer) => truncate_to_tokens(content, max_size, tokenizer),
+        None => truncate_content(content, max_size),
+    };
+    let window_size = max_size.min(2000);
+    let pieces = |content: &str| -> Vec<String> {
+        match overlap {
+            Some(overlap) if content.len() > window_size => split_windows(content, window_size, overlap)
+                .into_iter()
+                .map(|window| match tokenizer {
+                    Some(tokenizer) => truncate_to_tokens(&window, usize::MAX, tokenizer),
+                    None => window,
+                })
+                .collect(),
+            _ => vec![truncate(content, max_size)],
         }
-    }
+    };
+    let function_chunks = |func: &crate::kb_loader::Function, file_path: &String, language: &str| {
+        let mut windows = Vec::new();
+        let content = format_function_with_context(func, file_path, kb, call_limits);
+        push_windows(&mut windows, pieces(&content), Chunk {
+            id: func.id.clone(),
+            chunk_type: ChunkType::Function,
+            content: String::new(),
+            metadata: ChunkMetadata {
+                file_path: Some(file_path.clone()),
+                language: Some(language.to_string()),
+                line_start: Some(func.line_start),
+                line_end: Some(func.line_end),
+                name: func.name.clone(),
+                complexity: Some(func.complexity),
+            },
+            tags: generate_tags(func, "function"),
+            importance_score: func.importance_score,
+        });
+        windows
+    };
 
-    // Chunk 2: Regular functions
-    for (file_path, file_struct) in &kb.structure {
-        for func in &file_struct.functions {
-            // Skip if already added as entry point
-            if chunks.iter().any(|c| c.id == func.id) {
-                continue;
+    // Chunk 1: Entry points (highest priority)
+    let mut chunks: Vec<Chunk> = kb
+        .entry_points
+        .par_iter()
+        .flat_map_iter(|entry_point| {
+            let mut windows = Vec::new();
+            if let Some((file_path, func)) = kb.get_function(&entry_point.function) {
+                let content = format_function_with_context(func, file_path, kb, call_limits);
+                push_windows(&mut windows, pieces(&content), Chunk {
+                    id: entry_point.function.clone(),
+                    chunk_type: ChunkType::EntryPoint,
+                    content: String::new(),
+                    metadata: ChunkMetadata {
+                        file_path: Some(file_path.clone()),
+                        language: Some(kb.structure[file_path].language.clone()),
+                        line_start: Some(func.line_start),
+                        line_end: Some(func.line_end),
+                        name: func.name.clone(),
+                        complexity: Some(func.complexity),
+                    },
+                    tags: generate_tags(func, &entry_point.entry_type),
+                    importance_score: 1.0, // Entry points are most important
+                });
             }
-
-            let content = format_function_with_context(func, file_path, kb);
-            chunks.push(Chunk {
-                id: func.id.clone(),
-                chunk_type: ChunkType::Function,
-                content: truncate_content(&content, max_size),
-                metadata: ChunkMetadata {
-                    file_path: Some(file_path.clone()),
-                    language: Some(file_struct.language.clone()),
-                    line_start: Some(func.line_start),
-                    line_end: Some(func.line_end),
-                    name: func.name.clone(),
-                    complexity: Some(func.complexity),
-                },
-                tags: generate_tags(func, "function"),
-                importance_score: func.importance_score,
-            });
-        }
-    }
-
-    // Chunk 3: Classes and methods
-    for (file_path, file_struct) in &kb.structure {
-        for class in &file_struct.classes {
-            // Create chunk for class overview
-            let class_content = format_class_overview(class, file_path);
-            chunks.push(Chunk {
-                id: class.id.clone(),
-                chunk_type: ChunkType::Class,
-                content: truncate_content(&class_content, max_size),
-                metadata: ChunkMetadata {
-                    file_path: Some(file_path.clone()),
-                    language: Some(file_struct.language.clone()),
-                    line_start: Some(class.line_start),
-                    line_end: Some(class.line_end),
-                    name: class.name.clone(),
-                    complexity: None,
-                },
-                tags: vec!["class".to_string(), file_struct.language.clone()],
-                importance_score: 0.7,
-            });
-
-            // Create chunks for each method
-            for method in &class.methods {
-                let method_content = format_method_with_class_context(method, class, file_path, kb);
-                chunks.push(Chunk {
-                    id: method.id.clone(),
-                    chunk_type: ChunkType::Method,
-                    content: truncate_content(&method_content, max_size),
+            windows
+        })
+        .collect();
+
+    // Files are independent, so they're chunked in parallel. Path order keeps
+    // the result independent of HashMap iteration and thread scheduling.
+    let mut files: Vec<(&String, &crate::kb_loader::FileStructure)> = kb.structure.iter().collect();
+    files.sort_by_key(|(file_path, _)| *file_path);
+
+    let per_file: Vec<FileChunks> = files
+        .par_iter()
+        .map(|&(file_path, file_struct)| {
+            // Chunk 2: Regular functions, one group of windows each
+            let functions = file_struct
+                .functions
+                .iter()
+                .map(|func| (func.id.as_str(), function_chunks(func, file_path, &file_struct.language)))
+                .collect();
+
+            // Chunk 3: Classes and methods
+            let mut classes = Vec::new();
+            for class in &file_struct.classes {
+                // Create chunk for class overview
+                let class_content = format_class_overview(class, file_path);
+                classes.push(Chunk {
+                    id: class.id.clone(),
+                    chunk_type: ChunkType::Class,
+                    content: truncate(&class_content, max_size),
                     metadata: ChunkMetadata {
                         file_path: Some(file_path.clone()),
                         language: Some(file_struct.language.clone()),
-                        line_start: Some(method.line_start),
-                        line_end: Some(method.line_end),
-                        name: format!("{}.{}", class.name, method.name),
-                        complexity: Some(method.complexity),
+                        line_start: Some(class.line_start),
+                        line_end: Some(class.line_end),
+                        name: class.name.clone(),
+                        complexity: None,
                     },
-                    tags: generate_tags(method, "method"),
-                    importance_score: method.importance_score,
+                    tags: vec!["class".to_string(), file_struct.language.clone()],
+                    importance_score: 0.7,
                 });
+
+                // Create chunks for each method
+                for method in &class.methods {
+                    let method_content = format_method_with_class_context(method, class, file_path, kb, call_limits);
+                    push_windows(&mut classes, pieces(&method_content), Chunk {
+                        id: method.id.clone(),
+                        chunk_type: ChunkType::Method,
+                        content: String::new(),
+                        metadata: ChunkMetadata {
+                            file_path: Some(file_path.clone()),
+                            language: Some(file_struct.language.clone()),
+                            line_start: Some(method.line_start),
+                            line_end: Some(method.line_end),
+                            name: format!("{}.{}", class.name, method.name),
+                            complexity: Some(method.complexity),
+                        },
+                        tags: generate_tags(method, "method"),
+                        importance_score: method.importance_score,
+                    });
+                }
             }
-        }
-    }
 
-    // Chunk 4: File-level summaries (optional, for context)
-    for (file_path, file_struct) in &kb.structure {
-        let file_summary = format_file_summary(file_path, file_struct, kb);
-        if !file_summary.is_empty() {
-            chunks.push(Chunk {
+            // Chunk 4: File-level summaries (optional, for context)
+            let file_summary = format_file_summary(file_path, file_struct, kb);
+            let summary = (!file_summary.is_empty()).then(|| Chunk {
                 id: format!("file:{}", file_path),
                 chunk_type: ChunkType::File,
-                content: truncate_content(&file_summary, max_size),
+                content: truncate(&file_summary, max_size),
                 metadata: ChunkMetadata {
                     file_path: Some(file_path.clone()),
                     language: Some(file_struct.language.clone()),
@@ -148,16 +239,88 @@ pub fn chunk_knowledge_base(kb: &KnowledgeBase, max_size: usize) -> Vec<Chunk> {
                 tags: vec!["file".to_string(), file_struct.language.clone()],
                 importance_score: 0.5,
             });
+
+            FileChunks { functions, classes, summary }
+        })
+        .collect();
+
+    // Skip functions already added as an entry point or from an earlier file
+    // with the same id
+    let mut seen: HashSet<String> = chunks.iter().map(|c| base_id(&c.id).to_string()).collect();
+    for file in per_file {
+        for (id, windows) in file.functions {
+            if seen.insert(id.to_string()) {
+                chunks.extend(windows);
+            }
         }
+        chunks.extend(file.classes);
+        chunks.extend(file.summary);
     }
 
+    // Windows of a split chunk stay in order: `#2` before `#10`
+    chunks.sort_by_cached_key(|c| {
+        (base_id(&c.id).to_string(), c.id.rsplit_once('#').and_then(|(_, n)| n.parse::<usize>().ok()))
+    });
     chunks
 }
 
+/// Everything `chunk_knowledge_base` produces for one file
+struct FileChunks<'a> {
+    /// Function id and its chunk windows
+    functions: Vec<(&'a str, Vec<Chunk>)>,
+    classes: Vec<Chunk>,
+    summary: Option<Chunk>,
+}
+
+/// Strip the `#n` window suffix added to split chunks
+pub fn base_id(id: &str) -> &str {
+    match id.rsplit_once('#') {
+        Some((base, index)) if !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit()) => base,
+        _ => id,
+    }
+}
+
+/// Push `template` once per content window, suffixing ids only when split
+fn push_windows(chunks: &mut Vec<Chunk>, pieces: Vec<String>, template: Chunk) {
+    if pieces.len() == 1 {
+        let content = pieces.into_iter().next().unwrap_or_default();
+        chunks.push(Chunk { content, ..template });
+        return;
+    }
+
+    for (i, content) in pieces.into_iter().enumerate() {
+        chunks.push(Chunk {
+            id: format!("{}#{}", template.id, i),
+            content,
+            ..template.clone()
+        });
+    }
+}
+
+/// Split into windows of `window_size` new characters, each prefixed with
+/// the last `overlap` characters of the previous window
+fn split_windows(content: &str, window_size: usize, overlap: usize) -> Vec<String> {
+    let chars: Vec<char> = content.chars().collect();
+
+    window_ranges(chars.len(), window_size, overlap)
+        .into_iter()
+        .map(|range| chars[range].iter().collect())
+        .collect()
+}
+
+/// Char ranges of the windows `split_windows` cuts `len` chars into
+pub(crate) fn window_ranges(len: usize, window_size: usize, overlap: usize) -> Vec<std::ops::Range<usize>> {
+    (0..len)
+        .step_by(window_size.max(1))
+        .map(|start| start.saturating_sub(overlap)..(start + window_size.max(1)).min(len))
+        .collect()
+}
+
 fn format_function_with_context(
     func: &crate::kb_loader::Function,
     file_path: &str,
     _kb: &KnowledgeBase,
+    call_limits: CallLimits,
 ) -> String {
     let mut content = String::new();
 
@@ -197,24 +360,18 @@ fn format_function_with_context(
     // Calls made by this function
     if !func.calls.is_empty() {
         content.push_str("Calls:\n");
-        for call in func.calls.iter().take(10) {
-            content.push_str(&format!("  - {} (line {})\n", call.callee, call.line));
-        }
-        if func.calls.len() > 10 {
-            content.push_str(&format!("  ... and {} more\n", func.calls.len() - 10));
-        }
+        push_limited(&mut content, &func.calls, call_limits.calls, |call| {
+            format!("  - {} (line {})\n", call.callee, call.line)
+        });
         content.push_str("\n");
     }
 
     // Called by
     if !func.called_by.is_empty() {
         content.push_str("Called by:\n");
-        for caller in func.called_by.iter().take(5) {
-            content.push_str(&format!("  - {} in {}\n", caller.function, caller.file));
-        }
-        if func.called_by.len() > 5 {
-            content.push_str(&format!("  ... and {} more\n", func.called_by.len() - 5));
-        }
+        push_limited(&mut content, &func.called_by, call_limits.called_by, |caller| {
+            format!("  - {} in {}\n", caller.function, caller.file)
+        });
         content.push_str("\n");
     }
 
@@ -228,25 +385,50 @@ fn format_function_with_context(
     }
 
     // Exception handling
-    if !func.exceptions.raises.is_empty() || !func.exceptions.handles.is_empty() {
+    if !func.exceptions.raises.is_empty()
+        || !func.exceptions.propagates.is_empty()
+        || !func.exceptions.handles.is_empty()
+    {
         content.push_str("Exceptions:\n");
         if !func.exceptions.raises.is_empty() {
             content.push_str(&format!("  Raises: {}\n", func.exceptions.raises.join(", ")));
         }
+        if !func.exceptions.propagates.is_empty() {
+            content.push_str(&format!("  Propagates: {}\n", func.exceptions.propagates.join(", ")));
+        }
         if !func.exceptions.handles.is_empty() {
             content.push_str(&format!("  Handles: {}\n", func.exceptions.handles.join(", ")));
         }
         content.push_str("\n");
     }
 
+    // Code goes last so truncating an oversized chunk keeps the summary
+    if let Some(source) = &func.source {
+        content.push_str("Source:\n");
+        content.push_str(source);
+        content.push('\n');
+    }
+
     content
 }
 
+/// One line per item up to `limit`, then "... and N more" for the rest
+fn push_limited<T>(content: &mut String, items: &[T], limit: Option<usize>, line: impl Fn(&T) -> String) {
+    let shown = limit.unwrap_or(items.len()).min(items.len());
+    for item in &items[..shown] {
+        content.push_str(&line(item));
+    }
+    if items.len() > shown {
+        content.push_str(&format!("  ... and {} more\n", items.len() - shown));
+    }
+}
+
 fn format_method_with_class_context(
     method: &crate::kb_loader::Function,
     class: &crate::kb_loader::Class,
     file_path: &str,
     kb: &KnowledgeBase,
+    call_limits: CallLimits,
 ) -> String {
     let mut content = String::new();
 
@@ -264,7 +446,7 @@ fn format_method_with_class_context(
     }
 
     content.push_str("\n");
-    content.push_str(&format_function_with_context(method, file_path, kb));
+    content.push_str(&format_function_with_context(method, file_path, kb, call_limits));
 
     content
 }
@@ -302,8 +484,22 @@ fn format_class_overview(class: &crate::kb_loader::Class, file_path: &str) -> St
     if !class.methods.is_empty() {
         content.push_str(&format!("Methods ({}):\n", class.methods.len()));
         for method in &class.methods {
-            content.push_str(&format!("  - {}{}\n", method.name,
-                if method.is_async { " (async)" } else { "" }));
+            let mut labels = Vec::new();
+            match method.method_kind {
+                MethodKind::Instance => {}
+                MethodKind::Static => labels.push("static"),
+                MethodKind::Class => labels.push("classmethod"),
+                MethodKind::Property => labels.push("property"),
+            }
+            if method.is_async {
+                labels.push("async");
+            }
+
+            if labels.is_empty() {
+                content.push_str(&format!("  - {}\n", method.name));
+            } else {
+                content.push_str(&format!("  - {} ({})\n", method.name, labels.join(", ")));
+            }
         }
         content.push_str("\n");
     }
@@ -355,42 +551,33 @@ fn format_file_summary(
     content
 }
 
-fn generate_tags(func: &crate::kb_loader::Function, base_tag: &str) -> Vec<String> {
-    let mut tags = vec![base_tag.to_string()];
-
-    // Add async tag
-    if func.is_async {
-        tags.push("async".to_string());
-    }
+fn truncate_content(content: &str, max_size: usize) -> String {
+    // Conservative estimate: 1 token ≈ 4 characters
+    // BERT models have 512 token limit, so ~2000 chars is safe
+    truncate_chars(content, max_size.min(2000))
+}
 
-    // Add tags from function
-    tags.extend(func.tags.iter().cloned());
+/// Keep at most `max_size` characters and as many leading tokens as the model
+/// will actually see, mapping the last kept token back to its source offset
+pub(crate) fn truncate_to_tokens(content: &str, max_size: usize, tokenizer: &Tokenizer) -> String {
+    let content = truncate_chars(content, max_size);
 
-    // Add complexity-based tags
-    if func.complexity > 10 {
-        tags.push("complex".to_string());
-    }
+    let encoding = match tokenizer.encode(content.as_str(), false) {
+        Ok(encoding) => encoding,
+        Err(_) => return truncate_content(&content, max_size),
+    };
 
-    // Add decorator-based tags
-    for decorator in &func.decorators {
-        if decorator.contains("api") || decorator.contains("route") {
-            tags.push("api".to_string());
-        }
-        if decorator.contains("test") {
-            tags.push("test".to_string());
-        }
+    // Leave room for the special tokens the backend adds when embedding
+    let budget = MAX_MODEL_TOKENS - 2;
+    if encoding.len() <= budget {
+        return content;
     }
 
-    tags.sort();
-    tags.dedup();
-    tags
+    let (_, end) = encoding.get_offsets()[budget - 1];
+    content[..end].to_string()
 }
 
-fn truncate_content(content: &str, max_size: usize) -> String {
-    // Conservative estimate: 1 token ≈ 4 characters
-    // BERT models have 512 token limit, so ~2000 chars is safe
-    let safe_max = max_size.min(2000);
-
+fn truncate_chars(content: &str, safe_max: usize) -> String {
     if content.len() <= safe_max {
         content.to_string()
     } else {
@@ -403,3 +590,270 @@ fn truncate_content(content: &str, max_size: usize) -> String {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kb_loader::EntryPoint;
+    use std::collections::HashMap;
+    use tokenizers::models::wordlevel::WordLevel;
+    use tokenizers::pre_tokenizers::whitespace::Whitespace;
+
+    fn word_tokenizer(words: &[&str]) -> Tokenizer {
+        let mut vocab: HashMap<String, u32> = words
+            .iter()
+            .enumerate()
+            .map(|(i, w)| (w.to_string(), i as u32 + 1))
+            .collect();
+        vocab.insert("[UNK]".to_string(), 0);
+
+        let model = WordLevel::builder()
+            .vocab(vocab)
+            .unk_token("[UNK]".to_string())
+            .build()
+            .unwrap();
+        let mut tokenizer = Tokenizer::new(model);
+        tokenizer.with_pre_tokenizer(Whitespace {});
+        tokenizer
+    }
+
+    #[test]
+    fn test_truncate_to_tokens_cuts_at_model_limit() {
+        let tokenizer = word_tokenizer(&["def", "x"]);
+        let content: String = (0..600).map(|i| format!("validate_session_{} ", i % 10)).collect();
+
+        let truncated = truncate_to_tokens(&content, usize::MAX, &tokenizer);
+        let tokens = tokenizer.encode(truncated.as_str(), false).unwrap().len();
+
+        assert_eq!(tokens, MAX_MODEL_TOKENS - 2);
+        assert!(content.starts_with(&truncated));
+        // The char heuristic would have kept far less of this content
+        assert!(truncated.len() > truncate_content(&content, usize::MAX).len());
+    }
+
+    #[test]
+    fn test_truncate_to_tokens_keeps_short_content() {
+        let tokenizer = word_tokenizer(&["def", "login"]);
+        assert_eq!(truncate_to_tokens("def login", 2000, &tokenizer), "def login");
+    }
+
+    fn kb_with_docstring(docstring: &str) -> KnowledgeBase {
+        kb_with_structure(serde_json::json!({
+            "big.py": {
+                "language": "python", "loc": 10, "imports": [], "classes": [], "global_vars": [],
+                "functions": [{
+                    "id": "func_big", "name": "big", "signature": "def big()", "params": [],
+                    "return_type": "", "docstring": docstring, "line_start": 1, "line_end": 10
+                }]
+            }
+        }))
+    }
+
+    /// A knowledge base with the given `structure` map and nothing else
+    fn kb_with_structure(structure: serde_json::Value) -> KnowledgeBase {
+        serde_json::from_value(serde_json::json!({
+            "metadata": {
+                "project_name": "demo", "version": "1", "parsed_at": "", "languages": ["python"],
+                "total_files": 1, "total_loc": 10, "total_functions": 1, "total_classes": 0, "total_methods": 0
+            },
+            "structure": structure,
+            "call_graph": { "nodes": [], "edges": [] },
+            "dependency_graph": { "nodes": [], "edges": [] },
+            "indices": {},
+            "entry_points": [],
+            "external_dependencies": [],
+            "patterns": { "architecture_style": null }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_oversized_function_split_into_overlapping_windows() {
+        let kb = kb_with_docstring(&"a".repeat(5900));
+        let (file, func) = kb.get_function("func_big").unwrap();
+        let content = format_function_with_context(func, file, &kb, CallLimits::default());
+        assert!(content.len() > 4000 && content.len() <= 6000);
+
+        let chunks = chunk_knowledge_base(&kb, 2000, Some(200), None, CallLimits::default());
+        let windows: Vec<&Chunk> = chunks.iter().filter(|c| c.chunk_type == ChunkType::Function).collect();
+
+        let ids: Vec<&str> = windows.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["func_big#0", "func_big#1", "func_big#2"]);
+        assert!(windows.iter().all(|c| c.metadata.name == "big" && base_id(&c.id) == "func_big"));
+
+        // Each window repeats the tail of the one before it
+        assert_eq!(windows[0].content.len(), 2000);
+        assert!(windows[1].content.starts_with(&windows[0].content[1800..]));
+        assert_eq!(windows[2].content.len(), content.len() - 4000 + 200);
+
+        // Without overlap the function is still truncated into a single chunk
+        let chunks = chunk_knowledge_base(&kb, 2000, None, None, CallLimits::default());
+        assert!(chunks.iter().any(|c| c.id == "func_big"));
+    }
+
+    #[test]
+    fn test_function_source_appended_and_truncated() {
+        let mut kb = kb_with_docstring("Loads everything.");
+        kb.structure.get_mut("big.py").unwrap().functions[0].source =
+            Some(format!("def big():\n{}", "    step()\n".repeat(100)));
+
+        let (file, func) = kb.get_function("func_big").unwrap();
+        let content = format_function_with_context(func, file, &kb, CallLimits::default());
+        assert!(content.find("Loads everything.").unwrap() < content.find("Source:\ndef big():").unwrap());
+
+        let chunks = chunk_knowledge_base(&kb, 300, None, None, CallLimits::default());
+        let chunk = chunks.iter().find(|c| c.id == "func_big").unwrap();
+        assert!(chunk.content.len() <= 300);
+        assert!(chunk.content.contains("Loads everything."));
+    }
+
+    #[test]
+    fn test_call_limits_cap_or_list_every_call() {
+        let mut kb = kb_with_docstring("Calls a lot.");
+        let func = &mut kb.structure.get_mut("big.py").unwrap().functions[0];
+        func.calls = serde_json::from_value(serde_json::json!(
+            (0..25).map(|i| serde_json::json!({ "callee": format!("step_{}", i), "line": i + 2, "context": "unconditional" })).collect::<Vec<_>>()
+        ))
+        .unwrap();
+
+        let (file, func) = kb.get_function("func_big").unwrap();
+        let capped = format_function_with_context(func, file, &kb, CallLimits::default());
+        assert!(capped.contains("  - step_9 (line 11)\n"));
+        assert!(!capped.contains("step_10 "));
+        assert!(capped.contains("  ... and 15 more\n"));
+
+        let unlimited = CallLimits { calls: None, called_by: None };
+        let full = format_function_with_context(func, file, &kb, unlimited);
+        assert!(full.contains("  - step_24 (line 26)\n"));
+        assert!(!full.contains("more\n"));
+
+        // Still cut at max_chunk_size
+        let chunks = chunk_knowledge_base(&kb, 300, None, None, unlimited);
+        let chunk = chunks.iter().find(|c| c.id == "func_big").unwrap();
+        assert!(chunk.content.len() <= 300);
+    }
+
+    #[test]
+    fn test_strip_headers_keeps_metadata() {
+        let kb = kb_with_docstring("Does big things.");
+        let chunks = chunk_knowledge_base(&kb, 2000, None, None, CallLimits::default());
+        let chunk = chunks.iter().find(|c| c.id == "func_big").unwrap();
+        assert!(chunk.content.starts_with("// File: big.py\n"));
+
+        let cleanup = TextCleanup { strip_headers: true, collapse_whitespace: false };
+        let embedded = cleanup.for_embedding(chunk);
+        for header in ["// File:", "// Function:", "// Lines:", "// Complexity:"] {
+            assert!(!embedded.content.contains(header), "{} left in {:?}", header, embedded.content);
+        }
+        assert!(embedded.content.starts_with("// Description: Does big things.\n"));
+        assert!(embedded.content.contains("def big()"));
+
+        assert_eq!(embedded.id, chunk.id);
+        assert_eq!(embedded.metadata.file_path.as_deref(), Some("big.py"));
+        assert_eq!((embedded.metadata.line_start, embedded.metadata.line_end), (Some(1), Some(10)));
+        assert_eq!(embedded.metadata.name, "big");
+
+        let collapsed = TextCleanup { strip_headers: true, collapse_whitespace: true }.for_embedding(chunk);
+        assert!(!collapsed.content.contains('\n') && !collapsed.content.contains("  "));
+        assert_eq!(TextCleanup::default().for_embedding(chunk).content, chunk.content);
+    }
+
+    #[test]
+    fn test_chunk_type_names_parse() {
+        let parsed: Vec<ChunkType> = ["function", " Method", "entry-point", "entry_point", "FILE"]
+            .iter()
+            .map(|name| name.parse().unwrap())
+            .collect();
+        assert_eq!(parsed, vec![
+            ChunkType::Function,
+            ChunkType::Method,
+            ChunkType::EntryPoint,
+            ChunkType::EntryPoint,
+            ChunkType::File,
+        ]);
+
+        let err = "functions".parse::<ChunkType>().unwrap_err();
+        assert!(err.contains("functions") && err.contains("entrypoint"));
+    }
+
+    #[test]
+    fn test_chunking_is_identical_across_thread_counts() {
+        let function = |id: &str, docstring: &str| serde_json::json!({
+            "id": id, "name": id, "signature": format!("def {}()", id), "params": [],
+            "return_type": "", "docstring": docstring, "line_start": 1, "line_end": 5
+        });
+        let mut structure = serde_json::Map::new();
+        for i in 0..12 {
+            structure.insert(format!("pkg/mod_{}.py", i), serde_json::json!({
+                "language": "python", "loc": 40, "imports": [], "global_vars": [],
+                "functions": [
+                    function(&format!("func_{}", i), "small"),
+                    function(&format!("func_big_{}", i), &"a".repeat(3000)),
+                    // Same id in every file, only the first one is kept
+                    function("func_shared", "shared"),
+                ],
+                "classes": [{
+                    "id": format!("class_C{}", i), "name": format!("C{}", i), "line_start": 10, "line_end": 30,
+                    "methods": [{
+                        "id": format!("method_C{}_run", i), "name": "run", "signature": "def run(self)",
+                        "params": [], "return_type": "", "line_start": 11, "line_end": 12
+                    }]
+                }]
+            }));
+        }
+        let mut kb = kb_with_structure(structure.into());
+        kb.entry_points.push(EntryPoint {
+            entry_type: "main".to_string(),
+            path: None,
+            function: "func_3".to_string(),
+            handler: "func_3".to_string(),
+            file: "pkg/mod_3.py".to_string(),
+            line: 1,
+            methods: None,
+        });
+
+        let chunk_with = |threads: usize| {
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            pool.install(|| chunk_knowledge_base(&kb, 2000, Some(200), None, CallLimits::default()))
+        };
+        let serial = chunk_with(1);
+        let parallel = chunk_with(4);
+
+        let summary = |chunks: &[Chunk]| -> Vec<(String, ChunkType, String)> {
+            chunks.iter().map(|c| (c.id.clone(), c.chunk_type.clone(), c.content.clone())).collect()
+        };
+        assert_eq!(summary(&serial), summary(&parallel));
+
+        assert_eq!(serial.iter().filter(|c| base_id(&c.id) == "func_shared").count(), 1);
+        assert_eq!(serial.iter().filter(|c| base_id(&c.id) == "func_3").count(), 1);
+        assert!(serial.iter().any(|c| c.id == "func_3" && c.chunk_type == ChunkType::EntryPoint));
+        assert!(serial.iter().any(|c| c.id == "func_big_7#1"));
+        assert!(serial.windows(2).all(|pair| base_id(&pair[0].id) <= base_id(&pair[1].id)));
+    }
+
+    #[test]
+    fn test_class_overview_labels_method_kinds() {
+        let method = |name: &str, kind: &str, is_async: bool| serde_json::json!({
+            "id": format!("method_Account_{}", name), "name": name, "signature": "", "params": [],
+            "return_type": "", "line_start": 1, "line_end": 2, "method_kind": kind, "is_async": is_async
+        });
+        let class: crate::kb_loader::Class = serde_json::from_value(serde_json::json!({
+            "id": "class_Account", "name": "Account", "line_start": 1, "line_end": 20,
+            "methods": [
+                method("deposit", "instance", false),
+                method("validate", "static", false),
+                method("open", "class", false),
+                method("balance", "property", false),
+                method("sync", "instance", true),
+            ]
+        }))
+        .unwrap();
+
+        let overview = format_class_overview(&class, "account.py");
+        assert!(overview.contains("  - deposit\n"));
+        assert!(overview.contains("  - validate (static)\n"));
+        assert!(overview.contains("  - open (classmethod)\n"));
+        assert!(overview.contains("  - balance (property)\n"));
+        assert!(overview.contains("  - sync (async)\n"));
+    }
+}
@@ -0,0 +1,112 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::aggregate::AggregateMode;
+use crate::chunker::ChunkType;
+use crate::embedder::EmbeddingBackend;
+
+/// `[embeddings]` section of `eulix.toml`, the project config file shared
+/// with the eulix CLI and eulix_parser. Values are defaults for the `embed`
+/// command: a flag given on the command line wins over the file, and the
+/// file over the built-in default. Other sections and unknown keys (such as
+/// the CLI's `dimension`) are ignored.
+#[derive(Debug, Default, Deserialize)]
+pub struct EmbedConfig {
+    pub model: Option<String>,
+    /// Same values as `--device`; the eulix CLI writes it as `backend`
+    #[serde(alias = "device")]
+    pub backend: Option<String>,
+    pub kb_path: Option<String>,
+    pub output: Option<String>,
+    pub max_chunk_size: Option<usize>,
+    pub overlap: Option<usize>,
+    pub batch_size: Option<usize>,
+    pub threads: Option<usize>,
+    pub chunk_types: Option<Vec<String>>,
+    pub exclude_tests: Option<bool>,
+    pub strip_headers: Option<bool>,
+    pub collapse_whitespace: Option<bool>,
+    pub aggregate: Option<String>,
+    pub offline: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    embeddings: EmbedConfig,
+}
+
+impl EmbedConfig {
+    pub const FILE_NAME: &'static str = "eulix.toml";
+
+    /// Load `explicit` (which must exist) or else `./eulix.toml` if there is one
+    pub fn discover(explicit: Option<&str>) -> Result<Self> {
+        let path = match explicit {
+            Some(path) => PathBuf::from(path),
+            None if Path::new(Self::FILE_NAME).is_file() => PathBuf::from(Self::FILE_NAME),
+            None => return Ok(Self::default()),
+        };
+
+        let text = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+        Self::from_toml(&text).with_context(|| format!("Invalid config {}", path.display()))
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self> {
+        Ok(toml::from_str::<ConfigFile>(text)?.embeddings)
+    }
+
+    /// `backend`, with `auto` (the CLI's default) meaning no preference
+    pub fn device(&self) -> Result<Option<EmbeddingBackend>> {
+        match self.backend.as_deref() {
+            None => Ok(None),
+            Some(value) if value.eq_ignore_ascii_case("auto") => Ok(None),
+            Some(value) => value.parse().map(Some).context("eulix.toml backend"),
+        }
+    }
+
+    pub fn chunk_types(&self) -> Result<Option<Vec<ChunkType>>> {
+        self.chunk_types
+            .as_ref()
+            .map(|names| names.iter().map(|name| name.parse()).collect::<Result<Vec<ChunkType>, String>>())
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("eulix.toml chunk_types: {}", e))
+    }
+
+    pub fn aggregate(&self) -> Result<AggregateMode> {
+        self.aggregate
+            .as_deref()
+            .map_or(Ok(AggregateMode::None), str::parse)
+            .map_err(|e| anyhow::anyhow!("eulix.toml aggregate: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_config_with_custom_chunk_size() {
+        let path = std::env::temp_dir().join(format!("eulix_config_{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            "[parser]\nthreads = 4\n\n[embeddings]\nmodel = \"BAAI/bge-small-en-v1.5\"\nbackend = \"auto\"\n\
+             dimension = 384\nmax_chunk_size = 1500\nchunk_types = [\"function\", \"method\"]\n",
+        )
+        .unwrap();
+        let config = EmbedConfig::discover(path.to_str());
+        std::fs::remove_file(&path).unwrap();
+        let config = config.unwrap();
+
+        assert_eq!(config.max_chunk_size, Some(1500));
+        assert_eq!(config.model.as_deref(), Some("BAAI/bge-small-en-v1.5"));
+        assert_eq!(config.device().unwrap(), None);
+        assert_eq!(config.chunk_types().unwrap(), Some(vec![ChunkType::Function, ChunkType::Method]));
+        assert_eq!(config.aggregate().unwrap(), AggregateMode::None);
+
+        let bad = EmbedConfig::from_toml("[embeddings]\naggregate = \"everything\"\n").unwrap();
+        assert!(bad.aggregate().is_err());
+        assert!(EmbedConfig::discover(Some("/nonexistent/eulix.toml")).is_err());
+    }
+}
@@ -5,7 +5,8 @@ use std::fs::File;
 use std::io::BufWriter;
 use std::path::Path;
 
-use crate::chunker::{Chunk, ChunkType};
+use crate::chunker::{base_id, CallLimits, Chunk, ChunkType};
+use crate::index::EmbedInput;
 use crate::kb_loader::KnowledgeBase;
 
 /// Lightweight context index for LLM queries (no embeddings stored)
@@ -28,6 +29,13 @@ pub struct ContextMetadata {
     pub embedding_dimension: usize,
     pub languages: Vec<String>,
     pub architecture_style: Option<String>,
+    /// Copied from the KB, tying the index to the source it was built from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_branch: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_dirty: Option<bool>,
 }
 
 /// Lightweight chunk representation (no embedding vector)
@@ -108,7 +116,7 @@ impl ContextIndex {
                 }
 
                 // Check if this is an entry point
-                let is_entry_point = kb.entry_points.iter().any(|ep| ep.function == chunk.id);
+                let is_entry_point = kb.entry_points.iter().any(|ep| ep.function == base_id(&chunk.id));
 
                 ContextChunk {
                     id: chunk.id,
@@ -142,6 +150,9 @@ impl ContextIndex {
                 embedding_dimension,
                 languages: kb.metadata.languages.clone(),
                 architecture_style: kb.patterns.architecture_style.clone(),
+                git_commit: kb.metadata.git_commit.clone(),
+                git_branch: kb.metadata.git_branch.clone(),
+                git_dirty: kb.metadata.git_dirty,
             },
             chunks: context_chunks,
             relationships,
@@ -194,48 +205,10 @@ impl ContextIndex {
             total_nodes: kb.call_graph.nodes.len(),
             total_edges: kb.call_graph.edges.len(),
             entry_points_count: kb.entry_points.len(),
-            max_depth: Self::calculate_max_call_depth(kb),
+            max_depth: kb.call_graph.max_depth_from_entry_points(),
         }
     }
 
-    fn calculate_max_call_depth(kb: &KnowledgeBase) -> usize {
-        // Simple BFS to find max depth from entry points
-        use std::collections::{HashMap, VecDeque};
-
-        let mut max_depth = 0;
-        let adjacency: HashMap<String, Vec<String>> = kb.call_graph.edges
-            .iter()
-            .fold(HashMap::new(), |mut acc, edge| {
-                acc.entry(edge.from.clone())
-                    .or_insert_with(Vec::new)
-                    .push(edge.to.clone());
-                acc
-            });
-
-        for entry_point in &kb.entry_points {
-            let mut queue = VecDeque::new();
-            let mut visited = HashMap::new();
-
-            queue.push_back((entry_point.function.clone(), 0));
-            visited.insert(entry_point.function.clone(), 0);
-
-            while let Some((node, depth)) = queue.pop_front() {
-                max_depth = max_depth.max(depth);
-
-                if let Some(neighbors) = adjacency.get(&node) {
-                    for neighbor in neighbors {
-                        if !visited.contains_key(neighbor) {
-                            visited.insert(neighbor.clone(), depth + 1);
-                            queue.push_back((neighbor.clone(), depth + 1));
-                        }
-                    }
-                }
-            }
-        }
-
-        max_depth
-    }
-
     fn extract_entry_points(kb: &KnowledgeBase) -> Vec<EntryPointInfo> {
         kb.entry_points
             .iter()
@@ -389,6 +362,9 @@ impl ContextIndex {
             entry_points: self.entry_points.len(),
             languages: self.metadata.languages.clone(),
             call_graph_depth: self.call_graph_summary.max_depth,
+            git_commit: self.metadata.git_commit.clone(),
+            git_branch: self.metadata.git_branch.clone(),
+            git_dirty: self.metadata.git_dirty,
         }
     }
 }
@@ -401,12 +377,41 @@ pub struct IndexStats {
     pub entry_points: usize,
     pub languages: Vec<String>,
     pub call_graph_depth: usize,
+    pub git_commit: Option<String>,
+    pub git_branch: Option<String>,
+    pub git_dirty: Option<bool>,
+}
+
+/// `vectors.bin` format version written by `save_binary` and
+/// `append_binary`; version 1 files (no header) can still be read
+const VECTORS_VERSION: u32 = 2;
+
+/// What the vectors in a `vectors.bin` were produced with. `--resume` only
+/// reuses a checkpoint whose header matches the current run: chunk ids stay
+/// the same when the model, text settings or chunk size change, but the
+/// vectors behind them don't.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VectorHeader {
+    pub model: String,
+    pub dimension: usize,
+    pub input: EmbedInput,
+    pub max_chunk_size: usize,
+    pub overlap: Option<usize>,
+    pub call_limits: CallLimits,
 }
 
 /// Binary vector storage for efficient similarity search
 #[derive(Debug)]
 pub struct VectorStore {
     pub vectors: HashMap<String, Vec<f32>>,
+    /// Written as JSON after the binary header; `None` for version 1 files
+    pub header: Option<VectorHeader>,
+    /// Ids whose content was cut at the model's token limit when embedded;
+    /// only known for vectors generated in this run, never saved
+    pub truncated: Vec<String>,
+    /// Ids of blank chunks that were left out rather than embedded as an
+    /// all-zero vector; like `truncated`, only known for this run
+    pub empty: Vec<String>,
 }
 
 impl Default for VectorStore {
@@ -419,6 +424,9 @@ impl VectorStore {
     pub fn new() -> Self {
         Self {
             vectors: HashMap::new(),
+            header: None,
+            truncated: Vec::new(),
+            empty: Vec::new(),
         }
     }
 
@@ -473,17 +481,22 @@ impl VectorStore {
 
         let mut file = std::fs::File::create(path)?;
 
-        // Write header: [version: u32, count: u64, dimension: u32]
-        let version: u32 = 1;
+        // Write header: [version: u32, count: u64, dimension: u32, header_len: u32, header: JSON]
         let count = self.vectors.len() as u64;
         let dimension = self.vectors.values()
             .next()
             .map(|v| v.len() as u32)
             .unwrap_or(0);
+        let header = match &self.header {
+            Some(header) => serde_json::to_vec(header)?,
+            None => Vec::new(),
+        };
 
-        file.write_all(&version.to_le_bytes())?;
+        file.write_all(&VECTORS_VERSION.to_le_bytes())?;
         file.write_all(&count.to_le_bytes())?;
         file.write_all(&dimension.to_le_bytes())?;
+        file.write_all(&(header.len() as u32).to_le_bytes())?;
+        file.write_all(&header)?;
 
         // Write each vector with its ID
         for (id, vector) in &self.vectors {
@@ -501,6 +514,71 @@ impl VectorStore {
         Ok(())
     }
 
+    /// Append vectors to a file in the `save_binary` format, creating it
+    /// (without a `VectorHeader`) if needed. The header count is bumped only
+    /// after the records are written, so a crash mid-append leaves a file
+    /// that still loads. A file saved with no vectors yet takes its dimension
+    /// from the first append.
+    pub fn append_binary(path: &Path, entries: &[(String, Vec<f32>)]) -> Result<()> {
+        use std::io::{Read, Seek, SeekFrom, Write};
+
+        let Some((_, first)) = entries.first() else {
+            return Ok(());
+        };
+
+        let mut file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        let (count, dimension) = if file.metadata()?.len() == 0 {
+            let dimension = first.len() as u32;
+            file.write_all(&VECTORS_VERSION.to_le_bytes())?;
+            file.write_all(&0u64.to_le_bytes())?;
+            file.write_all(&dimension.to_le_bytes())?;
+            file.write_all(&0u32.to_le_bytes())?;
+            (0u64, dimension as usize)
+        } else {
+            let mut header = [0u8; 16];
+            file.read_exact(&mut header)?;
+            let count = u64::from_le_bytes(header[4..12].try_into()?);
+            let dimension = u32::from_le_bytes(header[12..16].try_into()?) as usize;
+            if count == 0 && dimension != first.len() {
+                file.seek(SeekFrom::Start(12))?;
+                file.write_all(&(first.len() as u32).to_le_bytes())?;
+                (count, first.len())
+            } else {
+                (count, dimension)
+            }
+        };
+
+        let mut writer = std::io::BufWriter::new(&mut file);
+        writer.seek(SeekFrom::End(0))?;
+        for (id, vector) in entries {
+            if vector.len() != dimension {
+                anyhow::bail!(
+                    "Vector '{}' has dimension {} but {} stores dimension {}",
+                    id, vector.len(), path.display(), dimension
+                );
+            }
+
+            let id_bytes = id.as_bytes();
+            writer.write_all(&(id_bytes.len() as u32).to_le_bytes())?;
+            writer.write_all(id_bytes)?;
+            for &value in vector {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+        }
+        writer.flush()?;
+        drop(writer);
+
+        file.seek(SeekFrom::Start(4))?;
+        file.write_all(&(count + entries.len() as u64).to_le_bytes())?;
+        Ok(())
+    }
+
     /// Load from binary format
     pub fn load_binary(path: &Path) -> Result<Self> {
         use std::io::Read;
@@ -517,10 +595,27 @@ impl VectorStore {
         file.read_exact(&mut count_bytes)?;
         file.read_exact(&mut dimension_bytes)?;
 
-        let _version = u32::from_le_bytes(version_bytes);
+        let version = u32::from_le_bytes(version_bytes);
         let count = u64::from_le_bytes(count_bytes);
         let dimension = u32::from_le_bytes(dimension_bytes) as usize;
 
+        match version {
+            1 => {}
+            VECTORS_VERSION => {
+                let mut len_bytes = [0u8; 4];
+                file.read_exact(&mut len_bytes)?;
+                let mut header = vec![0u8; u32::from_le_bytes(len_bytes) as usize];
+                file.read_exact(&mut header)?;
+                if !header.is_empty() {
+                    store.header = Some(serde_json::from_slice(&header)?);
+                }
+            }
+            _ => anyhow::bail!(
+                "Unsupported vectors.bin version {} (expected 1 to {})",
+                version, VECTORS_VERSION
+            ),
+        }
+
         // Read vectors
         for _ in 0..count {
             // Read ID
@@ -559,7 +654,7 @@ impl VectorStore {
             })
             .collect();
 
-        results.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap());
+        results.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
         results.truncate(top_k);
         results
     }
@@ -582,3 +677,57 @@ fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
         dot_product / (magnitude_a * magnitude_b)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_binary_round_trip() {
+        let path = std::env::temp_dir().join(format!("eulix_vectors_{}.bin", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        VectorStore::append_binary(&path, &[("func_a".to_string(), vec![1.0, 2.0])]).unwrap();
+        VectorStore::append_binary(&path, &[
+            ("func_b".to_string(), vec![3.0, 4.0]),
+            ("func_c".to_string(), vec![5.0, 6.0]),
+        ]).unwrap();
+        let bad_dimension = VectorStore::append_binary(&path, &[("func_d".to_string(), vec![1.0])]);
+
+        let store = VectorStore::load_binary(&path);
+        std::fs::remove_file(&path).unwrap();
+        let store = store.unwrap();
+
+        assert!(bad_dimension.is_err());
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.get("func_b"), Some(&vec![3.0, 4.0]));
+        assert!(store.header.is_none());
+    }
+
+    #[test]
+    fn test_header_survives_appends() {
+        let path = std::env::temp_dir().join(format!("eulix_vectors_header_{}.bin", std::process::id()));
+        let header = VectorHeader {
+            model: "BAAI/bge-small-en-v1.5".to_string(),
+            dimension: 2,
+            input: EmbedInput { text_cleanup: Default::default(), max_tokens: 512 },
+            max_chunk_size: 2000,
+            overlap: None,
+            call_limits: CallLimits::default(),
+        };
+        let mut store = VectorStore::new();
+        store.header = Some(header.clone());
+        store.save_binary(&path).unwrap();
+
+        VectorStore::append_binary(&path, &[("func_a".to_string(), vec![1.0, 2.0])]).unwrap();
+        VectorStore::append_binary(&path, &[("func_b".to_string(), vec![3.0, 4.0])]).unwrap();
+
+        let loaded = VectorStore::load_binary(&path);
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.header, Some(header));
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.get("func_a"), Some(&vec![1.0, 2.0]));
+    }
+}
@@ -1,10 +1,15 @@
 use anyhow::{anyhow, Context, Result};
 use rayon::prelude::*;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use tokenizers::Tokenizer;
 
-use crate::chunker::Chunk;
+use crate::chunker::{Chunk, MAX_MODEL_TOKENS};
 use crate::context::VectorStore;
-use crate::onnx_backend::{OnnxBackend, DeviceType};
+use crate::onnx_backend::{DeviceType, HiddenStates, OnnxBackend, TokenBatch};
+
+/// Batches each pipeline stage may run ahead of the next one
+const PIPELINE_DEPTH: usize = 2;
 
 /// Embedding backend types
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -112,7 +117,7 @@ impl EmbeddingBackend {
         false
     }
 
-    pub fn description(&self) -> &str {
+    pub fn description(&self) -> &'static str {
         match self {
             Self::OnnxCuda => "ONNX Runtime with CUDA (NVIDIA GPU)",
             Self::OnnxRocm => "ONNX Runtime with ROCm (AMD GPU)",
@@ -122,6 +127,50 @@ impl EmbeddingBackend {
     }
 }
 
+/// How token-level model output is reduced to a single vector
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PoolingStrategy {
+    /// Attention-masked mean over `last_hidden_state`
+    #[default]
+    Mean,
+    /// First ([CLS]) token of `last_hidden_state`
+    Cls,
+    /// The model's own pooled output (`sentence_embedding` or `pooler_output`)
+    Pooled,
+}
+
+impl std::str::FromStr for PoolingStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "mean" => Ok(Self::Mean),
+            "cls" => Ok(Self::Cls),
+            "pooled" => Ok(Self::Pooled),
+            _ => Err(anyhow!("Unknown pooling strategy: {}. Options: mean, cls, pooled", s)),
+        }
+    }
+}
+
+/// Where downloaded models and tokenizers are stored
+#[derive(Debug, Clone, Default)]
+pub struct ModelCache {
+    /// Cache directory; `None` uses hf-hub's default (`HF_HOME` or ~/.cache/huggingface)
+    pub dir: Option<PathBuf>,
+    /// Fail instead of downloading when a file isn't cached
+    pub offline: bool,
+}
+
+impl ModelCache {
+    /// Cache settings from `EULIX_CACHE_DIR`, if set
+    pub fn from_env() -> Self {
+        Self {
+            dir: std::env::var_os("EULIX_CACHE_DIR").map(PathBuf::from),
+            offline: false,
+        }
+    }
+}
+
 /// Configuration for the embedding generator
 pub struct EmbedderConfig {
     pub backend: EmbeddingBackend,
@@ -130,11 +179,24 @@ pub struct EmbedderConfig {
     pub dimension: usize,
     pub batch_size: usize,
     pub normalize: bool,
+    /// `None` uses the model's `sentence_embedding` output when it has one,
+    /// otherwise mean pooling
+    pub pooling: Option<PoolingStrategy>,
+    pub cache: ModelCache,
+    /// Tokens kept per text; anything longer is cut and counted in
+    /// `VectorStore::truncated`. Capped at what the model itself accepts.
+    pub max_tokens: usize,
 }
 
 impl Default for EmbedderConfig {
     fn default() -> Self {
-        let backend = EmbeddingBackend::auto_detect();
+        Self::for_backend(EmbeddingBackend::auto_detect())
+    }
+}
+
+impl EmbedderConfig {
+    /// Default configuration for a specific backend
+    pub fn for_backend(backend: EmbeddingBackend) -> Self {
         // Use larger batch size for GPU backends
         let batch_size = match backend {
             EmbeddingBackend::OnnxCuda | EmbeddingBackend::OnnxRocm => 128,
@@ -149,6 +211,9 @@ impl Default for EmbedderConfig {
             dimension: 384,
             batch_size,
             normalize: true,
+            pooling: None,
+            cache: ModelCache::from_env(),
+            max_tokens: MAX_MODEL_TOKENS,
         }
     }
 }
@@ -159,13 +224,56 @@ pub struct EmbeddingGenerator {
 }
 
 impl EmbeddingGenerator {
-    /// Create a new embedding generator with auto-detected backend
-    pub fn new(model_name: &str) -> Result<Self> {
-        let config = EmbedderConfig {
+    /// Create a new embedding generator on `device`, or with `None` the
+    /// first provider that initializes out of CUDA, ROCm and CPU
+    pub fn new(model_name: &str, device: Option<EmbeddingBackend>, cache: ModelCache) -> Result<Self> {
+        match device {
+            Some(backend) => Self::with_config(EmbedderConfig {
+                model_name: model_name.to_string(),
+                cache,
+                ..EmbedderConfig::for_backend(backend)
+            }),
+            None => Self::with_auto_device(model_name, cache),
+        }
+    }
+
+    fn with_auto_device(model_name: &str, cache: ModelCache) -> Result<Self> {
+        println!("  Auto-selecting execution provider...");
+
+        let gpus = [
+            (EmbeddingBackend::OnnxCuda, DeviceType::Cuda, EmbeddingBackend::is_cuda_available()),
+            (EmbeddingBackend::OnnxRocm, DeviceType::Rocm, EmbeddingBackend::is_rocm_available()),
+        ];
+
+        for (backend, device_type, available) in gpus {
+            if !available {
+                continue;
+            }
+
+            let config = EmbedderConfig {
+                model_name: model_name.to_string(),
+                cache: cache.clone(),
+                ..EmbedderConfig::for_backend(backend)
+            };
+            match OnnxBackend::new(&config, device_type) {
+                Ok(onnx) => {
+                    println!("  ✓ Initialized {}", backend.description());
+                    return Ok(Self {
+                        config,
+                        backend_impl: Box::new(onnx),
+                    });
+                }
+                Err(e) => {
+                    println!("  ℹ {} failed to initialize: {}", backend.description(), e);
+                }
+            }
+        }
+
+        Self::with_config(EmbedderConfig {
             model_name: model_name.to_string(),
-            ..Default::default()
-        };
-        Self::with_config(config)
+            cache,
+            ..EmbedderConfig::for_backend(EmbeddingBackend::OnnxCpu)
+        })
     }
 
     /// Create with explicit configuration
@@ -198,6 +306,12 @@ impl EmbeddingGenerator {
         })
     }
 
+    /// Number of chunks sent to the backend per inference call
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.config.batch_size = batch_size.max(1);
+        self
+    }
+
     /// Try to create ONNX backend with fallback to dummy
     fn try_create_onnx_backend(
         config: &EmbedderConfig,
@@ -214,7 +328,7 @@ impl EmbeddingGenerator {
                 eprintln!("    - GPU driver issues");
                 eprintln!("\n    Solutions:");
                 eprintln!("    1. Check internet connection");
-                eprintln!("    2. Set HF_HOME environment variable");
+                eprintln!("    2. Point --cache-dir (or EULIX_CACHE_DIR) at a directory holding the model");
                 eprintln!("    3. Download ONNX model manually and use --model-path");
                 eprintln!("    4. Try CPU backend: --backend cpu");
                 eprintln!("    5. Use dummy backend: --backend dummy");
@@ -225,46 +339,114 @@ impl EmbeddingGenerator {
         }
     }
 
-    pub fn generate_vectors(&self, chunks: Vec<Chunk>) -> Result<VectorStore> {
-        let total = chunks.len();
+    /// Embed chunks batch by batch, calling `progress(done, total)` after each
+    /// batch. With a `checkpoint` path every finished batch is also appended
+    /// there, so an interrupted run can be resumed.
+    ///
+    /// Batches go through three overlapping stages: one thread tokenizes and
+    /// pads batches (tokenizing on the rayon pool), one runs inference, and
+    /// this thread pools, checkpoints and reports progress. Inference is
+    /// serialized by the session lock anyway, so the win is that tokenizing
+    /// batch n+1 and pooling batch n-1 no longer wait for it; with a GPU the
+    /// session stays busy instead of idling between batches.
+    ///
+    /// Blank chunks get no vector (see `VectorStore::empty`): with nothing
+    /// to pool they would come out all zeros and match every query equally.
+    pub fn generate_vectors(
+        &self,
+        chunks: Vec<Chunk>,
+        checkpoint: Option<&Path>,
+        mut progress: impl FnMut(usize, usize),
+    ) -> Result<VectorStore> {
         let mut store = VectorStore::new();
+        let chunks = skip_empty_chunks(chunks, &mut store);
+        let total = chunks.len();
 
         println!(" Processing {} chunks in batches...", total);
         let start = std::time::Instant::now();
 
         let batch_size = self.config.batch_size;
+        let backend = &self.backend_impl;
+        let chunks = &chunks;
+        let mut done = 0;
+
+        std::thread::scope(|scope| -> Result<()> {
+            let (prepared_tx, prepared_rx) = mpsc::sync_channel::<Result<PreparedBatch>>(PIPELINE_DEPTH);
+            let (output_tx, output_rx) = mpsc::sync_channel(PIPELINE_DEPTH);
+
+            // A send only fails once the stage downstream has stopped on an error
+            scope.spawn(move || {
+                for chunk_batch in chunks.chunks(batch_size) {
+                    let texts: Vec<&str> = chunk_batch.iter().map(|c| c.content.as_str()).collect();
+                    if prepared_tx.send(backend.prepare_batch(&texts)).is_err() {
+                        break;
+                    }
+                }
+            });
+            scope.spawn(move || {
+                for prepared in prepared_rx {
+                    let output = prepared.and_then(|batch| {
+                        let output = backend.infer_batch(&batch)?;
+                        Ok((batch, output))
+                    });
+                    if output_tx.send(output).is_err() {
+                        break;
+                    }
+                }
+            });
+
+            for (chunk_batch, output) in chunks.chunks(batch_size).zip(output_rx) {
+                let (embeddings, truncated): (Vec<Vec<f32>>, Vec<bool>) = output
+                    .and_then(|(batch, output)| backend.finish_batch(batch, output))
+                    .with_context(|| format!(
+                        "Failed to generate embeddings for batch starting at chunk: {}",
+                        chunk_batch[0].id
+                    ))?
+                    .into_iter()
+                    .unzip();
+                store.truncated.extend(
+                    chunk_batch.iter().zip(truncated).filter(|(_, cut)| *cut).map(|(chunk, _)| chunk.id.clone()),
+                );
+
+                let batch: Vec<(String, Vec<f32>)> = chunk_batch.iter()
+                    .map(|chunk| chunk.id.clone())
+                    .zip(embeddings)
+                    .collect();
+
+                if let Some(path) = checkpoint {
+                    VectorStore::append_binary(path, &batch)
+                        .with_context(|| format!("Failed to checkpoint vectors to {}", path.display()))?;
+                }
 
-        for (batch_idx, chunk_batch) in chunks.chunks(batch_size).enumerate() {
-            let batch_start = batch_idx * batch_size;
+                for (id, embedding) in batch {
+                    store.add(id, embedding);
+                }
 
-            if batch_start % 100 == 0 && batch_start > 0 {
-                let elapsed = start.elapsed().as_secs_f32();
-                let rate = batch_start as f32 / elapsed;
-                let eta = ((total - batch_start) as f32 / rate).round();
-                println!("     Progress: {}/{} ({:.1} chunks/sec, ETA: {:.0}s)",
-                         batch_start, total, rate, eta);
+                done += chunk_batch.len();
+                progress(done, total);
             }
 
-            for chunk in chunk_batch {
-                let embedding = self.backend_impl
-                    .generate_embedding(&chunk.content)
-                    .context(format!("Failed to generate embedding for chunk: {}", chunk.id))?;
-
-                store.add(chunk.id.clone(), embedding);
-            }
-        }
+            Ok(())
+        })?;
 
         let elapsed = start.elapsed();
         println!("  ✓ Completed all embeddings in {:.2}s", elapsed.as_secs_f32());
         println!("     Average: {:.1} chunks/sec", total as f32 / elapsed.as_secs_f32());
+        if !store.truncated.is_empty() {
+            println!("     Truncated: {} chunks at the {}-token limit", store.truncated.len(), self.config.max_tokens);
+        }
+        if !store.empty.is_empty() {
+            println!("     Skipped:   {} empty chunks", store.empty.len());
+        }
 
         Ok(store)
     }
 
     /// Parallel processing version (for CPU/multi-GPU scenarios)
     pub fn generate_vectors_parallel(&self, chunks: Vec<Chunk>) -> Result<VectorStore> {
-        let total = chunks.len();
         let mut store = VectorStore::new();
+        let chunks = skip_empty_chunks(chunks, &mut store);
+        let total = chunks.len();
 
         println!(" Processing {} chunks in parallel...", total);
         let start = std::time::Instant::now();
@@ -309,12 +491,70 @@ impl EmbeddingGenerator {
     pub fn model_name(&self) -> &str {
         &self.config.model_name
     }
+
+    pub fn max_tokens(&self) -> usize {
+        self.config.max_tokens
+    }
+
+    /// Tokenizer of the loaded model, if the backend has one
+    pub fn tokenizer(&self) -> Option<&Tokenizer> {
+        self.backend_impl.tokenizer()
+    }
+}
+
+/// A batch between the stages of `generate_vectors`
+enum PreparedBatch {
+    /// Backends without a separate preparation step embed the raw texts
+    /// in `infer_batch`
+    Texts(Vec<String>),
+    Tokens(TokenBatch),
+}
+
+enum BatchOutput {
+    Embedded(Vec<(Vec<f32>, bool)>),
+    Hidden(HiddenStates),
 }
 
 /// Trait for different embedding backends
 trait EmbeddingBackendTrait {
     fn generate_embedding(&self, text: &str) -> Result<Vec<f32>>;
+
+    /// Embed several texts in one call, each paired with whether it was cut
+    /// at the token limit; backends without real batching loop
+    fn generate_embeddings_batch(&self, texts: &[&str]) -> Result<Vec<(Vec<f32>, bool)>> {
+        texts.iter().map(|text| Ok((self.generate_embedding(text)?, false))).collect()
+    }
+
+    /// `generate_embeddings_batch` split into stages that `generate_vectors`
+    /// runs on different threads: CPU-side preparation, the model call, and
+    /// CPU-side post-processing. By default all the work happens in
+    /// `infer_batch`.
+    fn prepare_batch(&self, texts: &[&str]) -> Result<PreparedBatch> {
+        Ok(PreparedBatch::Texts(texts.iter().map(|text| text.to_string()).collect()))
+    }
+
+    fn infer_batch(&self, batch: &PreparedBatch) -> Result<BatchOutput> {
+        match batch {
+            PreparedBatch::Texts(texts) => {
+                let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+                Ok(BatchOutput::Embedded(self.generate_embeddings_batch(&texts)?))
+            }
+            PreparedBatch::Tokens(_) => Err(anyhow!("Backend can't run a tokenized batch")),
+        }
+    }
+
+    fn finish_batch(&self, _batch: PreparedBatch, output: BatchOutput) -> Result<Vec<(Vec<f32>, bool)>> {
+        match output {
+            BatchOutput::Embedded(embeddings) => Ok(embeddings),
+            BatchOutput::Hidden(_) => Err(anyhow!("Backend can't pool hidden states")),
+        }
+    }
+
     fn dimension(&self) -> usize;
+
+    fn tokenizer(&self) -> Option<&Tokenizer> {
+        None
+    }
 }
 
 impl EmbeddingBackendTrait for OnnxBackend {
@@ -322,15 +562,46 @@ impl EmbeddingBackendTrait for OnnxBackend {
         self.generate_embedding(text)
     }
 
+    fn generate_embeddings_batch(&self, texts: &[&str]) -> Result<Vec<(Vec<f32>, bool)>> {
+        self.generate_embeddings_batch(texts)
+    }
+
+    fn prepare_batch(&self, texts: &[&str]) -> Result<PreparedBatch> {
+        Ok(PreparedBatch::Tokens(self.prepare_batch(texts)?))
+    }
+
+    fn infer_batch(&self, batch: &PreparedBatch) -> Result<BatchOutput> {
+        match batch {
+            PreparedBatch::Tokens(tokens) => Ok(BatchOutput::Hidden(self.infer_batch(tokens)?)),
+            PreparedBatch::Texts(texts) => {
+                let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+                Ok(BatchOutput::Embedded(self.generate_embeddings_batch(&texts)?))
+            }
+        }
+    }
+
+    fn finish_batch(&self, batch: PreparedBatch, output: BatchOutput) -> Result<Vec<(Vec<f32>, bool)>> {
+        match (batch, output) {
+            (PreparedBatch::Tokens(tokens), BatchOutput::Hidden(hidden)) => self.finish_batch(tokens, hidden),
+            (_, BatchOutput::Embedded(embeddings)) => Ok(embeddings),
+            (PreparedBatch::Texts(_), BatchOutput::Hidden(_)) => Err(anyhow!("Hidden states without a token batch")),
+        }
+    }
+
     fn dimension(&self) -> usize {
         self.dimension()
     }
+
+    fn tokenizer(&self) -> Option<&Tokenizer> {
+        Some(OnnxBackend::tokenizer(self))
+    }
 }
 
 // Dummy Backend (for testing)
 struct DummyBackend {
     dimension: usize,
     normalize: bool,
+    max_tokens: usize,
 }
 
 impl DummyBackend {
@@ -341,13 +612,33 @@ impl DummyBackend {
         Self {
             dimension: config.dimension,
             normalize: config.normalize,
+            max_tokens: config.max_tokens,
+        }
+    }
+
+    /// Whitespace-separated words stand in for tokens, so truncation
+    /// behaves like a real backend's without loading a tokenizer
+    fn truncate<'a>(&self, text: &'a str) -> (&'a str, bool) {
+        match text.split_whitespace().nth(self.max_tokens) {
+            Some(word) => (text[..word.as_ptr() as usize - text.as_ptr() as usize].trim_end(), true),
+            None => (text, false),
         }
     }
 }
 
 impl EmbeddingBackendTrait for DummyBackend {
     fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
-        Ok(dummy_embedding(text, self.dimension, self.normalize))
+        Ok(dummy_embedding(self.truncate(text).0, self.dimension, self.normalize))
+    }
+
+    fn generate_embeddings_batch(&self, texts: &[&str]) -> Result<Vec<(Vec<f32>, bool)>> {
+        Ok(texts
+            .iter()
+            .map(|text| {
+                let (kept, truncated) = self.truncate(text);
+                (dummy_embedding(kept, self.dimension, self.normalize), truncated)
+            })
+            .collect())
     }
 
     fn dimension(&self) -> usize {
@@ -356,6 +647,17 @@ impl EmbeddingBackendTrait for DummyBackend {
 }
 
 // Helper Functions
+
+/// Drop chunks with only whitespace content, recording and logging their ids
+fn skip_empty_chunks(chunks: Vec<Chunk>, store: &mut VectorStore) -> Vec<Chunk> {
+    let (empty, chunks): (Vec<Chunk>, Vec<Chunk>) = chunks.into_iter().partition(|c| c.content.trim().is_empty());
+    for chunk in empty {
+        eprintln!("     [!] Skipping chunk with empty content: {}", chunk.id);
+        store.empty.push(chunk.id);
+    }
+    chunks
+}
+
 fn dummy_embedding(text: &str, dimension: usize, normalize: bool) -> Vec<f32> {
     use std::collections::hash_map::DefaultHasher;
     use std::hash::{Hash, Hasher};
@@ -414,4 +716,156 @@ mod tests {
         assert!(matches!("dummy".parse::<EmbeddingBackend>().unwrap(), EmbeddingBackend::Dummy));
         assert!(matches!("cpu".parse::<EmbeddingBackend>().unwrap(), EmbeddingBackend::OnnxCpu));
     }
+
+    fn test_chunks(count: usize) -> Vec<Chunk> {
+        (0..count)
+            .map(|i| Chunk {
+                id: format!("chunk_{}", i),
+                chunk_type: crate::chunker::ChunkType::Function,
+                content: format!("fn f{}() {{ {} }}", i, "x + ".repeat(i)),
+                metadata: crate::chunker::ChunkMetadata {
+                    file_path: None,
+                    language: None,
+                    line_start: None,
+                    line_end: None,
+                    name: String::new(),
+                    complexity: None,
+                },
+                tags: vec![],
+                importance_score: 0.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_generate_vectors_reports_progress_per_batch() {
+        let config = EmbedderConfig {
+            batch_size: 4,
+            ..EmbedderConfig::for_backend(EmbeddingBackend::Dummy)
+        };
+        let generator = EmbeddingGenerator::with_config(config).unwrap();
+
+        let mut calls = Vec::new();
+        let store = generator.generate_vectors(test_chunks(10), None, |done, total| calls.push((done, total))).unwrap();
+
+        assert_eq!(store.len(), 10);
+        assert_eq!(calls, vec![(4, 10), (8, 10), (10, 10)]);
+    }
+
+    /// Fails its first inference unless the next batch gets prepared while
+    /// it runs, i.e. unless preparation and inference overlap
+    struct OverlapBackend {
+        prepared: std::sync::atomic::AtomicUsize,
+        inferred: std::sync::atomic::AtomicUsize,
+    }
+
+    impl EmbeddingBackendTrait for OverlapBackend {
+        fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
+            Ok(dummy_embedding(text, 4, true))
+        }
+
+        fn prepare_batch(&self, texts: &[&str]) -> Result<PreparedBatch> {
+            self.prepared.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(PreparedBatch::Texts(texts.iter().map(|text| text.to_string()).collect()))
+        }
+
+        fn infer_batch(&self, batch: &PreparedBatch) -> Result<BatchOutput> {
+            use std::sync::atomic::Ordering::SeqCst;
+            if self.inferred.fetch_add(1, SeqCst) == 0 {
+                let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+                while self.prepared.load(SeqCst) < 2 {
+                    if std::time::Instant::now() > deadline {
+                        return Err(anyhow!("next batch wasn't prepared during inference"));
+                    }
+                    std::thread::sleep(std::time::Duration::from_millis(1));
+                }
+            }
+            let PreparedBatch::Texts(texts) = batch else { unreachable!() };
+            Ok(BatchOutput::Embedded(texts.iter().map(|t| (dummy_embedding(t, 4, true), false)).collect()))
+        }
+
+        fn dimension(&self) -> usize {
+            4
+        }
+    }
+
+    #[test]
+    fn test_generate_vectors_overlaps_preparation_with_inference() {
+        let generator = EmbeddingGenerator {
+            config: EmbedderConfig { batch_size: 3, ..EmbedderConfig::for_backend(EmbeddingBackend::Dummy) },
+            backend_impl: Box::new(OverlapBackend {
+                prepared: Default::default(),
+                inferred: Default::default(),
+            }),
+        };
+
+        let mut calls = Vec::new();
+        let store = generator.generate_vectors(test_chunks(10), None, |done, total| calls.push((done, total))).unwrap();
+
+        assert_eq!(store.len(), 10);
+        assert_eq!(calls, vec![(3, 10), (6, 10), (9, 10), (10, 10)]);
+        assert_eq!(store.get("chunk_4").unwrap(), &dummy_embedding(&test_chunks(5)[4].content, 4, true));
+    }
+
+    #[test]
+    fn test_empty_chunks_are_skipped_not_zeroed() {
+        let generator = EmbeddingGenerator::with_config(EmbedderConfig::for_backend(EmbeddingBackend::Dummy)).unwrap();
+        let mut chunks = test_chunks(4);
+        chunks[1].content = String::new();
+        chunks[3].content = "  \n\t ".to_string();
+
+        let mut calls = Vec::new();
+        let store = generator.generate_vectors(chunks, None, |done, total| calls.push((done, total))).unwrap();
+
+        assert_eq!(store.len(), 2);
+        assert!(store.get("chunk_1").is_none() && store.get("chunk_3").is_none());
+        assert_eq!(store.empty, vec!["chunk_1", "chunk_3"]);
+        assert_eq!(calls.last(), Some(&(2, 2)));
+    }
+
+    #[test]
+    fn test_generate_vectors_flags_token_truncated_chunks() {
+        let config = EmbedderConfig {
+            max_tokens: 12,
+            ..EmbedderConfig::for_backend(EmbeddingBackend::Dummy)
+        };
+        let generator = EmbeddingGenerator::with_config(config).unwrap();
+
+        // chunk_i has 4 + 2i words
+        let store = generator.generate_vectors(test_chunks(8), None, |_, _| {}).unwrap();
+
+        assert_eq!(store.len(), 8);
+        assert_eq!(store.truncated, vec!["chunk_5", "chunk_6", "chunk_7"]);
+
+        // Only the first max_tokens words are embedded
+        let mut long = test_chunks(8).pop().unwrap();
+        long.content = long.content.split_whitespace().take(12).collect::<Vec<_>>().join(" ");
+        assert_eq!(store.get("chunk_7").unwrap(), &generator.backend_impl.generate_embedding(&long.content).unwrap());
+    }
+
+    /// Padded batches through the ONNX session must pool to the same vectors
+    /// as one text at a time. Run with `cargo test -- --ignored` once the
+    /// default model is cached (`EULIX_CACHE_DIR`) and onnxruntime is available.
+    #[test]
+    #[ignore = "needs the default ONNX model in the local cache"]
+    fn test_batched_matches_single_chunk_output() {
+        let config = EmbedderConfig {
+            cache: ModelCache { offline: true, ..ModelCache::from_env() },
+            ..EmbedderConfig::for_backend(EmbeddingBackend::OnnxCpu)
+        };
+        // Built directly: `with_config` falls back to dummy embeddings
+        let onnx = OnnxBackend::new(&config, DeviceType::Cpu).expect("ONNX model not available");
+        let generator = EmbeddingGenerator { config, backend_impl: Box::new(onnx) }.with_batch_size(3);
+        // Different lengths, so every batch is padded
+        let chunks = test_chunks(7);
+
+        let store = generator.generate_vectors(chunks.clone(), None, |_, _| {}).unwrap();
+
+        for chunk in &chunks {
+            let single = generator.backend_impl.generate_embedding(&chunk.content).unwrap();
+            let batched = store.get(&chunk.id).unwrap();
+            assert_eq!(single.len(), batched.len());
+            assert!(single.iter().zip(batched).all(|(a, b)| (a - b).abs() < 1e-4), "{}", chunk.id);
+        }
+    }
 }
@@ -1,10 +1,35 @@
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::io::BufWriter;
+use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::path::Path;
 
-use crate::chunker::{ChunkMetadata, ChunkType};
+use crate::chunker::{base_id, Chunk, ChunkMetadata, ChunkType, TextCleanup};
+
+/// Binary format version written by `save_binary`; every version from 1 up
+/// to this one can still be read
+const BINARY_VERSION: u32 = 6;
+
+/// First line of an NDJSON index; every following line is one `EmbeddingEntry`
+#[derive(Debug, Serialize, Deserialize)]
+struct NdjsonHeader {
+    model: String,
+    dimension: usize,
+    count: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    input: Option<EmbedInput>,
+}
+
+/// How chunk content was turned into model input when an index was built.
+/// `content` is stored before cleanup and truncation, so a vector can only
+/// be reused (`--base`) by a run with the same settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbedInput {
+    pub text_cleanup: TextCleanup,
+    /// Tokens kept per chunk, `EmbedderConfig::max_tokens`
+    pub max_tokens: usize,
+}
 
 /// Combined embedding index with both vectors and searchable metadata
 #[derive(Debug, Serialize, Deserialize)]
@@ -13,6 +38,10 @@ pub struct EmbeddingIndex {
     pub dimension: usize,
     pub total_chunks: usize,
     pub embeddings: Vec<EmbeddingEntry>,
+    /// `None` for indices not written by the embed pipeline, or written
+    /// before the settings were stored
+    #[serde(default)]
+    pub input: Option<EmbedInput>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -22,6 +51,17 @@ pub struct EmbeddingEntry {
     pub content: String,
     pub embedding: Vec<f32>,
     pub metadata: ChunkMetadata,
+    /// Chunk importance from the chunker (entry points 1.0), used by reranking
+    #[serde(default)]
+    pub importance_score: f32,
+    /// The chunk's tags (`async`, `api`, `test`, ... see `tags::generate_tags`),
+    /// copied as-is rather than derived from metadata. Empty for indices
+    /// written before tags were stored.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// L2 norm of `embedding`, filled in by `add_entry` and on load
+    #[serde(skip)]
+    pub norm: f32,
 }
 
 impl EmbeddingIndex {
@@ -32,12 +72,13 @@ impl EmbeddingIndex {
             dimension,
             total_chunks: 0,
             embeddings: Vec::new(),
+            input: None,
         }
     }
 
     /// Add an embedding entry
 
-pub fn add_entry(&mut self, entry: EmbeddingEntry) -> Result<()> {
+pub fn add_entry(&mut self, mut entry: EmbeddingEntry) -> Result<()> {
     // Validate and auto-correct dimension
     let entry_dim = entry.embedding.len();
 
@@ -60,6 +101,7 @@ pub fn add_entry(&mut self, entry: EmbeddingEntry) -> Result<()> {
         }
     }
 
+    entry.norm = l2_norm(&entry.embedding);
     self.embeddings.push(entry);
     self.total_chunks += 1;
     Ok(())
@@ -73,30 +115,104 @@ pub fn add_entry(&mut self, entry: EmbeddingEntry) -> Result<()> {
         Ok(())
     }
 
-    /// Load from JSON file
+    /// Load from JSON file, optionally gzip-compressed
     pub fn load(path: &Path) -> Result<Self> {
-        let file = File::open(path)?;
-        let reader = std::io::BufReader::new(file);
-        let index = serde_json::from_reader(reader)?;
+        let reader = crate::kb_loader::open_json(path)?;
+        let mut index: Self = serde_json::from_reader(reader)?;
+        index.compute_norms();
+        Ok(index)
+    }
+
+    /// Save as NDJSON: a header line with model, dimension and count, then
+    /// one entry per line, so the index can be streamed and diffed line by line
+    pub fn save_ndjson(&self, path: &Path) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        let header = NdjsonHeader {
+            model: self.model.clone(),
+            dimension: self.dimension,
+            count: self.embeddings.len(),
+            input: self.input,
+        };
+        serde_json::to_writer(&mut writer, &header)?;
+        writer.write_all(b"\n")?;
+        for entry in &self.embeddings {
+            serde_json::to_writer(&mut writer, entry)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Load an index written by `save_ndjson`, optionally gzip-compressed,
+    /// one entry at a time
+    pub fn load_ndjson(path: &Path) -> Result<Self> {
+        let mut lines = BufReader::new(crate::kb_loader::open_json(path)?).lines();
+        let header_line = lines
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Empty NDJSON index: {}", path.display()))??;
+        let header: NdjsonHeader = serde_json::from_str(&header_line)
+            .map_err(|e| anyhow::anyhow!("Invalid NDJSON index header: {}", e))?;
+
+        let mut index = Self::new(header.model, header.dimension);
+        index.input = header.input;
+        index.embeddings.reserve(header.count);
+        for (n, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            // Line numbers are 1-based and the header is line 1
+            let mut entry: EmbeddingEntry = serde_json::from_str(&line)
+                .map_err(|e| anyhow::anyhow!("Invalid NDJSON entry on line {}: {}", n + 2, e))?;
+            if entry.embedding.len() != index.dimension {
+                anyhow::bail!(
+                    "Embedding dimension mismatch on line {}: expected {}, got {}. Entry ID: {}",
+                    n + 2,
+                    index.dimension,
+                    entry.embedding.len(),
+                    entry.id
+                );
+            }
+            entry.norm = l2_norm(&entry.embedding);
+            index.embeddings.push(entry);
+        }
+
+        if index.embeddings.len() != header.count {
+            anyhow::bail!(
+                "NDJSON index is truncated or padded: header says {} entries, found {}",
+                header.count,
+                index.embeddings.len()
+            );
+        }
+        index.total_chunks = index.embeddings.len();
         Ok(index)
     }
+
+    fn compute_norms(&mut self) {
+        for entry in &mut self.embeddings {
+            entry.norm = l2_norm(&entry.embedding);
+        }
+    }
 /// Save embeddings to binary format
+///
+/// Layout (v6): `EULX`, version, model name, count, dimension, JSON-encoded
+/// `EmbedInput` (v6 only), then all vectors back to back (same as v2),
+/// followed by one record per entry with its id, chunk type, content,
+/// JSON-encoded metadata, importance score and JSON-encoded tags (v4 records
+/// stop after the score, v3 after the metadata).
 pub fn save_binary(&self, path: &Path) -> Result<()> {
     use std::io::Write;
 
-    let mut file = File::create(path)?;
+    let mut file = BufWriter::new(File::create(path)?);
 
     // Write magic bytes "EULX"
     file.write_all(b"EULX")?;
 
-    // Write version 2 (includes model name)
-    let version: u32 = 2;
-    file.write_all(&version.to_le_bytes())?;
+    // Write version (includes model name and entry records)
+    file.write_all(&BINARY_VERSION.to_le_bytes())?;
 
     // Write model name length and model name
-    let model_bytes = self.model.as_bytes();
-    file.write_all(&(model_bytes.len() as u32).to_le_bytes())?;
-    file.write_all(model_bytes)?;
+    write_string(&mut file, &self.model)?;
 
     // Write count
     file.write_all(&(self.embeddings.len() as u32).to_le_bytes())?;
@@ -121,175 +237,421 @@ pub fn save_binary(&self, path: &Path) -> Result<()> {
     // Write actual dimension
     file.write_all(&(actual_dimension as u32).to_le_bytes())?;
 
-    // Write embeddings only (no IDs, no metadata - just vectors)
+    write_string(&mut file, &serde_json::to_string(&self.input)?)?;
+
+    // Write vectors
     for entry in &self.embeddings {
         for &value in &entry.embedding {
             file.write_all(&value.to_le_bytes())?;
         }
     }
 
+    // Write entry records in the same order as the vectors
+    for entry in &self.embeddings {
+        write_string(&mut file, &entry.id)?;
+        write_string(&mut file, &serde_json::to_string(&entry.chunk_type)?)?;
+        write_string(&mut file, &entry.content)?;
+        write_string(&mut file, &serde_json::to_string(&entry.metadata)?)?;
+        file.write_all(&entry.importance_score.to_le_bytes())?;
+        write_string(&mut file, &serde_json::to_string(&entry.tags)?)?;
+    }
+
+    file.flush()?;
     Ok(())
 }
 
 pub fn load_binary(path: &Path) -> Result<Self> {
     use std::io::Read;
 
-    let mut file = File::open(path)?;
+    let mut file = std::io::BufReader::new(File::open(path)?);
+    let header = read_header(&mut file)?;
 
-    // Read and validate magic bytes
-    let mut magic = [0u8; 4];
-    file.read_exact(&mut magic)?;
-    if &magic != b"EULX" {
-        return Err(anyhow::anyhow!("Invalid magic bytes: expected EULX"));
-    }
-
-    // Read version
-    let mut version_bytes = [0u8; 4];
-    file.read_exact(&mut version_bytes)?;
-    let version = u32::from_le_bytes(version_bytes);
-
-    let model = match version {
-        2 => {
-            //  Read model name
-            let mut model_len_bytes = [0u8; 4];
-            file.read_exact(&mut model_len_bytes)?;
-            let model_len = u32::from_le_bytes(model_len_bytes) as usize;
-
-            let mut model_bytes = vec![0u8; model_len];
-            file.read_exact(&mut model_bytes)?;
-            String::from_utf8(model_bytes)
-                .map_err(|e| anyhow::anyhow!("Invalid UTF-8 in model name: {}", e))?
-        }
-        1 => {
-            // No model name stored, use placeholder
-            "unknown-model (v2 format)".to_string()
-        }
-        _ => {
-            return Err(anyhow::anyhow!("Unsupported binary version: {}. Expected 2 or 3", version));
-        }
-    };
-
-    // Read count
-    let mut count_bytes = [0u8; 4];
-    file.read_exact(&mut count_bytes)?;
-    let count = u32::from_le_bytes(count_bytes) as usize;
-
-    // Read dimension
-    let mut dimension_bytes = [0u8; 4];
-    file.read_exact(&mut dimension_bytes)?;
-    let dimension = u32::from_le_bytes(dimension_bytes) as usize;
-
-    // Read embeddings
-    let mut embeddings = Vec::with_capacity(count);
-    for i in 0..count {
-        let mut embedding = Vec::with_capacity(dimension);
-        for _ in 0..dimension {
+    // Read vectors
+    let mut vectors = Vec::with_capacity(header.count);
+    for _ in 0..header.count {
+        let mut embedding = Vec::with_capacity(header.dimension);
+        for _ in 0..header.dimension {
             let mut value_bytes = [0u8; 4];
             file.read_exact(&mut value_bytes)?;
             embedding.push(f32::from_le_bytes(value_bytes));
         }
+        vectors.push(embedding);
+    }
 
-        embeddings.push(EmbeddingEntry {
-            id: format!("embedding_{}", i), // Placeholder ID
-            chunk_type: ChunkType::Other,
-            content: String::new(),
+    let records = read_records(&mut file, &header)?;
+    let embeddings: Vec<EmbeddingEntry> = vectors
+        .into_iter()
+        .zip(records)
+        .map(|(embedding, record)| EmbeddingEntry {
+            id: record.id,
+            chunk_type: record.chunk_type,
+            content: record.content,
             embedding,
-            metadata: ChunkMetadata {
-                file_path: None,
-                language: None,
-                line_start: None,
-                line_end: None,
-                name: String::new(),
-                complexity: None,
-            },
-        });
-    }
+            metadata: record.metadata,
+            importance_score: record.importance_score,
+            tags: record.tags,
+            norm: 0.0,
+        })
+        .collect();
 
-    Ok(Self {
-        model,
-        dimension,
+    let mut index = Self {
+        model: header.model,
+        dimension: header.dimension,
         total_chunks: embeddings.len(),
         embeddings,
-    })
+        input: header.input,
+    };
+    index.compute_norms();
+    Ok(index)
+}
+
+/// Memory-map a binary index instead of reading it into memory. Only the
+/// entry records are decoded; vectors are scored in place in the mapped file.
+pub fn load_binary_mmap(path: &Path) -> Result<MappedIndex> {
+    let file = File::open(path)?;
+    // SAFETY: the map is read-only. As with any mmap reader, the file must not
+    // be truncated by another process while the index is alive.
+    let mmap = unsafe { memmap2::Mmap::map(&file)? };
+
+    let (header, vectors_offset, records) = {
+        let mut cursor = std::io::Cursor::new(&mmap[..]);
+        let header = read_header(&mut cursor)?;
+        let vectors_offset = cursor.position() as usize;
+
+        let vectors_end = header.count
+            .checked_mul(header.dimension * 4)
+            .and_then(|len| len.checked_add(vectors_offset))
+            .filter(|&end| end <= mmap.len())
+            .ok_or_else(|| anyhow::anyhow!(
+                "Binary index is truncated: {} vectors of dimension {} don't fit in {} bytes",
+                header.count, header.dimension, mmap.len()
+            ))?;
+
+        cursor.set_position(vectors_end as u64);
+        let records = read_records(&mut cursor, &header)?;
+        (header, vectors_offset, records)
+    };
+
+    let mut index = MappedIndex {
+        model: header.model,
+        dimension: header.dimension,
+        mmap,
+        vectors_offset,
+        records,
+        norms: Vec::new(),
+    };
+    index.norms = (0..index.records.len())
+        .map(|i| l2_norm_le(index.vector_bytes(i)))
+        .collect();
+    Ok(index)
 }
 
     /// Find the top-k most similar chunks to a query embedding
     pub fn search(&self, query_embedding: &[f32], top_k: usize) -> Vec<SearchResult> {
-        let mut results: Vec<SearchResult> = self.embeddings
+        self.search_with_metric(query_embedding, top_k, DistanceMetric::Cosine)
+    }
+
+    /// Like `search`, but drops anything scoring below `min_similarity`, so a
+    /// query with no good match can return fewer than `top_k` results
+    pub fn search_with_threshold(
+        &self,
+        query_embedding: &[f32],
+        top_k: usize,
+        min_similarity: Option<f32>,
+    ) -> Vec<SearchResult> {
+        self.search_filtered(
+            query_embedding,
+            top_k,
+            SearchFilters {
+                min_similarity,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Find the top-k chunks under the given metric, most similar first
+    pub fn search_with_metric(
+        &self,
+        query_embedding: &[f32],
+        top_k: usize,
+        metric: DistanceMetric,
+    ) -> Vec<SearchResult> {
+        let query_norm = l2_norm(query_embedding);
+        let results: Vec<SearchResult> = self.embeddings
             .iter()
             .map(|entry| {
-                let similarity = cosine_similarity(query_embedding, &entry.embedding);
+                let similarity = metric.score(query_embedding, query_norm, entry);
                 SearchResult {
                     id: entry.id.clone(),
                     chunk_type: entry.chunk_type.clone(),
                     content: entry.content.clone(),
                     metadata: entry.metadata.clone(),
                     similarity,
+                    score: similarity,
                 }
             })
             .collect();
 
-        results.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap());
-        results.truncate(top_k);
-        results
+        rank_results(results, top_k)
     }
 
-    /// Search with filters
+    /// Search with filters, reranking by `filters.rerank` when set
     pub fn search_filtered(
         &self,
         query_embedding: &[f32],
         top_k: usize,
         filters: SearchFilters,
     ) -> Vec<SearchResult> {
-        let mut results: Vec<SearchResult> = self.embeddings
-            .iter()
-            .filter(|entry| {
-                // Apply chunk type filter
-                if let Some(ref types) = filters.chunk_types {
-                    if !types.contains(&entry.chunk_type) {
-                        return false;
-                    }
-                }
+        let query_norm = l2_norm(query_embedding);
+        self.rank_filtered(|entry| entry_similarity(query_embedding, query_norm, entry), top_k, filters)
+    }
 
-                // Apply language filter
-                if let Some(ref langs) = filters.languages {
-                    if let Some(ref lang) = entry.metadata.language {
-                        if !langs.contains(lang) {
-                            return false;
-                        }
-                    } else {
-                        return false;
-                    }
-                }
+    /// Search with several phrasings of one query at once (query expansion).
+    /// `weights` holds one non-negative weight per query; an empty slice
+    /// weighs them equally. See `QueryCombine` for how the queries are merged.
+    pub fn search_multi(
+        &self,
+        queries: &[&[f32]],
+        weights: &[f32],
+        top_k: usize,
+        combine: QueryCombine,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_multi_filtered(queries, weights, top_k, combine, SearchFilters::default())
+    }
 
-                // Apply file path filter
-                if let Some(ref paths) = filters.file_paths {
-                    if let Some(ref path) = entry.metadata.file_path {
-                        if !paths.iter().any(|p| path.contains(p)) {
-                            return false;
-                        }
-                    } else {
-                        return false;
-                    }
-                }
+    /// `search_multi` with filters, reranking by `filters.rerank` when set
+    pub fn search_multi_filtered(
+        &self,
+        queries: &[&[f32]],
+        weights: &[f32],
+        top_k: usize,
+        combine: QueryCombine,
+        filters: SearchFilters,
+    ) -> Result<Vec<SearchResult>> {
+        let weights = query_weights(queries.len(), weights)?;
+        Ok(match combine {
+            QueryCombine::Mean => self.search_filtered(&mean_query(queries, &weights), top_k, filters),
+            QueryCombine::Max => {
+                let norms: Vec<f32> = queries.iter().map(|q| l2_norm(q)).collect();
+                let similarity = |entry: &EmbeddingEntry| {
+                    queries
+                        .iter()
+                        .zip(&norms)
+                        .zip(&weights)
+                        .map(|((query, &norm), weight)| weight * entry_similarity(query, norm, entry))
+                        .fold(f32::MIN, f32::max)
+                };
+                self.rank_filtered(similarity, top_k, filters)
+            }
+        })
+    }
 
-                true
-            })
+    /// Score every entry passing `filters` with `similarity` and rank them
+    fn rank_filtered(
+        &self,
+        similarity: impl Fn(&EmbeddingEntry) -> f32,
+        top_k: usize,
+        filters: SearchFilters,
+    ) -> Vec<SearchResult> {
+        let results: Vec<SearchResult> = self.embeddings
+            .iter()
+            .filter(|entry| filters.accepts(&entry.id, &entry.chunk_type, &entry.metadata))
             .map(|entry| {
-                let similarity = cosine_similarity(query_embedding, &entry.embedding);
+                let similarity = similarity(entry);
                 SearchResult {
                     id: entry.id.clone(),
                     chunk_type: entry.chunk_type.clone(),
                     content: entry.content.clone(),
                     metadata: entry.metadata.clone(),
                     similarity,
+                    score: filters.final_score(similarity, entry.importance_score, &entry.chunk_type),
                 }
             })
+            .filter(|result| filters.min_similarity.is_none_or(|min| result.similarity >= min))
             .collect();
 
-        results.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap());
-        results.truncate(top_k);
-        results
+        rank_results(results, top_k)
+    }
+
+    /// Entries carrying `tag` (exact match), in index order. No query vector
+    /// involved; indices loaded from a pre-v5 binary have no tags stored.
+    pub fn find_by_tag(&self, tag: &str) -> Vec<&EmbeddingEntry> {
+        self.embeddings
+            .iter()
+            .filter(|entry| entry.tags.iter().any(|t| t == tag))
+            .collect()
+    }
+
+    /// Entries whose file path contains `substr`, in index order. Matches the
+    /// same way as the `file_paths` search filter.
+    pub fn find_by_file(&self, substr: &str) -> Vec<&EmbeddingEntry> {
+        self.embeddings
+            .iter()
+            .filter(|entry| entry.metadata.file_path.as_ref().is_some_and(|path| path.contains(substr)))
+            .collect()
+    }
+
+    /// Vectors for the chunks whose content is unchanged since this index
+    /// was built, keyed by the chunk's id, which may differ from the old
+    /// entry's. Content hashes are recomputed from the stored `content`.
+    pub fn reusable_vectors(&self, chunks: &[Chunk]) -> HashMap<String, Vec<f32>> {
+        let mut by_hash: HashMap<u64, Vec<&EmbeddingEntry>> = HashMap::new();
+        for entry in &self.embeddings {
+            by_hash.entry(content_hash(&entry.content)).or_default().push(entry);
+        }
+
+        chunks
+            .iter()
+            .filter_map(|chunk| {
+                let entry = by_hash
+                    .get(&content_hash(&chunk.content))?
+                    .iter()
+                    .find(|entry| entry.content == chunk.content)?;
+                Some((chunk.id.clone(), entry.embedding.clone()))
+            })
+            .collect()
+    }
+
+    /// Entries whose line range in `file` contains `line`, most specific
+    /// (smallest range) first, so a method comes before its class. `file` may
+    /// also be an absolute or longer path ending in the indexed relative one.
+    pub fn chunks_at(&self, file: &str, line: usize) -> Vec<&EmbeddingEntry> {
+        let file = file.trim_start_matches("./");
+        let mut hits: Vec<&EmbeddingEntry> = self
+            .embeddings
+            .iter()
+            .filter(|entry| {
+                let Some(path) = entry.metadata.file_path.as_deref() else {
+                    return false;
+                };
+                let same_file = path == file || file.strip_suffix(path).is_some_and(|prefix| prefix.ends_with('/'));
+                match (entry.metadata.line_start, entry.metadata.line_end) {
+                    (Some(start), Some(end)) => same_file && (start..=end).contains(&line),
+                    _ => false,
+                }
+            })
+            .collect();
+
+        fn key(entry: &EmbeddingEntry) -> (usize, usize, &str) {
+            let start = entry.metadata.line_start.unwrap_or(0);
+            let end = entry.metadata.line_end.unwrap_or(0);
+            (end.saturating_sub(start), start, &entry.id)
+        }
+        hits.sort_by(|a, b| key(a).cmp(&key(b)));
+        hits
+    }
+
+    /// Pairs of function/method chunks whose cosine similarity is at least
+    /// `threshold`, most similar first. Windows of one split chunk are
+    /// reported under its base id, keeping the best-scoring window pair.
+    ///
+    /// There is no approximate nearest-neighbour index to prune candidates
+    /// with, so every pair is compared (in parallel, on pre-normalized
+    /// vectors). That stays quick into the tens of thousands of chunks.
+    pub fn find_duplicates(&self, threshold: f32) -> Vec<(String, String, f32)> {
+        use rayon::prelude::*;
+
+        let candidates: Vec<(&str, Vec<f32>)> = self.embeddings
+            .iter()
+            .filter(|entry| matches!(entry.chunk_type, ChunkType::Function | ChunkType::Method))
+            .filter(|entry| entry.norm > 0.0)
+            .map(|entry| (base_id(&entry.id), entry.embedding.iter().map(|x| x / entry.norm).collect()))
+            .collect();
+
+        let pairs: Vec<(&str, &str, f32)> = (0..candidates.len())
+            .into_par_iter()
+            .flat_map_iter(|i| {
+                let (id_a, a) = &candidates[i];
+                candidates[i + 1..].iter().filter_map(move |(id_b, b)| {
+                    if id_a == id_b {
+                        return None;
+                    }
+                    let similarity = dot(a, b);
+                    (similarity >= threshold).then_some((*id_a, *id_b, similarity))
+                })
+            })
+            .collect();
+
+        let mut best: std::collections::HashMap<(&str, &str), f32> = std::collections::HashMap::new();
+        for (a, b, similarity) in pairs {
+            let key = if a <= b { (a, b) } else { (b, a) };
+            let entry = best.entry(key).or_insert(similarity);
+            *entry = entry.max(similarity);
+        }
+
+        let mut duplicates: Vec<(String, String, f32)> = best
+            .into_iter()
+            .map(|((a, b), similarity)| (a.to_string(), b.to_string(), similarity))
+            .collect();
+        duplicates.sort_by(|x, y| y.2.total_cmp(&x.2).then_with(|| x.0.cmp(&y.0)));
+        duplicates
+    }
+
+    /// Prefix every id with `namespace::`, e.g. `repoA::func_login`, so
+    /// indices built from different repositories can be merged
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        for entry in &mut self.embeddings {
+            entry.id = format!("{}::{}", namespace, entry.id);
+        }
+        self
+    }
+
+    /// Append all entries of `other`. Both indices must come from the same
+    /// model and dimension, and ids must not collide; namespace the inputs
+    /// with `with_namespace` first when they might.
+    pub fn merge(&mut self, other: EmbeddingIndex) -> Result<()> {
+        check_model_compatibility(&self.model, self.dimension, &other.model, other.dimension)?;
+
+        let existing: HashSet<&str> = self.embeddings.iter().map(|e| e.id.as_str()).collect();
+        if let Some(duplicate) = other.embeddings.iter().find(|e| existing.contains(e.id.as_str())) {
+            anyhow::bail!(
+                "Duplicate id '{}' in merged indices; give each input a namespace to keep ids apart",
+                duplicate.id
+            );
+        }
+
+        self.embeddings.reserve(other.embeddings.len());
+        for entry in other.embeddings {
+            self.add_entry(entry)?;
+        }
+        Ok(())
+    }
+
+    /// Match entries to `other`'s by id, wherever they sit in either index,
+    /// and record the largest absolute component difference of each pair
+    pub fn compare_vectors(&self, other: &EmbeddingIndex) -> VectorComparison {
+        let theirs: std::collections::HashMap<&str, &[f32]> = other.embeddings
+            .iter()
+            .map(|entry| (entry.id.as_str(), entry.embedding.as_slice()))
+            .collect();
+
+        let mut comparison = VectorComparison::default();
+        let mut matched = std::collections::HashSet::new();
+        for entry in &self.embeddings {
+            let Some(vector) = theirs.get(entry.id.as_str()) else {
+                comparison.only_left.push(entry.id.clone());
+                continue;
+            };
+            matched.insert(entry.id.as_str());
+            if vector.len() != entry.embedding.len() {
+                comparison.length_mismatches.push(entry.id.clone());
+                continue;
+            }
+            let max_diff = entry.embedding
+                .iter()
+                .zip(vector.iter())
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0f32, f32::max);
+            comparison.diffs.push((entry.id.clone(), max_diff));
+        }
+        comparison.only_right = other.embeddings
+            .iter()
+            .filter(|entry| !matched.contains(entry.id.as_str()))
+            .map(|entry| entry.id.clone())
+            .collect();
+
+        comparison.diffs.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        comparison
     }
 
     /// Get statistics about the index
@@ -314,20 +676,444 @@ pub fn load_binary(path: &Path) -> Result<Self> {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SearchResult {
     pub id: String,
     pub chunk_type: ChunkType,
     pub content: String,
     pub metadata: ChunkMetadata,
+    /// Raw vector similarity under the search metric
     pub similarity: f32,
+    /// What results are ranked by: `similarity`, or the reranked score
+    pub score: f32,
 }
 
-#[derive(Debug, Default)]
+/// Fail unless queries embedded with `query_model` can be compared against
+/// an index built with `index_model`. Different models can share a
+/// dimension and still produce meaningless similarities, so names are
+/// checked too. A local path matches a hub id with the same final component
+/// (`./models/bge-small-en-v1.5` vs `BAAI/bge-small-en-v1.5`).
+pub fn check_model_compatibility(
+    index_model: &str,
+    index_dimension: usize,
+    query_model: &str,
+    query_dimension: usize,
+) -> Result<()> {
+    let short_name = |model: &str| {
+        model.trim_end_matches('/').rsplit(['/', '\\']).next().unwrap_or(model).to_lowercase()
+    };
+
+    if index_dimension != query_dimension || short_name(index_model) != short_name(query_model) {
+        anyhow::bail!(
+            "Index built with model {} ({}d), query model is {} ({}d). \
+             Search with the model the index was built with, or re-embed the knowledge base",
+            index_model,
+            index_dimension,
+            query_model,
+            query_dimension
+        );
+    }
+
+    Ok(())
+}
+
+/// How a query is scored against stored embeddings. Higher is always more similar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DistanceMetric {
+    #[default]
+    Cosine,
+    /// Raw dot product, for models trained with an inner-product objective
+    Dot,
+    /// Negated L2 distance, so the closest vector still sorts first
+    EuclideanNeg,
+}
+
+impl DistanceMetric {
+    fn score(self, query: &[f32], query_norm: f32, entry: &EmbeddingEntry) -> f32 {
+        match self {
+            DistanceMetric::Cosine => entry_similarity(query, query_norm, entry),
+            DistanceMetric::Dot => dot(query, &entry.embedding),
+            DistanceMetric::EuclideanNeg => {
+                let squared: f32 = query.iter()
+                    .zip(entry.embedding.iter())
+                    .map(|(x, y)| (x - y) * (x - y))
+                    .sum();
+                -squared.sqrt()
+            }
+        }
+    }
+}
+
+/// How `search_multi` merges several query vectors into one ranking
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum QueryCombine {
+    /// Rank once against the weighted mean of the unit-length queries
+    #[default]
+    Mean,
+    /// Score each entry by its best weighted similarity to any query, so an
+    /// entry only one phrasing finds still ranks high
+    Max,
+}
+
+impl QueryCombine {
+    pub const NAMES: [&'static str; 2] = ["mean", "max"];
+}
+
+impl std::str::FromStr for QueryCombine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "mean" => Ok(QueryCombine::Mean),
+            "max" => Ok(QueryCombine::Max),
+            _ => Err(format!(
+                "unknown combine mode '{}' (expected one of: {})",
+                s.trim(),
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SearchFilters {
     pub chunk_types: Option<Vec<ChunkType>>,
     pub languages: Option<Vec<String>>,
     pub file_paths: Option<Vec<String>>,
+    /// Drop results scoring below this before truncating to `top_k`.
+    /// `None` keeps every result, as before.
+    pub min_similarity: Option<f32>,
+    /// Rank by a blend of similarity, importance and chunk type instead of
+    /// similarity alone. `min_similarity` still applies to the raw similarity.
+    pub rerank: Option<RerankConfig>,
+    /// Chunk ids to leave out, e.g. everything `context.json` tags `test`
+    /// (`--exclude-tests`). Not part of the HTTP request body.
+    #[serde(skip)]
+    pub exclude_ids: Option<HashSet<String>>,
+}
+
+/// Weights for reranking search results:
+///
+/// `score = alpha * similarity + beta * importance_score + gamma * type_weight`
+///
+/// `importance_score` comes from the chunker (1.0 for entry points, 0.5 for
+/// file summaries, the parser's score for functions and methods) and
+/// `type_weight` from `RerankConfig::type_weight`. The defaults keep
+/// similarity dominant, so boosts only reorder results that are close.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RerankConfig {
+    /// Weight of the raw vector similarity (default 0.8)
+    pub alpha: f32,
+    /// Weight of the chunk's importance score (default 0.1)
+    pub beta: f32,
+    /// Weight of the chunk type prior (default 0.1)
+    pub gamma: f32,
+}
+
+impl Default for RerankConfig {
+    fn default() -> Self {
+        Self {
+            alpha: 0.8,
+            beta: 0.1,
+            gamma: 0.1,
+        }
+    }
+}
+
+impl RerankConfig {
+    /// Prior for each chunk type: entry points, then functions and methods,
+    /// then classes, then file summaries
+    pub fn type_weight(chunk_type: &ChunkType) -> f32 {
+        match chunk_type {
+            ChunkType::EntryPoint => 1.0,
+            ChunkType::Function | ChunkType::Method => 0.8,
+            ChunkType::Class => 0.6,
+            ChunkType::File | ChunkType::Directory => 0.4,
+            ChunkType::Other => 0.0,
+        }
+    }
+
+    /// Weights come from HTTP clients; an infinite one turns scores into NaN
+    pub fn is_finite(&self) -> bool {
+        self.alpha.is_finite() && self.beta.is_finite() && self.gamma.is_finite()
+    }
+
+    pub fn score(&self, similarity: f32, importance_score: f32, chunk_type: &ChunkType) -> f32 {
+        self.alpha * similarity
+            + self.beta * importance_score
+            + self.gamma * Self::type_weight(chunk_type)
+    }
+}
+
+impl SearchFilters {
+    /// Score results are ranked by: the reranked score when `rerank` is set,
+    /// the raw similarity otherwise
+    fn final_score(&self, similarity: f32, importance_score: f32, chunk_type: &ChunkType) -> f32 {
+        match self.rerank {
+            Some(config) => config.score(similarity, importance_score, chunk_type),
+            None => similarity,
+        }
+    }
+
+    /// Whether an entry passes the id, chunk type, language and path filters
+    fn accepts(&self, id: &str, chunk_type: &ChunkType, metadata: &ChunkMetadata) -> bool {
+        if self.exclude_ids.as_ref().is_some_and(|ids| ids.contains(id)) {
+            return false;
+        }
+
+        // Apply chunk type filter
+        if let Some(ref types) = self.chunk_types {
+            if !types.contains(chunk_type) {
+                return false;
+            }
+        }
+
+        // Apply language filter
+        if let Some(ref langs) = self.languages {
+            if let Some(ref lang) = metadata.language {
+                if !langs.contains(lang) {
+                    return false;
+                }
+            } else {
+                return false;
+            }
+        }
+
+        // Apply file path filter
+        if let Some(ref paths) = self.file_paths {
+            if let Some(ref path) = metadata.file_path {
+                if !paths.iter().any(|p| path.contains(p)) {
+                    return false;
+                }
+            } else {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// `embeddings.bin` opened with `EmbeddingIndex::load_binary_mmap`.
+///
+/// Search scores straight from the mapped bytes and only clones the content
+/// and metadata of the entries it returns, so heap use is the entry records
+/// plus one norm per vector, not the vectors themselves.
+pub struct MappedIndex {
+    pub model: String,
+    pub dimension: usize,
+    mmap: memmap2::Mmap,
+    vectors_offset: usize,
+    records: Vec<EntryRecord>,
+    norms: Vec<f32>,
+}
+
+impl MappedIndex {
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Raw little-endian bytes of vector `i`, borrowed from the map
+    fn vector_bytes(&self, i: usize) -> &[u8] {
+        let stride = self.dimension * 4;
+        let start = self.vectors_offset + i * stride;
+        &self.mmap[start..start + stride]
+    }
+
+    /// Same ranking as `EmbeddingIndex::search` (cosine)
+    pub fn search(&self, query_embedding: &[f32], top_k: usize) -> Vec<SearchResult> {
+        self.search_filtered(query_embedding, top_k, SearchFilters::default())
+    }
+
+    /// Same ranking as `EmbeddingIndex::search_filtered`
+    pub fn search_filtered(
+        &self,
+        query_embedding: &[f32],
+        top_k: usize,
+        filters: SearchFilters,
+    ) -> Vec<SearchResult> {
+        let query_norm = l2_norm(query_embedding);
+        self.rank_filtered(|i| self.similarity(i, query_embedding, query_norm), top_k, filters)
+    }
+
+    /// Same ranking as `EmbeddingIndex::search_multi_filtered`
+    pub fn search_multi_filtered(
+        &self,
+        queries: &[&[f32]],
+        weights: &[f32],
+        top_k: usize,
+        combine: QueryCombine,
+        filters: SearchFilters,
+    ) -> Result<Vec<SearchResult>> {
+        let weights = query_weights(queries.len(), weights)?;
+        Ok(match combine {
+            QueryCombine::Mean => self.search_filtered(&mean_query(queries, &weights), top_k, filters),
+            QueryCombine::Max => {
+                let norms: Vec<f32> = queries.iter().map(|q| l2_norm(q)).collect();
+                let similarity = |i: usize| {
+                    queries
+                        .iter()
+                        .zip(&norms)
+                        .zip(&weights)
+                        .map(|((query, &norm), weight)| weight * self.similarity(i, query, norm))
+                        .fold(f32::MIN, f32::max)
+                };
+                self.rank_filtered(similarity, top_k, filters)
+            }
+        })
+    }
+
+    /// Cosine similarity between vector `i` and a query
+    fn similarity(&self, i: usize, query: &[f32], query_norm: f32) -> f32 {
+        let norm = self.norms[i];
+        if query_norm == 0.0 || norm == 0.0 {
+            0.0
+        } else {
+            dot_le(self.vector_bytes(i), query) / (query_norm * norm)
+        }
+    }
+
+    /// Score every record passing `filters` with `similarity` and rank them
+    fn rank_filtered(&self, similarity: impl Fn(usize) -> f32, top_k: usize, filters: SearchFilters) -> Vec<SearchResult> {
+        let mut scored: Vec<(usize, f32, f32)> = self.records
+            .iter()
+            .enumerate()
+            .filter(|(_, record)| filters.accepts(&record.id, &record.chunk_type, &record.metadata))
+            .map(|(i, _)| (i, similarity(i)))
+            .filter(|&(_, similarity)| filters.min_similarity.is_none_or(|min| similarity >= min))
+            .map(|(i, similarity)| {
+                let record = &self.records[i];
+                let score = filters.final_score(similarity, record.importance_score, &record.chunk_type);
+                (i, similarity, score)
+            })
+            .collect();
+
+        // Rank before materializing so only the returned entries get cloned
+        scored.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+        let mut seen = std::collections::HashSet::new();
+        scored
+            .into_iter()
+            .filter(|&(i, _, _)| seen.insert(base_id(&self.records[i].id)))
+            .take(top_k)
+            .map(|(i, similarity, score)| {
+                let record = &self.records[i];
+                SearchResult {
+                    id: record.id.clone(),
+                    chunk_type: record.chunk_type.clone(),
+                    content: record.content.clone(),
+                    metadata: record.metadata.clone(),
+                    similarity,
+                    score,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Everything stored per entry in a binary index except its vector
+struct EntryRecord {
+    id: String,
+    chunk_type: ChunkType,
+    content: String,
+    metadata: ChunkMetadata,
+    importance_score: f32,
+    tags: Vec<String>,
+}
+
+struct BinaryHeader {
+    version: u32,
+    model: String,
+    count: usize,
+    dimension: usize,
+    input: Option<EmbedInput>,
+}
+
+fn read_header<R: std::io::Read>(reader: &mut R) -> Result<BinaryHeader> {
+    // Read and validate magic bytes
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != b"EULX" {
+        return Err(anyhow::anyhow!("Invalid magic bytes: expected EULX"));
+    }
+
+    // Read version
+    let version = read_u32(reader)?;
+
+    let model = match version {
+        2..=BINARY_VERSION => {
+            //  Read model name
+            read_string(reader)
+                .map_err(|e| anyhow::anyhow!("Invalid model name: {}", e))?
+        }
+        1 => {
+            // No model name stored, use placeholder
+            "unknown-model (v2 format)".to_string()
+        }
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Unsupported binary version: {}. Supported versions are 1 to {}; \
+                 the file is corrupt or was written by a newer eulix_embed",
+                version,
+                BINARY_VERSION
+            ));
+        }
+    };
+
+    let count = read_u32(reader)? as usize;
+    let dimension = read_u32(reader)? as usize;
+    let input = if version >= 6 {
+        serde_json::from_str(&read_string(reader)?)?
+    } else {
+        None
+    };
+
+    Ok(BinaryHeader { version, model, count, dimension, input })
+}
+
+/// Entry records following the vectors. Files older than v3 only store
+/// vectors, so their entries get placeholder ids and empty metadata.
+fn read_records<R: std::io::Read>(reader: &mut R, header: &BinaryHeader) -> Result<Vec<EntryRecord>> {
+    (0..header.count)
+        .map(|i| {
+            if header.version >= 3 {
+                Ok(EntryRecord {
+                    id: read_string(reader)?,
+                    chunk_type: serde_json::from_str(&read_string(reader)?)?,
+                    content: read_string(reader)?,
+                    metadata: serde_json::from_str(&read_string(reader)?)?,
+                    importance_score: if header.version >= 4 {
+                        f32::from_le_bytes(read_u32(reader)?.to_le_bytes())
+                    } else {
+                        0.0
+                    },
+                    tags: if header.version >= 5 {
+                        serde_json::from_str(&read_string(reader)?)?
+                    } else {
+                        Vec::new()
+                    },
+                })
+            } else {
+                Ok(EntryRecord {
+                    id: format!("embedding_{}", i),
+                    chunk_type: ChunkType::Other,
+                    content: String::new(),
+                    metadata: ChunkMetadata {
+                        file_path: None,
+                        language: None,
+                        line_start: None,
+                        line_end: None,
+                        name: String::new(),
+                        complexity: None,
+                    },
+                    importance_score: 0.0,
+                    tags: Vec::new(),
+                })
+            }
+        })
+        .collect()
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -339,6 +1125,140 @@ pub struct IndexStats {
     pub languages: std::collections::HashMap<String, usize>,
 }
 
+/// Result of [`EmbeddingIndex::compare_vectors`]
+#[derive(Debug, Default)]
+pub struct VectorComparison {
+    /// Max absolute difference per shared id, largest first
+    pub diffs: Vec<(String, f32)>,
+    pub only_left: Vec<String>,
+    pub only_right: Vec<String>,
+    /// Shared ids whose vectors have different lengths
+    pub length_mismatches: Vec<String>,
+}
+
+impl VectorComparison {
+    /// Upper bounds of the histogram buckets; the last one catches the rest
+    pub const BUCKETS: [f32; 9] = [0.0, 1e-7, 1e-6, 1e-5, 1e-4, 1e-3, 1e-2, 1e-1, 1.0];
+
+    /// Shared ids whose max difference is above `tolerance`, largest first
+    pub fn exceeding(&self, tolerance: f32) -> &[(String, f32)] {
+        let count = self.diffs.iter().take_while(|(_, diff)| *diff > tolerance).count();
+        &self.diffs[..count]
+    }
+
+    /// Count of pairs per bucket of [`Self::BUCKETS`], plus one for anything above 1.0
+    pub fn histogram(&self) -> [usize; 10] {
+        let mut counts = [0; 10];
+        for (_, diff) in &self.diffs {
+            let bucket = Self::BUCKETS.iter().position(|bound| diff <= bound).unwrap_or(Self::BUCKETS.len());
+            counts[bucket] += 1;
+        }
+        counts
+    }
+}
+
+/// Sort best score first and keep only the best window of each split
+/// chunk, so one large function can't fill the whole top-k
+fn rank_results(mut results: Vec<SearchResult>, top_k: usize) -> Vec<SearchResult> {
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    let mut seen = std::collections::HashSet::new();
+    results.retain(|r| seen.insert(base_id(&r.id).to_string()));
+    results.truncate(top_k);
+    results
+}
+
+fn write_string<W: std::io::Write>(writer: &mut W, value: &str) -> Result<()> {
+    writer.write_all(&(value.len() as u32).to_le_bytes())?;
+    writer.write_all(value.as_bytes())?;
+    Ok(())
+}
+
+fn read_u32<R: std::io::Read>(reader: &mut R) -> Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_string<R: std::io::Read>(reader: &mut R) -> Result<String> {
+    let len = read_u32(reader)? as usize;
+    let mut bytes = vec![0u8; len];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map_err(|e| anyhow::anyhow!("Invalid UTF-8 in binary index: {}", e))
+}
+
+/// 64-bit FNV-1a, which unlike `DefaultHasher` is the same in every build
+fn content_hash(content: &str) -> u64 {
+    content.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+fn l2_norm(v: &[f32]) -> f32 {
+    v.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+/// Cosine similarity using the entry's precomputed norm. For normalized
+/// embeddings both norms are 1 and this is just the dot product.
+fn entry_similarity(query: &[f32], query_norm: f32, entry: &EmbeddingEntry) -> f32 {
+    if query_norm == 0.0 || entry.norm == 0.0 {
+        return 0.0;
+    }
+
+    dot(query, &entry.embedding) / (query_norm * entry.norm)
+}
+
+/// Weights scaled so the largest is 1, which leaves `Max` scores comparable
+/// to plain similarities; an empty slice weighs every query equally
+fn query_weights(count: usize, weights: &[f32]) -> Result<Vec<f32>> {
+    anyhow::ensure!(count > 0, "at least one query is required");
+    if weights.is_empty() {
+        return Ok(vec![1.0; count]);
+    }
+    anyhow::ensure!(weights.len() == count, "{} weights given for {} queries", weights.len(), count);
+    anyhow::ensure!(
+        weights.iter().all(|w| w.is_finite() && *w >= 0.0),
+        "query weights must be non-negative numbers"
+    );
+    let max = weights.iter().copied().fold(0.0, f32::max);
+    anyhow::ensure!(max > 0.0, "at least one query weight must be positive");
+    Ok(weights.iter().map(|w| w / max).collect())
+}
+
+/// Weighted mean of the queries after scaling each to unit length, so one
+/// phrasing with a larger norm can't dominate the others
+fn mean_query(queries: &[&[f32]], weights: &[f32]) -> Vec<f32> {
+    let mut mean = vec![0.0f32; queries.first().map_or(0, |q| q.len())];
+    for (query, weight) in queries.iter().zip(weights) {
+        let norm = l2_norm(query);
+        if norm == 0.0 {
+            continue;
+        }
+        for (acc, x) in mean.iter_mut().zip(query.iter()) {
+            *acc += weight * x / norm;
+        }
+    }
+    let total: f32 = weights.iter().sum();
+    mean.iter_mut().for_each(|x| *x /= total);
+    mean
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+/// Iterate a vector stored as little-endian f32 bytes without copying it out
+fn le_values(bytes: &[u8]) -> impl Iterator<Item = f32> + '_ {
+    bytes.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn dot_le(bytes: &[u8], other: &[f32]) -> f32 {
+    le_values(bytes).zip(other.iter()).map(|(x, y)| x * y).sum()
+}
+
+fn l2_norm_le(bytes: &[u8]) -> f32 {
+    le_values(bytes).map(|x| x * x).sum::<f32>().sqrt()
+}
+
+#[cfg(test)]
 fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     let dot_product: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
     let magnitude_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
@@ -372,4 +1292,560 @@ mod tests {
         assert_eq!(index.total_chunks, 0);
         assert_eq!(index.dimension, 384);
     }
+
+    #[test]
+    fn test_binary_round_trip_keeps_entries() {
+        let mut index = EmbeddingIndex::new("test-model".to_string(), 2);
+        index.add_entry(EmbeddingEntry {
+            id: "func_login".to_string(),
+            chunk_type: ChunkType::Function,
+            content: "def login(user):\n    pass".to_string(),
+            embedding: vec![0.5, -1.25],
+            metadata: ChunkMetadata {
+                file_path: Some("auth.py".to_string()),
+                language: Some("python".to_string()),
+                line_start: Some(3),
+                line_end: Some(4),
+                name: "login".to_string(),
+                complexity: Some(1),
+            },
+            importance_score: 0.9,
+            tags: vec!["api".to_string(), "function".to_string()],
+            norm: 0.0,
+        }).unwrap();
+
+        index.input = Some(EmbedInput {
+            text_cleanup: TextCleanup { strip_headers: true, collapse_whitespace: false },
+            max_tokens: 256,
+        });
+
+        let path = std::env::temp_dir().join(format!("eulix_index_{}.bin", std::process::id()));
+        index.save_binary(&path).unwrap();
+        let loaded = EmbeddingIndex::load_binary(&path);
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.model, "test-model");
+        assert_eq!(loaded.input, index.input);
+        assert_eq!(loaded.total_chunks, 1);
+
+        let entry = &loaded.embeddings[0];
+        assert_eq!(entry.id, "func_login");
+        assert_eq!(entry.chunk_type, ChunkType::Function);
+        assert_eq!(entry.content, "def login(user):\n    pass");
+        assert_eq!(entry.embedding, vec![0.5, -1.25]);
+        assert_eq!(entry.metadata.file_path.as_deref(), Some("auth.py"));
+        assert_eq!(entry.metadata.line_end, Some(4));
+        assert_eq!(entry.metadata.name, "login");
+        assert_eq!(entry.importance_score, 0.9);
+        assert_eq!(entry.tags, vec!["api", "function"]);
+        assert!((entry.norm - l2_norm(&entry.embedding)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_ndjson_round_trip_one_entry_per_line() {
+        let mut index = EmbeddingIndex::new("test-model".to_string(), 2);
+        for (id, embedding) in [("func_login", vec![0.5, -1.25]), ("func_logout", vec![1.0, 0.0])] {
+            index.add_entry(EmbeddingEntry {
+                id: id.to_string(),
+                chunk_type: ChunkType::Function,
+                content: format!("def {}():\n    pass", id),
+                embedding,
+                metadata: ChunkMetadata {
+                    file_path: Some("auth.py".to_string()),
+                    language: Some("python".to_string()),
+                    line_start: Some(1),
+                    line_end: Some(2),
+                    name: id.to_string(),
+                    complexity: Some(1),
+                },
+                importance_score: 0.5,
+                tags: vec!["function".to_string()],
+                norm: 0.0,
+            }).unwrap();
+        }
+
+        let path = std::env::temp_dir().join(format!("eulix_index_{}.ndjson", std::process::id()));
+        index.save_ndjson(&path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        let loaded = EmbeddingIndex::load_ndjson(&path);
+
+        // Drop the last entry to simulate an interrupted write
+        let truncated: Vec<&str> = text.lines().take(2).collect();
+        std::fs::write(&path, truncated.join("\n")).unwrap();
+        let truncated = EmbeddingIndex::load_ndjson(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], r#"{"model":"test-model","dimension":2,"count":2}"#);
+        assert!(lines[1].contains(r#""id":"func_login""#));
+
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.model, "test-model");
+        assert_eq!(loaded.total_chunks, 2);
+        assert_eq!(loaded.embeddings[1].id, "func_logout");
+        assert_eq!(loaded.embeddings[0].content, "def func_login():\n    pass");
+        assert_eq!(loaded.embeddings[0].embedding, vec![0.5, -1.25]);
+        assert!((loaded.embeddings[0].norm - l2_norm(&[0.5, -1.25])).abs() < 1e-6);
+
+        let err = truncated.err().unwrap().to_string();
+        assert!(err.contains("header says 2 entries, found 1"), "{}", err);
+    }
+
+    #[test]
+    fn test_chunks_at_orders_most_specific_first() {
+        let mut index = EmbeddingIndex::new("test-model".to_string(), 1);
+        let ranges = [
+            ("class_Session", "auth/session.py", Some((10, 60))),
+            ("method_Session_refresh", "auth/session.py", Some((20, 35))),
+            ("func_login", "auth/session.py", Some((62, 70))),
+            ("func_other", "billing/session.py", Some((20, 35))),
+            ("file_agg:auth/session.py", "auth/session.py", None),
+        ];
+        for (id, file, range) in ranges {
+            index.add_entry(EmbeddingEntry {
+                id: id.to_string(),
+                chunk_type: ChunkType::Function,
+                content: String::new(),
+                embedding: vec![1.0],
+                metadata: ChunkMetadata {
+                    file_path: Some(file.to_string()),
+                    language: Some("python".to_string()),
+                    line_start: range.map(|(start, _)| start),
+                    line_end: range.map(|(_, end)| end),
+                    name: id.to_string(),
+                    complexity: None,
+                },
+                importance_score: 0.5,
+                tags: vec![],
+                norm: 0.0,
+            }).unwrap();
+        }
+
+        let ids = |file: &str, line: usize| -> Vec<String> {
+            index.chunks_at(file, line).iter().map(|e| e.id.clone()).collect()
+        };
+
+        assert_eq!(ids("auth/session.py", 20), vec!["method_Session_refresh", "class_Session"]);
+        assert_eq!(ids("auth/session.py", 36), vec!["class_Session"]);
+        assert_eq!(ids("auth/session.py", 70), vec!["func_login"]);
+        assert!(ids("auth/session.py", 61).is_empty());
+        assert_eq!(ids("/home/dev/project/auth/session.py", 62), vec!["func_login"]);
+        assert_eq!(ids("./auth/session.py", 62), vec!["func_login"]);
+        // A path that merely ends with the same characters isn't the same file
+        assert!(ids("oauth/session.py", 62).is_empty());
+    }
+
+    #[test]
+    fn test_reusable_vectors_skip_changed_chunks() {
+        let chunk = |id: &str, content: &str| Chunk {
+            id: id.to_string(),
+            chunk_type: ChunkType::Function,
+            content: content.to_string(),
+            metadata: ChunkMetadata {
+                file_path: Some("app.py".to_string()),
+                language: Some("python".to_string()),
+                line_start: Some(1),
+                line_end: Some(2),
+                name: id.to_string(),
+                complexity: None,
+            },
+            tags: vec![],
+            importance_score: 0.5,
+        };
+
+        let mut base = EmbeddingIndex::new("test-model".to_string(), 2);
+        for (id, content, embedding) in [("func_load", "def load(): ...", [1.0, 0.0]), ("func_save", "def save(): ...", [0.0, 1.0])] {
+            let old = chunk(id, content);
+            base.add_entry(EmbeddingEntry {
+                id: old.id,
+                chunk_type: old.chunk_type,
+                content: old.content,
+                embedding: embedding.to_vec(),
+                metadata: old.metadata,
+                importance_score: 0.5,
+                tags: vec![],
+                norm: 0.0,
+            }).unwrap();
+        }
+
+        // func_save changed; func_load moved under a new id but kept its content
+        let chunks = vec![
+            chunk("func_load_v2", "def load(): ..."),
+            chunk("func_save", "def save(path): ..."),
+        ];
+        let reused = base.reusable_vectors(&chunks);
+        assert_eq!(reused.len(), 1);
+        assert_eq!(reused["func_load_v2"], vec![1.0, 0.0]);
+
+        let pending: Vec<&str> = chunks.iter().map(|c| c.id.as_str()).filter(|id| !reused.contains_key(*id)).collect();
+        assert_eq!(pending, vec!["func_save"]);
+    }
+
+    #[test]
+    fn test_search_multi_combines_phrasings() {
+        let mut index = EmbeddingIndex::new("test-model".to_string(), 3);
+        for (id, embedding) in [
+            ("func_login", vec![1.0, 0.0, 0.0]),
+            ("func_authenticate", vec![0.0, 1.0, 0.0]),
+            ("func_session", vec![0.7, 0.7, 0.1]),
+            ("func_unrelated", vec![0.0, 0.0, 1.0]),
+        ] {
+            index.add_entry(EmbeddingEntry {
+                id: id.to_string(),
+                chunk_type: ChunkType::Function,
+                content: String::new(),
+                embedding,
+                metadata: ChunkMetadata {
+                    file_path: Some("auth.py".to_string()),
+                    language: Some("python".to_string()),
+                    line_start: None,
+                    line_end: None,
+                    name: id.to_string(),
+                    complexity: None,
+                },
+                importance_score: 0.5,
+                tags: vec![],
+                norm: 0.0,
+            }).unwrap();
+        }
+
+        let login = [2.0, 0.0, 0.0];
+        let authenticate = [0.0, 1.0, 0.0];
+        let ids = |results: Vec<SearchResult>| -> Vec<String> { results.into_iter().map(|r| r.id).collect() };
+
+        // One phrasing alone ranks its own match first
+        assert_eq!(ids(index.search(&login, 3)), vec!["func_login", "func_session", "func_authenticate"]);
+
+        // The mean sits between both phrasings, where only the session chunk is close
+        let mean = index.search_multi(&[&login, &authenticate], &[], 3, QueryCombine::Mean).unwrap();
+        assert_eq!(mean[0].id, "func_session");
+        assert!((mean[1].similarity - mean[2].similarity).abs() < 1e-6);
+
+        // Max keeps the best match of each phrasing on top
+        let max = index.search_multi(&[&login, &authenticate], &[], 2, QueryCombine::Max).unwrap();
+        assert_eq!(max.len(), 2);
+        assert!(max.iter().all(|r| (r.similarity - 1.0).abs() < 1e-6));
+
+        // Down-weighting a phrasing demotes what only it matches
+        let weighted = index.search_multi(&[&login, &authenticate], &[1.0, 0.5], 4, QueryCombine::Max).unwrap();
+        assert_eq!(ids(weighted), vec!["func_login", "func_session", "func_authenticate", "func_unrelated"]);
+
+        let err = index.search_multi(&[&login, &authenticate], &[1.0], 3, QueryCombine::Mean).unwrap_err();
+        assert!(err.to_string().contains("1 weights given for 2 queries"));
+    }
+
+    #[test]
+    fn test_unknown_binary_version_names_supported_range() {
+        let mut bytes = b"EULX".to_vec();
+        bytes.extend_from_slice(&99u32.to_le_bytes());
+
+        let path = std::env::temp_dir().join(format!("eulix_index_v99_{}.bin", std::process::id()));
+        std::fs::write(&path, &bytes).unwrap();
+        let loaded = EmbeddingIndex::load_binary(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let err = loaded.err().unwrap().to_string();
+        assert!(err.contains("Unsupported binary version: 99"));
+        assert!(err.contains("Supported versions are 1 to 6"));
+    }
+
+    #[test]
+    fn test_gzip_json_round_trip() {
+        use std::io::Write;
+
+        let index = index_of(&[vec![0.5, -1.25], vec![3.0, 4.0]]);
+        let json = serde_json::to_vec(&index).unwrap();
+
+        let dir = std::env::temp_dir();
+        let gz_path = dir.join(format!("eulix_index_{}.json.gz", std::process::id()));
+        // Magic bytes alone are enough, whatever the extension
+        let sniffed_path = dir.join(format!("eulix_index_gz_{}.json", std::process::id()));
+        for path in [&gz_path, &sniffed_path] {
+            let mut encoder = flate2::write::GzEncoder::new(File::create(path).unwrap(), flate2::Compression::default());
+            encoder.write_all(&json).unwrap();
+            encoder.finish().unwrap();
+        }
+
+        let loaded = EmbeddingIndex::load(&gz_path);
+        let sniffed = EmbeddingIndex::load(&sniffed_path);
+        std::fs::remove_file(&gz_path).unwrap();
+        std::fs::remove_file(&sniffed_path).unwrap();
+
+        for loaded in [loaded.unwrap(), sniffed.unwrap()] {
+            assert_eq!(loaded.total_chunks, 2);
+            assert_eq!(loaded.embeddings[1].id, "chunk_1");
+            assert_eq!(loaded.embeddings[1].embedding, vec![3.0, 4.0]);
+            assert_eq!(loaded.embeddings[1].norm, 5.0);
+        }
+    }
+
+    #[test]
+    fn test_precomputed_norm_search_matches_cosine() {
+        let vectors: Vec<Vec<f32>> = (0..200)
+            .map(|i| (0..16).map(|d| ((i * 31 + d * 7) % 17) as f32 - 8.0).collect())
+            .collect();
+        let index = index_of(&vectors);
+
+        let query: Vec<f32> = (0..16).map(|d| (d as f32 * 0.37).sin()).collect();
+        let results = index.search(&query, vectors.len());
+        assert_eq!(results.len(), vectors.len());
+
+        for result in &results {
+            let i: usize = result.id.trim_start_matches("chunk_").parse().unwrap();
+            let expected = cosine_similarity(&query, &vectors[i]);
+            assert!((result.similarity - expected).abs() < 1e-6);
+        }
+    }
+
+    fn index_of(vectors: &[Vec<f32>]) -> EmbeddingIndex {
+        let mut index = EmbeddingIndex::new("test-model".to_string(), vectors[0].len());
+        for (i, embedding) in vectors.iter().enumerate() {
+            index.add_entry(EmbeddingEntry {
+                id: format!("chunk_{}", i),
+                chunk_type: ChunkType::Function,
+                content: String::new(),
+                embedding: embedding.clone(),
+                metadata: ChunkMetadata {
+                    file_path: None,
+                    language: None,
+                    line_start: None,
+                    line_end: None,
+                    name: String::new(),
+                    complexity: None,
+                },
+                importance_score: 0.5,
+                tags: vec![],
+                norm: 0.0,
+            }).unwrap();
+        }
+        index
+    }
+
+    #[test]
+    fn test_find_by_tag_and_file_without_query() {
+        let mut index = index_of(&[vec![1.0, 0.0], vec![0.0, 1.0], vec![1.0, 1.0]]);
+        let files = [Some("src/api/routes.py"), Some("src/db.py"), None];
+        let tags = [vec!["api", "async"], vec!["async"], vec![]];
+        for ((entry, file), tags) in index.embeddings.iter_mut().zip(files).zip(tags) {
+            entry.metadata.file_path = file.map(str::to_string);
+            entry.tags = tags.into_iter().map(str::to_string).collect();
+        }
+
+        let ids = |entries: Vec<&EmbeddingEntry>| entries.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(index.find_by_tag("async")), vec!["chunk_0", "chunk_1"]);
+        assert_eq!(ids(index.find_by_tag("api")), vec!["chunk_0"]);
+        assert!(index.find_by_tag("asy").is_empty());
+
+        assert_eq!(ids(index.find_by_file("src/")), vec!["chunk_0", "chunk_1"]);
+        assert_eq!(ids(index.find_by_file("routes")), vec!["chunk_0"]);
+        assert!(index.find_by_file("main.rs").is_empty());
+    }
+
+    #[test]
+    fn test_model_compatibility() {
+        let err = check_model_compatibility(
+            "BAAI/bge-small-en-v1.5", 384,
+            "sentence-transformers/all-mpnet-base-v2", 768,
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("BAAI/bge-small-en-v1.5 (384d)"));
+        assert!(err.contains("sentence-transformers/all-mpnet-base-v2 (768d)"));
+
+        // Same dimension, different model
+        assert!(check_model_compatibility(
+            "sentence-transformers/all-MiniLM-L6-v2", 384,
+            "BAAI/bge-small-en-v1.5", 384,
+        )
+        .is_err());
+
+        assert!(check_model_compatibility(
+            "BAAI/bge-small-en-v1.5", 384,
+            "/models/bge-small-en-v1.5/", 384,
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_find_duplicates_reports_near_identical_functions() {
+        let mut index = index_of(&[
+            vec![1.0, 0.2, 0.0],
+            vec![1.0, 0.21, 0.0],
+            vec![0.0, 1.0, 0.0],
+            vec![1.0, 0.2, 0.0],
+            vec![0.0, 0.0, 1.0],
+            vec![0.0, 0.0, 1.0],
+        ]);
+        index.embeddings[3].chunk_type = ChunkType::Class;
+        index.embeddings[4].id = "func_big#0".to_string();
+        index.embeddings[5].id = "func_big#1".to_string();
+
+        let dupes = index.find_duplicates(0.95);
+        assert_eq!(dupes.len(), 1);
+        let (a, b, similarity) = &dupes[0];
+        assert_eq!((a.as_str(), b.as_str()), ("chunk_0", "chunk_1"));
+        assert!(*similarity > 0.99);
+
+        assert!(index.find_duplicates(1.01).is_empty());
+    }
+
+    #[test]
+    fn test_compare_vectors_matches_by_id() {
+        let left = index_of(&[vec![1.0, 0.0], vec![0.5, 0.5], vec![0.0, 1.0], vec![1.0, 1.0]]);
+        let mut right = index_of(&[vec![1.0, 0.0], vec![0.5, 0.5], vec![0.0, 1.0005], vec![1.0, 1.0]]);
+        // Same vectors in a different order, one perturbed, one id renamed
+        right.embeddings.swap(0, 2);
+        right.embeddings[3].id = "chunk_9".to_string();
+
+        let comparison = left.compare_vectors(&right);
+        assert_eq!(comparison.only_left, vec!["chunk_3".to_string()]);
+        assert_eq!(comparison.only_right, vec!["chunk_9".to_string()]);
+        assert!(comparison.length_mismatches.is_empty());
+        assert_eq!(comparison.diffs.len(), 3);
+        assert_eq!(comparison.diffs[0].0, "chunk_2");
+        assert!((comparison.diffs[0].1 - 0.0005).abs() < 1e-6);
+
+        assert_eq!(comparison.exceeding(1e-6).len(), 1);
+        assert!(comparison.exceeding(1e-3).is_empty());
+        let histogram = comparison.histogram();
+        assert_eq!(histogram[0], 2);
+        assert_eq!(histogram[5], 1);
+
+        let same = left.compare_vectors(&left);
+        assert!(same.exceeding(0.0).is_empty() && same.only_left.is_empty() && same.only_right.is_empty());
+    }
+
+    #[test]
+    fn test_merge_appends_entries_under_namespaces() {
+        let a = index_of(&[vec![1.0, 0.0], vec![0.0, 1.0]]);
+        let b = index_of(&[vec![1.0, 1.0], vec![0.5, 0.0], vec![0.0, 2.0]]);
+
+        let mut clashing = index_of(&[vec![1.0, 0.0]]);
+        assert!(clashing.merge(index_of(&[vec![0.0, 1.0]])).is_err());
+
+        let mut merged = a.with_namespace("repoA");
+        merged.merge(b.with_namespace("repoB")).unwrap();
+        assert_eq!(merged.total_chunks, 5);
+        assert_eq!(merged.embeddings[0].id, "repoA::chunk_0");
+        assert_eq!(merged.embeddings[4].id, "repoB::chunk_2");
+        assert_eq!(merged.embeddings[4].norm, 2.0);
+
+        let mut other_model = EmbeddingIndex::new("other-model".to_string(), 2);
+        assert!(other_model.merge(index_of(&[vec![1.0, 0.0]])).is_err());
+    }
+
+    #[test]
+    fn test_cosine_and_dot_agree_on_normalized_vectors() {
+        let vectors: Vec<Vec<f32>> = (0..20)
+            .map(|i| {
+                let v: Vec<f32> = (0..8).map(|d| ((i * 13 + d * 5) % 11) as f32 - 5.0).collect();
+                let norm = l2_norm(&v);
+                v.iter().map(|x| x / norm).collect()
+            })
+            .collect();
+        let index = index_of(&vectors);
+        let query: Vec<f32> = vec![0.5, 0.5, 0.5, 0.5, 0.0, 0.0, 0.0, 0.0];
+
+        let ids = |metric| -> Vec<String> {
+            index.search_with_metric(&query, 10, metric).into_iter().map(|r| r.id).collect()
+        };
+        assert_eq!(ids(DistanceMetric::Cosine), ids(DistanceMetric::Dot));
+    }
+
+    #[test]
+    fn test_euclidean_neg_ranks_closest_first() {
+        let index = index_of(&[vec![10.0, 0.0], vec![1.0, 1.0], vec![3.0, 3.0]]);
+        let results = index.search_with_metric(&[1.0, 0.5], 3, DistanceMetric::EuclideanNeg);
+
+        let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["chunk_1", "chunk_2", "chunk_0"]);
+        assert!((results[0].similarity + 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_min_similarity_drops_weak_matches() {
+        let index = index_of(&[vec![1.0, 0.0, 0.0], vec![0.0, 1.0, 0.0]]);
+        let orthogonal = [0.0, 0.0, 1.0];
+
+        assert_eq!(index.search_with_threshold(&orthogonal, 5, None).len(), 2);
+        assert!(index.search_with_threshold(&orthogonal, 5, Some(0.5)).is_empty());
+
+        let results = index.search_with_threshold(&[1.0, 0.1, 0.0], 5, Some(0.5));
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, "chunk_0");
+    }
+
+    #[test]
+    fn test_mmap_search_matches_eager_load() {
+        let vectors: Vec<Vec<f32>> = (0..50)
+            .map(|i| (0..12).map(|d| ((i * 17 + d * 3) % 13) as f32 - 6.0).collect())
+            .collect();
+        let mut index = index_of(&vectors);
+        for (i, entry) in index.embeddings.iter_mut().enumerate() {
+            entry.metadata.language = Some(if i % 3 == 0 { "go" } else { "python" }.to_string());
+        }
+        index.embeddings[4].id = "func_big#0".to_string();
+        index.embeddings[5].id = "func_big#1".to_string();
+
+        let path = std::env::temp_dir().join(format!("eulix_mmap_{}.bin", std::process::id()));
+        index.save_binary(&path).unwrap();
+        let eager = EmbeddingIndex::load_binary(&path).unwrap();
+        let mapped = EmbeddingIndex::load_binary_mmap(&path).unwrap();
+        assert_eq!(mapped.len(), 50);
+        assert_eq!(mapped.dimension, 12);
+
+        let query: Vec<f32> = (0..12).map(|d| (d as f32 * 0.61).cos()).collect();
+        let ranking = |results: Vec<SearchResult>| -> Vec<(String, f32)> {
+            results.into_iter().map(|r| (r.id, r.similarity)).collect()
+        };
+
+        assert_eq!(ranking(eager.search(&query, 10)), ranking(mapped.search(&query, 10)));
+
+        let go_only = || SearchFilters {
+            languages: Some(vec!["go".to_string()]),
+            min_similarity: Some(0.0),
+            ..Default::default()
+        };
+        let filtered = ranking(mapped.search_filtered(&query, 50, go_only()));
+        assert!(!filtered.is_empty());
+        assert_eq!(ranking(eager.search_filtered(&query, 50, go_only())), filtered);
+
+        drop(mapped);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_rerank_prefers_entry_point_at_equal_similarity() {
+        let mut index = index_of(&[vec![1.0, 0.0], vec![1.0, 0.0], vec![0.0, 1.0]]);
+        index.embeddings[0].id = "file_main".to_string();
+        index.embeddings[0].chunk_type = ChunkType::File;
+        index.embeddings[1].id = "entry_main".to_string();
+        index.embeddings[1].chunk_type = ChunkType::EntryPoint;
+
+        let reranked = index.search_filtered(&[1.0, 0.0], 3, SearchFilters {
+            rerank: Some(RerankConfig::default()),
+            ..Default::default()
+        });
+        let ids: Vec<&str> = reranked.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["entry_main", "file_main", "chunk_2"]);
+
+        // Raw similarity is still reported alongside the boosted score
+        assert!((reranked[0].similarity - reranked[1].similarity).abs() < 1e-6);
+        assert!((reranked[0].score - (0.8 + 0.05 + 0.1)).abs() < 1e-6);
+
+        let plain = index.search(&[1.0, 0.0], 3);
+        assert!(plain.iter().all(|r| r.score == r.similarity));
+    }
+
+    #[test]
+    fn test_search_keeps_best_window_per_chunk() {
+        let mut index = index_of(&[vec![1.0, 0.0], vec![0.9, 0.1], vec![0.0, 1.0]]);
+        index.embeddings[0].id = "func_big#0".to_string();
+        index.embeddings[1].id = "func_big#1".to_string();
+
+        let ids: Vec<String> = index.search(&[1.0, 0.0], 2).into_iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec!["func_big#0", "chunk_2"]);
+    }
 }
@@ -1,8 +1,8 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufRead, BufReader, Read};
 use std::path::Path;
 
 /// Root structure matching the JSON schema
@@ -18,8 +18,14 @@ pub struct KnowledgeBase {
     pub patterns: Patterns,
 }
 
+/// kb.json layout this crate reads, the parser's `SCHEMA_VERSION`
+pub const SCHEMA_VERSION: u32 = 2;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Metadata {
+    /// Missing in KBs written before versioning, which read as version 1
+    #[serde(default = "default_schema_version")]
+    pub schema_version: u32,
     pub project_name: String,
     pub version: String,
     pub parsed_at: String,
@@ -29,6 +35,18 @@ pub struct Metadata {
     pub total_functions: usize,
     pub total_classes: usize,
     pub total_methods: usize,
+    /// Source revision the parser ran on; absent outside a git repo and in
+    /// KBs from older parsers
+    #[serde(default)]
+    pub git_commit: Option<String>,
+    #[serde(default)]
+    pub git_branch: Option<String>,
+    #[serde(default)]
+    pub git_dirty: Option<bool>,
+}
+
+fn default_schema_version() -> u32 {
+    1
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -79,6 +97,11 @@ pub struct Function {
     #[serde(default)]
     pub is_async: bool,
     #[serde(default)]
+    pub method_kind: MethodKind,
+    /// Present when the parser ran with `--include-source`
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
     pub decorators: Vec<String>,
     #[serde(default)]
     pub tags: Vec<String>,
@@ -86,6 +109,16 @@ pub struct Function {
     pub importance_score: f32,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MethodKind {
+    #[default]
+    Instance,
+    Static,
+    Class,
+    Property,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Parameter {
     pub name: String,
@@ -98,6 +131,8 @@ pub struct Parameter {
 pub struct FunctionCall {
     pub callee: String,
     pub defined_in: Option<String>,
+    #[serde(default)]
+    pub candidates: Vec<String>,
     pub line: usize,
     #[serde(default)]
     pub args: Vec<String>,
@@ -139,6 +174,8 @@ pub struct ControlFlow {
     #[serde(default)]
     pub complexity: usize,
     #[serde(default)]
+    pub cognitive_complexity: usize,
+    #[serde(default)]
     pub branches: Vec<Branch>,
     #[serde(default)]
     pub loops: Vec<Loop>,
@@ -259,6 +296,8 @@ pub struct CallGraph {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CallGraphNode {
     pub id: String,
+    #[serde(default)]
+    pub name: String,
     pub node_type: String, // "function" | "method" | "class"
     pub file: String,
     #[serde(default)]
@@ -342,13 +381,118 @@ pub struct Patterns {
     pub architecture_style: Option<String>, // "layered" | "microservices" | "mvc"
 }
 
+/// Load a kb.json (optionally gzipped), refusing KBs written for another
+/// schema version. When the file doesn't deserialize at all, its version is
+/// read on its own so an outdated KB still gets the clear error.
 pub fn load_knowledge_base(path: &Path) -> Result<KnowledgeBase> {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    let kb = serde_json::from_reader(reader)?;
+    let kb: KnowledgeBase = match serde_json::from_reader(open_json(path)?) {
+        Ok(kb) => kb,
+        Err(err) => {
+            #[derive(Deserialize)]
+            struct Header {
+                metadata: VersionOnly,
+            }
+            #[derive(Deserialize)]
+            struct VersionOnly {
+                #[serde(default = "default_schema_version")]
+                schema_version: u32,
+            }
+
+            if let Ok(header) = serde_json::from_reader::<_, Header>(open_json(path)?) {
+                check_schema_version(header.metadata.schema_version)?;
+            }
+            return Err(err.into());
+        }
+    };
+    check_schema_version(kb.metadata.schema_version)?;
     Ok(kb)
 }
 
+/// Error unless `version` is the schema this crate was built for
+pub fn check_schema_version(version: u32) -> Result<()> {
+    if version < SCHEMA_VERSION {
+        bail!("KB schema v{} required, got v{}; re-run eulix_parser", SCHEMA_VERSION, version);
+    }
+    if version > SCHEMA_VERSION {
+        bail!("KB schema v{} is newer than the supported v{}; update eulix_embed", version, SCHEMA_VERSION);
+    }
+    Ok(())
+}
+
+/// Open a JSON file for reading, transparently decompressing it when it has
+/// a `.gz` extension or starts with the gzip magic bytes
+pub fn open_json(path: &Path) -> Result<Box<dyn Read>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let gzipped = path.extension().is_some_and(|ext| ext == "gz")
+        || reader.fill_buf()?.starts_with(&[0x1f, 0x8b]);
+
+    if gzipped {
+        Ok(Box::new(BufReader::new(flate2::bufread::MultiGzDecoder::new(reader))))
+    } else {
+        Ok(Box::new(reader))
+    }
+}
+
+impl CallGraph {
+    /// Longest call chain from an entry point, counted in functions. Mirrors
+    /// the parser's `CallGraph::max_depth_from_entry_points`: edges resolve by
+    /// callee name and calls back into the current path are skipped.
+    pub fn max_depth_from_entry_points(&self) -> usize {
+        let mut index_by_id: HashMap<&str, usize> = HashMap::new();
+        let mut by_name: HashMap<&str, Vec<usize>> = HashMap::new();
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            index_by_id.insert(node.id.as_str(), i);
+            let name = if node.name.is_empty() {
+                node.id.strip_prefix("func_").unwrap_or(&node.id)
+            } else {
+                node.name.as_str()
+            };
+            by_name.entry(name).or_default().push(i);
+        }
+
+        let mut callees: Vec<Vec<usize>> = vec![Vec::new(); self.nodes.len()];
+        for edge in self.edges.iter().filter(|e| e.edge_type == "calls") {
+            if let (Some(&from), Some(targets)) = (index_by_id.get(edge.from.as_str()), by_name.get(edge.to.as_str())) {
+                callees[from].extend(targets.iter().filter(|&&t| t != from));
+            }
+        }
+
+        let mut memo = vec![None; self.nodes.len()];
+        let mut on_path = vec![false; self.nodes.len()];
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.is_entry_point)
+            .map(|(i, _)| longest_path(i, &callees, &mut memo, &mut on_path))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+fn longest_path(
+    node: usize,
+    callees: &[Vec<usize>],
+    memo: &mut [Option<usize>],
+    on_path: &mut [bool],
+) -> usize {
+    if let Some(depth) = memo[node] {
+        return depth;
+    }
+
+    on_path[node] = true;
+    let mut deepest = 0;
+    for &callee in &callees[node] {
+        if !on_path[callee] {
+            deepest = deepest.max(longest_path(callee, callees, memo, on_path));
+        }
+    }
+    on_path[node] = false;
+
+    memo[node] = Some(deepest + 1);
+    deepest + 1
+}
+
 impl KnowledgeBase {
     /// Get all functions across all files
     pub fn all_functions(&self) -> Vec<(&String, &Function)> {
@@ -446,3 +590,65 @@ impl KnowledgeBase {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_kb(name: &str, metadata: serde_json::Value) -> std::path::PathBuf {
+        let kb = serde_json::json!({
+            "metadata": metadata,
+            "structure": {},
+            "call_graph": { "nodes": [], "edges": [] },
+            "dependency_graph": { "nodes": [], "edges": [] },
+            "indices": {},
+            "entry_points": [],
+            "external_dependencies": [],
+            "patterns": { "architecture_style": null }
+        });
+        let path = std::env::temp_dir().join(format!("eulix_kb_{}_{}.json", name, std::process::id()));
+        std::fs::write(&path, kb.to_string()).unwrap();
+        path
+    }
+
+    fn metadata(schema_version: Option<u32>) -> serde_json::Value {
+        let mut metadata = serde_json::json!({
+            "project_name": "demo", "version": "1.0", "parsed_at": "", "languages": ["python"],
+            "total_files": 0, "total_loc": 0, "total_functions": 0, "total_classes": 0, "total_methods": 0
+        });
+        if let Some(version) = schema_version {
+            metadata["schema_version"] = version.into();
+        }
+        metadata
+    }
+
+    #[test]
+    fn test_matching_schema_version_loads() {
+        let path = write_kb("current", metadata(Some(SCHEMA_VERSION)));
+        let kb = load_knowledge_base(&path);
+        std::fs::remove_file(&path).ok();
+        assert_eq!(kb.unwrap().metadata.schema_version, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_missing_schema_version_reads_as_v1() {
+        let path = write_kb("unversioned", metadata(None));
+        let err = load_knowledge_base(&path).unwrap_err().to_string();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(err, format!("KB schema v{} required, got v1; re-run eulix_parser", SCHEMA_VERSION));
+    }
+
+    #[test]
+    fn test_old_or_newer_schema_version_is_refused() {
+        assert!(check_schema_version(SCHEMA_VERSION - 1).unwrap_err().to_string().contains("re-run eulix_parser"));
+        assert!(check_schema_version(SCHEMA_VERSION + 1).unwrap_err().to_string().contains("update eulix_embed"));
+
+        // An old KB that no longer even deserializes still reports its version
+        let mut old = metadata(Some(1));
+        old.as_object_mut().unwrap().remove("total_methods");
+        let path = write_kb("old", old);
+        let err = load_knowledge_base(&path).unwrap_err().to_string();
+        std::fs::remove_file(&path).ok();
+        assert!(err.starts_with(&format!("KB schema v{} required, got v1", SCHEMA_VERSION)), "{}", err);
+    }
+}
@@ -0,0 +1,6 @@
+//! Library half of `eulix_embed`: the knowledge base format the parser
+//! writes and the tagging applied to each embedded chunk. The binary
+//! builds on these modules for everything else.
+
+pub mod kb_loader;
+pub mod tags;
@@ -4,29 +4,60 @@ use std::time::Instant;
 
 // Module declarations
 mod onnx_backend;
+mod aggregate;
 mod chunker;
+mod config;
 mod context;
 mod embedder;
 mod index;
-mod kb_loader;
-
-use chunker::{chunk_knowledge_base, Chunk, ChunkMetadata, ChunkType};
-use context::{ContextIndex, VectorStore};
-use embedder::EmbeddingGenerator;
-use index::{EmbeddingEntry, EmbeddingIndex};
+mod query_cache;
+mod report;
+mod server;
+mod text;
+
+use eulix_embed::{kb_loader, tags};
+
+use aggregate::{aggregate_chunks, AggregateMode};
+use chunker::{chunk_knowledge_base, CallLimits, Chunk, ChunkMetadata, ChunkType, TextCleanup};
+use config::EmbedConfig;
+use context::{ContextIndex, VectorHeader, VectorStore};
+use embedder::{EmbeddingBackend, EmbeddingGenerator, ModelCache};
+use index::{
+    check_model_compatibility, EmbedInput, EmbeddingEntry, EmbeddingIndex, QueryCombine, RerankConfig, SearchFilters,
+};
 use kb_loader::load_knowledge_base;
+use report::PipelineReport;
+use text::chunk_text_dir;
 
 pub struct EmbeddingPipeline {
     generator: EmbeddingGenerator,
     max_chunk_size: usize,
+    overlap: Option<usize>,
+    call_limits: CallLimits,
+    quiet: bool,
+    resume: bool,
+    chunk_types: Option<Vec<ChunkType>>,
+    exclude_tests: bool,
+    aggregate: AggregateMode,
+    text_cleanup: TextCleanup,
+    base: Option<EmbeddingIndex>,
 }
 
 impl EmbeddingPipeline {
-    pub fn new(model_name: &str) -> Result<Self> {
-        let generator = EmbeddingGenerator::new(model_name)?;
+    pub fn new(model_name: &str, device: Option<EmbeddingBackend>, cache: ModelCache) -> Result<Self> {
+        let generator = EmbeddingGenerator::new(model_name, device, cache)?;
         Ok(Self {
             generator,
             max_chunk_size: 2000,
+            overlap: None,
+            call_limits: CallLimits::default(),
+            quiet: false,
+            resume: false,
+            chunk_types: None,
+            exclude_tests: false,
+            aggregate: AggregateMode::None,
+            text_cleanup: TextCleanup::default(),
+            base: None,
         })
     }
 
@@ -35,6 +66,88 @@ impl EmbeddingPipeline {
         self
     }
 
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.generator = self.generator.with_batch_size(batch_size);
+        self
+    }
+
+    /// Reuse vectors from a partial `vectors.bin` in the output directory left
+    /// by an interrupted run. A no-op when there is no such file.
+    pub fn with_resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Reuse the vector of every chunk whose content is unchanged in `base`,
+    /// a previous index built with the same model, text cleanup and token
+    /// limit, and embed only the rest
+    pub fn with_base(mut self, base: EmbeddingIndex) -> Self {
+        self.base = Some(base);
+        self
+    }
+
+    /// Hide the per-batch progress line while embedding
+    pub fn with_quiet(mut self, quiet: bool) -> Self {
+        self.quiet = quiet;
+        self
+    }
+
+    /// Only embed chunks of these types; every type is embedded by default
+    pub fn with_chunk_types(mut self, chunk_types: Vec<ChunkType>) -> Self {
+        self.chunk_types = Some(chunk_types);
+        self
+    }
+
+    /// Skip chunks the parser tagged `test` (test functions, pytest fixtures)
+    pub fn with_exclude_tests(mut self, exclude_tests: bool) -> Self {
+        self.exclude_tests = exclude_tests;
+        self
+    }
+
+    /// Add file and/or top-level directory chunks whose vectors are the mean
+    /// of the function vectors they contain
+    pub fn with_aggregate(mut self, aggregate: AggregateMode) -> Self {
+        self.aggregate = aggregate;
+        self
+    }
+
+    /// Clean up chunk text before it reaches the model; stored chunks keep
+    /// their headers, and the header values stay in their metadata
+    pub fn with_text_cleanup(mut self, text_cleanup: TextCleanup) -> Self {
+        self.text_cleanup = text_cleanup;
+        self
+    }
+
+    /// Split oversized function chunks into windows overlapping by `overlap` chars
+    pub fn with_overlap(mut self, overlap: usize) -> Self {
+        self.overlap = Some(overlap);
+        self
+    }
+
+    /// List at most `limit` calls and callers per function chunk (`None`:
+    /// all of them) instead of the default 10 calls and 5 callers. Longer
+    /// lists are still cut off by `max_chunk_size` like any other content.
+    pub fn with_call_limit(mut self, limit: Option<usize>) -> Self {
+        self.call_limits = CallLimits { calls: limit, called_by: limit };
+        self
+    }
+
+    /// Text settings recorded in the index, see `EmbedInput`
+    fn embed_input(&self) -> EmbedInput {
+        EmbedInput { text_cleanup: self.text_cleanup, max_tokens: self.generator.max_tokens() }
+    }
+
+    fn vector_header(&self) -> VectorHeader {
+        VectorHeader {
+            model: self.generator.model_name().to_string(),
+            dimension: self.generator.dimension(),
+            input: self.embed_input(),
+            max_chunk_size: self.max_chunk_size,
+            overlap: self.overlap,
+            call_limits: self.call_limits,
+        }
+    }
+
     pub fn process(
         &self,
         kb_path: &Path,
@@ -72,7 +185,8 @@ impl EmbeddingPipeline {
         println!("       Classes:      {}", total_classes);
         println!("       Methods:      {}", total_methods);
         println!("       Entry Points: {}", kb.entry_points.len());
-        println!("       Time:         {:.2}s", step_start.elapsed().as_secs_f64());
+        let load_time = step_start.elapsed().as_secs_f64();
+        println!("       Time:         {:.2}s", load_time);
         println!();
 
         // Step 2: Chunk processing
@@ -80,7 +194,23 @@ impl EmbeddingPipeline {
         println!("{}", "-".repeat(70));
         let step_start = Instant::now();
 
-        let chunks = chunk_knowledge_base(&kb, self.max_chunk_size);
+        let mut chunks = chunk_knowledge_base(
+            &kb,
+            self.max_chunk_size,
+            self.overlap,
+            self.generator.tokenizer(),
+            self.call_limits,
+        );
+        if let Some(types) = &self.chunk_types {
+            let before = chunks.len();
+            chunks.retain(|chunk| types.contains(&chunk.chunk_type));
+            println!("  [OK] Kept {} of {} chunks matching --chunk-types", chunks.len(), before);
+        }
+        if self.exclude_tests {
+            let before = chunks.len();
+            chunks.retain(|chunk| !chunk.tags.iter().any(|tag| tag == "test"));
+            println!("  [OK] Skipped {} test chunks", before - chunks.len());
+        }
 
         // Show chunk type breakdown
         let mut chunk_type_counts = std::collections::HashMap::new();
@@ -96,7 +226,8 @@ impl EmbeddingPipeline {
         for (chunk_type, count) in &chunk_type_counts {
             println!("         {:20} {}", format!("{}:", chunk_type), count);
         }
-        println!("       Time:         {:.2}s", step_start.elapsed().as_secs_f64());
+        let chunking_time = step_start.elapsed().as_secs_f64();
+        println!("       Time:         {:.2}s", chunking_time);
         println!();
 
         // Step 3: Generate embeddings
@@ -104,14 +235,118 @@ impl EmbeddingPipeline {
         println!("{}", "-".repeat(70));
         let step_start = Instant::now();
 
-        let vector_store = self.generator.generate_vectors(chunks.clone())?;
+        // vectors.bin doubles as the checkpoint file while embedding
+        std::fs::create_dir_all(output_dir)?;
+        let vectors_bin = output_dir.join("vectors.bin");
+        let mut vector_store = if self.resume && vectors_bin.exists() {
+            let mut partial = VectorStore::load_binary(&vectors_bin)
+                .context("Failed to load partial vectors.bin for --resume")?;
+            let Some(header) = partial.header.take() else {
+                anyhow::bail!(
+                    "{} doesn't record the model and settings it was embedded with; re-embed without --resume",
+                    vectors_bin.display()
+                );
+            };
+            check_model_compatibility(
+                &header.model,
+                header.dimension,
+                self.generator.model_name(),
+                self.generator.dimension(),
+            )
+            .context("--resume checkpoint can't be reused")?;
+            if header != self.vector_header() {
+                anyhow::bail!(
+                    "{} was embedded with {:?}, this run uses {:?}; re-embed without --resume",
+                    vectors_bin.display(),
+                    header,
+                    self.vector_header()
+                );
+            }
+            let chunk_ids: std::collections::HashSet<&str> = chunks.iter().map(|c| c.id.as_str()).collect();
+            partial.vectors.retain(|id, _| chunk_ids.contains(id.as_str()));
+            println!("  [OK] Resuming: {} chunks already embedded", partial.len());
+            partial
+        } else {
+            if vectors_bin.exists() {
+                std::fs::remove_file(&vectors_bin)?;
+            }
+            VectorStore::new()
+        };
+
+        if let Some(base) = &self.base {
+            check_model_compatibility(
+                &base.model,
+                base.dimension,
+                self.generator.model_name(),
+                self.generator.dimension(),
+            )
+            .context("--base index can't be reused")?;
+            // Equal content only means an equal vector if it was cleaned up
+            // and truncated the same way before embedding
+            if base.input != Some(self.embed_input()) {
+                anyhow::bail!(
+                    "--base index was embedded with {}, this run uses {:?}; re-embed without --base",
+                    base.input.map_or("unknown text settings".to_string(), |input| format!("{:?}", input)),
+                    self.embed_input()
+                );
+            }
+            let before = vector_store.len();
+            for (id, vector) in base.reusable_vectors(&chunks) {
+                vector_store.vectors.entry(id).or_insert(vector);
+            }
+            println!("  [OK] Reusing {} unchanged chunks from --base", vector_store.len() - before);
+        }
+        let reused_chunks = vector_store.len();
+
+        // Rewrite the checkpoint with only the vectors being kept, headed by
+        // what this run embeds with so a later --resume can check it
+        vector_store.header = Some(self.vector_header());
+        vector_store.save_binary(&vectors_bin)?;
+
+        let pending: Vec<Chunk> = chunks.iter()
+            .filter(|c| vector_store.get(&c.id).is_none())
+            .map(|c| self.text_cleanup.for_embedding(c))
+            .collect();
+
+        let embedded_chunks = pending.len();
+        let embed_start = Instant::now();
+        let quiet = self.quiet;
+        let new_vectors = self.generator.generate_vectors(pending, Some(&vectors_bin), |done, total| {
+            if quiet {
+                return;
+            }
+
+            let rate = done as f64 / embed_start.elapsed().as_secs_f64().max(1e-3);
+            let eta = (total - done) as f64 / rate.max(1e-3);
+            print!("\r     Progress: {}/{} ({:.0}%, {:.1} chunks/sec, ETA: {:.0}s)   ",
+                   done, total, done as f64 * 100.0 / total as f64, rate, eta);
+            let _ = std::io::Write::flush(&mut std::io::stdout());
+            if done == total {
+                println!();
+            }
+        })?;
+        vector_store.vectors.extend(new_vectors.vectors);
+        vector_store.truncated = new_vectors.truncated;
+        vector_store.empty = new_vectors.empty;
 
         println!("  [OK] Embeddings generated");
+        if self.aggregate != AggregateMode::None {
+            let mut aggregates = aggregate_chunks(&chunks, &vector_store, self.aggregate);
+            if let Some(types) = &self.chunk_types {
+                aggregates.retain(|(chunk, _)| types.contains(&chunk.chunk_type));
+            }
+            println!("       Aggregates:     {}", aggregates.len());
+            for (chunk, vector) in aggregates {
+                vector_store.add(chunk.id.clone(), vector);
+                chunks.push(chunk);
+            }
+        }
         println!("       Total Vectors:  {}", vector_store.len());
         println!("       Vector Size:    {:.2} MB", vector_store.size_mb());
         println!("       Model:          {}", self.generator.model_name());
         println!("       Dimension:      {}", self.generator.dimension());
-        println!("       Time:           {:.2}s", step_start.elapsed().as_secs_f64());
+        let embedding_time = step_start.elapsed().as_secs_f64();
+        println!("       Time:           {:.2}s", embedding_time);
         println!();
 
         // Step 4: Build index
@@ -123,6 +358,7 @@ impl EmbeddingPipeline {
             self.generator.model_name().to_string(),
             self.generator.dimension(),
         );
+        embedding_index.input = Some(self.embed_input());
 
         for chunk in chunks.clone() {
             if let Some(embedding) = vector_store.get(&chunk.id) {
@@ -132,13 +368,17 @@ impl EmbeddingPipeline {
                     content: chunk.content.clone(),
                     embedding: embedding.clone(),
                     metadata: chunk.metadata.clone(),
-                });
+                    importance_score: chunk.importance_score,
+                    tags: chunk.tags.clone(),
+                    norm: 0.0,
+                })?;
             }
         }
 
         println!("  [OK] Index built successfully");
         println!("       Total Entries:  {}", embedding_index.total_chunks);
-        println!("       Time:           {:.2}s", step_start.elapsed().as_secs_f64());
+        let indexing_time = step_start.elapsed().as_secs_f64();
+        println!("       Time:           {:.2}s", indexing_time);
         println!();
 
         // Step 5: Create context index
@@ -151,7 +391,8 @@ impl EmbeddingPipeline {
         println!("  [OK] Context index created");
         println!("       Tags:           {}", context_index.tags.len());
         println!("       Relationships:  {}", context_index.relationships.len());
-        println!("       Time:           {:.2}s", step_start.elapsed().as_secs_f64());
+        let context_time = step_start.elapsed().as_secs_f64();
+        println!("       Time:           {:.2}s", context_time);
         println!();
 
         // Step 6: Save outputs
@@ -171,7 +412,6 @@ impl EmbeddingPipeline {
         let bin_size = std::fs::metadata(&embeddings_bin)?.len();
         println!("  [OK] embeddings.bin  ({:.2} MB)", bin_size as f64 / 1_048_576.0);
 
-        let vectors_bin = output_dir.join("vectors.bin");
         vector_store.save_binary(&vectors_bin)?;
         let vec_size = std::fs::metadata(&vectors_bin)?.len();
         println!("  [OK] vectors.bin     ({:.2} MB)", vec_size as f64 / 1_048_576.0);
@@ -181,89 +421,49 @@ impl EmbeddingPipeline {
         let ctx_size = std::fs::metadata(&context_json)?.len();
         println!("  [OK] context.json    ({:.2} MB)", ctx_size as f64 / 1_048_576.0);
 
+        let mut report = PipelineReport::from_indices(&embedding_index, &context_index);
+        report.truncated_chunks = vector_store.truncated.len();
+        report.empty_chunks = vector_store.empty.len();
+        report.reused_chunks = reused_chunks;
+        report.embedded_chunks = embedded_chunks.saturating_sub(report.empty_chunks);
+        report.output_files.insert("embeddings.json".to_string(), json_size);
+        report.output_files.insert("embeddings.bin".to_string(), bin_size);
+        report.output_files.insert("vectors.bin".to_string(), vec_size);
+        report.output_files.insert("context.json".to_string(), ctx_size);
+
         println!();
-        println!("       Total Size:     {:.2} MB",
-            (json_size + bin_size + vec_size + ctx_size) as f64 / 1_048_576.0);
-        println!("       Time:           {:.2}s", step_start.elapsed().as_secs_f64());
+        println!("       Total Size:     {:.2} MB", report.total_output_bytes() as f64 / 1_048_576.0);
+        let writing_time = step_start.elapsed().as_secs_f64();
+        println!("       Time:           {:.2}s", writing_time);
         println!();
 
+        report.timings = report::StepTimings {
+            load_kb: load_time,
+            chunking: chunking_time,
+            embedding: embedding_time,
+            indexing: indexing_time,
+            context: context_time,
+            writing: writing_time,
+            total: total_start.elapsed().as_secs_f64(),
+        };
+
         // Final summary
-        print_pipeline_summary(&embedding_index, &context_index, total_start.elapsed().as_secs_f64());
+        report.print_summary();
 
         Ok(EmbeddingPipelineOutput {
             embedding_index,
             vector_store,
             context_index,
+            report,
         })
     }
 }
 
-fn print_pipeline_summary(
-    embedding_index: &EmbeddingIndex,
-    context_index: &ContextIndex,
-    total_time: f64,
-) {
-    println!("{}", "=".repeat(70));
-    println!("  PIPELINE SUMMARY");
-    println!("{}", "=".repeat(70));
-    println!();
-
-    let stats = embedding_index.stats();
-
-    println!("EMBEDDING STATISTICS");
-    println!("{}", "-".repeat(70));
-    println!("  Model:              {}", stats.model);
-    println!("  Dimension:          {}", stats.dimension);
-    println!("  Total Chunks:       {}", stats.total_chunks);
-    println!();
-
-    if !stats.chunk_types.is_empty() {
-        println!("  Chunk Type Distribution:");
-        let mut sorted_types: Vec<_> = stats.chunk_types.iter().collect();
-        sorted_types.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
-        for (chunk_type, count) in sorted_types {
-            let percentage = (*count as f64 / stats.total_chunks as f64) * 100.0;
-            println!("    {:20} {:6} ({:5.1}%)",
-                format!("{}:", chunk_type), count, percentage);
-        }
-        println!();
-    }
-
-    if !stats.languages.is_empty() {
-        println!("  Language Distribution:");
-        let mut sorted_langs: Vec<_> = stats.languages.iter().collect();
-        sorted_langs.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
-        for (lang, count) in sorted_langs {
-            let percentage = (*count as f64 / stats.total_chunks as f64) * 100.0;
-            println!("    {:20} {:6} ({:5.1}%)",
-                format!("{}:", lang), count, percentage);
-        }
-        println!();
-    }
-
-    let context_stats = context_index.stats();
-    println!("CONTEXT INDEX STATISTICS");
-    println!("{}", "-".repeat(70));
-    println!("  Relationships:      {}", context_stats.total_relationships);
-    println!("  Entry Points:       {}", context_stats.entry_points);
-    println!("  Call Graph Depth:   {}", context_stats.call_graph_depth);
-    println!();
-
-    println!("EXECUTION TIME");
-    println!("{}", "-".repeat(70));
-    println!("  Total Time:         {:.2}s", total_time);
-    println!();
-
-    println!("{}", "=".repeat(70));
-    println!("  PIPELINE COMPLETED SUCCESSFULLY");
-    println!("{}", "=".repeat(70));
-    println!();
-}
-
 pub struct EmbeddingPipelineOutput {
     pub embedding_index: EmbeddingIndex,
     pub vector_store: VectorStore,
     pub context_index: ContextIndex,
+    pub report: PipelineReport,
 }
 
 // Query embedding functionality
@@ -272,8 +472,8 @@ pub struct QueryEmbedder {
 }
 
 impl QueryEmbedder {
-    pub fn new(model_name: &str) -> Result<Self> {
-        let generator = EmbeddingGenerator::new(model_name)?;
+    pub fn new(model_name: &str, device: Option<EmbeddingBackend>, cache: ModelCache) -> Result<Self> {
+        let generator = EmbeddingGenerator::new(model_name, device, cache)?;
         Ok(Self { generator })
     }
 
@@ -297,7 +497,7 @@ impl QueryEmbedder {
             importance_score: 0.0,
         };
 
-        let vector_store = self.generator.generate_vectors(vec![query_chunk])?;
+        let vector_store = self.generator.generate_vectors(vec![query_chunk], None, |_, _| {})?;
 
         let embedding = vector_store.get("query")
             .context("Failed to get query embedding")?
@@ -313,6 +513,12 @@ impl QueryEmbedder {
     pub fn model_name(&self) -> &str {
         self.generator.model_name()
     }
+
+    /// Error unless this model's embeddings can be searched against an index
+    /// built with `index_model` at `index_dimension`
+    pub fn check_compatible(&self, index_model: &str, index_dimension: usize) -> Result<()> {
+        check_model_compatibility(index_model, index_dimension, self.model_name(), self.dimension())
+    }
 }
 
 fn print_help() {
@@ -321,15 +527,101 @@ fn print_help() {
     println!("    eulix_embed [COMMAND] [OPTIONS]\n");
     println!("COMMANDS:");
     println!("    embed              Generate embeddings for knowledge base (default)");
-    println!("    query              Generate embedding for a query string\n");
+    println!("    embed-text         Embed a directory of docs or other text files, no knowledge base needed");
+    println!("    query              Generate embedding for a query string");
+    println!("    search             Search an existing embedding index");
+    println!("    serve              Serve POST /search over HTTP until Ctrl-C");
+    println!("    dupes              List near-duplicate functions in an existing index");
+    println!("    locate             Find the chunks covering a source line: locate -f <FILE> -l <LINE>");
+    println!("    merge              Combine indices: merge <OUT> <INDEX>... [--namespace <LIST>]");
+    println!("    compare            Check a JSON index against its binary copy: compare <JSON> <BIN>\n");
     println!("EMBED OPTIONS:");
     println!("    -k, --kb-path <PATH>     Path to knowledge base JSON file");
     println!("    -o, --output <DIR>       Output directory for embeddings");
-    println!("    -m, --model <NAME>       HuggingFace model name or local path\n");
+    println!("    -m, --model <NAME>       HuggingFace model name or local path");
+    println!("        --config <PATH>      Defaults from the [embeddings] table (default: ./eulix.toml if present)");
+    println!("        --max-chunk-size <N> Longest chunk in chars before it is truncated or split (default: 2000)");
+    println!("        --overlap <N>        Split oversized functions into windows overlapping by N chars");
+    println!("        --call-limit <N|all> Calls and callers listed per function (default: 10 calls, 5 callers)");
+    println!("        --quiet              Don't print embedding progress");
+    println!("        --batch-size <N>     Chunks per inference call (default: 32 on CPU, 128 on GPU)");
+    println!("        --threads <N>        Worker threads for chunking and tokenization (default: one per core)");
+    println!("        --resume             Skip chunks already in a partial vectors.bin (no-op if none)");
+    println!("        --base <PATH>        Reuse vectors of unchanged chunks from a previous .json, .ndjson or .bin index");
+    println!("        --json-summary <PATH>  Write timings, chunk counts and output sizes as JSON");
+    println!("        --chunk-types <LIST> Only embed these types: function, class, method, file, directory, entrypoint");
+    println!("        --exclude-tests      Skip functions tagged as tests (test_*, @pytest.*, TestXxx, BenchmarkXxx)");
+    println!("        --strip-headers      Embed chunks without the // File:, // Lines: ... headers (kept in metadata)");
+    println!("        --collapse-whitespace  Embed chunks with every run of whitespace squeezed to one space");
+    println!("        --aggregate <MODE>   Add mean-vector chunks per file/top-level dir: none (default), file, dir, both");
+    println!("    -d, --device <DEVICE>    cpu, cuda, rocm or auto (default: auto)");
+    println!("        --cache-dir <PATH>   Model cache directory (default: $EULIX_CACHE_DIR or HF cache)");
+    println!("        --offline            Only use cached models, never download\n");
+    println!("EMBED-TEXT OPTIONS:");
+    println!("    -i, --input <DIR>        Directory of text files to embed (hidden files are skipped)");
+    println!("    -o, --output <DIR>       Output directory for embeddings (default: ./embeddings)");
+    println!("    -m, --model <NAME>       HuggingFace model name or local path");
+    println!("        --max-chunk-size <N> Window size in chars (default: 2000)");
+    println!("        --overlap <N>        Chars each window repeats from the previous one (default: 200)");
+    println!("        --batch-size <N>     Chunks per inference call (default: 32 on CPU, 128 on GPU)");
+    println!("        --quiet              Don't print embedding progress");
+    println!("    -d, --device <DEVICE>    cpu, cuda, rocm or auto (default: auto)");
+    println!("        --cache-dir <PATH>   Model cache directory (default: $EULIX_CACHE_DIR or HF cache)");
+    println!("        --offline            Only use cached models, never download\n");
     println!("QUERY OPTIONS:");
-    println!("    -q, --query <TEXT>       Query text to embed");
+    println!("    -q, --query <TEXT>       Query text to embed (- or omitted with piped input: read stdin)");
     println!("    -m, --model <NAME>       HuggingFace model name or local path");
-    println!("    -f, --format <FORMAT>    Output format: json (default) or binary\n");
+    println!("    -f, --format <FORMAT>    Output format: json (default) or binary");
+    println!("    -d, --device <DEVICE>    cpu, cuda, rocm or auto (default: auto)");
+    println!("        --cache-dir <PATH>   Model cache directory (default: $EULIX_CACHE_DIR or HF cache)");
+    println!("        --offline            Only use cached models, never download\n");
+    println!("SEARCH OPTIONS:");
+    println!("    -q, --query <TEXT>       Query text to search for (- or omitted with piped input: read stdin);");
+    println!("                             repeat for several phrasings, ranked together");
+    println!("        --weights <LIST>     Comma-separated weight per -q, e.g. 1,0.5 (default: equal)");
+    println!("        --combine <MODE>     mean (default): search with the weighted mean query;");
+    println!("                             max: score each chunk by its best weighted match to any query");
+    println!("    -i, --index <PATH>       embeddings.json, .ndjson or .bin (default: ./embeddings/embeddings.json)");
+    println!("    -k, --top-k <N>          Number of results (default: 5)");
+    println!("    -m, --model <NAME>       Query model, e.g. a local copy of the index's model (default: the index's)");
+    println!("        --rerank             Boost entry points and important chunks (0.8*sim + 0.1*importance + 0.1*type)");
+    println!("        --exclude-tests      Drop chunks context.json tags as tests (needs context.json beside the index)");
+    println!("    -d, --device <DEVICE>    cpu, cuda, rocm or auto (default: auto)");
+    println!("        --cache-dir <PATH>   Model cache directory (default: $EULIX_CACHE_DIR or HF cache)");
+    println!("        --offline            Only use cached models, never download\n");
+    println!("SERVE OPTIONS:");
+    println!("    -i, --index <PATH>       embeddings.json, .ndjson or .bin (default: ./embeddings/embeddings.json)");
+    println!("    -m, --model <NAME>       Query model, e.g. a local copy of the index's model (default: the index's)");
+    println!("        --host <ADDR>        Address to bind (default: 127.0.0.1)");
+    println!("        --port <N>           Port to listen on (default: 8080)");
+    println!("        --query-cache-size <N>  Recent queries kept in memory (default: 256, 0 disables);");
+    println!("                                responses report X-Cache: hit|miss and running totals");
+    println!("    -d, --device <DEVICE>    cpu, cuda, rocm or auto (default: auto)");
+    println!("        --cache-dir <PATH>   Model cache directory (default: $EULIX_CACHE_DIR or HF cache)");
+    println!("        --offline            Only use cached models, never download");
+    println!("    Ctrl-C stops accepting requests, finishes the one in flight and exits cleanly\n");
+    println!("DUPES OPTIONS:");
+    println!("    -i, --index <PATH>       embeddings.json, .ndjson or .bin (default: ./embeddings/embeddings.json)");
+    println!("        --threshold <SIM>    Minimum cosine similarity to report (default: 0.95)\n");
+    println!("LOCATE OPTIONS:");
+    println!("    -i, --index <PATH>       embeddings.json, .ndjson or .bin (default: ./embeddings/embeddings.json)");
+    println!("    -f, --file <PATH>        Source file, relative to the project root or ending in such a path");
+    println!("    -l, --line <N>           1-based line number");
+    println!("    Chunks are listed most specific first, e.g. a method before its class\n");
+    println!("MERGE OPTIONS:");
+    println!("        --namespace <LIST>   Comma-separated prefix per input index, e.g. repoA,repoB (ids become repoA::...)");
+    println!("    All inputs must share a model; without namespaces a repeated id is an error.");
+    println!("    OUT ending in .bin is written in the binary format, .ndjson as one entry per line,");
+    println!("    anything else as JSON\n");
+    println!("COMPARE OPTIONS:");
+    println!("        --tolerance <F>      Largest allowed per-value difference (default: 1e-6; fp16 needs ~1e-3)");
+    println!("        --all                Compare every vector by id, with a histogram and the worst offenders");
+    println!("    Exits non-zero when metadata differs or any vector exceeds the tolerance\n");
+    println!("CONFIG FILE:");
+    println!("    embed reads the [embeddings] table of eulix.toml; keys match the long flags");
+    println!("    (model, backend, kb_path, output, max_chunk_size, overlap, batch_size, threads,");
+    println!("    chunk_types, exclude_tests, strip_headers, collapse_whitespace, aggregate, offline).");
+    println!("    Precedence: flags > eulix.toml > built-in defaults\n");
     println!("GENERAL OPTIONS:");
     println!("    -h, --help               Show this help message");
     println!("    -v, --version            Show version\n");
@@ -340,10 +632,31 @@ fn print_help() {
     println!("EXAMPLES:");
     println!("    # Generate embeddings");
     println!("    eulix_embed embed -k kb.json -o ./embeddings\n");
+    println!("    # Also embed per-file and per-directory summaries");
+    println!("    eulix_embed embed -k kb.json -o ./embeddings --aggregate both\n");
+    println!("    # Only embed chunks that changed since the last run");
+    println!("    eulix_embed embed -k kb.json -o ./embeddings-new --base ./embeddings/embeddings.bin\n");
+    println!("    # Embed project documentation so it can be searched like code");
+    println!("    eulix_embed embed-text -i ./docs -o ./doc-embeddings\n");
     println!("    # Embed a query (JSON output)");
     println!("    eulix_embed query -q \"how does login work\" -m BAAI/bge-small-en-v1.5\n");
     println!("    # Embed a query (binary output)");
-    println!("    eulix_embed query -q \"authentication flow\" -f binary > query.bin");
+    println!("    eulix_embed query -q \"authentication flow\" -f binary > query.bin\n");
+    println!("    # Embed a code snippet piped from another tool");
+    println!("    git diff HEAD~1 | eulix_embed query -q -\n");
+    println!("    # Search an index");
+    println!("    eulix_embed search -q \"how does login work\" -i ./embeddings/embeddings.json -k 5\n");
+    println!("    # Search with several phrasings of the same question");
+    println!("    eulix_embed search -q \"login\" -q \"authenticate user\" --combine max\n");
+    println!("    # Find copy-pasted functions");
+    println!("    eulix_embed dupes -i ./embeddings/embeddings.json --threshold 0.95\n");
+    println!("    # Which chunks cover line 42 of the file open in the editor");
+    println!("    eulix_embed locate -i ./embeddings/embeddings.json -f src/auth/session.py -l 42\n");
+    println!("    # Combine two repositories into one searchable index");
+    println!("    eulix_embed merge all.json a/embeddings.json b/embeddings.json --namespace repoA,repoB\n");
+    println!("    # Serve searches for an editor integration");
+    println!("    eulix_embed serve -i ./embeddings/embeddings.json --port 8080");
+    println!("    curl -d '{{\"query\": \"login\", \"top_k\": 3, \"filters\": {{\"languages\": [\"go\"]}}}}' localhost:8080/search");
 }
 
 fn main() -> Result<()> {
@@ -369,19 +682,14 @@ fn main() -> Result<()> {
 
     match command {
         "query" => run_query_command(&args),
+        "search" => run_search_command(&args),
+        "serve" => run_serve_command(&args),
+        "dupes" => run_dupes_command(&args),
+        "locate" => run_locate_command(&args),
+        "merge" => run_merge_command(&args),
         "embed" => run_embed_command(&args),
-        "compare" => {
-    if args.len() < 4 {
-        eprintln!("Usage: {} compare <json_index.json> <index.bin>", args[0]);
-        std::process::exit(1);
-    }
-
-    let json_path = std::path::Path::new(&args[2]);
-    let bin_path  = std::path::Path::new(&args[3]);
-
-    compare_indices(json_path, bin_path)
-}
-
+        "embed-text" => run_embed_text_command(&args),
+        "compare" => run_compare_command(&args),
         _ => {
             eprintln!("Error: Unknown command '{}'\n", command);
             print_help();
@@ -389,7 +697,52 @@ fn main() -> Result<()> {
         }
     }
 }
-fn compare_indices(json_path: &Path, bin_path: &Path) -> Result<()> {
+fn run_compare_command(args: &[String]) -> Result<()> {
+    let mut paths = Vec::new();
+    let mut tolerance: f32 = 1e-6;
+    let mut compare_all = false;
+
+    let mut i = 2; // Skip program name and "compare" command
+    while i < args.len() {
+        match args[i].as_str() {
+            "--tolerance" => {
+                if i + 1 < args.len() {
+                    tolerance = args[i + 1].parse().unwrap_or_else(|_| {
+                        eprintln!("Error: {} expects a number, got '{}'\n", args[i], args[i + 1]);
+                        std::process::exit(1);
+                    });
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--all" => {
+                compare_all = true;
+                i += 1;
+            }
+            arg if arg.starts_with('-') => {
+                eprintln!("Error: Unknown argument '{}'\n", arg);
+                print_help();
+                std::process::exit(1);
+            }
+            path => {
+                paths.push(path.to_string());
+                i += 1;
+            }
+        }
+    }
+
+    if paths.len() != 2 {
+        eprintln!("Usage: {} compare <json_index.json> <index.bin> [--tolerance <F>] [--all]", args[0]);
+        std::process::exit(1);
+    }
+
+    compare_indices(Path::new(&paths[0]), Path::new(&paths[1]), tolerance, compare_all)
+}
+
+fn compare_indices(json_path: &Path, bin_path: &Path, tolerance: f32, compare_all: bool) -> Result<()> {
     println!("Comparing index files...\n");
 
     // Check files exist
@@ -447,13 +800,38 @@ fn compare_indices(json_path: &Path, bin_path: &Path) -> Result<()> {
         issues.push("Different number of embeddings");
     }
 
+    // --all matches vectors by id below, so only the positional check needs the order to agree
+    println!("IDs:");
+    let id_mismatches: Vec<(usize, &str, &str)> = json_index.embeddings.iter()
+        .zip(bin_index.embeddings.iter())
+        .enumerate()
+        .filter(|(_, (j, b))| j.id != b.id)
+        .map(|(i, (j, b))| (i, j.id.as_str(), b.id.as_str()))
+        .collect();
+    if id_mismatches.is_empty() {
+        println!("✓ Match\n");
+    } else {
+        println!("x {} of {} IDs differ", id_mismatches.len(), json_index.total_chunks.min(bin_index.total_chunks));
+        for (i, json_id, bin_id) in id_mismatches.iter().take(5) {
+            println!("  [{}] JSON: '{}'  Binary: '{}'", i, json_id, bin_id);
+        }
+        println!();
+        if !compare_all {
+            issues.push("Embedding IDs don't match (binary may predate the v3 format)");
+        }
+    }
+
     // Compare first embedding if both exist
     if !json_index.embeddings.is_empty() && !bin_index.embeddings.is_empty() {
         println!("{:-<70}", "");
         println!("First Embedding Comparison:\n");
 
         let json_first = &json_index.embeddings[0];
-        let bin_first = &bin_index.embeddings[0];
+        let bin_first = if compare_all {
+            bin_index.embeddings.iter().find(|entry| entry.id == json_first.id).unwrap_or(&bin_index.embeddings[0])
+        } else {
+            &bin_index.embeddings[0]
+        };
 
         println!("  ID:");
         println!("    JSON:   '{}'", json_first.id);
@@ -501,8 +879,8 @@ fn compare_indices(json_path: &Path, bin_path: &Path) -> Result<()> {
 
             println!("\n  Maximum difference: {:.2e}", max_diff);
 
-            if max_diff < 1e-6 {
-                println!("✓ Vectors match (within tolerance)\n");
+            if max_diff <= tolerance {
+                println!("✓ Vectors match (within tolerance {:.0e})\n", tolerance);
             } else {
                 println!("x Vectors don't match!\n");
                 issues.push("Embedding values are different");
@@ -513,6 +891,54 @@ fn compare_indices(json_path: &Path, bin_path: &Path) -> Result<()> {
         }
     }
 
+    if compare_all {
+        println!("{:-<70}", "");
+        println!("All Embeddings (matched by id, tolerance {:.0e}):\n", tolerance);
+
+        let comparison = json_index.compare_vectors(&bin_index);
+        println!("  Compared pairs:  {}", comparison.diffs.len());
+        println!("  Only in JSON:    {}", comparison.only_left.len());
+        println!("  Only in Binary:  {}", comparison.only_right.len());
+        println!("  Length mismatch: {}", comparison.length_mismatches.len());
+        for (label, ids) in [("JSON", &comparison.only_left), ("Binary", &comparison.only_right)] {
+            for id in ids.iter().take(5) {
+                println!("    only in {}: '{}'", label, id);
+            }
+        }
+        for id in comparison.length_mismatches.iter().take(5) {
+            println!("    length differs: '{}'", id);
+        }
+
+        println!("\n  Max difference per pair:");
+        let buckets = index::VectorComparison::BUCKETS;
+        for (bucket, count) in comparison.histogram().iter().enumerate() {
+            let label = match bucket {
+                0 => "exact".to_string(),
+                b if b < buckets.len() => format!("<= {:.0e}", buckets[b]),
+                _ => format!("> {:.0e}", buckets[buckets.len() - 1]),
+            };
+            println!("    {:>10}  {:>8}", label, count);
+        }
+
+        let exceeding = comparison.exceeding(tolerance);
+        if exceeding.is_empty() {
+            println!("\n✓ Every pair within tolerance\n");
+        } else {
+            println!("\n  Worst offenders:");
+            for (id, diff) in exceeding.iter().take(10) {
+                println!("    {:>10.2e}  {}", diff, id);
+            }
+            println!("\nx {} of {} pairs exceed the tolerance\n", exceeding.len(), comparison.diffs.len());
+        }
+
+        if !exceeding.is_empty() || !comparison.length_mismatches.is_empty() {
+            issues.push("Vectors with the same id differ beyond the tolerance");
+        }
+        if !comparison.only_left.is_empty() || !comparison.only_right.is_empty() {
+            issues.push("Some ids exist in only one of the indices");
+        }
+    }
+
     println!("{:=<70}\n", "");
 
     // Summary
@@ -536,10 +962,27 @@ fn compare_indices(json_path: &Path, bin_path: &Path) -> Result<()> {
 
     Ok(())
 }
+/// Parse a `--device` value; `auto` (None) picks the best provider at startup
+fn parse_device(value: &str) -> Option<EmbeddingBackend> {
+    if value.eq_ignore_ascii_case("auto") {
+        return None;
+    }
+
+    match value.parse() {
+        Ok(backend) => Some(backend),
+        Err(e) => {
+            eprintln!("Error: {}\n", e);
+            std::process::exit(1);
+        }
+    }
+}
+
 fn run_query_command(args: &[String]) -> Result<()> {
     let mut query = String::new();
     let mut model = "sentence-transformers/all-MiniLM-L6-v2".to_string();
     let mut format = "json".to_string();
+    let mut device: Option<EmbeddingBackend> = None;
+    let mut cache = ModelCache::from_env();
 
     // Parse arguments
     let mut i = 2; // Skip program name and "query" command
@@ -575,6 +1018,30 @@ fn run_query_command(args: &[String]) -> Result<()> {
                     std::process::exit(1);
                 }
             }
+            "--device" | "-d" => {
+                if i + 1 < args.len() {
+                    device = parse_device(&args[i + 1]);
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--cache-dir" => {
+                if i + 1 < args.len() {
+                    cache.dir = Some(args[i + 1].clone().into());
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--offline" => {
+                cache.offline = true;
+                i += 1;
+            }
             _ => {
                 eprintln!("Error: Unknown argument '{}'\n", args[i]);
                 print_help();
@@ -583,14 +1050,15 @@ fn run_query_command(args: &[String]) -> Result<()> {
         }
     }
 
+    let query = read_query(query)?;
     if query.is_empty() {
-        eprintln!("Error: --query is required\n");
+        eprintln!("Error: --query is required (pass -q - or pipe the query on stdin)\n");
         print_help();
         std::process::exit(1);
     }
 
     eprintln!("Initializing embedding model: {}", model);
-    let embedder = QueryEmbedder::new(&model)?;
+    let embedder = QueryEmbedder::new(&model, device, cache)?;
 
     eprintln!("Generating embedding for query...");
     let embedding = embedder.embed_query(&query)?;
@@ -626,20 +1094,25 @@ fn run_query_command(args: &[String]) -> Result<()> {
     Ok(())
 }
 
-fn run_embed_command(args: &[String]) -> Result<()> {
-    let mut kb_path = "knowledge_base.json".to_string();
-    let mut output_dir = "./embeddings".to_string();
-    let mut model = "sentence-transformers/all-MiniLM-L6-v2".to_string();
-
-    // Parse arguments (skip "embed" command if present)
-    let start_idx = if args.len() > 1 && args[1] == "embed" { 2 } else { 1 };
-    let mut i = start_idx;
+fn run_search_command(args: &[String]) -> Result<()> {
+    let mut queries: Vec<String> = Vec::new();
+    let mut weights: Vec<f32> = Vec::new();
+    let mut combine = QueryCombine::default();
+    let mut index_path = "./embeddings/embeddings.json".to_string();
+    let mut top_k: usize = 5;
+    let mut model: Option<String> = None;
+    let mut rerank = false;
+    let mut exclude_tests = false;
+    let mut device: Option<EmbeddingBackend> = None;
+    let mut cache = ModelCache::from_env();
 
+    // Parse arguments
+    let mut i = 2; // Skip program name and "search" command
     while i < args.len() {
         match args[i].as_str() {
-            "--kb-path" | "-k" => {
+            "--query" | "-q" => {
                 if i + 1 < args.len() {
-                    kb_path = args[i + 1].clone();
+                    queries.push(args[i + 1].clone());
                     i += 2;
                 } else {
                     eprintln!("Error: {} requires a value\n", args[i]);
@@ -647,9 +1120,40 @@ fn run_embed_command(args: &[String]) -> Result<()> {
                     std::process::exit(1);
                 }
             }
-            "--output" | "-o" => {
+            "--weights" => {
                 if i + 1 < args.len() {
-                    output_dir = args[i + 1].clone();
+                    weights = args[i + 1]
+                        .split(',')
+                        .map(|w| {
+                            w.trim().parse().unwrap_or_else(|_| {
+                                eprintln!("Error: {} expects comma-separated numbers, got '{}'\n", args[i], w);
+                                std::process::exit(1);
+                            })
+                        })
+                        .collect();
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--combine" => {
+                if i + 1 < args.len() {
+                    combine = args[i + 1].parse().unwrap_or_else(|e| {
+                        eprintln!("Error: {}\n", e);
+                        std::process::exit(1);
+                    });
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--index" | "-i" => {
+                if i + 1 < args.len() {
+                    index_path = args[i + 1].clone();
                     i += 2;
                 } else {
                     eprintln!("Error: {} requires a value\n", args[i]);
@@ -659,7 +1163,40 @@ fn run_embed_command(args: &[String]) -> Result<()> {
             }
             "--model" | "-m" => {
                 if i + 1 < args.len() {
-                    model = args[i + 1].clone();
+                    model = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--top-k" | "-k" => {
+                if i + 1 < args.len() {
+                    top_k = args[i + 1].parse().unwrap_or_else(|_| {
+                        eprintln!("Error: {} expects a number, got '{}'\n", args[i], args[i + 1]);
+                        std::process::exit(1);
+                    });
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--device" | "-d" => {
+                if i + 1 < args.len() {
+                    device = parse_device(&args[i + 1]);
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--cache-dir" => {
+                if i + 1 < args.len() {
+                    cache.dir = Some(args[i + 1].clone().into());
                     i += 2;
                 } else {
                     eprintln!("Error: {} requires a value\n", args[i]);
@@ -667,6 +1204,18 @@ fn run_embed_command(args: &[String]) -> Result<()> {
                     std::process::exit(1);
                 }
             }
+            "--rerank" => {
+                rerank = true;
+                i += 1;
+            }
+            "--exclude-tests" => {
+                exclude_tests = true;
+                i += 1;
+            }
+            "--offline" => {
+                cache.offline = true;
+                i += 1;
+            }
             _ => {
                 eprintln!("Error: Unknown argument '{}'\n", args[i]);
                 print_help();
@@ -675,36 +1224,1124 @@ fn run_embed_command(args: &[String]) -> Result<()> {
         }
     }
 
-    println!();
-    println!("{}", "=".repeat(70));
-    println!("  EULIX EMBED - EMBEDDING GENERATOR");
-    println!("{}", "=".repeat(70));
-    println!();
-    println!("CONFIGURATION");
-    println!("{}", "-".repeat(70));
-    println!("  KB Path:         {}", kb_path);
-
-    let abs_path = std::fs::canonicalize(&kb_path)
-        .unwrap_or_else(|_| Path::new(&kb_path).to_path_buf());
-    println!("  Absolute Path:   {:?}", abs_path);
-
-    println!("  Output Dir:      {}", output_dir);
-    println!("  Model:           {}", model);
-    println!();
-
-    if !Path::new(&kb_path).exists() {
-        println!("{}", "=".repeat(70));
-        eprintln!("[ERROR] Knowledge base file not found: {}", kb_path);
-        eprintln!("        Current directory: {:?}", std::env::current_dir().unwrap());
-        eprintln!();
-        eprintln!("[TIP]   Create a knowledge base file or specify the correct path");
-        eprintln!("        using --kb-path option");
-        println!("{}", "=".repeat(70));
+    if queries.is_empty() {
+        queries.push(String::new());
+    }
+    let queries = queries.into_iter().map(read_query).collect::<Result<Vec<_>>>()?;
+    if queries.iter().any(|query| query.is_empty()) {
+        eprintln!("Error: --query is required (pass -q - or pipe the query on stdin)\n");
+        print_help();
         std::process::exit(1);
     }
-
-    let pipeline = EmbeddingPipeline::new(&model)?;
-    pipeline.process(Path::new(&kb_path), Path::new(&output_dir))?;
+    if !weights.is_empty() && weights.len() != queries.len() {
+        eprintln!("Error: --weights needs one weight per -q ({} given for {} queries)\n", weights.len(), queries.len());
+        std::process::exit(1);
+    }
+
+    let index_path = Path::new(&index_path);
+    // Per-chunk tags live in context.json, written next to the index
+    let exclude_ids = if exclude_tests {
+        let context_path = index_path.with_file_name("context.json");
+        let context = ContextIndex::load(&context_path)
+            .with_context(|| format!("--exclude-tests needs {} for chunk tags", context_path.display()))?;
+        Some(context.get_by_tag("test").into_iter().map(|chunk| chunk.id.clone()).collect())
+    } else {
+        None
+    };
+    let filters = SearchFilters {
+        rerank: rerank.then(RerankConfig::default),
+        exclude_ids,
+        ..Default::default()
+    };
+
+    // Binary indices are searched in place through a memory map, so even a
+    // multi-GB embeddings.bin doesn't have to be read into memory first
+    let results = if index_path.extension().is_some_and(|ext| ext == "bin") {
+        if !index_path.exists() {
+            anyhow::bail!("Index file not found: {}", index_path.display());
+        }
+        eprintln!("Mapping index: {}", index_path.display());
+        let index = EmbeddingIndex::load_binary_mmap(index_path)?;
+        let embeddings = embed_for_index(&queries, model.as_deref(), &index.model, index.dimension, device, cache)?;
+        match embeddings.as_slice() {
+            [embedding] => index.search_filtered(embedding, top_k, filters),
+            _ => {
+                let embeddings: Vec<&[f32]> = embeddings.iter().map(Vec::as_slice).collect();
+                index.search_multi_filtered(&embeddings, &weights, top_k, combine, filters)?
+            }
+        }
+    } else {
+        let index = load_index(index_path)?;
+        let embeddings = embed_for_index(&queries, model.as_deref(), &index.model, index.dimension, device, cache)?;
+        match embeddings.as_slice() {
+            [embedding] => index.search_filtered(embedding, top_k, filters),
+            _ => {
+                let embeddings: Vec<&[f32]> = embeddings.iter().map(Vec::as_slice).collect();
+                index.search_multi_filtered(&embeddings, &weights, top_k, combine, filters)?
+            }
+        }
+    };
+    if results.is_empty() {
+        println!("No results.");
+        return Ok(());
+    }
+
+    for (rank, result) in results.iter().enumerate() {
+        let location = match (&result.metadata.file_path, result.metadata.line_start) {
+            (Some(file), Some(line)) => format!("{}:{}", file, line),
+            (Some(file), None) => file.clone(),
+            _ => "-".to_string(),
+        };
+
+        if rerank {
+            println!(
+                "{:>2}. {}  ({})  score {:.4}  similarity {:.4}",
+                rank + 1, result.id, location, result.score, result.similarity
+            );
+        } else {
+            println!("{:>2}. {}  ({})  similarity {:.4}", rank + 1, result.id, location, result.similarity);
+        }
+
+        let snippet: String = result.content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .take(3)
+            .collect::<Vec<_>>()
+            .join("\n      ");
+        println!("      {}\n", snippet.chars().take(300).collect::<String>());
+    }
+
+    Ok(())
+}
+
+/// Embed each query with `query_model` (default: the model the index was
+/// built with), refusing models whose vectors can't be compared against the index
+fn embed_for_index(
+    queries: &[String],
+    query_model: Option<&str>,
+    index_model: &str,
+    index_dimension: usize,
+    device: Option<EmbeddingBackend>,
+    cache: ModelCache,
+) -> Result<Vec<Vec<f32>>> {
+    let model = query_model.unwrap_or(index_model);
+    eprintln!("Initializing embedding model: {}", model);
+    let embedder = QueryEmbedder::new(model, device, cache)?;
+    embedder.check_compatible(index_model, index_dimension)?;
+
+    let mut embeddings = Vec::with_capacity(queries.len());
+    for query in queries {
+        let embedding = embedder.embed_query(query)?;
+        check_model_compatibility(index_model, index_dimension, embedder.model_name(), embedding.len())?;
+        embeddings.push(embedding);
+    }
+
+    Ok(embeddings)
+}
+
+/// `-q -`, or no `-q` with stdin redirected, reads the whole query from
+/// stdin so multi-line snippets can be piped in without shell escaping
+fn read_query(query: String) -> Result<String> {
+    use std::io::{IsTerminal, Read};
+
+    let stdin = std::io::stdin();
+    if query == "-" || (query.is_empty() && !stdin.is_terminal()) {
+        let mut text = String::new();
+        stdin.lock().read_to_string(&mut text).context("Failed to read query from stdin")?;
+        return Ok(text.trim().to_string());
+    }
+
+    Ok(query)
+}
+
+fn load_index(index_path: &Path) -> Result<EmbeddingIndex> {
+    if !index_path.exists() {
+        anyhow::bail!("Index file not found: {}", index_path.display());
+    }
+
+    eprintln!("Loading index: {}", index_path.display());
+    if index_path.extension().is_some_and(|ext| ext == "bin") {
+        EmbeddingIndex::load_binary(index_path)
+    } else if is_ndjson(index_path) {
+        EmbeddingIndex::load_ndjson(index_path)
+    } else {
+        EmbeddingIndex::load(index_path)
+    }
+}
+
+/// `.ndjson`, or `.ndjson.gz` when compressed
+fn is_ndjson(path: &Path) -> bool {
+    let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+    name.ends_with(".ndjson") || name.ends_with(".ndjson.gz")
+}
+
+fn run_dupes_command(args: &[String]) -> Result<()> {
+    let mut index_path = "./embeddings/embeddings.json".to_string();
+    let mut threshold: f32 = 0.95;
+
+    let mut i = 2; // Skip program name and "dupes" command
+    while i < args.len() {
+        match args[i].as_str() {
+            "--index" | "-i" => {
+                if i + 1 < args.len() {
+                    index_path = args[i + 1].clone();
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--threshold" => {
+                if i + 1 < args.len() {
+                    threshold = args[i + 1].parse().unwrap_or_else(|_| {
+                        eprintln!("Error: {} expects a number, got '{}'\n", args[i], args[i + 1]);
+                        std::process::exit(1);
+                    });
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            _ => {
+                eprintln!("Error: Unknown argument '{}'\n", args[i]);
+                print_help();
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let index = load_index(Path::new(&index_path))?;
+    let duplicates = index.find_duplicates(threshold);
+    if duplicates.is_empty() {
+        println!("No function pairs with similarity >= {:.2}.", threshold);
+        return Ok(());
+    }
+
+    // Pairs come back under base ids, so locate them through any of their windows
+    let mut locations = std::collections::HashMap::new();
+    for entry in &index.embeddings {
+        locations.entry(chunker::base_id(&entry.id)).or_insert_with(|| {
+            match (&entry.metadata.file_path, entry.metadata.line_start) {
+                (Some(file), Some(line)) => format!("{}:{}", file, line),
+                (Some(file), None) => file.clone(),
+                _ => "-".to_string(),
+            }
+        });
+    }
+    let location = |id: &str| locations.get(id).cloned().unwrap_or_else(|| "-".to_string());
+
+    println!("{} near-duplicate pairs (similarity >= {:.2}):\n", duplicates.len(), threshold);
+    for (a, b, similarity) in &duplicates {
+        println!("  {:.4}  {}  ({})", similarity, a, location(a));
+        println!("          {}  ({})\n", b, location(b));
+    }
+
+    Ok(())
+}
+
+fn run_locate_command(args: &[String]) -> Result<()> {
+    let mut index_path = "./embeddings/embeddings.json".to_string();
+    let mut file: Option<String> = None;
+    let mut line: Option<usize> = None;
+
+    let mut i = 2; // Skip program name and "locate" command
+    while i < args.len() {
+        match args[i].as_str() {
+            "--index" | "-i" => {
+                if i + 1 < args.len() {
+                    index_path = args[i + 1].clone();
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--file" | "-f" => {
+                if i + 1 < args.len() {
+                    file = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--line" | "-l" => {
+                if i + 1 < args.len() {
+                    line = Some(args[i + 1].parse().unwrap_or_else(|_| {
+                        eprintln!("Error: {} expects a line number, got '{}'\n", args[i], args[i + 1]);
+                        std::process::exit(1);
+                    }));
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            _ => {
+                eprintln!("Error: Unknown argument '{}'\n", args[i]);
+                print_help();
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let (Some(file), Some(line)) = (file, line) else {
+        eprintln!("Error: locate needs --file and --line\n");
+        print_help();
+        std::process::exit(1);
+    };
+
+    let index = load_index(Path::new(&index_path))?;
+    let chunks = index.chunks_at(&file, line);
+    if chunks.is_empty() {
+        println!("No chunks cover {}:{}.", file, line);
+        return Ok(());
+    }
+
+    for entry in chunks {
+        println!(
+            "  {}  {:?} {}  ({}:{}-{})",
+            entry.id,
+            entry.chunk_type,
+            entry.metadata.name,
+            entry.metadata.file_path.as_deref().unwrap_or("-"),
+            entry.metadata.line_start.unwrap_or(0),
+            entry.metadata.line_end.unwrap_or(0)
+        );
+    }
+
+    Ok(())
+}
+
+fn run_merge_command(args: &[String]) -> Result<()> {
+    let mut paths: Vec<String> = Vec::new();
+    let mut namespaces: Option<Vec<String>> = None;
+
+    let mut i = 2; // Skip program name and "merge" command
+    while i < args.len() {
+        match args[i].as_str() {
+            "--namespace" => {
+                if i + 1 < args.len() {
+                    namespaces = Some(args[i + 1].split(',').map(|ns| ns.trim().to_string()).collect());
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            arg if arg.starts_with('-') => {
+                eprintln!("Error: Unknown argument '{}'\n", args[i]);
+                print_help();
+                std::process::exit(1);
+            }
+            _ => {
+                paths.push(args[i].clone());
+                i += 1;
+            }
+        }
+    }
+
+    if paths.len() < 3 {
+        eprintln!("Error: merge needs an output path and at least two input indices\n");
+        print_help();
+        std::process::exit(1);
+    }
+    let output = Path::new(&paths[0]);
+    let inputs = &paths[1..];
+
+    if let Some(ref namespaces) = namespaces {
+        if namespaces.len() != inputs.len() || namespaces.iter().any(|ns| ns.is_empty()) {
+            anyhow::bail!(
+                "--namespace needs one non-empty name per input index ({} given for {} inputs)",
+                namespaces.len(),
+                inputs.len()
+            );
+        }
+    }
+
+    let mut merged: Option<EmbeddingIndex> = None;
+    for (n, input) in inputs.iter().enumerate() {
+        let mut index = load_index(Path::new(input))?;
+        if let Some(ref namespaces) = namespaces {
+            index = index.with_namespace(&namespaces[n]);
+        }
+        match merged {
+            None => merged = Some(index),
+            Some(ref mut merged) => merged
+                .merge(index)
+                .with_context(|| format!("Failed to merge {}", input))?,
+        }
+    }
+    let merged = merged.expect("at least two inputs");
+
+    if output.extension().is_some_and(|ext| ext == "bin") {
+        merged.save_binary(output)?;
+    } else if output.extension().is_some_and(|ext| ext == "ndjson") {
+        merged.save_ndjson(output)?;
+    } else {
+        merged.save(output)?;
+    }
+    println!(
+        "Merged {} indices ({} chunks, {}, {}d) into {}",
+        inputs.len(),
+        merged.total_chunks,
+        merged.model,
+        merged.dimension,
+        output.display()
+    );
+
+    Ok(())
+}
+
+fn run_serve_command(args: &[String]) -> Result<()> {
+    let mut index_path = "./embeddings/embeddings.json".to_string();
+    let mut model: Option<String> = None;
+    let mut host = "127.0.0.1".to_string();
+    let mut port: u16 = 8080;
+    let mut query_cache_size: usize = 256;
+    let mut device: Option<EmbeddingBackend> = None;
+    let mut cache = ModelCache::from_env();
+
+    let mut i = 2; // Skip program name and "serve" command
+    while i < args.len() {
+        match args[i].as_str() {
+            "--index" | "-i" => {
+                if i + 1 < args.len() {
+                    index_path = args[i + 1].clone();
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--model" | "-m" => {
+                if i + 1 < args.len() {
+                    model = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--host" => {
+                if i + 1 < args.len() {
+                    host = args[i + 1].clone();
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--port" => {
+                if i + 1 < args.len() {
+                    port = args[i + 1].parse().unwrap_or_else(|_| {
+                        eprintln!("Error: {} expects a port number, got '{}'\n", args[i], args[i + 1]);
+                        std::process::exit(1);
+                    });
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--query-cache-size" => {
+                if i + 1 < args.len() {
+                    query_cache_size = args[i + 1].parse().unwrap_or_else(|_| {
+                        eprintln!("Error: {} expects a number, got '{}'\n", args[i], args[i + 1]);
+                        std::process::exit(1);
+                    });
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--device" | "-d" => {
+                if i + 1 < args.len() {
+                    device = parse_device(&args[i + 1]);
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--cache-dir" => {
+                if i + 1 < args.len() {
+                    cache.dir = Some(args[i + 1].clone().into());
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--offline" => {
+                cache.offline = true;
+                i += 1;
+            }
+            _ => {
+                eprintln!("Error: Unknown argument '{}'\n", args[i]);
+                print_help();
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let index = load_index(Path::new(&index_path))?;
+
+    let model = model.unwrap_or_else(|| index.model.clone());
+    eprintln!("Initializing embedding model: {}", model);
+    let embedder = QueryEmbedder::new(&model, device, cache)?;
+    embedder.check_compatible(&index.model, index.dimension)?;
+
+    let mut server = server::SearchServer::new(
+        index,
+        |query: &str| embedder.embed_query(query),
+        query_cache_size,
+    );
+    server.run(&format!("{}:{}", host, port))
+}
+
+fn run_embed_text_command(args: &[String]) -> Result<()> {
+    let mut input: Option<String> = None;
+    let mut output_dir = "./embeddings".to_string();
+    let mut model = "sentence-transformers/all-MiniLM-L6-v2".to_string();
+    let mut max_chunk_size = 2000;
+    let mut overlap = 200;
+    let mut batch_size: Option<usize> = None;
+    let mut quiet = false;
+    let mut device: Option<EmbeddingBackend> = None;
+    let mut cache = ModelCache::from_env();
+
+    let mut i = 2; // Skip program name and "embed-text" command
+    while i < args.len() {
+        match args[i].as_str() {
+            "--input" | "-i" => {
+                if i + 1 < args.len() {
+                    input = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--output" | "-o" => {
+                if i + 1 < args.len() {
+                    output_dir = args[i + 1].clone();
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--model" | "-m" => {
+                if i + 1 < args.len() {
+                    model = args[i + 1].clone();
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--max-chunk-size" | "--overlap" | "--batch-size" => {
+                if i + 1 < args.len() {
+                    let value: usize = args[i + 1].parse().unwrap_or_else(|_| {
+                        eprintln!("Error: {} expects a number, got '{}'\n", args[i], args[i + 1]);
+                        std::process::exit(1);
+                    });
+                    match args[i].as_str() {
+                        "--max-chunk-size" => max_chunk_size = value,
+                        "--overlap" => overlap = value,
+                        _ => batch_size = Some(value),
+                    }
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--device" | "-d" => {
+                if i + 1 < args.len() {
+                    device = parse_device(&args[i + 1]);
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--cache-dir" => {
+                if i + 1 < args.len() {
+                    cache.dir = Some(args[i + 1].clone().into());
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--offline" => {
+                cache.offline = true;
+                i += 1;
+            }
+            "--quiet" => {
+                quiet = true;
+                i += 1;
+            }
+            _ => {
+                eprintln!("Error: Unknown argument '{}'\n", args[i]);
+                print_help();
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let Some(input) = input else {
+        eprintln!("Error: embed-text requires --input <DIR>\n");
+        print_help();
+        std::process::exit(1);
+    };
+    if !Path::new(&input).is_dir() {
+        anyhow::bail!("Input directory not found: {}", input);
+    }
+
+    let mut generator = EmbeddingGenerator::new(&model, device, cache)?;
+    if let Some(batch_size) = batch_size {
+        generator = generator.with_batch_size(batch_size);
+    }
+
+    let chunks = chunk_text_dir(Path::new(&input), max_chunk_size, overlap, generator.tokenizer())?;
+    if chunks.is_empty() {
+        anyhow::bail!("No text files found under {}", input);
+    }
+    let files: std::collections::HashSet<_> = chunks.iter().filter_map(|c| c.metadata.file_path.as_ref()).collect();
+    println!("Embedding {} chunks from {} files in {}", chunks.len(), files.len(), input);
+
+    let vectors = generator.generate_vectors(chunks.clone(), None, |done, total| {
+        if !quiet {
+            print!("\r     Progress: {}/{}   ", done, total);
+            let _ = std::io::Write::flush(&mut std::io::stdout());
+            if done == total {
+                println!();
+            }
+        }
+    })?;
+
+    let mut index = EmbeddingIndex::new(generator.model_name().to_string(), generator.dimension());
+    for chunk in chunks {
+        if let Some(embedding) = vectors.get(&chunk.id) {
+            index.add_entry(EmbeddingEntry {
+                embedding: embedding.clone(),
+                id: chunk.id,
+                chunk_type: chunk.chunk_type,
+                content: chunk.content,
+                metadata: chunk.metadata,
+                importance_score: chunk.importance_score,
+                tags: chunk.tags,
+                norm: 0.0,
+            })?;
+        }
+    }
+
+    let output_dir = Path::new(&output_dir);
+    std::fs::create_dir_all(output_dir)?;
+    index.save(&output_dir.join("embeddings.json"))?;
+    index.save_binary(&output_dir.join("embeddings.bin"))?;
+    println!(
+        "  [OK] {} entries ({}, {}d) written to {}",
+        index.total_chunks,
+        index.model,
+        index.dimension,
+        output_dir.display()
+    );
+    if !vectors.truncated.is_empty() {
+        println!("  [!] {} chunks truncated at token limit.", vectors.truncated.len());
+    }
+    if !vectors.empty.is_empty() {
+        println!("  [!] {} empty chunks skipped.", vectors.empty.len());
+    }
+
+    Ok(())
+}
+
+fn run_embed_command(args: &[String]) -> Result<()> {
+    // eulix.toml supplies the starting values; flags parsed below override them
+    let config_path = args.iter().position(|a| a == "--config").and_then(|i| args.get(i + 1));
+    let config = EmbedConfig::discover(config_path.map(String::as_str))?;
+
+    let mut chunk_types: Option<Vec<ChunkType>> = config.chunk_types()?;
+    let mut aggregate = config.aggregate()?;
+    let mut device: Option<EmbeddingBackend> = config.device()?;
+    let mut kb_path = config.kb_path.unwrap_or_else(|| "knowledge_base.json".to_string());
+    let mut output_dir = config.output.unwrap_or_else(|| "./embeddings".to_string());
+    let mut model = config.model.unwrap_or_else(|| "sentence-transformers/all-MiniLM-L6-v2".to_string());
+    let mut max_chunk_size: Option<usize> = config.max_chunk_size;
+    let mut overlap: Option<usize> = config.overlap;
+    let mut call_limit: Option<Option<usize>> = None;
+    let mut quiet = false;
+    let mut batch_size: Option<usize> = config.batch_size;
+    let mut threads: Option<usize> = config.threads;
+    let mut base: Option<String> = None;
+    let mut resume = false;
+    let mut json_summary: Option<String> = None;
+    let mut exclude_tests = config.exclude_tests.unwrap_or(false);
+    let mut text_cleanup = TextCleanup {
+        strip_headers: config.strip_headers.unwrap_or(false),
+        collapse_whitespace: config.collapse_whitespace.unwrap_or(false),
+    };
+    let mut cache = ModelCache::from_env();
+    cache.offline |= config.offline.unwrap_or(false);
+
+    // Parse arguments (skip "embed" command if present)
+    let start_idx = if args.len() > 1 && args[1] == "embed" { 2 } else { 1 };
+    let mut i = start_idx;
+
+    while i < args.len() {
+        match args[i].as_str() {
+            "--kb-path" | "-k" => {
+                if i + 1 < args.len() {
+                    kb_path = args[i + 1].clone();
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--output" | "-o" => {
+                if i + 1 < args.len() {
+                    output_dir = args[i + 1].clone();
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--model" | "-m" => {
+                if i + 1 < args.len() {
+                    model = args[i + 1].clone();
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--config" => {
+                // Already loaded above
+                if i + 1 < args.len() {
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--max-chunk-size" => {
+                if i + 1 < args.len() {
+                    max_chunk_size = Some(args[i + 1].parse().unwrap_or_else(|_| {
+                        eprintln!("Error: {} expects a number, got '{}'\n", args[i], args[i + 1]);
+                        std::process::exit(1);
+                    }));
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--overlap" => {
+                if i + 1 < args.len() {
+                    overlap = Some(args[i + 1].parse().unwrap_or_else(|_| {
+                        eprintln!("Error: {} expects a number, got '{}'\n", args[i], args[i + 1]);
+                        std::process::exit(1);
+                    }));
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--call-limit" => {
+                if i + 1 < args.len() {
+                    call_limit = Some(match args[i + 1].as_str() {
+                        "all" => None,
+                        value => Some(value.parse().unwrap_or_else(|_| {
+                            eprintln!("Error: {} expects a number or 'all', got '{}'\n", args[i], value);
+                            std::process::exit(1);
+                        })),
+                    });
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--device" | "-d" => {
+                if i + 1 < args.len() {
+                    device = parse_device(&args[i + 1]);
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--cache-dir" => {
+                if i + 1 < args.len() {
+                    cache.dir = Some(args[i + 1].clone().into());
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--offline" => {
+                cache.offline = true;
+                i += 1;
+            }
+            "--quiet" => {
+                quiet = true;
+                i += 1;
+            }
+            "--batch-size" => {
+                if i + 1 < args.len() {
+                    batch_size = Some(args[i + 1].parse().unwrap_or_else(|_| {
+                        eprintln!("Error: {} expects a number, got '{}'\n", args[i], args[i + 1]);
+                        std::process::exit(1);
+                    }));
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--base" => {
+                if i + 1 < args.len() {
+                    base = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--threads" => {
+                if i + 1 < args.len() {
+                    threads = Some(args[i + 1].parse().ok().filter(|&n: &usize| n > 0).unwrap_or_else(|| {
+                        eprintln!("Error: {} expects a positive number, got '{}'\n", args[i], args[i + 1]);
+                        std::process::exit(1);
+                    }));
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--resume" => {
+                resume = true;
+                i += 1;
+            }
+            "--exclude-tests" => {
+                exclude_tests = true;
+                i += 1;
+            }
+            "--strip-headers" => {
+                text_cleanup.strip_headers = true;
+                i += 1;
+            }
+            "--collapse-whitespace" => {
+                text_cleanup.collapse_whitespace = true;
+                i += 1;
+            }
+            "--json-summary" => {
+                if i + 1 < args.len() {
+                    json_summary = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--chunk-types" => {
+                if i + 1 < args.len() {
+                    let parsed: Result<Vec<ChunkType>, String> = args[i + 1]
+                        .split(',')
+                        .filter(|name| !name.trim().is_empty())
+                        .map(str::parse)
+                        .collect();
+                    let parsed = parsed.unwrap_or_else(|e| {
+                        eprintln!("Error: {}: {}\n", args[i], e);
+                        std::process::exit(1);
+                    });
+                    if parsed.is_empty() {
+                        eprintln!("Error: {} expects at least one chunk type\n", args[i]);
+                        std::process::exit(1);
+                    }
+                    chunk_types = Some(parsed);
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            "--aggregate" => {
+                if i + 1 < args.len() {
+                    aggregate = args[i + 1].parse().unwrap_or_else(|e| {
+                        eprintln!("Error: {}: {}\n", args[i], e);
+                        std::process::exit(1);
+                    });
+                    i += 2;
+                } else {
+                    eprintln!("Error: {} requires a value\n", args[i]);
+                    print_help();
+                    std::process::exit(1);
+                }
+            }
+            _ => {
+                eprintln!("Error: Unknown argument '{}'\n", args[i]);
+                print_help();
+                std::process::exit(1);
+            }
+        }
+    }
+
+    println!();
+    println!("{}", "=".repeat(70));
+    println!("  EULIX EMBED - EMBEDDING GENERATOR");
+    println!("{}", "=".repeat(70));
+    println!();
+    println!("CONFIGURATION");
+    println!("{}", "-".repeat(70));
+    println!("  KB Path:         {}", kb_path);
+
+    let abs_path = std::fs::canonicalize(&kb_path)
+        .unwrap_or_else(|_| Path::new(&kb_path).to_path_buf());
+    println!("  Absolute Path:   {:?}", abs_path);
+
+    println!("  Output Dir:      {}", output_dir);
+    println!("  Model:           {}", model);
+    println!("  Device:          {}", device.map(|d| d.description()).unwrap_or("auto"));
+    if let Some(dir) = &cache.dir {
+        println!("  Model Cache:     {}", dir.display());
+    }
+    if cache.offline {
+        println!("  Offline:         yes");
+    }
+    if let Some(size) = max_chunk_size {
+        println!("  Max Chunk Size:  {} chars", size);
+    }
+    if let Some(overlap) = overlap {
+        println!("  Window Overlap:  {} chars", overlap);
+    }
+    match call_limit {
+        Some(Some(limit)) => println!("  Call Limit:      {}", limit),
+        Some(None) => println!("  Call Limit:      all"),
+        None => {}
+    }
+    if let Some(types) = &chunk_types {
+        let names: Vec<String> = types.iter().map(|t| format!("{:?}", t)).collect();
+        println!("  Chunk Types:     {}", names.join(", "));
+    }
+    if exclude_tests {
+        println!("  Exclude Tests:   yes");
+    }
+    if text_cleanup.strip_headers {
+        println!("  Strip Headers:   yes");
+    }
+    if text_cleanup.collapse_whitespace {
+        println!("  Collapse Spaces: yes");
+    }
+    if aggregate != AggregateMode::None {
+        println!("  Aggregate:       {:?}", aggregate);
+    }
+    if let Some(threads) = threads {
+        println!("  Threads:         {}", threads);
+    }
+    if let Some(base) = &base {
+        println!("  Base Index:      {}", base);
+    }
+    println!();
+
+    if !Path::new(&kb_path).exists() {
+        println!("{}", "=".repeat(70));
+        eprintln!("[ERROR] Knowledge base file not found: {}", kb_path);
+        eprintln!("        Current directory: {:?}", std::env::current_dir().unwrap());
+        eprintln!();
+        eprintln!("[TIP]   Create a knowledge base file or specify the correct path");
+        eprintln!("        using --kb-path option");
+        println!("{}", "=".repeat(70));
+        std::process::exit(1);
+    }
+
+    // Chunking and tokenization both run on the global rayon pool
+    if let Some(threads) = threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .context("Failed to configure the rayon thread pool")?;
+    }
+
+    let mut pipeline = EmbeddingPipeline::new(&model, device, cache)?
+        .with_quiet(quiet)
+        .with_resume(resume)
+        .with_exclude_tests(exclude_tests)
+        .with_text_cleanup(text_cleanup)
+        .with_aggregate(aggregate);
+    if let Some(size) = max_chunk_size {
+        pipeline = pipeline.with_max_chunk_size(size);
+    }
+    if let Some(overlap) = overlap {
+        pipeline = pipeline.with_overlap(overlap);
+    }
+    if let Some(limit) = call_limit {
+        pipeline = pipeline.with_call_limit(limit);
+    }
+    if let Some(batch_size) = batch_size {
+        pipeline = pipeline.with_batch_size(batch_size);
+    }
+    if let Some(chunk_types) = chunk_types {
+        pipeline = pipeline.with_chunk_types(chunk_types);
+    }
+    if let Some(base) = base {
+        pipeline = pipeline.with_base(load_index(Path::new(&base))?);
+    }
+    let output = pipeline.process(Path::new(&kb_path), Path::new(&output_dir))?;
+
+    if let Some(path) = json_summary {
+        output.report.save(Path::new(&path))
+            .with_context(|| format!("Failed to write JSON summary to {}", path))?;
+        println!("  [OK] JSON summary written to {}", path);
+    }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Knowledge base with `login` and `logout` in auth.py
+    fn write_kb(path: &Path, login_docstring: &str) {
+        let function = |name: &str, docstring: &str, line: usize| {
+            serde_json::json!({
+                "id": format!("func_{}", name), "name": name, "signature": format!("def {}(user)", name),
+                "params": [], "return_type": "", "docstring": docstring, "line_start": line, "line_end": line + 2
+            })
+        };
+        let kb = serde_json::json!({
+            "metadata": {
+                "schema_version": kb_loader::SCHEMA_VERSION,
+                "project_name": "demo", "version": "1", "parsed_at": "", "languages": ["python"],
+                "total_files": 1, "total_loc": 6, "total_functions": 2, "total_classes": 0, "total_methods": 0
+            },
+            "structure": {
+                "auth.py": {
+                    "language": "python", "loc": 6, "imports": [], "classes": [], "global_vars": [],
+                    "functions": [function("login", login_docstring, 1), function("logout", "Log a user out.", 4)]
+                }
+            },
+            "call_graph": { "nodes": [], "edges": [] },
+            "dependency_graph": { "nodes": [], "edges": [] },
+            "indices": {},
+            "entry_points": [],
+            "external_dependencies": [],
+            "patterns": { "architecture_style": null }
+        });
+        std::fs::write(path, kb.to_string()).unwrap();
+    }
+
+    fn pipeline() -> EmbeddingPipeline {
+        EmbeddingPipeline::new("test-model", Some(EmbeddingBackend::Dummy), ModelCache::from_env())
+            .unwrap()
+            .with_quiet(true)
+    }
+
+    #[test]
+    fn test_base_reembeds_only_changed_chunks() {
+        let dir = std::env::temp_dir().join(format!("eulix_pipeline_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let kb_path = dir.join("kb.json");
+
+        write_kb(&kb_path, "Log a user in.");
+        let first = pipeline().process(&kb_path, &dir.join("v1")).unwrap();
+        let before: HashMap<String, (String, Vec<f32>)> = first
+            .embedding_index
+            .embeddings
+            .iter()
+            .map(|e| (e.id.clone(), (e.content.clone(), e.embedding.clone())))
+            .collect();
+
+        write_kb(&kb_path, "Log a user in with a password.");
+        let second = pipeline().with_base(first.embedding_index).process(&kb_path, &dir.join("v2"));
+        let stripped = pipeline()
+            .with_text_cleanup(TextCleanup { strip_headers: true, collapse_whitespace: false })
+            .with_base(EmbeddingIndex::load_binary(&dir.join("v1/embeddings.bin")).unwrap())
+            .process(&kb_path, &dir.join("v3"));
+        std::fs::remove_dir_all(&dir).unwrap();
+        let second = second.unwrap();
+
+        let changed: Vec<&str> = second
+            .embedding_index
+            .embeddings
+            .iter()
+            .filter(|e| before[&e.id].0 != e.content)
+            .map(|e| e.id.as_str())
+            .collect();
+        assert_eq!(changed, vec!["func_login"]);
+        assert_eq!(second.report.embedded_chunks, 1);
+        assert_eq!(second.report.reused_chunks, before.len() - 1);
+        for entry in &second.embedding_index.embeddings {
+            assert_eq!(entry.embedding == before[&entry.id].1, entry.id != "func_login", "{}", entry.id);
+        }
+
+        // Stored content is what went in before cleanup, so other settings can't reuse it
+        let error = stripped.err().unwrap().to_string();
+        assert!(error.contains("--base index was embedded with"), "{}", error);
+    }
+
+    #[test]
+    fn test_resume_refuses_checkpoint_from_other_settings() {
+        let dir = std::env::temp_dir().join(format!("eulix_resume_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let kb_path = dir.join("kb.json");
+        write_kb(&kb_path, "Log a user in.");
+
+        let first = pipeline().process(&kb_path, &dir).map(|output| output.report);
+        let resumed = pipeline().with_resume(true).process(&kb_path, &dir).map(|output| output.report);
+        let resized = pipeline().with_resume(true).with_max_chunk_size(500).process(&kb_path, &dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        let (first, resumed) = (first.unwrap(), resumed.unwrap());
+
+        assert_eq!(resumed.embedded_chunks, 0);
+        assert_eq!(resumed.reused_chunks, first.embedded_chunks);
+        let error = resized.err().unwrap().to_string();
+        assert!(error.contains("re-embed without --resume"), "{}", error);
+    }
+}
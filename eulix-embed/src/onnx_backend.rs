@@ -1,5 +1,6 @@
 use anyhow::{anyhow, Result};
 use ndarray::{Array2, Axis};
+use rayon::prelude::*;
 use ort::session::builder::GraphOptimizationLevel;
 use ort::session::Session;
 use ort::value::Value;
@@ -8,7 +9,8 @@ use std::path::PathBuf;
 use std::sync::Mutex;
 use std::sync::atomic::{AtomicUsize, Ordering};  // ADD THIS
 
-use crate::embedder::EmbedderConfig;
+use crate::chunker::MAX_MODEL_TOKENS;
+use crate::embedder::{EmbedderConfig, ModelCache, PoolingStrategy};
 
 #[derive(Debug, Clone, Copy)]
 pub enum DeviceType {
@@ -17,12 +19,32 @@ pub enum DeviceType {
     Cpu,
 }
 
-#[derive(Debug, Clone, Copy)]
+/// Which inputs the ONNX graph takes, read from the session rather than
+/// guessed from the model name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum ModelType {
-    // Bert,
-    // Sentence,
+    /// BERT-style graphs that also declare `token_type_ids`
     Standard,
-    MPNet,
+    /// `input_ids` and `attention_mask` only (MPNet, many MiniLM exports)
+    NoTokenTypes,
+}
+
+/// A tokenized, padded batch from `OnnxBackend::prepare_batch`, row-major
+/// `[rows, seq_len]`
+pub struct TokenBatch {
+    input_ids: Vec<i64>,
+    attention_mask: Vec<i64>,
+    token_type_ids: Vec<i64>,
+    /// One entry per row: whether it was cut at `max_tokens`
+    truncated: Vec<bool>,
+    seq_len: usize,
+}
+
+/// Session output for a `TokenBatch`: `[rows, seq_len, hidden_dim]` token
+/// states, or `[rows, hidden_dim]` for pooled outputs
+pub struct HiddenStates {
+    data: Vec<f32>,
+    hidden_dim: usize,
 }
 
 pub struct OnnxBackend {
@@ -31,20 +53,22 @@ pub struct OnnxBackend {
     dimension: AtomicUsize,  // CHANGED: was usize, now AtomicUsize
     normalize: bool,
     model_type: ModelType,
+    pooling: PoolingStrategy,
+    /// Graph output read for `PoolingStrategy::Pooled`
+    pooled_output: Option<String>,
+    /// `EmbedderConfig::max_tokens` capped at the model's own limit
+    max_tokens: usize,
 }
 
 impl OnnxBackend {
     pub fn new(config: &EmbedderConfig, device_type: DeviceType) -> Result<Self> {
         println!("     Loading ONNX model...");
 
-        let model_type = Self::detect_model_type(&config.model_name);
-        println!("     Detected model type: {:?}", model_type);
-
         // Start with config dimension, but we'll update it on first inference
         let dimension = config.dimension;
         println!("     Initial dimension (from config): {}", dimension);
 
-        let model_path = Self::download_model(&config.model_name)?;
+        let model_path = Self::download_model(config)?;
         let model_bytes = std::fs::read(&model_path)
             .map_err(|e| anyhow!("Failed to read model file: {}", e))?;
 
@@ -98,23 +122,52 @@ impl OnnxBackend {
 
         println!("     Device initialized: {:?}", device_type);
 
+        let input_names: Vec<&str> = session.inputs.iter().map(|i| i.name.as_str()).collect();
+        let model_type = Self::detect_model_type(&input_names)?;
+        println!("     Detected model type: {:?}", model_type);
+
+        let output_names: Vec<&str> = session.outputs.iter().map(|o| o.name.as_str()).collect();
+        let pooled_output = ["sentence_embedding", "pooler_output"]
+            .into_iter()
+            .find(|name| output_names.contains(name))
+            .map(str::to_string);
+
+        let pooling = match config.pooling {
+            Some(PoolingStrategy::Pooled) if pooled_output.is_none() => {
+                return Err(anyhow!(
+                    "Pooled output requested but the model has no sentence_embedding or pooler_output. Available outputs: {:?}",
+                    output_names
+                ));
+            }
+            Some(pooling) => pooling,
+            None if output_names.contains(&"sentence_embedding") => PoolingStrategy::Pooled,
+            None => PoolingStrategy::Mean,
+        };
+        println!("     Pooling: {:?}", pooling);
+
         let tokenizer_path = if let Some(ref local_path) = config.model_path {
             println!("     Using local tokenizer from: {:?}", local_path);
             local_path.join("tokenizer.json")
         } else {
-            println!("     Downloading tokenizer from HuggingFace Hub...");
-            let api = hf_hub::api::sync::Api::new()
-                .map_err(|e| anyhow!("Failed to initialize HuggingFace API: {}. Try setting HF_HOME env variable", e))?;
-
-            let repo_api = api.model(config.model_name.clone());
-            repo_api.get("tokenizer.json")
-                .map_err(|e| anyhow!("Failed to download tokenizer.json: {}", e))?
+            fetch_model_file(&config.cache, &config.model_name, &["tokenizer.json"])
+                .map_err(|e| anyhow!("Failed to get tokenizer.json: {}", e))?
         };
 
         println!("     Loading tokenizer...");
-        let tokenizer = Tokenizer::from_file(tokenizer_path)
+        let mut tokenizer = Tokenizer::from_file(tokenizer_path)
             .map_err(|e| anyhow!("Failed to load tokenizer: {}", e))?;
 
+        // A tokenizer.json truncation setting is the model's real limit. Turn it
+        // off and cut ourselves so truncated chunks can be counted.
+        let model_max_tokens = tokenizer
+            .get_truncation()
+            .map_or(MAX_MODEL_TOKENS, |t| t.max_length.min(MAX_MODEL_TOKENS));
+        tokenizer
+            .with_truncation(None)
+            .map_err(|e| anyhow!("Failed to configure tokenizer: {}", e))?;
+        let max_tokens = config.max_tokens.clamp(1, model_max_tokens);
+        println!("     Max tokens: {}", max_tokens);
+
         println!("     ONNX model loaded successfully!");
 
         Ok(Self {
@@ -123,43 +176,43 @@ impl OnnxBackend {
             dimension: AtomicUsize::new(dimension),  // CHANGED: wrap in AtomicUsize
             normalize: config.normalize,
             model_type,
+            pooling,
+            pooled_output,
+            max_tokens,
         })
     }
 
-    fn detect_model_type(model_name: &str) -> ModelType {
-        let name_lower = model_name.to_lowercase();
+    /// Pass `token_type_ids` only when the graph declares it; feeding an
+    /// input the graph doesn't have fails inference with a tensor error
+    fn detect_model_type(input_names: &[&str]) -> Result<ModelType> {
+        if let Some(missing) = ["input_ids", "attention_mask"].iter().find(|name| !input_names.contains(name)) {
+            return Err(anyhow!(
+                "Model has no {} input; eulix_embed needs input_ids and attention_mask. Available inputs: {:?}",
+                missing,
+                input_names
+            ));
+        }
 
-        if name_lower.contains("mpnet") {
-            ModelType::MPNet
+        if input_names.contains(&"token_type_ids") {
+            Ok(ModelType::Standard)
         } else {
-            ModelType::Standard
+            Ok(ModelType::NoTokenTypes)
         }
-        // } else if name_lower.contains("minilm") || name_lower.contains("all-minilm") {
-            // ModelType::Sentence
-        // } else {
-            // ModelType::Bert
-        // }
     }
 
-    fn download_model(model_name: &str) -> Result<PathBuf> {
-        println!("     Downloading ONNX model from HuggingFace Hub...");
-
-        let api = hf_hub::api::sync::Api::new()
-            .map_err(|e| anyhow!("Failed to initialize HuggingFace API: {}", e))?;
+    fn download_model(config: &EmbedderConfig) -> Result<PathBuf> {
+        let model_path = fetch_model_file(&config.cache, &config.model_name, &["onnx/model.onnx", "model.onnx"])
+            .map_err(|e| anyhow!("Failed to get ONNX model: {}. Make sure the model has an ONNX version available.", e))?;
 
-        let repo_api = api.model(model_name.to_string());
-
-        let model_path = repo_api.get("onnx/model.onnx")
-            .or_else(|_| repo_api.get("model.onnx"))
-            .map_err(|e| anyhow!("Failed to download ONNX model: {}. Make sure the model has an ONNX version available.", e))?;
-
-        println!("     Model downloaded successfully");
+        println!("     Model ready: {}", model_path.display());
         Ok(model_path)
     }
 
-    pub fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
-        const MAX_TOKENS: usize = 512;
+    pub fn tokenizer(&self) -> &Tokenizer {
+        &self.tokenizer
+    }
 
+    pub fn generate_embedding(&self, text: &str) -> Result<Vec<f32>> {
         let encoding = self
             .tokenizer
             .encode(text, true)
@@ -169,10 +222,10 @@ impl OnnxBackend {
         let mut attention_mask = encoding.get_attention_mask().to_vec();
         let mut token_type_ids = encoding.get_type_ids().to_vec();
 
-        if input_ids.len() > MAX_TOKENS {
-            input_ids.truncate(MAX_TOKENS);
-            attention_mask.truncate(MAX_TOKENS);
-            token_type_ids.truncate(MAX_TOKENS);
+        if input_ids.len() > self.max_tokens {
+            input_ids.truncate(self.max_tokens);
+            attention_mask.truncate(self.max_tokens);
+            token_type_ids.truncate(self.max_tokens);
         }
 
         let seq_len = input_ids.len();
@@ -191,8 +244,7 @@ impl OnnxBackend {
             .map_err(|e| anyhow!("Failed to lock session: {}", e))?;
 
         let outputs = match self.model_type {
-            ModelType::MPNet => {
-                // MPNet: only needs input_ids and attention_mask
+            ModelType::NoTokenTypes => {
                 let inputs = ort::inputs![
                     "input_ids" => input_ids_value,
                     "attention_mask" => attention_mask_value,
@@ -201,7 +253,6 @@ impl OnnxBackend {
                     .map_err(|e| anyhow!("Failed to run inference: {:?}", e))?
             }
             ModelType::Standard => {
-                // Standard BERT-like models: need token_type_ids too
                 let token_type_ids_value = Value::from_array(([1, seq_len], token_type_ids_i64))
                     .map_err(|e| anyhow!("Failed to create token_type_ids tensor: {:?}", e))?;
 
@@ -215,7 +266,7 @@ impl OnnxBackend {
             }
         };
 
-        let output_name = "last_hidden_state";
+        let output_name = self.output_name();
 
         let Ok((output_shape, embeddings_data)) = outputs
             .get(output_name)
@@ -230,62 +281,40 @@ impl OnnxBackend {
                     available
                 )
             })?
-            .try_extract_tensor::<f32>() else { todo!() };
-
-
-            // Get actual dimension from model output
-        let actual_hidden_dim = if output_shape.len() == 3 {
-            output_shape[2] as usize
-        } else {
-            return Err(anyhow!(
-                "Unexpected output shape dimensions: {:?}. Expected [batch, seq_len, hidden_dim]",
-                output_shape
-            ));
-        };
-
-        // Update stored dimension if this is the first time we see the real value
-        let stored_dim = self.dimension.load(Ordering::Relaxed);
-        if actual_hidden_dim != stored_dim {
-            println!(
-                "     ✓ Actual model dimension: {}d (config estimated: {}d)",
-                actual_hidden_dim, stored_dim
-            );
-            self.dimension.store(actual_hidden_dim, Ordering::Relaxed);
-        }
-
-        let expected_elements = seq_len * actual_hidden_dim;
+            .try_extract_tensor::<f32>() else {
+                return Err(anyhow!("Failed to extract tensor"));
+            };
 
-        if embeddings_data.len() != expected_elements {
+        // Pooled outputs are [batch, hidden_dim], token outputs [batch, seq_len, hidden_dim]
+        let expected_rank = if self.pooling == PoolingStrategy::Pooled { 2 } else { 3 };
+        if output_shape.len() != expected_rank {
             return Err(anyhow!(
-                "Unexpected embedding shape. Expected {} elements ({}x{}), got {}. Output shape: {:?}",
-                expected_elements,
-                seq_len,
-                actual_hidden_dim,
-                embeddings_data.len(),
-                output_shape
+                "Unexpected output shape dimensions: {:?} for '{}'",
+                output_shape,
+                output_name
             ));
         }
+        let actual_hidden_dim = output_shape[expected_rank - 1] as usize;
+        self.record_dimension(actual_hidden_dim);
 
-        let embeddings = Array2::from_shape_vec((seq_len, actual_hidden_dim), embeddings_data.to_vec())
-            .map_err(|e| anyhow!("Failed to reshape embeddings: {}", e))?;
-
-        let attention_mask_f32: Vec<f32> = attention_mask.iter().map(|&x| x as f32).collect();
-        let attention_mask_array = Array2::from_shape_vec((seq_len, 1), attention_mask_f32)
-            .map_err(|e| anyhow!("Failed to create attention mask array: {}", e))?;
-
-        let attention_expanded = attention_mask_array
-            .broadcast((seq_len, actual_hidden_dim))
-            .ok_or_else(|| anyhow!("Failed to broadcast attention mask"))?;
-
-        let masked_embeddings = &embeddings * &attention_expanded;
-        let sum_embeddings = masked_embeddings.sum_axis(Axis(0));
-        let sum_mask = attention_expanded.sum_axis(Axis(0));
+        let mut embedding = if self.pooling == PoolingStrategy::Pooled {
+            embeddings_data.to_vec()
+        } else {
+            let expected_elements = seq_len * actual_hidden_dim;
+
+            if embeddings_data.len() != expected_elements {
+                return Err(anyhow!(
+                    "Unexpected embedding shape. Expected {} elements ({}x{}), got {}. Output shape: {:?}",
+                    expected_elements,
+                    seq_len,
+                    actual_hidden_dim,
+                    embeddings_data.len(),
+                    output_shape
+                ));
+            }
 
-        let mut embedding: Vec<f32> = sum_embeddings
-            .iter()
-            .zip(sum_mask.iter())
-            .map(|(sum, mask)| if *mask > 0.0 { sum / mask } else { 0.0 })
-            .collect();
+            pool_tokens(embeddings_data, &attention_mask, seq_len, actual_hidden_dim, self.pooling)?
+        };
 
         assert_eq!(embedding.len(), actual_hidden_dim, "Embedding size mismatch");
 
@@ -296,17 +325,22 @@ impl OnnxBackend {
         Ok(embedding)
     }
 
-    pub fn generate_embeddings_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+    /// Embed `texts` in one inference call, flagging those cut at `max_tokens`
+    pub fn generate_embeddings_batch(&self, texts: &[&str]) -> Result<Vec<(Vec<f32>, bool)>> {
         if texts.is_empty() {
             return Ok(Vec::new());
         }
 
-        const MAX_TOKENS: usize = 512;
-        let batch_size = texts.len();
+        let batch = self.prepare_batch(texts)?;
+        let hidden = self.infer_batch(&batch)?;
+        self.finish_batch(batch, hidden)
+    }
 
-        // Tokenize all texts
-        let encodings: Vec<_> = texts
-            .iter()
+    /// Tokenize `texts` on the rayon pool and pad them into batch tensors.
+    /// Needs no session lock, so it can run while another batch is inferred.
+    pub fn prepare_batch(&self, texts: &[&str]) -> Result<TokenBatch> {
+        let encodings = texts
+            .par_iter()
             .map(|text| {
                 self.tokenizer
                     .encode(*text, true)
@@ -315,47 +349,47 @@ impl OnnxBackend {
             .collect::<Result<Vec<_>>>()?;
 
         // Find max sequence length in batch (for padding)
-        let max_seq_len = encodings
+        let seq_len = encodings
             .iter()
-            .map(|enc| enc.get_ids().len().min(MAX_TOKENS))
+            .map(|enc| enc.get_ids().len().min(self.max_tokens))
             .max()
             .unwrap_or(0);
 
-        // Prepare batched tensors with padding
-        let mut batch_input_ids = Vec::with_capacity(batch_size * max_seq_len);
-        let mut batch_attention_mask = Vec::with_capacity(batch_size * max_seq_len);
-        let mut batch_token_type_ids = Vec::with_capacity(batch_size * max_seq_len);
+        let batch_size = encodings.len();
+        let mut batch = TokenBatch {
+            input_ids: Vec::with_capacity(batch_size * seq_len),
+            attention_mask: Vec::with_capacity(batch_size * seq_len),
+            token_type_ids: Vec::with_capacity(batch_size * seq_len),
+            truncated: Vec::with_capacity(batch_size),
+            seq_len,
+        };
 
         for encoding in &encodings {
-            let mut input_ids = encoding.get_ids().to_vec();
-            let mut attention_mask = encoding.get_attention_mask().to_vec();
-            let mut token_type_ids = encoding.get_type_ids().to_vec();
-
-            // Truncate if needed
-            if input_ids.len() > MAX_TOKENS {
-                input_ids.truncate(MAX_TOKENS);
-                attention_mask.truncate(MAX_TOKENS);
-                token_type_ids.truncate(MAX_TOKENS);
-            }
+            // Truncate to max_tokens, then pad to the batch's longest sequence
+            let push_padded = |row: &mut Vec<i64>, values: &[u32]| {
+                let kept = &values[..values.len().min(self.max_tokens)];
+                row.extend(kept.iter().map(|&x| x as i64));
+                row.resize(row.len() + seq_len - kept.len(), 0);
+            };
+            push_padded(&mut batch.input_ids, encoding.get_ids());
+            push_padded(&mut batch.attention_mask, encoding.get_attention_mask());
+            push_padded(&mut batch.token_type_ids, encoding.get_type_ids());
+            batch.truncated.push(encoding.get_ids().len() > self.max_tokens);
+        }
 
-            let _seq_len = input_ids.len();
+        Ok(batch)
+    }
 
-            // Pad to max_seq_len
-            input_ids.resize(max_seq_len, 0);
-            attention_mask.resize(max_seq_len, 0);
-            token_type_ids.resize(max_seq_len, 0);
-
-            // Add to batch
-            batch_input_ids.extend(input_ids.iter().map(|&x| x as i64));
-            batch_attention_mask.extend(attention_mask.iter().map(|&x| x as i64));
-            batch_token_type_ids.extend(token_type_ids.iter().map(|&x| x as i64));
-        }
+    /// Run the session on a prepared batch. Holds the session lock for the
+    /// call and copies the output out so the lock isn't needed for pooling.
+    pub fn infer_batch(&self, batch: &TokenBatch) -> Result<HiddenStates> {
+        let shape = [batch.truncated.len(), batch.seq_len];
 
         // Create tensors
-        let input_ids_value = Value::from_array(([batch_size, max_seq_len], batch_input_ids))
+        let input_ids_value = Value::from_array((shape, batch.input_ids.clone()))
             .map_err(|e| anyhow!("Failed to create input_ids tensor: {:?}", e))?;
 
-        let attention_mask_value = Value::from_array(([batch_size, max_seq_len], batch_attention_mask.clone()))
+        let attention_mask_value = Value::from_array((shape, batch.attention_mask.clone()))
             .map_err(|e| anyhow!("Failed to create attention_mask tensor: {:?}", e))?;
 
         let mut session_guard = self.session.lock()
@@ -363,7 +397,7 @@ impl OnnxBackend {
 
         // Run inference
         let outputs = match self.model_type {
-            ModelType::MPNet => {
+            ModelType::NoTokenTypes => {
                 let inputs = ort::inputs![
                     "input_ids" => input_ids_value,
                     "attention_mask" => attention_mask_value,
@@ -372,7 +406,7 @@ impl OnnxBackend {
                     .map_err(|e| anyhow!("Failed to run inference: {:?}", e))?
             }
             ModelType::Standard => {
-                let token_type_ids_value = Value::from_array(([batch_size, max_seq_len], batch_token_type_ids))
+                let token_type_ids_value = Value::from_array((shape, batch.token_type_ids.clone()))
                     .map_err(|e| anyhow!("Failed to create token_type_ids tensor: {:?}", e))?;
 
                 let inputs = ort::inputs![
@@ -385,7 +419,7 @@ impl OnnxBackend {
             }
         };
 
-        let output_name = "last_hidden_state";
+        let output_name = self.output_name();
         let Ok((output_shape, embeddings_data)) = outputs
             .get(output_name)
             .ok_or_else(|| anyhow!("No output named '{}'", output_name))?
@@ -393,14 +427,55 @@ impl OnnxBackend {
                 return Err(anyhow!("Failed to extract tensor"));
             };
 
-        // Get actual dimension from model output
-        let actual_hidden_dim = if output_shape.len() == 3 {
-            output_shape[2] as usize
-        } else {
+        let expected_rank = if self.pooling == PoolingStrategy::Pooled { 2 } else { 3 };
+        if output_shape.len() != expected_rank {
             return Err(anyhow!("Unexpected output shape: {:?}", output_shape));
-        };
+        }
+        let hidden_dim = output_shape[expected_rank - 1] as usize;
+        self.record_dimension(hidden_dim);
+
+        Ok(HiddenStates { data: embeddings_data.to_vec(), hidden_dim })
+    }
+
+    /// Pool (in parallel across rows) and normalize the session output
+    pub fn finish_batch(&self, batch: TokenBatch, hidden: HiddenStates) -> Result<Vec<(Vec<f32>, bool)>> {
+        let HiddenStates { data, hidden_dim } = hidden;
+        let seq_len = batch.seq_len;
+
+        batch.truncated
+            .par_iter()
+            .enumerate()
+            .map(|(i, &truncated)| {
+                let mut embedding = if self.pooling == PoolingStrategy::Pooled {
+                    data[i * hidden_dim..(i + 1) * hidden_dim].to_vec()
+                } else {
+                    pool_tokens(
+                        &data[i * seq_len * hidden_dim..(i + 1) * seq_len * hidden_dim],
+                        &batch.attention_mask[i * seq_len..(i + 1) * seq_len],
+                        seq_len,
+                        hidden_dim,
+                        self.pooling,
+                    )?
+                };
+
+                if self.normalize {
+                    Self::normalize_vector(&mut embedding);
+                }
+
+                Ok((embedding, truncated))
+            })
+            .collect()
+    }
 
-        // Update stored dimension if needed
+    fn output_name(&self) -> &str {
+        match (self.pooling, &self.pooled_output) {
+            (PoolingStrategy::Pooled, Some(name)) => name,
+            _ => "last_hidden_state",
+        }
+    }
+
+    /// Update stored dimension if this is the first time we see the real value
+    fn record_dimension(&self, actual_hidden_dim: usize) {
         let stored_dim = self.dimension.load(Ordering::Relaxed);
         if actual_hidden_dim != stored_dim {
             println!(
@@ -409,53 +484,6 @@ impl OnnxBackend {
             );
             self.dimension.store(actual_hidden_dim, Ordering::Relaxed);
         }
-
-        // Process each item in the batch
-        let mut result = Vec::with_capacity(batch_size);
-
-        for i in 0..batch_size {
-            let start_idx = i * max_seq_len * actual_hidden_dim;
-            let end_idx = start_idx + (max_seq_len * actual_hidden_dim);
-            let item_embeddings = &embeddings_data[start_idx..end_idx];
-
-            // Reshape to [seq_len, hidden_dim]
-            let embeddings = Array2::from_shape_vec((max_seq_len, actual_hidden_dim), item_embeddings.to_vec())
-                .map_err(|e| anyhow!("Failed to reshape embeddings: {}", e))?;
-
-            // Get attention mask for this item
-            let attention_start = i * max_seq_len;
-            let attention_end = attention_start + max_seq_len;
-            let attention_mask_f32: Vec<f32> = batch_attention_mask[attention_start..attention_end]
-                .iter()
-                .map(|&x| x as f32)
-                .collect();
-
-            let attention_mask_array = Array2::from_shape_vec((max_seq_len, 1), attention_mask_f32)
-                .map_err(|e| anyhow!("Failed to create attention mask array: {}", e))?;
-
-            let attention_expanded = attention_mask_array
-                .broadcast((max_seq_len, actual_hidden_dim))
-                .ok_or_else(|| anyhow!("Failed to broadcast attention mask"))?;
-
-            // Mean pooling
-            let masked_embeddings = &embeddings * &attention_expanded;
-            let sum_embeddings = masked_embeddings.sum_axis(Axis(0));
-            let sum_mask = attention_expanded.sum_axis(Axis(0));
-
-            let mut embedding: Vec<f32> = sum_embeddings
-                .iter()
-                .zip(sum_mask.iter())
-                .map(|(sum, mask)| if *mask > 0.0 { sum / mask } else { 0.0 })
-                .collect();
-
-            if self.normalize {
-                Self::normalize_vector(&mut embedding);
-            }
-
-            result.push(embedding);
-        }
-
-        Ok(result)
     }
 
     fn normalize_vector(vec: &mut [f32]) {
@@ -469,3 +497,122 @@ impl OnnxBackend {
         self.dimension.load(Ordering::Relaxed)  // CHANGED: load from atomic
     }
 }
+
+/// Resolve the first of `filenames` in the model repo, using the cache when
+/// possible and downloading otherwise (unless offline)
+fn fetch_model_file(cache: &ModelCache, model_name: &str, filenames: &[&str]) -> Result<PathBuf> {
+    let hf_cache = match &cache.dir {
+        Some(dir) => hf_hub::Cache::new(dir.clone()),
+        None => hf_hub::Cache::from_env(),
+    };
+
+    let cached = filenames
+        .iter()
+        .find_map(|name| hf_cache.model(model_name.to_string()).get(name));
+    if let Some(path) = cached {
+        return Ok(path);
+    }
+
+    if cache.offline {
+        return Err(anyhow!(
+            "{} for '{}' is not cached in {} and offline mode is on",
+            filenames.join(" / "),
+            model_name,
+            hf_cache.path().display()
+        ));
+    }
+
+    println!("     Downloading {} for {} from HuggingFace Hub...", filenames[0], model_name);
+    let api = hf_hub::api::sync::ApiBuilder::from_cache(hf_cache)
+        .build()
+        .map_err(|e| anyhow!("Failed to initialize HuggingFace API: {}", e))?;
+    let repo_api = api.model(model_name.to_string());
+
+    let mut last_error = None;
+    for name in filenames {
+        match repo_api.get(name) {
+            Ok(path) => return Ok(path),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(anyhow!("{}", last_error.map(|e| e.to_string()).unwrap_or_default()))
+}
+
+/// Reduce one sequence of `[seq_len, hidden_dim]` token embeddings to a vector
+fn pool_tokens<M: Copy + Into<i64>>(
+    token_embeddings: &[f32],
+    attention_mask: &[M],
+    seq_len: usize,
+    hidden_dim: usize,
+    pooling: PoolingStrategy,
+) -> Result<Vec<f32>> {
+    let embeddings = Array2::from_shape_vec((seq_len, hidden_dim), token_embeddings.to_vec())
+        .map_err(|e| anyhow!("Failed to reshape embeddings: {}", e))?;
+
+    if pooling == PoolingStrategy::Cls {
+        return Ok(embeddings.row(0).to_vec());
+    }
+
+    let attention_mask_f32: Vec<f32> = attention_mask.iter().map(|&x| x.into() as f32).collect();
+    let attention_mask_array = Array2::from_shape_vec((seq_len, 1), attention_mask_f32)
+        .map_err(|e| anyhow!("Failed to create attention mask array: {}", e))?;
+
+    let attention_expanded = attention_mask_array
+        .broadcast((seq_len, hidden_dim))
+        .ok_or_else(|| anyhow!("Failed to broadcast attention mask"))?;
+
+    let masked_embeddings = &embeddings * &attention_expanded;
+    let sum_embeddings = masked_embeddings.sum_axis(Axis(0));
+    let sum_mask = attention_expanded.sum_axis(Axis(0));
+
+    Ok(sum_embeddings
+        .iter()
+        .zip(sum_mask.iter())
+        .map(|(sum, mask)| if *mask > 0.0 { sum / mask } else { 0.0 })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Three tokens, the last one padding
+    const TOKENS: [f32; 6] = [1.0, 2.0, 3.0, 4.0, 100.0, 100.0];
+    const MASK: [u32; 3] = [1, 1, 0];
+
+    #[test]
+    fn test_detect_model_type_from_graph_inputs() {
+        let bert = ["input_ids", "attention_mask", "token_type_ids"];
+        assert_eq!(OnnxBackend::detect_model_type(&bert).unwrap(), ModelType::Standard);
+
+        let minilm = ["input_ids", "attention_mask"];
+        assert_eq!(OnnxBackend::detect_model_type(&minilm).unwrap(), ModelType::NoTokenTypes);
+
+        let err = OnnxBackend::detect_model_type(&["input_ids", "token_type_ids"]).unwrap_err();
+        assert!(err.to_string().contains("attention_mask"));
+    }
+
+    #[test]
+    fn test_cls_pooling_takes_first_token() {
+        let cls = pool_tokens(&TOKENS, &MASK, 3, 2, PoolingStrategy::Cls).unwrap();
+        assert_eq!(cls, vec![1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_mean_pooling_skips_padding() {
+        let mean = pool_tokens(&TOKENS, &MASK, 3, 2, PoolingStrategy::Mean).unwrap();
+        assert_eq!(mean, vec![2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_offline_fetch_fails_without_network_when_uncached() {
+        let cache = ModelCache {
+            dir: Some(std::env::temp_dir().join(format!("eulix_cache_{}", std::process::id()))),
+            offline: true,
+        };
+
+        let err = fetch_model_file(&cache, "org/missing-model", &["tokenizer.json"]).unwrap_err();
+        assert!(err.to_string().contains("offline"));
+    }
+}
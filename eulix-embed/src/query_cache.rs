@@ -0,0 +1,128 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::index::SearchResult;
+
+/// LRU cache of query embeddings and their search results.
+///
+/// Keyed by the exact query string. Callers that vary `top_k` or filters per
+/// request should fold them into the key so cached results stay correct.
+#[derive(Debug)]
+pub struct QueryCache {
+    capacity: usize,
+    entries: HashMap<String, CachedQuery>,
+    /// Least recently used key at the front
+    order: VecDeque<String>,
+    hits: u64,
+    misses: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct CachedQuery {
+    #[allow(dead_code)] // Kept for re-ranking; serve mode only replays results
+    pub embedding: Vec<f32>,
+    pub results: Vec<SearchResult>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub capacity: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl QueryCache {
+    /// Create a cache holding at most `capacity` queries (0 disables caching)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Look up a query, marking it as most recently used on a hit
+    pub fn get(&mut self, key: &str) -> Option<&CachedQuery> {
+        if self.entries.contains_key(key) {
+            self.hits += 1;
+            self.touch(key);
+            self.entries.get(key)
+        } else {
+            self.misses += 1;
+            None
+        }
+    }
+
+    /// Insert a query, evicting the least recently used entry when full
+    pub fn insert(&mut self, key: String, value: CachedQuery) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if self.entries.insert(key.clone(), value).is_some() {
+            self.touch(&key);
+            return;
+        }
+
+        self.order.push_back(key);
+        while self.entries.len() > self.capacity {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.len(),
+            capacity: self.capacity,
+            hits: self.hits,
+            misses: self.misses,
+        }
+    }
+
+    fn touch(&mut self, key: &str) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            if let Some(k) = self.order.remove(pos) {
+                self.order.push_back(k);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cached(value: f32) -> CachedQuery {
+        CachedQuery {
+            embedding: vec![value],
+            results: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut cache = QueryCache::new(2);
+        cache.insert("a".to_string(), cached(1.0));
+        cache.insert("b".to_string(), cached(2.0));
+
+        // Touch "a" so "b" becomes the eviction candidate
+        assert!(cache.get("a").is_some());
+        cache.insert("c".to_string(), cached(3.0));
+
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.get("a").unwrap().embedding, vec![1.0]);
+        assert_eq!(cache.get("c").unwrap().embedding, vec![3.0]);
+
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 2);
+        assert_eq!(stats.hits, 3);
+        assert_eq!(stats.misses, 1);
+    }
+}
@@ -0,0 +1,226 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::context::ContextIndex;
+use crate::index::EmbeddingIndex;
+
+/// Everything the pipeline summary reports, in a form scripts can consume
+/// (`--json-summary`). Timings are in seconds, file sizes in bytes.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PipelineReport {
+    pub model: String,
+    pub dimension: usize,
+    pub total_chunks: usize,
+    pub total_vectors: usize,
+    pub chunk_types: BTreeMap<String, usize>,
+    pub languages: BTreeMap<String, usize>,
+    pub relationships: usize,
+    pub entry_points: usize,
+    pub call_graph_depth: usize,
+    /// Source revision from the KB, when it was parsed inside a git repo
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_branch: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_dirty: Option<bool>,
+    /// Chunks whose tail was cut at the model's token limit when embedded
+    #[serde(default)]
+    pub truncated_chunks: usize,
+    /// Blank chunks that were left out instead of embedded
+    #[serde(default)]
+    pub empty_chunks: usize,
+    /// Vectors carried over from `--base` or a `--resume` checkpoint
+    #[serde(default)]
+    pub reused_chunks: usize,
+    /// Chunks sent to the model in this run
+    #[serde(default)]
+    pub embedded_chunks: usize,
+    pub timings: StepTimings,
+    pub output_files: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct StepTimings {
+    pub load_kb: f64,
+    pub chunking: f64,
+    pub embedding: f64,
+    pub indexing: f64,
+    pub context: f64,
+    pub writing: f64,
+    pub total: f64,
+}
+
+impl PipelineReport {
+    pub fn from_indices(embedding_index: &EmbeddingIndex, context_index: &ContextIndex) -> Self {
+        let stats = embedding_index.stats();
+        let context_stats = context_index.stats();
+
+        Self {
+            model: stats.model,
+            dimension: stats.dimension,
+            total_chunks: context_stats.total_chunks,
+            total_vectors: stats.total_chunks,
+            chunk_types: stats.chunk_types.into_iter().collect(),
+            languages: stats.languages.into_iter().collect(),
+            relationships: context_stats.total_relationships,
+            entry_points: context_stats.entry_points,
+            call_graph_depth: context_stats.call_graph_depth,
+            git_commit: context_stats.git_commit,
+            git_branch: context_stats.git_branch,
+            git_dirty: context_stats.git_dirty,
+            ..Default::default()
+        }
+    }
+
+    pub fn total_output_bytes(&self) -> u64 {
+        self.output_files.values().sum()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn print_summary(&self) {
+        println!("{}", "=".repeat(70));
+        println!("  PIPELINE SUMMARY");
+        println!("{}", "=".repeat(70));
+        println!();
+
+        println!("EMBEDDING STATISTICS");
+        println!("{}", "-".repeat(70));
+        println!("  Model:              {}", self.model);
+        println!("  Dimension:          {}", self.dimension);
+        println!("  Total Chunks:       {}", self.total_vectors);
+        println!("  Vectors:            reused {} / embedded {}", self.reused_chunks, self.embedded_chunks);
+        if self.truncated_chunks > 0 {
+            println!("  [!] {} chunks truncated at token limit.", self.truncated_chunks);
+        }
+        if self.empty_chunks > 0 {
+            println!("  [!] {} empty chunks skipped.", self.empty_chunks);
+        }
+        println!();
+
+        print_distribution("Chunk Type Distribution", &self.chunk_types, self.total_vectors);
+        print_distribution("Language Distribution", &self.languages, self.total_vectors);
+
+        println!("CONTEXT INDEX STATISTICS");
+        println!("{}", "-".repeat(70));
+        println!("  Relationships:      {}", self.relationships);
+        println!("  Entry Points:       {}", self.entry_points);
+        println!("  Call Graph Depth:   {}", self.call_graph_depth);
+        if let Some(ref commit) = self.git_commit {
+            let branch = self.git_branch.as_deref().unwrap_or("detached");
+            let dirty = if self.git_dirty == Some(true) { " (uncommitted changes)" } else { "" };
+            println!("  Source:             {} @ {}{}", branch, &commit[..commit.len().min(12)], dirty);
+        }
+        println!();
+
+        println!("EXECUTION TIME");
+        println!("{}", "-".repeat(70));
+        println!("  Load KB:            {:.2}s", self.timings.load_kb);
+        println!("  Chunking:           {:.2}s", self.timings.chunking);
+        println!("  Embedding:          {:.2}s", self.timings.embedding);
+        println!("  Indexing:           {:.2}s", self.timings.indexing);
+        println!("  Context Index:      {:.2}s", self.timings.context);
+        println!("  Writing:            {:.2}s", self.timings.writing);
+        println!("  Total Time:         {:.2}s", self.timings.total);
+        println!();
+
+        println!("{}", "=".repeat(70));
+        println!("  PIPELINE COMPLETED SUCCESSFULLY");
+        println!("{}", "=".repeat(70));
+        println!();
+    }
+}
+
+fn print_distribution(title: &str, counts: &BTreeMap<String, usize>, total: usize) {
+    if counts.is_empty() {
+        return;
+    }
+
+    println!("  {}:", title);
+    let mut sorted: Vec<_> = counts.iter().collect();
+    sorted.sort_by_key(|(_, count)| std::cmp::Reverse(**count));
+    for (name, count) in sorted {
+        let percentage = (*count as f64 / total as f64) * 100.0;
+        println!("    {:20} {:6} ({:5.1}%)", format!("{}:", name), count, percentage);
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunker::{ChunkMetadata, ChunkType};
+    use crate::index::EmbeddingEntry;
+    use crate::kb_loader::KnowledgeBase;
+
+    fn empty_kb() -> KnowledgeBase {
+        serde_json::from_value(serde_json::json!({
+            "metadata": {
+                "project_name": "demo", "version": "1", "parsed_at": "", "languages": ["python"],
+                "total_files": 0, "total_loc": 0, "total_functions": 0, "total_classes": 0, "total_methods": 0
+            },
+            "structure": {},
+            "call_graph": { "nodes": [], "edges": [] },
+            "dependency_graph": { "nodes": [], "edges": [] },
+            "indices": {},
+            "entry_points": [],
+            "external_dependencies": [],
+            "patterns": { "architecture_style": null }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_report_round_trips_as_json() {
+        let mut index = EmbeddingIndex::new("test-model".to_string(), 2);
+        for (id, chunk_type) in [("a", ChunkType::Function), ("b", ChunkType::Function), ("c", ChunkType::Class)] {
+            index.add_entry(EmbeddingEntry {
+                id: id.to_string(),
+                chunk_type,
+                content: String::new(),
+                embedding: vec![1.0, 0.0],
+                metadata: ChunkMetadata {
+                    file_path: None,
+                    language: Some("python".to_string()),
+                    line_start: None,
+                    line_end: None,
+                    name: id.to_string(),
+                    complexity: None,
+                },
+                importance_score: 0.0,
+                tags: vec![],
+                norm: 0.0,
+            }).unwrap();
+        }
+        let context = ContextIndex::from_kb_and_chunks(&empty_kb(), vec![], 2);
+
+        let mut report = PipelineReport::from_indices(&index, &context);
+        report.output_files.insert("embeddings.json".to_string(), 100);
+        report.output_files.insert("vectors.bin".to_string(), 24);
+        report.timings.total = 1.5;
+
+        assert_eq!(report.total_vectors, 3);
+        assert_eq!(report.chunk_types["Function"], 2);
+        assert_eq!(report.languages["python"], 3);
+        assert_eq!(report.total_output_bytes(), 124);
+
+        let path = std::env::temp_dir().join(format!("eulix_report_{}.json", std::process::id()));
+        report.save(&path).unwrap();
+        let loaded: PipelineReport = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.model, "test-model");
+        assert_eq!(loaded.dimension, 2);
+        assert_eq!(loaded.output_files["vectors.bin"], 24);
+        assert_eq!(loaded.timings.total, 1.5);
+    }
+}
@@ -0,0 +1,285 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::index::{EmbeddingIndex, SearchFilters, SearchResult};
+use crate::query_cache::{CachedQuery, QueryCache};
+
+/// Body of `POST /search`
+#[derive(Debug, Deserialize)]
+pub struct SearchRequest {
+    pub query: String,
+    #[serde(default = "default_top_k")]
+    pub top_k: usize,
+    #[serde(default)]
+    pub filters: SearchFilters,
+}
+
+fn default_top_k() -> usize {
+    5
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+/// Long-running search service for editor integrations: the index and the
+/// model are loaded once instead of per CLI invocation.
+///
+/// Requests are handled one at a time on the calling thread, which keeps the
+/// embedder free of any `Sync` requirement. `embed` turns a query into a
+/// vector (normally `QueryEmbedder::embed_query`).
+pub struct SearchServer<E> {
+    index: EmbeddingIndex,
+    embed: E,
+    cache: QueryCache,
+}
+
+impl<E> SearchServer<E>
+where
+    E: FnMut(&str) -> Result<Vec<f32>>,
+{
+    pub fn new(index: EmbeddingIndex, embed: E, cache_size: usize) -> Self {
+        Self {
+            index,
+            embed,
+            cache: QueryCache::new(cache_size),
+        }
+    }
+
+    /// Serve until SIGINT/Ctrl-C. The signal unblocks the accept loop, so the
+    /// request in flight finishes and its response is sent before returning.
+    pub fn run(&mut self, addr: &str) -> Result<()> {
+        let server = Arc::new(Server::http(addr).map_err(|e| anyhow!("Failed to bind {}: {}", addr, e))?);
+
+        let shutdown = Arc::clone(&server);
+        ctrlc::set_handler(move || shutdown.unblock())?;
+
+        eprintln!("Listening on http://{}", addr);
+        eprintln!("  POST /search  {{\"query\": \"...\", \"top_k\": 5, \"filters\": {{...}}}}");
+        eprintln!("  GET  /health");
+        eprintln!("Press Ctrl-C to stop");
+
+        for request in server.incoming_requests() {
+            self.respond(request);
+        }
+
+        let stats = self.cache.stats();
+        eprintln!(
+            "\nShutting down ({}/{} queries cached, {} hits, {} misses)",
+            stats.entries, stats.capacity, stats.hits, stats.misses
+        );
+        Ok(())
+    }
+
+    /// Searches report how the cache served them in `X-Cache` (`hit` or
+    /// `miss`), with the running totals in `X-Cache-Hits`/`X-Cache-Misses`
+    fn respond(&mut self, mut request: Request) {
+        let before = self.cache.stats();
+        let (status, body) = match (request.method(), request.url()) {
+            (Method::Post, "/search") => {
+                let mut body = String::new();
+                match request.as_reader().read_to_string(&mut body) {
+                    Ok(_) => match self.handle_search(&body) {
+                        Ok(results) => (200, serde_json::to_string(&results)),
+                        Err((status, error)) => (status, serde_json::to_string(&ErrorBody { error })),
+                    },
+                    Err(e) => (400, serde_json::to_string(&ErrorBody { error: e.to_string() })),
+                }
+            }
+            (Method::Get, "/health") => (
+                200,
+                serde_json::to_string(&serde_json::json!({
+                    "status": "ok",
+                    "model": self.index.model,
+                    "chunks": self.index.total_chunks,
+                })),
+            ),
+            _ => (404, serde_json::to_string(&ErrorBody { error: "not found".to_string() })),
+        };
+
+        let body = body.unwrap_or_else(|e| format!("{{\"error\": \"{}\"}}", e));
+        let header = |name: &str, value: &str| {
+            Header::from_bytes(name.as_bytes(), value.as_bytes()).expect("cache headers are ASCII")
+        };
+        let mut response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(header("Content-Type", "application/json"));
+
+        // Rejected requests never reach the cache
+        let after = self.cache.stats();
+        let outcome = if after.hits > before.hits {
+            Some("hit")
+        } else if after.misses > before.misses {
+            Some("miss")
+        } else {
+            None
+        };
+        if let Some(outcome) = outcome {
+            response.add_header(header("X-Cache", outcome));
+            response.add_header(header("X-Cache-Hits", &after.hits.to_string()));
+            response.add_header(header("X-Cache-Misses", &after.misses.to_string()));
+        }
+
+        if let Err(e) = request.respond(response) {
+            eprintln!("Failed to send response: {}", e);
+        }
+    }
+
+    /// Run one `/search` body, returning the HTTP status and message on failure
+    pub fn handle_search(&mut self, body: &str) -> Result<Vec<SearchResult>, (u16, String)> {
+        let request: SearchRequest = serde_json::from_str(body)
+            .map_err(|e| (400, format!("Invalid request: {}", e)))?;
+
+        if request.query.trim().is_empty() {
+            return Err((400, "query must not be empty".to_string()));
+        }
+        if request.filters.rerank.is_some_and(|rerank| !rerank.is_finite()) {
+            return Err((400, "rerank weights must be finite numbers".to_string()));
+        }
+
+        // top_k and filters change the results, so they're part of the key
+        let key = format!(
+            "{}\u{0}{}\u{0}{}",
+            request.query,
+            request.top_k,
+            serde_json::to_string(&request.filters).unwrap_or_default()
+        );
+        if let Some(cached) = self.cache.get(&key) {
+            return Ok(cached.results.clone());
+        }
+
+        let embedding = (self.embed)(&request.query).map_err(|e| (500, e.to_string()))?;
+        if embedding.len() != self.index.dimension {
+            return Err((
+                500,
+                format!(
+                    "Query embedding has dimension {} but the index was built with dimension {} ({})",
+                    embedding.len(),
+                    self.index.dimension,
+                    self.index.model
+                ),
+            ));
+        }
+
+        let results = self.index.search_filtered(&embedding, request.top_k, request.filters);
+        self.cache.insert(key, CachedQuery { embedding, results: results.clone() });
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunker::{ChunkMetadata, ChunkType};
+    use crate::index::EmbeddingEntry;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn index() -> EmbeddingIndex {
+        let mut index = EmbeddingIndex::new("test-model".to_string(), 2);
+        for (id, embedding, language) in [
+            ("func_login", vec![1.0, 0.0], "python"),
+            ("func_logout", vec![0.8, 0.2], "go"),
+            ("func_render", vec![0.0, 1.0], "python"),
+        ] {
+            index.add_entry(EmbeddingEntry {
+                id: id.to_string(),
+                chunk_type: ChunkType::Function,
+                content: String::new(),
+                embedding,
+                metadata: ChunkMetadata {
+                    file_path: None,
+                    language: Some(language.to_string()),
+                    line_start: None,
+                    line_end: None,
+                    name: id.to_string(),
+                    complexity: None,
+                },
+                importance_score: 0.0,
+                tags: vec![],
+                norm: 0.0,
+            }).unwrap();
+        }
+        index
+    }
+
+    #[test]
+    fn test_handle_search_filters_and_caches() {
+        let calls = Rc::new(Cell::new(0));
+        let counter = Rc::clone(&calls);
+        let mut server = SearchServer::new(index(), move |_: &str| {
+            counter.set(counter.get() + 1);
+            Ok(vec![1.0, 0.0])
+        }, 8);
+
+        let results = server.handle_search(r#"{"query": "login", "top_k": 2}"#).unwrap();
+        let ids: Vec<&str> = results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["func_login", "func_logout"]);
+
+        let filtered = server
+            .handle_search(r#"{"query": "login", "filters": {"languages": ["go"]}}"#)
+            .unwrap();
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].id, "func_logout");
+
+        // Same query, top_k and filters: served from the cache
+        server.handle_search(r#"{"query": "login", "top_k": 2}"#).unwrap();
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn test_handle_search_rejects_bad_requests() {
+        let mut server = SearchServer::new(index(), |_: &str| Ok(vec![1.0, 0.0, 0.0]), 0);
+
+        assert_eq!(server.handle_search("not json").unwrap_err().0, 400);
+        assert_eq!(server.handle_search(r#"{"query": "  "}"#).unwrap_err().0, 400);
+        // 1e39 overflows f32 to infinity, which would make every score NaN
+        let overflow = r#"{"query": "login", "filters": {"rerank": {"beta": 1e39, "gamma": -1e39}}}"#;
+        assert_eq!(server.handle_search(overflow).unwrap_err().0, 400);
+
+        let (status, error) = server.handle_search(r#"{"query": "login"}"#).unwrap_err();
+        assert_eq!(status, 500);
+        assert!(error.contains("dimension 3"));
+    }
+
+    #[test]
+    fn test_responses_report_cache_outcome() {
+        use std::io::{Read, Write};
+
+        let http = Server::http("127.0.0.1:0").unwrap();
+        let addr = http.server_addr().to_ip().unwrap();
+        let bodies = [r#"{"query": "login"}"#, r#"{"query": "login"}"#, r#"{"query": ""}"#];
+        let client = std::thread::spawn(move || {
+            bodies
+                .iter()
+                .map(|body| {
+                    let mut stream = std::net::TcpStream::connect(addr).unwrap();
+                    write!(
+                        stream,
+                        "POST /search HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\
+                         Content-Length: {}\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                    .unwrap();
+                    let mut response = String::new();
+                    stream.read_to_string(&mut response).unwrap();
+                    response.to_lowercase()
+                })
+                .collect::<Vec<String>>()
+        });
+
+        let mut server = SearchServer::new(index(), |_: &str| Ok(vec![1.0, 0.0]), 8);
+        for _ in 0..bodies.len() {
+            server.respond(http.recv().unwrap());
+        }
+        let responses = client.join().unwrap();
+
+        assert!(responses[0].contains("x-cache: miss") && responses[0].contains("x-cache-misses: 1"));
+        assert!(responses[1].contains("x-cache: hit") && responses[1].contains("x-cache-hits: 1"));
+        assert!(!responses[2].contains("x-cache"));
+    }
+}
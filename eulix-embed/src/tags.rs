@@ -0,0 +1,129 @@
+//! Chunk types and tags. Public so that tools building their own knowledge
+//! base can tag chunks the way `eulix_embed` does and the context index expects.
+
+use serde::{Deserialize, Serialize};
+
+use crate::kb_loader::Function;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChunkType {
+    Function,
+    Class,
+    Method,
+    File,
+    Directory,
+    EntryPoint,
+    #[serde(other)]
+    Other,
+}
+
+impl ChunkType {
+    /// Names accepted by `--chunk-types`, matching the serialized form
+    pub const NAMES: [&'static str; 6] = ["function", "class", "method", "file", "directory", "entrypoint"];
+}
+
+impl std::str::FromStr for ChunkType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace(['-', '_'], "").as_str() {
+            "function" => Ok(ChunkType::Function),
+            "class" => Ok(ChunkType::Class),
+            "method" => Ok(ChunkType::Method),
+            "file" => Ok(ChunkType::File),
+            "directory" | "dir" => Ok(ChunkType::Directory),
+            "entrypoint" => Ok(ChunkType::EntryPoint),
+            _ => Err(format!(
+                "unknown chunk type '{}' (expected one of: {})",
+                s.trim(),
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// Tags for a function, method or entry point chunk: `base_tag` (the chunk
+/// kind, or the entry point type), the parser's own tags, plus
+///
+/// - `async` for async functions
+/// - `complex` when cyclomatic complexity is above 10
+/// - `api` for decorators mentioning `api` or `route` (`@app.route`, `@api_view`)
+/// - `test` for decorators mentioning `test`
+///
+/// The result is sorted and deduplicated. The context index files chunks
+/// under each tag, and `--exclude-tests` drops every chunk tagged `test`.
+pub fn generate_tags(func: &Function, base_tag: &str) -> Vec<String> {
+    let mut tags = vec![base_tag.to_string()];
+
+    // Add async tag
+    if func.is_async {
+        tags.push("async".to_string());
+    }
+
+    // Add tags from function
+    tags.extend(func.tags.iter().cloned());
+
+    // Add complexity-based tags
+    if func.complexity > 10 {
+        tags.push("complex".to_string());
+    }
+
+    // Add decorator-based tags
+    for decorator in &func.decorators {
+        if decorator.contains("api") || decorator.contains("route") {
+            tags.push("api".to_string());
+        }
+        if decorator.contains("test") {
+            tags.push("test".to_string());
+        }
+    }
+
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn function(extra: serde_json::Value) -> Function {
+        let mut json = serde_json::json!({
+            "id": "func_f",
+            "name": "f",
+            "signature": "def f()",
+            "params": [],
+            "return_type": "",
+            "line_start": 1,
+            "line_end": 2,
+        });
+        json.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_async_complex_function() {
+        let func = function(serde_json::json!({"is_async": true, "complexity": 11, "tags": ["io"]}));
+        assert_eq!(generate_tags(&func, "function"), vec!["async", "complex", "function", "io"]);
+
+        // 10 is the last value that doesn't count as complex
+        let func = function(serde_json::json!({"complexity": 10}));
+        assert_eq!(generate_tags(&func, "function"), vec!["function"]);
+    }
+
+    #[test]
+    fn test_route_decorator_tags_api() {
+        let func = function(serde_json::json!({"decorators": ["@app.route('/login')"]}));
+        assert_eq!(generate_tags(&func, "api_endpoint"), vec!["api", "api_endpoint"]);
+
+        let func = function(serde_json::json!({"decorators": ["@pytest.mark.parametrize", "@pytest.fixture"], "tags": ["test"]}));
+        assert_eq!(generate_tags(&func, "function"), vec!["function", "test"]);
+    }
+
+    #[test]
+    fn test_plain_helper_gets_only_base_tag() {
+        let func = function(serde_json::json!({"complexity": 1}));
+        assert_eq!(generate_tags(&func, "method"), vec!["method"]);
+    }
+}
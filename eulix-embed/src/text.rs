@@ -0,0 +1,146 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use tokenizers::Tokenizer;
+
+use crate::chunker::{truncate_to_tokens, window_ranges, Chunk, ChunkMetadata, ChunkType};
+
+/// Chunk every UTF-8 text file under `root` for `embed-text`, skipping
+/// hidden files and directories. Files are cut into windows of up to
+/// `max_size` characters (capped at 2000, like oversized functions), each
+/// starting with the last `overlap` characters of the window before it.
+/// Chunk ids are `text:<path>`, with `#0`, `#1`, ... when a file is split.
+pub fn chunk_text_dir(
+    root: &Path,
+    max_size: usize,
+    overlap: usize,
+    tokenizer: Option<&Tokenizer>,
+) -> Result<Vec<Chunk>> {
+    let mut files = Vec::new();
+    collect_files(root, &mut files)
+        .with_context(|| format!("Failed to read directory {}", root.display()))?;
+    files.sort();
+
+    let mut chunks = Vec::new();
+    for path in files {
+        // Binary files (images, PDFs) don't decode and are skipped
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        if content.trim().is_empty() || content.contains('\0') {
+            continue;
+        }
+
+        let relative = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+        chunks.extend(chunk_text(&relative, &content, max_size, overlap, tokenizer));
+    }
+
+    Ok(chunks)
+}
+
+fn chunk_text(
+    file_path: &str,
+    content: &str,
+    max_size: usize,
+    overlap: usize,
+    tokenizer: Option<&Tokenizer>,
+) -> Vec<Chunk> {
+    let chars: Vec<char> = content.chars().collect();
+    let ranges = window_ranges(chars.len(), max_size.min(2000), overlap);
+    let split = ranges.len() > 1;
+
+    let name = Path::new(file_path)
+        .file_name()
+        .map_or_else(|| file_path.to_string(), |n| n.to_string_lossy().into_owned());
+    let language = Path::new(file_path)
+        .extension()
+        .map_or_else(|| "text".to_string(), |ext| ext.to_string_lossy().to_lowercase());
+
+    ranges
+        .into_iter()
+        .enumerate()
+        .map(|(i, range)| {
+            let window = &chars[range.clone()];
+            let line_start = 1 + chars[..range.start].iter().filter(|&&c| c == '\n').count();
+            let newlines = window.iter().filter(|&&c| c == '\n').count();
+            let line_end = line_start + newlines - usize::from(window.last() == Some(&'\n'));
+
+            let text: String = window.iter().collect();
+            let content = match tokenizer {
+                Some(tokenizer) => truncate_to_tokens(&text, usize::MAX, tokenizer),
+                None => text,
+            };
+
+            Chunk {
+                id: if split { format!("text:{}#{}", file_path, i) } else { format!("text:{}", file_path) },
+                chunk_type: ChunkType::Other,
+                content,
+                metadata: ChunkMetadata {
+                    file_path: Some(file_path.to_string()),
+                    language: Some(language.clone()),
+                    line_start: Some(line_start),
+                    line_end: Some(line_end),
+                    name: name.clone(),
+                    complexity: None,
+                },
+                tags: vec!["text".to_string()],
+                importance_score: 0.5,
+            }
+        })
+        .collect()
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+
+        // file_type() doesn't follow symlinks, so linked directories can't loop
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(&entry.path(), files)?;
+        } else if file_type.is_file() {
+            files.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_markdown_files_with_line_ranges() {
+        let root = std::env::temp_dir().join(format!("eulix_text_{}", std::process::id()));
+        std::fs::create_dir_all(root.join("guides")).unwrap();
+        std::fs::create_dir_all(root.join(".git")).unwrap();
+        std::fs::write(root.join("README.md"), "# Eulix\n\nAsk questions about your codebase.\n").unwrap();
+        let long: String = (1..=40).map(|i| format!("Step {:02}: configure the server.\n", i)).collect();
+        std::fs::write(root.join("guides/setup.md"), &long).unwrap();
+        std::fs::write(root.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+        std::fs::write(root.join("logo.png"), [0x89, b'P', b'N', b'G', 0xff, 0xfe]).unwrap();
+
+        let chunks = chunk_text_dir(&root, 500, 100, None);
+        std::fs::remove_dir_all(&root).unwrap();
+        let chunks = chunks.unwrap();
+
+        let readme = &chunks[0];
+        assert_eq!(readme.id, "text:README.md");
+        assert_eq!(readme.chunk_type, ChunkType::Other);
+        assert_eq!(readme.metadata.file_path.as_deref(), Some("README.md"));
+        assert_eq!(readme.metadata.language.as_deref(), Some("md"));
+        assert_eq!((readme.metadata.line_start, readme.metadata.line_end), (Some(1), Some(3)));
+
+        // 40 lines of 32 chars split into 500-char windows overlapping by 100
+        let setup: Vec<&Chunk> = chunks[1..].iter().collect();
+        assert_eq!(chunks.len(), 1 + setup.len());
+        assert_eq!(setup.len(), 3);
+        assert_eq!(setup[0].id, "text:guides/setup.md#0");
+        assert_eq!(setup[0].metadata.line_start, Some(1));
+        assert!(setup[1].content.starts_with(&setup[0].content[400..]));
+        assert!(setup[1].metadata.line_start < setup[0].metadata.line_end.map(|l| l + 1));
+        assert_eq!(setup[2].metadata.line_end, Some(40));
+    }
+}
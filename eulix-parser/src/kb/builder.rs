@@ -5,8 +5,9 @@ use std::path::{Path, PathBuf};
 
 use crate::kb::types::{
     KnowledgeBase, Metadata, FileData, DependencyGraph, GraphNode, GraphEdge,
-    EntryPoint, ExternalDependency, CallGraph, Indices, PatternInfo,
+    EntryPoint, ExternalDependency, CallGraph, Indices, PatternInfo, SCHEMA_VERSION,
 };
+use crate::utils::git::GitInfo;
 
 pub struct KnowledgeBaseBuilder {
     root_path: PathBuf,
@@ -60,8 +61,11 @@ impl KnowledgeBaseBuilder {
             .unwrap_or("unknown")
             .to_string();
 
+        let git = GitInfo::detect(&self.root_path);
+
         Ok(KnowledgeBase {
             metadata: Metadata {
+                schema_version: SCHEMA_VERSION,
                 project_name,
                 version: "1.0".to_string(),
                 parsed_at: Utc::now().to_rfc3339(),
@@ -71,6 +75,9 @@ impl KnowledgeBaseBuilder {
                 total_functions,
                 total_classes,
                 total_methods,
+                git_commit: git.commit,
+                git_branch: git.branch,
+                git_dirty: git.dirty,
             },
             structure,
             dependency_graph,
@@ -178,6 +185,7 @@ impl KnowledgeBaseBuilder {
                         file: file_path.clone(),
                         line: func.line_start,
                         methods: None,
+                        router: None,
                     });
                 }
             }
@@ -197,6 +205,7 @@ impl KnowledgeBaseBuilder {
                             file: file_path.clone(),
                             line: var.line,
                             methods: None,
+                            router: None,
                         });
                     }
                 }
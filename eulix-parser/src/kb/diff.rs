@@ -0,0 +1,368 @@
+use crate::kb::types::{Class, Function, KnowledgeBase};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+
+/// Structural changes between two knowledge bases.
+///
+/// Functions, methods and classes are matched on `(file, id)`, so an entity
+/// that moved to another file shows up as removed plus added.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct KbDiff {
+    pub added_functions: Vec<EntityRef>,
+    pub removed_functions: Vec<EntityRef>,
+    /// Functions and methods whose signature or complexity changed
+    pub modified_functions: Vec<FunctionChange>,
+    pub added_classes: Vec<EntityRef>,
+    pub removed_classes: Vec<EntityRef>,
+    /// Classes whose bases or set of methods changed
+    pub modified_classes: Vec<ClassChange>,
+    /// External dependencies imported by the new run but not the old one
+    pub new_dependencies: Vec<String>,
+    /// Sum of complexity over all functions and methods, new minus old
+    pub complexity_delta: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityRef {
+    pub id: String,
+    pub name: String,
+    pub file: String,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionChange {
+    pub id: String,
+    pub name: String,
+    pub file: String,
+    pub old_signature: String,
+    pub new_signature: String,
+    pub old_complexity: usize,
+    pub new_complexity: usize,
+    pub complexity_delta: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassChange {
+    pub id: String,
+    pub name: String,
+    pub file: String,
+    pub added_methods: Vec<String>,
+    pub removed_methods: Vec<String>,
+    pub bases_changed: bool,
+}
+
+impl KbDiff {
+    pub fn between(old: &KnowledgeBase, new: &KnowledgeBase) -> Self {
+        let mut diff = Self::default();
+
+        let old_functions = functions_of(old);
+        let new_functions = functions_of(new);
+
+        for (key, (file, func)) in &new_functions {
+            match old_functions.get(key) {
+                None => diff.added_functions.push(EntityRef::function(file, func)),
+                Some((_, before)) => {
+                    if before.signature != func.signature || before.complexity != func.complexity {
+                        diff.modified_functions.push(FunctionChange {
+                            id: func.id.clone(),
+                            name: func.name.clone(),
+                            file: file.to_string(),
+                            old_signature: before.signature.clone(),
+                            new_signature: func.signature.clone(),
+                            old_complexity: before.complexity,
+                            new_complexity: func.complexity,
+                            complexity_delta: func.complexity as i64 - before.complexity as i64,
+                        });
+                    }
+                }
+            }
+        }
+        for (key, (file, func)) in &old_functions {
+            if !new_functions.contains_key(key) {
+                diff.removed_functions.push(EntityRef::function(file, func));
+            }
+        }
+
+        let old_classes = classes_of(old);
+        let new_classes = classes_of(new);
+
+        for (key, (file, class)) in &new_classes {
+            match old_classes.get(key) {
+                None => diff.added_classes.push(EntityRef::class(file, class)),
+                Some((_, before)) => {
+                    let before_methods: HashSet<&str> = before.methods.iter().map(|m| m.id.as_str()).collect();
+                    let after_methods: HashSet<&str> = class.methods.iter().map(|m| m.id.as_str()).collect();
+
+                    let mut added_methods: Vec<String> = after_methods
+                        .difference(&before_methods)
+                        .map(|id| id.to_string())
+                        .collect();
+                    let mut removed_methods: Vec<String> = before_methods
+                        .difference(&after_methods)
+                        .map(|id| id.to_string())
+                        .collect();
+                    added_methods.sort();
+                    removed_methods.sort();
+                    let bases_changed = before.bases != class.bases;
+
+                    if bases_changed || !added_methods.is_empty() || !removed_methods.is_empty() {
+                        diff.modified_classes.push(ClassChange {
+                            id: class.id.clone(),
+                            name: class.name.clone(),
+                            file: file.to_string(),
+                            added_methods,
+                            removed_methods,
+                            bases_changed,
+                        });
+                    }
+                }
+            }
+        }
+        for (key, (file, class)) in &old_classes {
+            if !new_classes.contains_key(key) {
+                diff.removed_classes.push(EntityRef::class(file, class));
+            }
+        }
+
+        let old_deps: HashSet<&str> = old.external_dependencies.iter().map(|d| d.name.as_str()).collect();
+        diff.new_dependencies = new.external_dependencies
+            .iter()
+            .map(|d| d.name.clone())
+            .filter(|name| !old_deps.contains(name.as_str()))
+            .collect();
+        diff.new_dependencies.sort();
+        diff.new_dependencies.dedup();
+
+        let total_complexity = |functions: &BTreeMap<(&str, &str), (&str, &Function)>| -> i64 {
+            functions.values().map(|(_, f)| f.complexity as i64).sum()
+        };
+        diff.complexity_delta = total_complexity(&new_functions) - total_complexity(&old_functions);
+
+        diff
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added_functions.is_empty() &&
+            self.removed_functions.is_empty() &&
+            self.modified_functions.is_empty() &&
+            self.added_classes.is_empty() &&
+            self.removed_classes.is_empty() &&
+            self.modified_classes.is_empty() &&
+            self.new_dependencies.is_empty()
+    }
+
+    /// Human-readable report for CI logs
+    pub fn summary(&self) -> String {
+        if self.is_empty() {
+            return "No structural changes\n".to_string();
+        }
+
+        let mut out = String::new();
+
+        let mut section = |title: &str, lines: Vec<String>| {
+            if !lines.is_empty() {
+                out.push_str(&format!("{} ({})\n", title, lines.len()));
+                for line in lines {
+                    out.push_str(&format!("   {}\n", line));
+                }
+                out.push('\n');
+            }
+        };
+
+        let entity = |e: &EntityRef| format!("{} ({}:{})", e.id, e.file, e.line);
+
+        section("ADDED FUNCTIONS", self.added_functions.iter().map(|e| format!("+ {}", entity(e))).collect());
+        section("REMOVED FUNCTIONS", self.removed_functions.iter().map(|e| format!("- {}", entity(e))).collect());
+        section(
+            "MODIFIED FUNCTIONS",
+            self.modified_functions
+                .iter()
+                .map(|c| {
+                    let mut line = format!("~ {} ({})", c.id, c.file);
+                    if c.complexity_delta != 0 {
+                        line.push_str(&format!(
+                            " complexity {} → {} ({:+})",
+                            c.old_complexity, c.new_complexity, c.complexity_delta
+                        ));
+                    }
+                    if c.old_signature != c.new_signature {
+                        line.push_str(&format!("\n       {}\n     → {}", c.old_signature, c.new_signature));
+                    }
+                    line
+                })
+                .collect(),
+        );
+        section("ADDED CLASSES", self.added_classes.iter().map(|e| format!("+ {}", entity(e))).collect());
+        section("REMOVED CLASSES", self.removed_classes.iter().map(|e| format!("- {}", entity(e))).collect());
+        section(
+            "MODIFIED CLASSES",
+            self.modified_classes
+                .iter()
+                .map(|c| {
+                    let mut changes = Vec::new();
+                    if c.bases_changed {
+                        changes.push("bases changed".to_string());
+                    }
+                    if !c.added_methods.is_empty() {
+                        changes.push(format!("+{}", c.added_methods.join(", +")));
+                    }
+                    if !c.removed_methods.is_empty() {
+                        changes.push(format!("-{}", c.removed_methods.join(", -")));
+                    }
+                    format!("~ {} ({}): {}", c.id, c.file, changes.join("; "))
+                })
+                .collect(),
+        );
+        section("NEW DEPENDENCIES", self.new_dependencies.iter().map(|d| format!("+ {}", d)).collect());
+
+        out.push_str(&format!("Total complexity delta: {:+}\n", self.complexity_delta));
+        out
+    }
+}
+
+impl EntityRef {
+    fn function(file: &str, func: &Function) -> Self {
+        Self {
+            id: func.id.clone(),
+            name: func.name.clone(),
+            file: file.to_string(),
+            line: func.line_start,
+        }
+    }
+
+    fn class(file: &str, class: &Class) -> Self {
+        Self {
+            id: class.id.clone(),
+            name: class.name.clone(),
+            file: file.to_string(),
+            line: class.line_start,
+        }
+    }
+}
+
+/// Functions and methods keyed by `(file, id)`; ordered so reports are stable
+fn functions_of(kb: &KnowledgeBase) -> BTreeMap<(&str, &str), (&str, &Function)> {
+    kb.structure
+        .iter()
+        .flat_map(|(file, filedata)| {
+            filedata.functions.iter()
+                .chain(filedata.classes.iter().flat_map(|c| c.methods.iter()))
+                .map(move |func| ((file.as_str(), func.id.as_str()), (file.as_str(), func)))
+        })
+        .collect()
+}
+
+fn classes_of(kb: &KnowledgeBase) -> BTreeMap<(&str, &str), (&str, &Class)> {
+    kb.structure
+        .iter()
+        .flat_map(|(file, filedata)| {
+            filedata.classes
+                .iter()
+                .map(move |class| ((file.as_str(), class.id.as_str()), (file.as_str(), class)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kb::types::*;
+    use crate::parser::analyze::Analyzer;
+    use crate::parser::python::PythonParser;
+    use std::collections::HashMap;
+
+    fn kb_from_python(files: &[(&str, &str)]) -> KnowledgeBase {
+        let structure: HashMap<String, FileData> = files
+            .iter()
+            .map(|(path, source)| (path.to_string(), PythonParser::new(source.to_string()).parse().unwrap()))
+            .collect();
+
+        let kb = KnowledgeBase {
+            metadata: Metadata {
+                schema_version: SCHEMA_VERSION,
+                project_name: "test".to_string(),
+                version: "1.0".to_string(),
+                parsed_at: String::new(),
+                languages: vec!["python".to_string()],
+                total_files: structure.len(),
+                total_loc: 0,
+                total_functions: 0,
+                total_classes: 0,
+                total_methods: 0,
+                git_commit: None,
+                git_branch: None,
+                git_dirty: None,
+            },
+            structure,
+            call_graph: CallGraph::default(),
+            dependency_graph: DependencyGraph::default(),
+            indices: Indices::default(),
+            entry_points: vec![],
+            external_dependencies: vec![],
+            patterns: PatternInfo::default(),
+        };
+
+        Analyzer::analyze_and_build(kb, false)
+    }
+
+    #[test]
+    fn test_diff_added_and_removed() {
+        let old = kb_from_python(&[
+            ("app.py", "def keep():\n    pass\n\ndef legacy():\n    pass\n\nclass Old:\n    pass\n"),
+        ]);
+        let new = kb_from_python(&[
+            ("app.py", "import requests\n\ndef keep():\n    pass\n\ndef fresh():\n    pass\n\nclass New:\n    pass\n"),
+        ]);
+
+        let diff = KbDiff::between(&old, &new);
+        let ids = |entities: &[EntityRef]| entities.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+
+        assert_eq!(ids(&diff.added_functions), vec!["func_fresh"]);
+        assert_eq!(ids(&diff.removed_functions), vec!["func_legacy"]);
+        assert_eq!(ids(&diff.added_classes), vec!["class_New"]);
+        assert_eq!(ids(&diff.removed_classes), vec!["class_Old"]);
+        assert!(diff.modified_functions.is_empty());
+        assert_eq!(diff.new_dependencies, vec!["requests"]);
+    }
+
+    #[test]
+    fn test_diff_modified_signature_and_complexity() {
+        let old = kb_from_python(&[
+            ("app.py", "def check(x):\n    return x\n\nclass User:\n    def save(self):\n        pass\n"),
+        ]);
+        let new = kb_from_python(&[(
+            "app.py",
+            "def check(x, strict):\n    if strict:\n        return x\n    return None\n\n\
+             class User(Base):\n    def save(self):\n        pass\n\n    def delete(self):\n        pass\n",
+        )]);
+
+        let diff = KbDiff::between(&old, &new);
+
+        assert_eq!(diff.modified_functions.len(), 1);
+        let change = &diff.modified_functions[0];
+        assert_eq!(change.id, "func_check");
+        assert_ne!(change.old_signature, change.new_signature);
+        assert!(change.complexity_delta > 0);
+        assert_eq!(change.complexity_delta, change.new_complexity as i64 - change.old_complexity as i64);
+
+        assert_eq!(diff.modified_classes.len(), 1);
+        let class = &diff.modified_classes[0];
+        assert!(class.bases_changed);
+        assert_eq!(class.added_methods, vec!["method_User_delete"]);
+        assert!(class.removed_methods.is_empty());
+
+        // The new method also counts as an added function
+        assert_eq!(diff.added_functions[0].id, "method_User_delete");
+        assert!(diff.complexity_delta > 0);
+        assert!(diff.summary().contains("MODIFIED FUNCTIONS (1)"));
+    }
+
+    #[test]
+    fn test_diff_identical_is_empty() {
+        let source = [("app.py", "def main():\n    pass\n")];
+        let diff = KbDiff::between(&kb_from_python(&source), &kb_from_python(&source));
+        assert!(diff.is_empty());
+        assert_eq!(diff.summary(), "No structural changes\n");
+    }
+}
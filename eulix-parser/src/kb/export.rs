@@ -0,0 +1,316 @@
+use crate::kb::types::{CallGraph, CallGraphEdge, CallGraphNode, DependencyGraph, FileData, KnowledgeBase};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+/// One line of `--output-format ndjson`
+#[derive(Serialize)]
+struct FileRecord<'a> {
+    path: &'a str,
+    data: &'a FileData,
+}
+
+impl KnowledgeBase {
+    /// Write `structure` as newline-delimited JSON, one
+    /// `{"path": ..., "data": FileData}` object per file in path order, so
+    /// consumers can stream files without loading the whole knowledge base.
+    ///
+    /// Only per-file data is written: there is no `metadata`/`structure`
+    /// wrapper, and KB-level analysis (call graph, indices, entry points)
+    /// lives in the `<output>_*.json` side files instead.
+    pub fn write_ndjson<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut paths: Vec<&String> = self.structure.keys().collect();
+        paths.sort();
+
+        for path in paths {
+            let record = FileRecord { path, data: &self.structure[path] };
+            serde_json::to_writer(&mut writer, &record)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()
+    }
+}
+
+impl CallGraph {
+    /// Render as a Graphviz `digraph` (`dot -Tsvg call_graph.dot -o call_graph.svg`)
+    ///
+    /// Edges name their target by callee or base-class name; each is drawn to
+    /// the node it resolves to (see `edge_target`), and edges to anything
+    /// outside the graph (library calls, ambiguous names) are left out so
+    /// Graphviz doesn't invent unstyled nodes for them.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph call_graph {\n");
+        dot.push_str("    rankdir=LR;\n");
+        dot.push_str("    node [shape=box, style=filled, fontname=\"Helvetica\"];\n\n");
+
+        for node in &self.nodes {
+            let color = match node.node_type.as_str() {
+                "function" => "lightblue",
+                "method" => "palegreen",
+                "class" => "lightsalmon",
+                _ => "lightgray",
+            };
+            let border = if node.is_entry_point { ", penwidth=2" } else { "" };
+
+            dot.push_str(&format!(
+                "    \"{}\" [label=\"{}\", fillcolor={}, tooltip=\"{}\"{}];\n",
+                escape(&node.id),
+                escape(&node.id),
+                color,
+                escape(&node.file),
+                border
+            ));
+        }
+
+        if !self.edges.is_empty() {
+            dot.push('\n');
+        }
+
+        let ids: HashMap<&str, &CallGraphNode> = self.nodes.iter().map(|n| (n.id.as_str(), n)).collect();
+        let mut by_name: HashMap<&str, Vec<&CallGraphNode>> = HashMap::new();
+        for node in &self.nodes {
+            by_name.entry(node.name.as_str()).or_default().push(node);
+        }
+
+        for edge in &self.edges {
+            let Some(target) = edge_target(edge, &ids, &by_name) else {
+                continue;
+            };
+            let mut attrs = match edge.edge_type.as_str() {
+                "inherits" => vec!["arrowhead=empty", "color=darkorange"],
+                "uses" => vec!["arrowhead=vee", "color=gray40"],
+                _ => vec!["arrowhead=normal"],
+            };
+            if edge.conditional {
+                attrs.push("style=dashed");
+            }
+
+            dot.push_str(&format!(
+                "    \"{}\" -> \"{}\" [{}];\n",
+                escape(&edge.from),
+                escape(target),
+                attrs.join(", ")
+            ));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl DependencyGraph {
+    /// Render as GraphML for Gephi/Cytoscape
+    pub fn to_graphml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        xml.push_str("  <key id=\"node_type\" for=\"node\" attr.name=\"node_type\" attr.type=\"string\"/>\n");
+        xml.push_str("  <key id=\"name\" for=\"node\" attr.name=\"name\" attr.type=\"string\"/>\n");
+        xml.push_str("  <key id=\"edge_type\" for=\"edge\" attr.name=\"edge_type\" attr.type=\"string\"/>\n");
+        xml.push_str("  <graph id=\"dependency_graph\" edgedefault=\"directed\">\n");
+
+        for node in &self.nodes {
+            xml.push_str(&format!("    <node id=\"{}\">\n", escape_xml(&node.id)));
+            xml.push_str(&format!("      <data key=\"node_type\">{}</data>\n", escape_xml(&node.node_type)));
+            xml.push_str(&format!("      <data key=\"name\">{}</data>\n", escape_xml(&node.name)));
+            xml.push_str("    </node>\n");
+        }
+
+        for (i, edge) in self.edges.iter().enumerate() {
+            xml.push_str(&format!(
+                "    <edge id=\"e{}\" source=\"{}\" target=\"{}\">\n",
+                i,
+                escape_xml(&edge.from),
+                escape_xml(&edge.to)
+            ));
+            xml.push_str(&format!("      <data key=\"edge_type\">{}</data>\n", escape_xml(&edge.edge_type)));
+            xml.push_str("    </edge>\n");
+        }
+
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+}
+
+/// Id of the node `edge` points at: `edge.to` itself when it is already an
+/// id, else the one node of the right kind with that name, preferring one in
+/// the caller's file when there are several
+fn edge_target<'a>(
+    edge: &'a CallGraphEdge,
+    ids: &HashMap<&str, &'a CallGraphNode>,
+    by_name: &HashMap<&str, Vec<&'a CallGraphNode>>,
+) -> Option<&'a str> {
+    if ids.contains_key(edge.to.as_str()) {
+        return Some(&edge.to);
+    }
+
+    // `self.save` and `pkg.helper` name the node `save` and `helper`
+    let name = edge.to.rsplit(['.', ':']).next().unwrap_or(&edge.to);
+    // A call may construct a class, but only a class can be inherited from
+    let wanted = |node: &&&CallGraphNode| edge.edge_type != "inherits" || node.node_type == "class";
+    let candidates: Vec<&&CallGraphNode> = by_name.get(name)?.iter().filter(wanted).collect();
+    match candidates.as_slice() {
+        [only] => Some(&only.id),
+        _ => {
+            let caller_file = ids.get(edge.from.as_str())?.file.as_str();
+            let mut local = candidates.iter().filter(|node| node.file == caller_file);
+            match (local.next(), local.next()) {
+                (Some(node), None) => Some(&node.id),
+                _ => None,
+            }
+        }
+    }
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kb::types::{
+        CallGraph, DependencyGraph, FileData, GraphEdge, GraphNode, Indices, KnowledgeBase, Metadata,
+        PatternInfo, SCHEMA_VERSION,
+    };
+    use crate::parser::analyze::Analyzer;
+    use crate::parser::python::PythonParser;
+
+    #[test]
+    fn test_to_dot_styles_nodes_and_edges() {
+        let source = "\
+import os
+
+class User:
+    def save(self):
+        pass
+
+class Admin(User):
+    pass
+
+def helper():
+    pass
+
+def main(flag):
+    helper()
+    if flag:
+        User().save()
+    os.getcwd()
+";
+        let structure = [("app.py".to_string(), PythonParser::new(source.to_string()).parse().unwrap())].into();
+        let graph = Analyzer::build_call_graph(&structure);
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("digraph call_graph {"));
+        assert!(dot.contains("\"func_main\" [label=\"func_main\", fillcolor=lightblue"));
+        assert!(dot.contains("\"method_User_save\" [label=\"method_User_save\", fillcolor=palegreen"));
+        assert!(dot.contains("\"func_main\" -> \"func_helper\" [arrowhead=normal];"));
+        assert!(dot.contains("\"func_main\" -> \"method_User_save\" [arrowhead=normal, style=dashed];"));
+        assert!(dot.contains("\"class_Admin\" -> \"class_User\" [arrowhead=empty, color=darkorange];"));
+        assert!(dot.contains("\"func_main\" -> \"class_User\" [arrowhead=normal, style=dashed];"));
+        assert!(dot.trim_end().ends_with('}'));
+
+        // Every edge ends at a declared node: no bare callee names, no library calls
+        let declared: Vec<&str> = graph.nodes.iter().map(|node| node.id.as_str()).collect();
+        for line in dot.lines().filter(|line| line.contains(" -> ")) {
+            let target = line.split(" -> ").nth(1).unwrap().split('"').nth(1).unwrap();
+            assert!(declared.contains(&target), "{}", line);
+        }
+        assert!(!dot.contains("getcwd"));
+    }
+
+    #[test]
+    fn test_to_graphml_escapes_names() {
+        let graph = DependencyGraph {
+            nodes: vec![
+                GraphNode {
+                    id: "file_src/lib.rs".to_string(),
+                    node_type: "file".to_string(),
+                    name: "src/lib.rs".to_string(),
+                },
+                GraphNode {
+                    id: "func_Vec<T>".to_string(),
+                    node_type: "function".to_string(),
+                    name: "operator&".to_string(),
+                },
+            ],
+            edges: vec![GraphEdge {
+                from: "file_src/lib.rs".to_string(),
+                to: "func_Vec<T>".to_string(),
+                edge_type: "contains".to_string(),
+            }],
+        };
+
+        let xml = graph.to_graphml();
+        assert!(xml.starts_with("<?xml"));
+        assert!(xml.contains("<node id=\"func_Vec&lt;T&gt;\">"));
+        assert!(xml.contains("<data key=\"name\">operator&amp;</data>"));
+        assert!(xml.contains("source=\"file_src/lib.rs\" target=\"func_Vec&lt;T&gt;\""));
+        assert!(xml.contains("<data key=\"edge_type\">contains</data>"));
+        assert!(xml.trim_end().ends_with("</graphml>"));
+    }
+
+    #[test]
+    fn test_ndjson_round_trip() {
+        let structure = [
+            ("pkg/b.py", "def helper():\n    return 1\n"),
+            ("pkg/a.py", "import os\n\nclass User:\n    def save(self):\n        helper()\n"),
+        ]
+        .into_iter()
+        .map(|(path, source)| (path.to_string(), PythonParser::new(source.to_string()).parse().unwrap()))
+        .collect();
+        let kb = KnowledgeBase {
+            metadata: Metadata {
+                schema_version: SCHEMA_VERSION,
+                project_name: "test".to_string(),
+                version: "1.0".to_string(),
+                parsed_at: String::new(),
+                languages: vec!["python".to_string()],
+                total_files: 2,
+                total_loc: 0,
+                total_functions: 0,
+                total_classes: 0,
+                total_methods: 0,
+                git_commit: None,
+                git_branch: None,
+                git_dirty: None,
+            },
+            structure,
+            call_graph: CallGraph::default(),
+            dependency_graph: DependencyGraph::default(),
+            indices: Indices::default(),
+            entry_points: vec![],
+            external_dependencies: vec![],
+            patterns: PatternInfo::default(),
+        };
+
+        let mut out = Vec::new();
+        kb.write_ndjson(&mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+
+        #[derive(serde::Deserialize)]
+        struct Record {
+            path: String,
+            data: FileData,
+        }
+        let records: Vec<Record> = lines.iter().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(records[0].path, "pkg/a.py");
+        assert_eq!(records[1].path, "pkg/b.py");
+        for record in &records {
+            assert_eq!(
+                serde_json::to_value(&record.data).unwrap(),
+                serde_json::to_value(&kb.structure[&record.path]).unwrap()
+            );
+        }
+        assert!(!text.contains("\"metadata\""));
+    }
+}
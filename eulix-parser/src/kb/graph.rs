@@ -0,0 +1,117 @@
+use crate::kb::types::CallGraph;
+use std::collections::HashMap;
+
+impl CallGraph {
+    /// Length, in functions, of the longest call chain starting at an entry
+    /// point, following `calls` edges. 0 when there are no entry points.
+    ///
+    /// Edges point at callee names, so a call to a name defined in several
+    /// places follows every definition. Recursive calls back into a function
+    /// already on the current path are skipped, which keeps the walk finite;
+    /// results are memoized per node, so cyclic graphs give a lower bound.
+    pub fn max_depth_from_entry_points(&self) -> usize {
+        let mut index_by_id: HashMap<&str, usize> = HashMap::new();
+        let mut by_name: HashMap<&str, Vec<usize>> = HashMap::new();
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            index_by_id.insert(node.id.as_str(), i);
+            // Knowledge bases written before nodes carried names
+            let name = if node.name.is_empty() {
+                node.id.strip_prefix("func_").unwrap_or(&node.id)
+            } else {
+                node.name.as_str()
+            };
+            by_name.entry(name).or_default().push(i);
+        }
+
+        let mut callees: Vec<Vec<usize>> = vec![Vec::new(); self.nodes.len()];
+        for edge in self.edges.iter().filter(|e| e.edge_type == "calls") {
+            if let (Some(&from), Some(targets)) = (index_by_id.get(edge.from.as_str()), by_name.get(edge.to.as_str())) {
+                callees[from].extend(targets.iter().filter(|&&t| t != from));
+            }
+        }
+
+        let mut memo = vec![None; self.nodes.len()];
+        let mut on_path = vec![false; self.nodes.len()];
+
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.is_entry_point)
+            .map(|(i, _)| longest_path(i, &callees, &mut memo, &mut on_path))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+fn longest_path(
+    node: usize,
+    callees: &[Vec<usize>],
+    memo: &mut [Option<usize>],
+    on_path: &mut [bool],
+) -> usize {
+    if let Some(depth) = memo[node] {
+        return depth;
+    }
+
+    on_path[node] = true;
+    let mut deepest = 0;
+    for &callee in &callees[node] {
+        if !on_path[callee] {
+            deepest = deepest.max(longest_path(callee, callees, memo, on_path));
+        }
+    }
+    on_path[node] = false;
+
+    memo[node] = Some(deepest + 1);
+    deepest + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::kb::types::{CallGraph, CallGraphEdge, CallGraphNode};
+
+    fn graph(names: &[&str], calls: &[(&str, &str)]) -> CallGraph {
+        CallGraph {
+            nodes: names
+                .iter()
+                .map(|name| CallGraphNode {
+                    id: format!("func_{}", name),
+                    name: name.to_string(),
+                    node_type: "function".to_string(),
+                    file: "app.py".to_string(),
+                    is_entry_point: *name == "main",
+                    call_count_estimate: 0,
+                })
+                .collect(),
+            edges: calls
+                .iter()
+                .map(|(from, to)| CallGraphEdge {
+                    from: format!("func_{}", from),
+                    to: to.to_string(),
+                    edge_type: "calls".to_string(),
+                    conditional: false,
+                    call_site_line: 1,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_linear_chain_depth() {
+        let chain = graph(
+            &["main", "load", "parse", "validate", "store", "unused"],
+            &[("main", "load"), ("load", "parse"), ("parse", "validate"), ("validate", "store")],
+        );
+        assert_eq!(chain.max_depth_from_entry_points(), 5);
+
+        // A cycle back to the start doesn't loop or inflate the depth
+        let cyclic = graph(
+            &["main", "load", "parse"],
+            &[("main", "load"), ("load", "parse"), ("parse", "main"), ("parse", "print")],
+        );
+        assert_eq!(cyclic.max_depth_from_entry_points(), 3);
+
+        assert_eq!(graph(&["load"], &[]).max_depth_from_entry_points(), 0);
+    }
+}
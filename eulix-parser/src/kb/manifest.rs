@@ -0,0 +1,189 @@
+use crate::kb::types::FileData;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Version of the cached `FileData` layout. Bump it whenever `FileData` or
+/// anything it contains changes, so manifests from older builds are thrown
+/// away instead of handing back entries with the new fields defaulted.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Parsed `FileData` from the previous run, keyed by path relative to the
+/// project root and stored in `<root>/.eulix/parse_manifest.json`.
+///
+/// Only raw parser output is cached; analysis always runs over the whole
+/// knowledge base since call edges cross file boundaries.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ParseManifest {
+    /// Manifests written by another parser version are discarded, since
+    /// the cached `FileData` would be missing whatever that version changed
+    pub parser_version: String,
+    /// `MANIFEST_VERSION` of the build that wrote it; manifests written
+    /// before the field existed read as 0 and are discarded too
+    #[serde(default)]
+    pub manifest_version: u32,
+    /// Whether the cached `FileData` carries function source text
+    #[serde(default)]
+    pub include_source: bool,
+    pub files: HashMap<String, ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Modification time in nanoseconds since the epoch
+    pub mtime: u64,
+    /// FNV-1a hash of the file contents
+    pub hash: String,
+    pub data: FileData,
+}
+
+/// What the manifest needs to know about a file on disk
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fingerprint {
+    pub mtime: u64,
+    pub hash: String,
+}
+
+impl ParseManifest {
+    pub fn path(root: &Path) -> PathBuf {
+        root.join(".eulix").join("parse_manifest.json")
+    }
+
+    /// An empty manifest for this build
+    pub fn new(include_source: bool) -> Self {
+        Self {
+            parser_version: env!("CARGO_PKG_VERSION").to_string(),
+            manifest_version: MANIFEST_VERSION,
+            include_source,
+            files: HashMap::new(),
+        }
+    }
+
+    /// Load the manifest for `root`, or an empty one if it's missing,
+    /// unreadable or from another parser version or manifest version
+    pub fn load(root: &Path) -> Self {
+        fs::read_to_string(Self::path(root))
+            .ok()
+            .and_then(|json| serde_json::from_str::<Self>(&json).ok())
+            .filter(|manifest| manifest.parser_version == env!("CARGO_PKG_VERSION"))
+            .filter(|manifest| manifest.manifest_version == MANIFEST_VERSION)
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, root: &Path) -> std::io::Result<()> {
+        let path = Self::path(root);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)
+    }
+
+    /// Cached data for `relative_path` if the file hasn't changed since it
+    /// was recorded. An unchanged mtime skips hashing entirely.
+    pub fn lookup(&self, relative_path: &str, file_path: &Path) -> Option<(FileData, Fingerprint)> {
+        let entry = self.files.get(relative_path)?;
+        let mtime = mtime_of(file_path)?;
+
+        let fingerprint = if mtime == entry.mtime {
+            Fingerprint { mtime, hash: entry.hash.clone() }
+        } else {
+            let fingerprint = Fingerprint { mtime, hash: content_hash(&fs::read(file_path).ok()?) };
+            if fingerprint.hash != entry.hash {
+                return None;
+            }
+            fingerprint
+        };
+
+        Some((entry.data.clone(), fingerprint))
+    }
+
+    pub fn insert(&mut self, relative_path: String, fingerprint: Fingerprint, data: FileData) {
+        self.files.insert(relative_path, ManifestEntry {
+            mtime: fingerprint.mtime,
+            hash: fingerprint.hash,
+            data,
+        });
+    }
+}
+
+impl Fingerprint {
+    pub fn of(file_path: &Path) -> Option<Self> {
+        Some(Self {
+            mtime: mtime_of(file_path)?,
+            hash: content_hash(&fs::read(file_path).ok()?),
+        })
+    }
+}
+
+fn mtime_of(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos() as u64)
+}
+
+/// FNV-1a: stable across Rust releases, unlike `DefaultHasher`
+fn content_hash(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::python::PythonParser;
+
+    #[test]
+    fn test_manifest_reuses_unchanged_files() {
+        let root = std::env::temp_dir().join(format!("eulix_manifest_{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let file = root.join("app.py");
+        fs::write(&file, "def main():\n    pass\n").unwrap();
+
+        let data = PythonParser::new(fs::read_to_string(&file).unwrap()).parse().unwrap();
+        let mut manifest = ParseManifest::new(false);
+        manifest.insert("app.py".to_string(), Fingerprint::of(&file).unwrap(), data);
+        manifest.save(&root).unwrap();
+
+        let loaded = ParseManifest::load(&root);
+        let (cached, _) = loaded.lookup("app.py", &file).expect("unchanged file should be reused");
+        assert_eq!(cached.functions[0].name, "main");
+        assert!(loaded.lookup("other.py", &file).is_none());
+
+        // Same mtime is trusted, so force a different one alongside the edit
+        fs::write(&file, "def changed():\n    pass\n").unwrap();
+        let mut stale = loaded;
+        stale.files.get_mut("app.py").unwrap().mtime = 0;
+        assert!(stale.lookup("app.py", &file).is_none());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_manifest_from_older_layout_is_discarded() {
+        let root = std::env::temp_dir().join(format!("eulix_manifest_layout_{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let file = root.join("app.py");
+        fs::write(&file, "def main():\n    pass\n").unwrap();
+
+        let data = PythonParser::new(fs::read_to_string(&file).unwrap()).parse().unwrap();
+        let mut manifest = ParseManifest::new(false);
+        manifest.insert("app.py".to_string(), Fingerprint::of(&file).unwrap(), data);
+        manifest.manifest_version = MANIFEST_VERSION - 1;
+        manifest.save(&root).unwrap();
+        let older = ParseManifest::load(&root);
+
+        // Written before `manifest_version` existed
+        let path = ParseManifest::path(&root);
+        let mut json: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        json.as_object_mut().unwrap().remove("manifest_version");
+        fs::write(&path, json.to_string()).unwrap();
+        let unversioned = ParseManifest::load(&root);
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(older.files.is_empty());
+        assert!(unversioned.files.is_empty());
+    }
+}
@@ -1,2 +1,6 @@
 pub mod types;
 pub mod builder;
+pub mod diff;
+pub mod export;
+pub mod graph;
+pub mod manifest;
@@ -13,8 +13,15 @@ pub struct KnowledgeBase {
     pub patterns: PatternInfo,
 }
 
+/// Version of the kb.json layout. Bump it when a change would make older
+/// consumers misread the KB; eulix_embed refuses anything older.
+pub const SCHEMA_VERSION: u32 = 2;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Metadata {
+    /// KBs written before the field existed read as version 1
+    #[serde(default = "default_schema_version")]
+    pub schema_version: u32,
     pub project_name: String,
     pub version: String,
     pub parsed_at: String,
@@ -24,6 +31,19 @@ pub struct Metadata {
     pub total_functions: usize,
     pub total_classes: usize,
     pub total_methods: usize,
+    /// Commit checked out when the KB was built; `None` outside a git repo
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_commit: Option<String>,
+    /// `None` on a detached HEAD as well
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_branch: Option<String>,
+    /// Whether the working tree had uncommitted changes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_dirty: Option<bool>,
+}
+
+fn default_schema_version() -> u32 {
+    1
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -36,6 +56,32 @@ pub struct FileData {
     pub global_vars: Vec<GlobalVar>,
     pub todos: Vec<Todo>,
     pub security_notes: Vec<SecurityNote>,
+    /// Names listed in a Python module's `__all__`; `None` when it has none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exports: Option<Vec<String>>,
+    /// `#define`s; C and C++ only
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub macros: Vec<Macro>,
+    /// `//go:build` constraint expressions, all of which must hold for the
+    /// file to be compiled (legacy `// +build` lines are converted); Go only
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub build_tags: Vec<String>,
+    /// `//go:generate`, `//go:embed` and other `//go:` directives other
+    /// than `go:build`; Go only
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub directives: Vec<Directive>,
+    /// Module-level type aliases and `NewType`/`NamedTuple`/`TypedDict`
+    /// assignments, which would otherwise be global vars; Python only
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub type_aliases: Vec<TypeAlias>,
+    /// Calls in module-level statements, outside any function or class
+    /// body (`app.add_url_rule(...)`, `main()` under `__main__`); Python only
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub module_calls: Vec<FunctionCall>,
+    /// Functions stored in variables, struct fields and arrays, used to
+    /// resolve indirect calls; C only
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub function_pointers: Vec<FunctionPointer>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -43,7 +89,10 @@ pub struct Import {
     pub module: String,
     pub items: Vec<String>,
     #[serde(rename = "type")]
-    pub import_type: String, // "external" | "internal"
+    pub import_type: String, // "external" | "internal" | "stdlib" | "type"
+    /// `from module import *`; `items` is then `["*"]`
+    #[serde(default)]
+    pub is_wildcard: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -53,6 +102,10 @@ pub struct Function {
     pub signature: String,
     pub params: Vec<Parameter>,
     pub return_type: String,
+    /// Component types of `return_type`, one per returned value. Only
+    /// filled for Go, where multi-value returns like `(int, error)` are common.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub return_types: Vec<String>,
     pub docstring: String,
     pub line_start: usize,
     pub line_end: usize,
@@ -72,10 +125,32 @@ pub struct Function {
 
     // Metadata
     pub complexity: usize,
+    /// `complexity` is above the threshold for the file's language
+    #[serde(default)]
+    pub needs_refactor: bool,
     pub is_async: bool,
+    /// Starts goroutines (`go f()`); Go only
+    #[serde(default)]
+    pub spawns_goroutines: bool,
+    /// How a method is bound; `Instance` for free functions too
+    #[serde(default)]
+    pub method_kind: MethodKind,
     pub decorators: Vec<String>,
     pub tags: Vec<String>,
     pub importance_score: f32,
+    /// Source text of the definition; only captured with `--include-source`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MethodKind {
+    #[default]
+    Instance,
+    Static,
+    Class,
+    Property,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -89,11 +164,22 @@ pub struct Parameter {
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 pub struct FunctionCall {
     pub callee: String,
+    /// What the callee was looked up on: `self` in `self.validate()`,
+    /// `self.repo` in `self.repo.save()`. Python only; `None` for bare calls.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receiver: Option<String>,
     pub defined_in: Option<String>, // File path where callee is defined
+    #[serde(default)]
+    pub candidates: Vec<String>, // Every possible definition file when the callee is ambiguous
     pub line: usize,
     pub args: Vec<String>,
     pub is_conditional: bool, // Inside if/loop/try block?
-    pub context: String, // "if", "else", "loop", "try", "unconditional"
+    pub context: String, // "if", "else", "loop", "try", "comprehension", "lambda", "unconditional"
+    /// Call through a function pointer (`ops->open(dev)`, `callback(ctx)`);
+    /// `callee` is the pointer's name until the analyzer resolves it to the
+    /// one function ever stored there. C and C++ only.
+    #[serde(default)]
+    pub is_indirect: bool,
 }
 
 // Caller information (reverse call graph)
@@ -127,9 +213,14 @@ pub struct VarTransformation {
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct ControlFlow {
     pub complexity: usize, // Cyclomatic complexity
+    #[serde(default)]
+    pub cognitive_complexity: usize, // Nesting-weighted (Sonar-style) complexity
     pub branches: Vec<Branch>,
     pub loops: Vec<Loop>,
     pub try_blocks: Vec<TryBlock>,
+    /// Python `with` statements: resources and transactions held for a scope
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub with_blocks: Vec<WithBlock>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -154,6 +245,9 @@ pub struct Loop {
     pub condition: String,
     pub line: usize,
     pub calls: Vec<String>,
+    /// `async for`; Python only
+    #[serde(default)]
+    pub is_async: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -164,6 +258,21 @@ pub struct TryBlock {
     pub finally_calls: Vec<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WithBlock {
+    pub line: usize,
+    pub managers: Vec<ContextManager>, // One per item: `with a() as x, b():`
+    pub calls: Vec<String>,            // Calls made inside the block
+    #[serde(default)]
+    pub is_async: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ContextManager {
+    pub expression: String, // e.g. "open(path)", "db.transaction()"
+    pub alias: Option<String>, // Target after `as`, if any
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ExceptClause {
     pub exception_type: String,
@@ -190,6 +299,9 @@ pub struct Class {
     pub methods: Vec<Function>,
     pub attributes: Vec<Attribute>,
     pub decorators: Vec<String>,
+    /// Generic type parameters, e.g. `["T any"]` for Go's `type Stack[T any] struct`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub type_params: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -207,6 +319,49 @@ pub struct GlobalVar {
     pub line: usize,
 }
 
+/// A preprocessor macro. `params` is `None` for object-like macros
+/// (`#define SIZE 10`) and the parameter list for function-like ones.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Macro {
+    pub name: String,
+    pub params: Option<Vec<String>>,
+    pub value: String,
+    pub line: usize,
+    /// Functions the expansion calls, so `#define LOG(m) log_write(m)`
+    /// still links uses of `LOG` to `log_write`
+    pub calls: Vec<String>,
+}
+
+/// `handler = &do_work;`, `void (*cb)(int) = log_line;` or the
+/// `.open = dev_open` entry of a dispatch table's initializer
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FunctionPointer {
+    /// Variable, struct field or array the function is stored in
+    pub name: String,
+    /// Function name, without the `&`
+    pub target: String,
+    pub line: usize,
+}
+
+/// `Vec = List[float]`; `kind` is "alias", "newtype", "namedtuple" or
+/// "typeddict", and `target` the source text of the right-hand side
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TypeAlias {
+    pub name: String,
+    pub target: String,
+    pub kind: String,
+    pub line: usize,
+}
+
+/// A `//go:name args` comment, e.g. `name: "generate"` with
+/// `args: "stringer -type=Pill"`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Directive {
+    pub name: String,
+    pub args: String,
+    pub line: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Todo {
     pub line: usize,
@@ -231,6 +386,9 @@ pub struct CallGraph {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CallGraphNode {
     pub id: String,
+    /// Plain function/class name, which is what `calls` edges point at
+    #[serde(default)]
+    pub name: String,
     pub node_type: String, // "function", "method", "class"
     pub file: String,
     pub is_entry_point: bool,
@@ -279,13 +437,16 @@ pub struct Indices {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct EntryPoint {
-    pub entry_type: String, // "api_endpoint", "cli_command", "main"
+    pub entry_type: String, // "api_endpoint", "cli_command", "main", "go_generate"
     pub path: Option<String>, // API path or CLI command
     pub function: String, // Added missing field
     pub handler: String,
     pub file: String,
     pub line: usize,
     pub methods: Option<Vec<String>>, // HTTP methods for API endpoints
+    /// App, blueprint or router the endpoint is registered on: `app`, `bp`, `router`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub router: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
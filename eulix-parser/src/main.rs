@@ -1,5 +1,7 @@
-use clap::Parser;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
 use rayon::prelude::*;
+use serde::Serialize;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -10,44 +12,97 @@ mod kb;
 mod parser;
 mod utils;
 
+use kb::diff::KbDiff;
+use kb::manifest::{Fingerprint, ParseManifest};
 use kb::types::*;
-use parser::analyze::Analyzer;
+use parser::analyze::{Analyzer, ComplexityEntry, ComplexityThresholds};
+use parser::error::ParseError;
 use parser::language::Language;
 use parser::python;
+use parser::javascript;
+use parser::typescript;
 use parser::go;
 use parser::c;
+use parser::cpp;
+use parser::source::attach_sources;
+use utils::config::ParserConfig;
 use utils::file_walker::FileWalker;
+use utils::git::GitInfo;
+use utils::ignore::PathFilter;
 
 #[derive(Debug, Clone)]
 struct ParseStats {
     parsed: Vec<String>,
-    skipped: Vec<String>,
-    failed: Vec<(String, String)>,
+    reused: Vec<String>,
+    skipped: Vec<(String, String)>,
+    failed: Vec<ParseFailure>,
+    /// Languages without a parser yet; expected, so never a --fail-on-parse-error
+    unsupported: Vec<ParseFailure>,
 }
 
 impl ParseStats {
     fn new() -> Self {
         Self {
             parsed: Vec::new(),
+            reused: Vec::new(),
             skipped: Vec::new(),
             failed: Vec::new(),
+            unsupported: Vec::new(),
         }
     }
 }
 
+/// A file that produced no FileData, as written to --errors-json
+#[derive(Debug, Clone, Serialize)]
+struct ParseFailure {
+    path: String,
+    language: String,
+    /// `ParseError::kind`: io, grammar_load, tree_sitter or unsupported
+    kind: &'static str,
+    error: String,
+}
+
 /// Fast multi-language code parser
 #[derive(Parser, Debug)]
 #[command(name = "eulix_parser")]
 #[command(about = "Fast multi-language code parser", long_about = None)]
+#[command(subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Project root directory
-    #[arg(short, long)]
-    root: String,
+    #[arg(short, long, required = true)]
+    root: Option<String>,
+
+    /// Config file with defaults for these options, overridden by any flag
+    /// given here (default: <root>/eulix.toml when it exists)
+    #[arg(long)]
+    config: Option<String>,
 
     /// Output file for knowledge base
     #[arg(short, long, default_value = "knowledge_base.json")]
     output: String,
 
+    /// Knowledge base format. `ndjson` writes one {"path", "data"} object per
+    /// file with no metadata/structure wrapper; analysis side files stay JSON
+    #[arg(long, value_enum, default_value_t = OutputFormat::Json)]
+    output_format: OutputFormat,
+
+    /// Gzip the knowledge base, adding .gz to --output if it isn't there.
+    /// eulix_embed reads the compressed file directly
+    #[arg(long)]
+    gzip: bool,
+
+    /// Indent the knowledge base and analysis JSON files for reading. They
+    /// are written compact by default, about half the size
+    #[arg(long, conflicts_with = "compact")]
+    pretty: bool,
+
+    /// Write JSON without indentation (the default; overrides `pretty` in eulix.toml)
+    #[arg(long)]
+    compact: bool,
+
     /// Number of threads for parallel parsing
     #[arg(short, long, default_value_t = 4)]
     threads: usize,
@@ -57,7 +112,7 @@ struct Args {
     verbose: bool,
 
     /// Languages to parse (comma-separated, or "all")
-    #[arg(short, long, default_value = "all")]
+    #[arg(short, long, default_value = "all", value_parser = validate_languages)]
     languages: String,
 
     /// Skip analysis phase (faster, only parse files)
@@ -67,10 +122,243 @@ struct Args {
     /// Path to custom .euignore file (defaults to <root>/.euignore)
     #[arg(long)]
     euignore: Option<String>,
+
+    /// Skip files larger than this many bytes, or that look minified (0 = no limit)
+    #[arg(long, default_value_t = 2 * 1024 * 1024)]
+    max_file_size: u64,
+
+    /// Re-parse every file instead of reusing .eulix/parse_manifest.json
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Store each function's source text in the knowledge base
+    #[arg(long)]
+    include_source: bool,
+
+    /// Parse only these files (comma-separated, relative to --root) instead of walking it
+    #[arg(long, value_delimiter = ',')]
+    files: Vec<String>,
+
+    /// Parse only the files listed in this file, one per line (e.g. `git diff --name-only`)
+    #[arg(long)]
+    files_from: Option<String>,
+
+    /// Parse only files matching these globs (comma-separated, .euignore
+    /// syntax, relative to --root), e.g. 'src/api/**'. .euignore still applies
+    #[arg(long, value_name = "GLOBS", value_delimiter = ',')]
+    include: Vec<String>,
+
+    /// Leave out files matching these globs, after .euignore and --include
+    #[arg(long, value_name = "GLOBS", value_delimiter = ',')]
+    exclude: Vec<String>,
+
+    /// Don't write <output>_call_graph.json (analysis still runs)
+    #[arg(long)]
+    no_callgraph_file: bool,
+
+    /// Don't write <output>_index.json (analysis still runs)
+    #[arg(long)]
+    no_indices_file: bool,
+
+    /// Don't write <output>_summary.json (analysis still runs)
+    #[arg(long)]
+    no_summary_file: bool,
+
+    /// Don't write <output>_dead_code.json
+    #[arg(long)]
+    no_dead_code_file: bool,
+
+    /// Don't write <output>_import_cycles.json
+    #[arg(long)]
+    no_import_cycles_file: bool,
+
+    /// Don't write <output>_hotspots.json
+    #[arg(long)]
+    no_hotspots_file: bool,
+
+    /// Format(s) to write the call graph in
+    #[arg(long, value_enum, default_value_t = CallGraphFormat::Json)]
+    call_graph_format: CallGraphFormat,
+
+    /// Also write the dependency graph as <output>_dependency.graphml
+    #[arg(long)]
+    emit_graphml: bool,
+
+    /// Number of functions to list in <output>_hotspots.json
+    #[arg(long, default_value_t = 50)]
+    hotspot_limit: usize,
+
+    /// Write the N most complex functions and methods to
+    /// <output>_complexity.json and print them as a table
+    #[arg(long, value_name = "N")]
+    report_complexity: Option<usize>,
+
+    /// Flag functions and methods whose cyclomatic complexity is above N
+    /// as `needs_refactor`
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    complexity_threshold: usize,
+
+    /// Per-language overrides of --complexity-threshold, e.g. go=15,python=8
+    #[arg(long, value_name = "LANG=N", value_delimiter = ',', value_parser = parse_language_threshold)]
+    language_complexity_threshold: Vec<(String, usize)>,
+
+    /// Exit with status 1, after writing all output, if any function or
+    /// method has a complexity above N
+    #[arg(long, value_name = "N")]
+    fail_on_complexity: Option<usize>,
+
+    /// Skip Go files whose `//go:build` constraints don't hold for these
+    /// tags, e.g. linux,amd64 (default: parse every Go file)
+    #[arg(long, value_name = "TAGS", value_delimiter = ',')]
+    go_build_tags: Vec<String>,
+
+    /// Exit with status 1, after writing all output, if any file failed to
+    /// parse. Files in languages without a parser yet don't count
+    #[arg(long)]
+    fail_on_parse_error: bool,
+
+    /// Write failed and unsupported files as JSON ({"failed": [{path,
+    /// language, kind, error}], "unsupported": [...]})
+    #[arg(long, value_name = "PATH")]
+    errors_json: Option<String>,
+}
+
+/// Fill in options from the config file that weren't given on the command line
+fn apply_config(args: &mut Args, config: ParserConfig, matches: &ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let on_cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+    macro_rules! from_config {
+        ($field:ident) => {
+            from_config!($field, |value| Ok::<_, String>(value))
+        };
+        ($field:ident, $convert:expr) => {
+            if let Some(value) = config.$field {
+                if !on_cli(stringify!($field)) {
+                    args.$field = $convert(value).map_err(|e| format!("eulix.toml {}: {}", stringify!($field), e))?;
+                }
+            }
+        };
+    }
+
+    from_config!(output);
+    from_config!(output_format, |v: String| OutputFormat::from_str(&v, true));
+    from_config!(gzip);
+    from_config!(pretty);
+    from_config!(threads);
+    from_config!(languages, |v: String| validate_languages(&v));
+    from_config!(no_analyze);
+    from_config!(euignore, |v| Ok::<_, String>(Some(v)));
+    from_config!(max_file_size);
+    from_config!(no_cache);
+    from_config!(include_source);
+    from_config!(no_callgraph_file);
+    from_config!(no_indices_file);
+    from_config!(no_summary_file);
+    from_config!(no_dead_code_file);
+    from_config!(no_import_cycles_file);
+    from_config!(no_hotspots_file);
+    from_config!(call_graph_format, |v: String| CallGraphFormat::from_str(&v, true));
+    from_config!(emit_graphml);
+    from_config!(hotspot_limit);
+    from_config!(report_complexity, |v| Ok::<_, String>(Some(v)));
+    from_config!(complexity_threshold);
+    from_config!(language_complexity_threshold, |v: HashMap<String, usize>| {
+        v.into_iter()
+            .map(|(language, n)| parse_language_threshold(&format!("{}={}", language, n)))
+            .collect::<Result<Vec<_>, _>>()
+    });
+    from_config!(fail_on_complexity, |v| Ok::<_, String>(Some(v)));
+    from_config!(fail_on_parse_error);
+    from_config!(errors_json, |v| Ok::<_, String>(Some(v)));
+    from_config!(go_build_tags);
+
+    Ok(())
+}
+
+/// Parse one `LANG=N` entry of --language-complexity-threshold, normalizing
+/// aliases like `golang` to the name stored on each file
+fn parse_language_threshold(entry: &str) -> Result<(String, usize), String> {
+    let (language, n) = entry
+        .split_once('=')
+        .ok_or_else(|| format!("expected LANG=N, got '{}'", entry))?;
+    let languages = Language::parse_filter(language).map_err(|_| {
+        format!("unknown language '{}' (valid: {})", language.trim(), Language::FILTER_NAMES.join(", "))
+    })?;
+    let language = match languages.as_slice() {
+        [language] => language.name().to_string(),
+        _ => return Err(format!("expected a single language in '{}'", entry)),
+    };
+    let n = n.trim().parse().map_err(|_| format!("expected a number after '=' in '{}'", entry))?;
+    Ok((language, n))
+}
+
+/// Reject typos like `--languages pyhton` up front instead of parsing no files
+fn validate_languages(languages: &str) -> Result<String, String> {
+    Language::parse_filter(languages).map(|_| languages.to_string())
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum OutputFormat {
+    Json,
+    Ndjson,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+enum CallGraphFormat {
+    Json,
+    Dot,
+    Both,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Extract the call-graph neighborhood of a function from an existing knowledge base
+    Neighborhood {
+        /// Knowledge base to read
+        #[arg(long)]
+        kb: String,
+
+        /// Function id (e.g. func_login) or name to center on
+        #[arg(short, long)]
+        function: String,
+
+        /// Number of caller/callee hops to include
+        #[arg(short, long, default_value_t = 2)]
+        depth: usize,
+
+        /// Output file for the neighborhood knowledge base
+        #[arg(short, long, default_value = "neighborhood.json")]
+        output: String,
+    },
+
+    /// Report structural changes between two knowledge bases
+    Diff {
+        /// Knowledge base from the earlier run
+        old: String,
+
+        /// Knowledge base from the later run
+        new: String,
+
+        /// Also write the diff as JSON to this file
+        #[arg(short, long)]
+        output: Option<String>,
+    },
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches)?;
+
+    if let Some(command) = args.command.take() {
+        return run_command(command);
+    }
+    let config = ParserConfig::discover(args.config.as_deref(), args.root.as_deref().unwrap_or("."))?;
+    apply_config(&mut args, config, &matches)?;
+    if args.gzip && !args.output.ends_with(".gz") {
+        args.output.push_str(".gz");
+    }
+    let root = args.root.clone().expect("--root is required");
+    let pretty = args.pretty && !args.compact;
 
     // Set thread pool size
     rayon::ThreadPoolBuilder::new()
@@ -85,14 +373,21 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         println!("║             EULIX PARSER - Code Analysis Tool                  ║");
         println!("╚════════════════════════════════════════════════════════════════╝");
         println!();
-        println!("Project Root:    {}", args.root);
+        println!("Project Root:    {}", root);
         println!("Threads:         {}", args.threads);
         println!("Output:          {}", args.output);
+        println!("JSON Style:      {}", if pretty { "pretty" } else { "compact" });
         println!("Languages:       {}", args.languages);
         println!("Skip Analysis:   {}", args.no_analyze);
         if let Some(ref ignore) = args.euignore {
             println!("[x] Ignore File:     {}", ignore);
         }
+        if !args.include.is_empty() {
+            println!("Include:         {}", args.include.join(", "));
+        }
+        if !args.exclude.is_empty() {
+            println!("Exclude:         {}", args.exclude.join(", "));
+        }
         println!();
         println!("{}", "═".repeat(64));
     }
@@ -103,18 +398,45 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         println!("{}", "─".repeat(64));
     }
     let parse_start = Instant::now();
-    let (mut kb, stats) = parse_directory(&args.root, &args.languages, args.euignore.as_deref(), args.verbose)?;
+    let path_filter = PathFilter::new(&args.include, &args.exclude);
+    let selection = match listed_files(&args.files, args.files_from.as_deref())? {
+        Some(entries) => FileSelection::List(
+            select_listed_files(Path::new(&root), &entries, &args.languages, args.verbose)?,
+        ),
+        None => FileSelection::Walk(&args.languages, &path_filter),
+    };
+    let (mut kb, stats) = parse_directory(
+        &root,
+        selection,
+        args.euignore.as_deref(),
+        !args.no_cache,
+        args.include_source,
+        SkipRules { max_file_size: args.max_file_size, go_build_tags: &args.go_build_tags },
+        args.verbose,
+    )?;
 
     if args.verbose {
         println!("\n{}", "─".repeat(64));
         println!("Parsing Complete!");
         println!("     Time:         {:.2}s", parse_start.elapsed().as_secs_f64());
         println!("     Parsed:       {} files", stats.parsed.len());
-        println!("     Skipped:      {} files", stats.skipped.len());
+        println!("     Reused:       {} files (cached)", stats.reused.len());
+        println!("     Skipped:      {} files (too large, minified or build-constrained)", stats.skipped.len());
         println!("     Failed:       {} files", stats.failed.len());
+        println!("     Unsupported:  {} files (no parser for the language yet)", stats.unsupported.len());
         println!("{}", "═".repeat(64));
     }
 
+    if let Some(ref errors_json) = args.errors_json {
+        write_errors_json(&stats, Path::new(errors_json), pretty)?;
+    }
+
+    let thresholds = ComplexityThresholds {
+        default: args.complexity_threshold,
+        per_language: args.language_complexity_threshold.iter().cloned().collect(),
+    };
+    let needs_refactor = Analyzer::flag_needs_refactor(&mut kb, &thresholds);
+
     if !args.no_analyze {
         // Phase 2: Analyze and build indices (parallel where possible)
         if args.verbose {
@@ -152,6 +474,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         if args.verbose {
             println!(" Summary generated in {:.2}s", summary_start.elapsed().as_secs_f64());
+            println!("   Call graph depth: {}", summary.call_graph_depth);
             println!("{}", "═".repeat(64));
         }
 
@@ -171,49 +494,126 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         fs::create_dir_all(output_dir)?;
 
         // Write main kb file
-        let kb_json = serde_json::to_string_pretty(&kb)?;
-        fs::write(output_path, kb_json)?;
+        write_kb(&kb, output_path, args.output_format, args.gzip, pretty)?;
         if args.verbose {
             let size = fs::metadata(output_path)?.len();
             println!("   ✓ {} ({:.2} KB)", args.output, size as f64 / 1024.0);
         }
 
         // Write additional analysis files in the same directory
-        let base_name = output_path
+        let base_name = Path::new(args.output.trim_end_matches(".gz"))
             .file_stem()
             .and_then(|s| s.to_str())
             .unwrap_or("kb");
 
         // Write index.json
-        let index_path = output_dir.join(format!("{}_index.json", base_name));
-        let index_json = serde_json::to_string_pretty(&kb.indices)?;
-        fs::write(&index_path, index_json)?;
-        if args.verbose {
-            let size = fs::metadata(&index_path)?.len();
-            println!("   ✓ {}_index.json ({:.2} KB)", base_name, size as f64 / 1024.0);
+        if !args.no_indices_file {
+            let index_path = output_dir.join(format!("{}_index.json", base_name));
+            let index_json = to_json(&kb.indices, pretty)?;
+            fs::write(&index_path, index_json)?;
+            if args.verbose {
+                let size = fs::metadata(&index_path)?.len();
+                println!("   ✓ {}_index.json ({:.2} KB)", base_name, size as f64 / 1024.0);
+            }
+        } else if args.verbose {
+            println!("   ⊘ {}_index.json (skipped)", base_name);
         }
 
         // Write summary.json
-        let summary_path = output_dir.join(format!("{}_summary.json", base_name));
-        let summary_json = serde_json::to_string_pretty(&summary)?;
-        fs::write(&summary_path, summary_json)?;
-        if args.verbose {
-            let size = fs::metadata(&summary_path)?.len();
-            println!("   ✓ {}_summary.json ({:.2} KB)", base_name, size as f64 / 1024.0);
+        if !args.no_summary_file {
+            let summary_path = output_dir.join(format!("{}_summary.json", base_name));
+            let summary_json = to_json(&summary, pretty)?;
+            fs::write(&summary_path, summary_json)?;
+            if args.verbose {
+                let size = fs::metadata(&summary_path)?.len();
+                println!("   ✓ {}_summary.json ({:.2} KB)", base_name, size as f64 / 1024.0);
+            }
+        } else if args.verbose {
+            println!("   ⊘ {}_summary.json (skipped)", base_name);
         }
 
-        // Write call_graph.json
-        let callgraph_path = output_dir.join(format!("{}_call_graph.json", base_name));
-        let callgraph_json = serde_json::to_string_pretty(&kb.call_graph)?;
-        fs::write(&callgraph_path, callgraph_json)?;
-        if args.verbose {
-            let size = fs::metadata(&callgraph_path)?.len();
-            println!("   ✓ {}_call_graph.json ({:.2} KB)", base_name, size as f64 / 1024.0);
+        // Write call_graph.json / call_graph.dot
+        if !args.no_callgraph_file {
+            if args.call_graph_format != CallGraphFormat::Dot {
+                let callgraph_path = output_dir.join(format!("{}_call_graph.json", base_name));
+                let callgraph_json = to_json(&kb.call_graph, pretty)?;
+                fs::write(&callgraph_path, callgraph_json)?;
+                if args.verbose {
+                    let size = fs::metadata(&callgraph_path)?.len();
+                    println!("   ✓ {}_call_graph.json ({:.2} KB)", base_name, size as f64 / 1024.0);
+                }
+            }
+            if args.call_graph_format != CallGraphFormat::Json {
+                let dot_path = output_dir.join(format!("{}_call_graph.dot", base_name));
+                fs::write(&dot_path, kb.call_graph.to_dot())?;
+                if args.verbose {
+                    let size = fs::metadata(&dot_path)?.len();
+                    println!("   ✓ {}_call_graph.dot ({:.2} KB)", base_name, size as f64 / 1024.0);
+                }
+            }
+        } else if args.verbose {
+            println!("   ⊘ {}_call_graph.json (skipped)", base_name);
+        }
+
+        // Write dependency.graphml
+        if args.emit_graphml {
+            let graphml_path = output_dir.join(format!("{}_dependency.graphml", base_name));
+            fs::write(&graphml_path, kb.dependency_graph.to_graphml())?;
+            if args.verbose {
+                let size = fs::metadata(&graphml_path)?.len();
+                println!("   ✓ {}_dependency.graphml ({:.2} KB)", base_name, size as f64 / 1024.0);
+            }
+        }
+
+        // Write dead_code.json
+        if !args.no_dead_code_file {
+            let dead_code = Analyzer::find_dead_code(&kb);
+            let dead_code_path = output_dir.join(format!("{}_dead_code.json", base_name));
+            fs::write(&dead_code_path, to_json(&dead_code, pretty)?)?;
+            if args.verbose {
+                println!("   ✓ {}_dead_code.json ({} unreferenced functions)", base_name, dead_code.len());
+            }
+        } else if args.verbose {
+            println!("   ⊘ {}_dead_code.json (skipped)", base_name);
+        }
+
+        // Write import_cycles.json
+        if !args.no_import_cycles_file {
+            let import_cycles = Analyzer::find_import_cycles(&kb);
+            let import_cycles_path = output_dir.join(format!("{}_import_cycles.json", base_name));
+            fs::write(&import_cycles_path, to_json(&import_cycles, pretty)?)?;
+            if args.verbose {
+                println!("   ✓ {}_import_cycles.json ({} cycles)", base_name, import_cycles.len());
+            }
+        } else if args.verbose {
+            println!("   ⊘ {}_import_cycles.json (skipped)", base_name);
+        }
+
+        // Write hotspots.json
+        if !args.no_hotspots_file {
+            let hotspots = Analyzer::generate_hotspots(&kb, args.hotspot_limit);
+            let hotspots_path = output_dir.join(format!("{}_hotspots.json", base_name));
+            fs::write(&hotspots_path, to_json(&hotspots, pretty)?)?;
+            if args.verbose {
+                println!("   ✓ {}_hotspots.json (top {} functions)", base_name, hotspots.len());
+            }
+        } else if args.verbose {
+            println!("   ⊘ {}_hotspots.json (skipped)", base_name);
+        }
+
+        // Write complexity.json
+        let complexity_report = args.report_complexity.map(|n| Analyzer::top_complexity(&kb, n));
+        if let Some(ref report) = complexity_report {
+            let complexity_path = output_dir.join(format!("{}_complexity.json", base_name));
+            fs::write(&complexity_path, to_json(report, pretty)?)?;
+            if args.verbose {
+                println!("   ✓ {}_complexity.json (top {} functions)", base_name, report.len());
+            }
         }
 
         if args.verbose {
             println!("{}", "═".repeat(64));
-            print_final_summary(&kb, &stats, start_time.elapsed().as_secs_f64());
+            print_final_summary(&kb, &stats, needs_refactor, start_time.elapsed().as_secs_f64());
         } else {
             println!(
                 "✓ Parsed {} files ({} LOC) in {:.2}s → {}",
@@ -223,6 +623,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 args.output
             );
         }
+
+        if let Some(ref report) = complexity_report {
+            print_complexity_table(report);
+        }
     } else {
         // Only write basic kb.json without analysis
         if args.verbose {
@@ -235,14 +639,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             fs::create_dir_all(parent)?;
         }
 
-        let kb_json = serde_json::to_string_pretty(&kb)?;
-        fs::write(output_path, kb_json)?;
+        write_kb(&kb, output_path, args.output_format, args.gzip, pretty)?;
 
         if args.verbose {
             let size = fs::metadata(output_path)?.len();
             println!("   ✓ {} ({:.2} KB)", args.output, size as f64 / 1024.0);
             println!("{}", "═".repeat(64));
-            print_final_summary(&kb, &stats, start_time.elapsed().as_secs_f64());
+            print_final_summary(&kb, &stats, needs_refactor, start_time.elapsed().as_secs_f64());
         } else {
             println!(
                 "✓ Parsed {} files ({} LOC) in {:.2}s → {} (no analysis)",
@@ -254,10 +657,126 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    if !args.verbose && !stats.skipped.is_empty() {
+        println!(
+            "⊘ Skipped {} files over --max-file-size, minified or excluded by --go-build-tags (run with -v for the list)",
+            stats.skipped.len()
+        );
+    }
+    if !args.verbose && !args.fail_on_parse_error && !stats.failed.is_empty() {
+        println!("✗ {} files failed to parse (run with -v for the list)", stats.failed.len());
+    }
+    if !args.verbose && needs_refactor > 0 {
+        println!(
+            "⚑ {} functions need refactoring (complexity above --complexity-threshold {})",
+            needs_refactor, args.complexity_threshold
+        );
+    }
+
+    if let Some(limit) = args.fail_on_complexity {
+        let over: Vec<ComplexityEntry> = Analyzer::top_complexity(&kb, usize::MAX)
+            .into_iter()
+            .take_while(|entry| entry.complexity > limit)
+            .collect();
+        if !over.is_empty() {
+            eprintln!("✗ {} functions exceed --fail-on-complexity {}:", over.len(), limit);
+            for entry in &over {
+                eprintln!("   {:>4}  {}:{}  {}", entry.complexity, entry.file, entry.line, entry.name);
+            }
+            std::process::exit(1);
+        }
+    }
+
+    if args.fail_on_parse_error && !stats.failed.is_empty() {
+        eprintln!("✗ {} files failed to parse (--fail-on-parse-error):", stats.failed.len());
+        for failure in &stats.failed {
+            eprintln!("   {} ({}) - {}", failure.path, failure.language, failure.error);
+        }
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+fn write_errors_json(stats: &ParseStats, path: &Path, pretty: bool) -> Result<(), Box<dyn std::error::Error>> {
+    #[derive(Serialize)]
+    struct ErrorReport<'a> {
+        failed: &'a [ParseFailure],
+        unsupported: &'a [ParseFailure],
+    }
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let report = ErrorReport { failed: &stats.failed, unsupported: &stats.unsupported };
+    fs::write(path, to_json(&report, pretty)?)?;
+    Ok(())
+}
+
+/// Indented with --pretty, one line otherwise
+fn to_json<T: Serialize + ?Sized>(value: &T, pretty: bool) -> serde_json::Result<String> {
+    if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    }
+}
+
+fn run_command(command: Command) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Command::Neighborhood { kb, function, depth, output } => {
+            let kb = read_kb(Path::new(&kb))?;
+
+            let neighborhood = Analyzer::extract_neighborhood(&kb, &function, depth)?;
+
+            let output_path = Path::new(&output);
+            if let Some(parent) = output_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(output_path, serde_json::to_string_pretty(&neighborhood)?)?;
+
+            println!(
+                "✓ Neighborhood of {} (depth {}): {} functions, {} methods across {} files → {}",
+                function,
+                depth,
+                neighborhood.metadata.total_functions,
+                neighborhood.metadata.total_methods,
+                neighborhood.metadata.total_files,
+                output
+            );
+        }
+        Command::Diff { old, new, output } => {
+            let old_kb = read_kb(Path::new(&old))?;
+            let new_kb = read_kb(Path::new(&new))?;
+
+            let diff = KbDiff::between(&old_kb, &new_kb);
+            print!("{}", diff.summary());
+
+            if let Some(output) = output {
+                let output_path = Path::new(&output);
+                if let Some(parent) = output_path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(output_path, serde_json::to_string_pretty(&diff)?)?;
+                println!("✓ Diff written to {}", output);
+            }
+        }
+    }
+
     Ok(())
 }
 
-fn print_final_summary(kb: &KnowledgeBase, stats: &ParseStats, total_time: f64) {
+fn print_complexity_table(report: &[ComplexityEntry]) {
+    println!();
+    println!("MOST COMPLEX FUNCTIONS");
+    println!("   {:>4}  {:>4}  {:<40}  FUNCTION", "CC", "COG", "LOCATION");
+    for entry in report {
+        let location = format!("{}:{}", entry.file, entry.line);
+        println!("   {:>4}  {:>4}  {:<40}  {}", entry.complexity, entry.cognitive_complexity, location, entry.name);
+    }
+}
+
+fn print_final_summary(kb: &KnowledgeBase, stats: &ParseStats, needs_refactor: usize, total_time: f64) {
     println!("EXECUTION TIME");
     println!("   Total:                  {:.2}s", total_time);
     println!();
@@ -268,8 +787,17 @@ fn print_final_summary(kb: &KnowledgeBase, stats: &ParseStats, total_time: f64)
     println!("   Functions:              {}", kb.metadata.total_functions);
     println!("   Classes:                {}", kb.metadata.total_classes);
     println!("   Methods:                {}", kb.metadata.total_methods);
+    println!("   Needs Refactor:         {}", needs_refactor);
     println!();
 
+    if let Some(ref commit) = kb.metadata.git_commit {
+        let branch = kb.metadata.git_branch.as_deref().unwrap_or("detached");
+        let dirty = if kb.metadata.git_dirty == Some(true) { " (uncommitted changes)" } else { "" };
+        println!("SOURCE");
+        println!("   Git:                    {} @ {}{}", branch, &commit[..commit.len().min(12)], dirty);
+        println!();
+    }
+
     println!("LANGUAGES DETECTED");
     for lang in &kb.metadata.languages {
         println!("   • {}", lang);
@@ -286,22 +814,92 @@ fn print_final_summary(kb: &KnowledgeBase, stats: &ParseStats, total_time: f64)
     if !stats.failed.is_empty() {
         println!();
         println!("[!]  FAILED FILES:");
-        for (file, reason) in &stats.failed {
+        for failure in &stats.failed {
+            println!("   • {} ({}) - {}", failure.path, failure.language, failure.error);
+        }
+    }
+
+    if !stats.unsupported.is_empty() {
+        println!();
+        println!("⊘  UNSUPPORTED FILES (no parser yet):");
+        for failure in &stats.unsupported {
+            println!("   • {} ({})", failure.path, failure.language);
+        }
+    }
+
+    if !stats.skipped.is_empty() {
+        println!("⊘  SKIPPED FILES:");
+        for (file, reason) in &stats.skipped {
             println!("   • {} - {}", file, reason);
         }
+        println!();
     }
 
     println!(" PARSING STATISTICS");
     println!("   ✓ Successfully Parsed:  {} files", stats.parsed.len());
+    println!("   ↺ Reused From Cache:    {} files", stats.reused.len());
     println!("   ⊘ Skipped:              {} files", stats.skipped.len());
     println!("   ✗ Failed:               {} files", stats.failed.len());
+    println!("   ⊘ Unsupported:          {} files", stats.unsupported.len());
     println!(" Analysis complete!");
 }
 
+fn write_kb(
+    kb: &KnowledgeBase,
+    path: &Path,
+    format: OutputFormat,
+    gzip: bool,
+    pretty: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    let write = |writer: &mut dyn Write| -> Result<(), Box<dyn std::error::Error>> {
+        match format {
+            OutputFormat::Json if pretty => serde_json::to_writer_pretty(&mut *writer, kb)?,
+            OutputFormat::Json => serde_json::to_writer(&mut *writer, kb)?,
+            OutputFormat::Ndjson => kb.write_ndjson(&mut *writer)?,
+        }
+        Ok(writer.flush()?)
+    };
+
+    let mut file = std::io::BufWriter::new(fs::File::create(path)?);
+    if gzip {
+        let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        write(&mut encoder)?;
+        encoder.finish()?.flush()?;
+    } else {
+        write(&mut file)?;
+    }
+    Ok(())
+}
+
+/// Load a JSON knowledge base, gunzipping it first if it is compressed
+fn read_kb(path: &Path) -> Result<KnowledgeBase, Box<dyn std::error::Error>> {
+    let bytes = fs::read(path)?;
+    let kb = if bytes.starts_with(&[0x1f, 0x8b]) {
+        serde_json::from_reader(flate2::read::MultiGzDecoder::new(bytes.as_slice()))?
+    } else {
+        serde_json::from_slice(&bytes)?
+    };
+    Ok(kb)
+}
+
+/// What `parse_directory` parses
+enum FileSelection<'a> {
+    /// Every file under the root matching this `--languages` filter and
+    /// `--include`/`--exclude`
+    Walk(&'a str, &'a PathFilter),
+    /// Exactly these files (`--files`/`--files-from`), already language-filtered
+    List(Vec<PathBuf>),
+}
+
 fn parse_directory(
     dir: &str,
-    languages: &str,
+    selection: FileSelection,
     euignore_path: Option<&str>,
+    use_cache: bool,
+    include_source: bool,
+    skip_rules: SkipRules,
     verbose: bool,
 ) -> Result<(KnowledgeBase, ParseStats), Box<dyn std::error::Error>> {
     let path = PathBuf::from(dir);
@@ -322,14 +920,32 @@ fn parse_directory(
         println!("   [!] Using .euignore: {:?}", euignore.as_ref().unwrap());
     }
 
-    // Collect all source files based on language filter
-    let files = collect_source_files(&path, languages, verbose)?;
+    // Collect all source files based on language filter, unless given a list
+    let (files, partial) = match selection {
+        FileSelection::Walk(languages, path_filter) => {
+            (collect_source_files(&path, euignore.as_deref(), languages, path_filter, verbose)?, false)
+        }
+        FileSelection::List(files) => (files, true),
+    };
 
     if verbose {
         println!("    Discovered {} source files", files.len());
         println!();
     }
 
+    // Files unchanged since the last run reuse their cached FileData
+    // Cached data without sources can't serve an --include-source run, and vice versa
+    let manifest = if use_cache {
+        ParseManifest::load(&path)
+    } else {
+        ParseManifest::default()
+    };
+    let manifest = if manifest.include_source == include_source {
+        manifest
+    } else {
+        ParseManifest::default()
+    };
+
     // Thread-safe stats collection
     let stats = Arc::new(Mutex::new(ParseStats::new()));
 
@@ -343,20 +959,74 @@ fn parse_directory(
                 .to_string_lossy()
                 .to_string();
 
-            match parse_file(file_path, &path) {
-                Ok(result) => {
+            if let Some(reason) = skip_rules.skip_reason(file_path) {
+                if verbose {
+                    println!("   ⊘ Skipped: {} - {}", relative_path, reason);
+                }
+                stats.lock().unwrap().skipped.push((relative_path, reason));
+                return None;
+            }
+
+            // Rejected before the manifest lookup, so they're never fingerprinted
+            let language = Language::detect(file_path);
+            if !language.has_parser() {
+                let e = ParseError::Unsupported { language: language.name() };
+                if verbose {
+                    println!("   ⊘ Unsupported: {} - {}", relative_path, e);
+                }
+                stats.lock().unwrap().unsupported.push(ParseFailure {
+                    path: relative_path,
+                    language: language.name().to_string(),
+                    kind: e.kind(),
+                    error: e.to_string(),
+                });
+                return None;
+            }
+
+            if let Some((file_data, fingerprint)) = manifest.lookup(&relative_path, file_path) {
+                if verbose {
+                    println!("   ↺ Cached:  {}", relative_path);
+                }
+                stats.lock().unwrap().reused.push(relative_path.clone());
+                return Some((relative_path, file_data, Some(fingerprint)));
+            }
+
+            let fingerprint = Fingerprint::of(file_path);
+            let parsed = parse_file(file_path, &path, language).and_then(|(relative_path, mut file_data)| {
+                if include_source {
+                    let source = fs::read_to_string(file_path)
+                        .map_err(|e| ParseError::io(file_path, e))?
+                        .replace("\r\n", "\n");
+                    attach_sources(&mut file_data, &source);
+                }
+                Ok((relative_path, file_data))
+            });
+            match parsed {
+                Ok((relative_path, file_data)) => {
                     if verbose {
                         println!("   ✓ Parsed:  {}", relative_path);
                     }
                     stats.lock().unwrap().parsed.push(relative_path.clone());
-                    Some(result)
+                    Some((relative_path, file_data, fingerprint))
                 }
                 Err(e) => {
-                    let error_msg = e.to_string();
+                    let unsupported = matches!(e, ParseError::Unsupported { .. });
                     if verbose {
-                        println!("   ✗ Failed:  {} - {}", relative_path, error_msg);
+                        let mark = if unsupported { "⊘ Unsupported:" } else { "✗ Failed: " };
+                        println!("   {} {} - {}", mark, relative_path, e);
+                    }
+                    let failure = ParseFailure {
+                        path: relative_path,
+                        language: language.name().to_string(),
+                        kind: e.kind(),
+                        error: e.to_string(),
+                    };
+                    let mut stats = stats.lock().unwrap();
+                    if unsupported {
+                        stats.unsupported.push(failure);
+                    } else {
+                        stats.failed.push(failure);
                     }
-                    stats.lock().unwrap().failed.push((relative_path, error_msg));
                     None
                 }
             }
@@ -365,6 +1035,23 @@ fn parse_directory(
 
     let final_stats = Arc::try_unwrap(stats).unwrap().into_inner().unwrap();
 
+    // Rewrite the manifest from this run so deleted files drop out of it.
+    // A partial run only refreshes the files it parsed.
+    let mut next_manifest = ParseManifest::new(include_source);
+    if partial {
+        next_manifest.files = manifest.files;
+    }
+    for (relative_path, file_data, fingerprint) in &results {
+        if let Some(fingerprint) = fingerprint {
+            next_manifest.insert(relative_path.clone(), fingerprint.clone(), file_data.clone());
+        }
+    }
+    if let Err(e) = next_manifest.save(&path) {
+        if verbose {
+            println!("   [!] Could not write {}: {}", ParseManifest::path(&path).display(), e);
+        }
+    }
+
     // Build knowledge base structure
     let mut structure = HashMap::new();
     let mut total_loc = 0;
@@ -373,7 +1060,7 @@ fn parse_directory(
     let mut total_methods = 0;
     let mut languages_set = std::collections::HashSet::new();
 
-    for (relative_path, file_data) in results {
+    for (relative_path, file_data, _) in results {
         total_loc += file_data.loc;
         total_functions += file_data.functions.len();
         total_classes += file_data.classes.len();
@@ -393,7 +1080,9 @@ fn parse_directory(
         .unwrap_or("unknown")
         .to_string();
 
+    let git = GitInfo::detect(&path);
     let metadata = Metadata {
+        schema_version: SCHEMA_VERSION,
         project_name,
         version: "1.0".to_string(),
         parsed_at: chrono::Utc::now().to_rfc3339(),
@@ -403,6 +1092,9 @@ fn parse_directory(
         total_functions,
         total_classes,
         total_methods,
+        git_commit: git.commit,
+        git_branch: git.branch,
+        git_dirty: git.dirty,
     };
 
     let kb = KnowledgeBase {
@@ -419,79 +1111,119 @@ fn parse_directory(
     Ok((kb, final_stats))
 }
 
+/// Entries from `--files` and `--files-from`, or `None` when neither was given.
+/// Blank lines and `#` comments in the list file are ignored.
+fn listed_files(
+    files: &[String],
+    files_from: Option<&str>,
+) -> Result<Option<Vec<String>>, Box<dyn std::error::Error>> {
+    if files.is_empty() && files_from.is_none() {
+        return Ok(None);
+    }
+
+    let mut entries: Vec<String> = files.iter().map(|f| f.trim().to_string()).collect();
+    if let Some(list_path) = files_from {
+        let list = fs::read_to_string(list_path)
+            .map_err(|e| format!("Failed to read --files-from {}: {}", list_path, e))?;
+        entries.extend(list.lines().map(str::trim).filter(|line| !line.starts_with('#')).map(String::from));
+    }
+    entries.retain(|entry| !entry.is_empty());
+
+    Ok(Some(entries))
+}
+
+/// Resolve listed paths against `root` (falling back to the working
+/// directory) and keep the ones in the `--languages` filter. Missing files,
+/// e.g. deletions in a diff, are skipped with a warning.
+fn select_listed_files(
+    root: &Path,
+    entries: &[String],
+    languages: &str,
+    verbose: bool,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let lang_filters = Language::parse_filter(languages)?;
+    let root_abs = fs::canonicalize(root).ok();
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let entry_path = Path::new(entry);
+        let resolved = if entry_path.is_absolute() {
+            // Keep absolute paths under the root relative to it as given
+            let relative = root_abs.as_ref().and_then(|root_abs| {
+                fs::canonicalize(entry_path).ok()?.strip_prefix(root_abs).ok().map(|rel| root.join(rel))
+            });
+            Some(relative.unwrap_or_else(|| entry_path.to_path_buf())).filter(|p| p.is_file())
+        } else {
+            [root.join(entry_path), entry_path.to_path_buf()].into_iter().find(|p| p.is_file())
+        };
+
+        let Some(file) = resolved else {
+            eprintln!("   [!] Skipping {}: no such file", entry);
+            continue;
+        };
+        if lang_filters.contains(&Language::detect(&file)) {
+            files.push(file);
+        } else if verbose {
+            println!("   ⊘ Skipped: {} - not in --languages", entry);
+        }
+    }
+
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
 #[allow(dead_code)]
 fn collect_source_files(
     root: &Path,
-    // euignore_path: Option<&Path>,
+    euignore_path: Option<&Path>,
     languages: &str,
+    path_filter: &PathFilter,
     verbose: bool,
 ) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
     let mut all_files = Vec::new();
 
     // Parse language filter
-    let lang_filters: Vec<Language> = if languages == "all" {
-        vec![
-            Language::C,
-            Language::Python,
-            Language::JavaScript,
-            Language::TypeScript,
-            Language::Go,
-            Language::Rust,
-        ]
-    } else {
-        languages
-            .split(',')
-            .map(|s| s.trim())
-            .filter_map(|lang_str| match lang_str.to_lowercase().as_str() {
-                "c" | "C" => Some(Language::C),
-                "python" | "py" => Some(Language::Python),
-                "javascript" | "js" => Some(Language::JavaScript),
-                "typescript" | "ts" => Some(Language::TypeScript),
-                "go" | "golang" => Some(Language::Go),
-                "rust" | "rs" => Some(Language::Rust),
-                _ => {
-                    if verbose {
-                        eprintln!("     Unknown language filter '{}'", lang_str);
-                    }
-                    None
-                }
-            })
-            .collect()
-    };
+    let lang_filters = Language::parse_filter(languages)?;
 
     if verbose {
         println!("    Searching for files...");
     }
 
     // Use FileWalker for all languages
-    let walker = FileWalker::new(root.to_path_buf());
+    let mut walker = FileWalker::new(root.to_path_buf()).with_path_filter(path_filter.clone());
+    if let Some(euignore) = euignore_path {
+        walker = walker.with_euignore(euignore);
+    }
 
     for lang in &lang_filters {
-        let extension = match lang {
-            Language::C => "c",
-            Language::Python => "py",
-            Language::JavaScript => "js",
-            Language::TypeScript => "ts",
-            Language::Go => "go",
-            Language::Rust => "rs",
+        let extensions: &[&str] = match lang {
+            Language::C => &["c"],
+            Language::Cpp => &["cpp", "cc", "cxx", "hpp", "hxx"],
+            Language::Python => &["py"],
+            Language::JavaScript => &["js"],
+            Language::TypeScript => &["ts", "tsx"],
+            Language::Go => &["go"],
+            Language::Rust => &["rs"],
             _ => continue,
         };
+        let label = extensions.join("/.");
 
         match walker.walk_files(|path| {
             path.extension()
                 .and_then(|ext| ext.to_str())
-                .map(|ext| ext == extension)
+                .map(|ext| extensions.contains(&ext))
                 .unwrap_or(false)
         }) {
             Ok(files) => {
                 if verbose && !files.is_empty() {
-                    println!("      • Found {} .{} files", files.len(), extension);
+                    println!("      • Found {} .{} files", files.len(), label);
                 }
                 all_files.extend(files)
             },
             Err(e) => {
                 if verbose {
-                    eprintln!("        Failed to collect .{} files: {}", extension, e);
+                    eprintln!("        Failed to collect .{} files: {}", label, e);
                 }
             }
         }
@@ -504,12 +1236,65 @@ fn collect_source_files(
     Ok(all_files)
 }
 
-fn parse_file(
-    file_path: &Path,
-    root: &Path,
-) -> Result<(String, FileData), Box<dyn std::error::Error>> {
-    let lang = Language::detect(file_path);
+/// Files `parse_directory` leaves out before parsing
+struct SkipRules<'a> {
+    /// --max-file-size; 0 disables the size and minified checks
+    max_file_size: u64,
+    /// --go-build-tags; empty parses every Go file
+    go_build_tags: &'a [String],
+}
+
+impl SkipRules<'_> {
+    fn skip_reason(&self, file_path: &Path) -> Option<String> {
+        size_skip_reason(file_path, self.max_file_size)
+            .or_else(|| go_build_skip_reason(file_path, self.go_build_tags))
+    }
+}
+
+/// Files whose average line is longer than this are treated as minified
+const MINIFIED_AVG_LINE_LENGTH: u64 = 500;
+/// Below this size a long average line is just a short file, not a bundle
+const MINIFIED_MIN_SIZE: u64 = 16 * 1024;
+
+/// Why a file is too big to be worth parsing, if it is. Generated blobs
+/// parse slowly and add nothing useful to the knowledge base.
+fn size_skip_reason(file_path: &Path, max_file_size: u64) -> Option<String> {
+    if max_file_size == 0 {
+        return None;
+    }
+
+    let size = fs::metadata(file_path).ok()?.len();
+    if size > max_file_size {
+        return Some(format!("{:.1} KB exceeds --max-file-size", size as f64 / 1024.0));
+    }
+
+    if size >= MINIFIED_MIN_SIZE {
+        let content = fs::read(file_path).ok()?;
+        let lines = content.iter().filter(|&&b| b == b'\n').count() as u64 + 1;
+        let avg_line = size / lines;
+        if avg_line > MINIFIED_AVG_LINE_LENGTH {
+            return Some(format!("looks minified ({} chars per line)", avg_line));
+        }
+    }
+
+    None
+}
+
+/// Why a Go file is left out under --go-build-tags, if it is
+fn go_build_skip_reason(file_path: &Path, tags: &[String]) -> Option<String> {
+    if tags.is_empty() || file_path.extension().and_then(|e| e.to_str()) != Some("go") {
+        return None;
+    }
 
+    let source = fs::read_to_string(file_path).ok()?;
+    let constraints = go::build_constraints(&source);
+    if go::build_tags_satisfied(&constraints, tags) {
+        return None;
+    }
+    Some(format!("excluded by build constraint `{}`", constraints.join(" && ")))
+}
+
+fn parse_file(file_path: &Path, root: &Path, lang: Language) -> Result<(String, FileData), ParseError> {
     let relative_path = file_path
         .strip_prefix(root)
         .unwrap_or(file_path)
@@ -522,10 +1307,12 @@ fn parse_file(
             Ok((relative_path, file_data))
         }
         Language::JavaScript => {
-            Err("JavaScript parsing not yet implemented".into())
+            let (_, file_data) = javascript::parse_file(file_path)?;
+            Ok((relative_path, file_data))
         }
         Language::TypeScript => {
-            Err("TypeScript parsing not yet implemented".into())
+            let (_, file_data) = typescript::parse_file(file_path)?;
+            Ok((relative_path, file_data))
         }
         Language::Go => {
             let (_, file_data) = go::parse_file(file_path)?;
@@ -535,9 +1322,178 @@ fn parse_file(
             let (_, file_data) = c::parse_file(file_path)?;
             Ok((relative_path, file_data))
         }
-        Language::Rust => {
-            Err("Rust parsing not yet implemented".into())
+        Language::Cpp => {
+            let (_, file_data) = cpp::parse_file(file_path)?;
+            Ok((relative_path, file_data))
         }
-        _ => Err(format!("Unsupported language: {:?}", lang).into()),
+        Language::Rust | Language::Unknown => Err(ParseError::Unsupported { language: lang.name() }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_explicit_file_list() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("pkg")).unwrap();
+        fs::write(root.join("pkg/auth.py"), "def login(user):\n    return user\n").unwrap();
+        fs::write(root.join("server.go"), "package main\n\nfunc main() {}\n").unwrap();
+        fs::write(root.join("unlisted.py"), "def other():\n    pass\n").unwrap();
+
+        let entries = listed_files(&["pkg/auth.py".to_string(), "server.go".to_string()], None)
+            .unwrap()
+            .unwrap();
+        let files = select_listed_files(root, &entries, "all", false).unwrap();
+        let (kb, stats) = parse_directory(
+            root.to_str().unwrap(),
+            FileSelection::List(files),
+            None,
+            false,
+            false,
+            SkipRules { max_file_size: 0, go_build_tags: &[] },
+            false,
+        )
+        .unwrap();
+
+        let mut parsed: Vec<&String> = kb.structure.keys().collect();
+        parsed.sort();
+        assert_eq!(parsed, vec!["pkg/auth.py", "server.go"]);
+        assert_eq!(stats.parsed.len(), 2);
+
+        // The language filter still applies to listed files
+        let python_only = select_listed_files(root, &entries, "python", false).unwrap();
+        assert_eq!(python_only, vec![root.join("pkg/auth.py")]);
+        assert_eq!(listed_files(&[], None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_cli_flags_override_config() {
+        let matches = Args::command()
+            .try_get_matches_from(["eulix_parser", "-r", ".", "--threads", "8"])
+            .unwrap();
+        let mut args = Args::from_arg_matches(&matches).unwrap();
+        let config = ParserConfig::from_toml(
+            "[parser]\nthreads = 2\nhotspot_limit = 10\ncall_graph_format = \"dot\"\n\
+             no_hotspots_file = true\nno_import_cycles_file = true\n",
+        )
+        .unwrap();
+
+        apply_config(&mut args, config, &matches).unwrap();
+        assert_eq!(args.threads, 8);
+        assert_eq!(args.hotspot_limit, 10);
+        assert!(args.no_hotspots_file && args.no_import_cycles_file);
+        assert_eq!(args.call_graph_format, CallGraphFormat::Dot);
+        assert_eq!(args.output, "knowledge_base.json");
+
+        let bad = ParserConfig::from_toml("[parser]\nlanguages = \"pyhton\"\n").unwrap();
+        assert!(apply_config(&mut args, bad, &matches).is_err());
+    }
+
+    #[test]
+    fn test_language_complexity_thresholds() {
+        let args = Args::try_parse_from([
+            "eulix_parser", "-r", ".", "--language-complexity-threshold", "golang=15,py=8",
+        ])
+        .unwrap();
+        assert_eq!(args.complexity_threshold, 10);
+        assert_eq!(
+            args.language_complexity_threshold,
+            vec![("go".to_string(), 15), ("python".to_string(), 8)]
+        );
+
+        assert!(parse_language_threshold("go").is_err());
+        assert!(parse_language_threshold("all=5").is_err());
+        assert!(parse_language_threshold("cobol=5").is_err());
+        assert!(parse_language_threshold("go=many").is_err());
+    }
+
+    #[test]
+    fn test_gzip_output_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join("app.py"), "def main():\n    run()\n").unwrap();
+        let (kb, _) = parse_directory(
+            dir.path().to_str().unwrap(),
+            FileSelection::Walk("all", &PathFilter::default()),
+            None,
+            false,
+            false,
+            SkipRules { max_file_size: 0, go_build_tags: &[] },
+            false,
+        )
+        .unwrap();
+
+        let path = dir.path().join("knowledge_base.json.gz");
+        write_kb(&kb, &path, OutputFormat::Json, true, false).unwrap();
+        assert!(fs::read(&path).unwrap().starts_with(&[0x1f, 0x8b]));
+
+        let loaded = read_kb(&path).unwrap();
+        assert_eq!(loaded.metadata.total_functions, kb.metadata.total_functions);
+        assert!(loaded.structure["app.py"].functions.iter().any(|f| f.name == "main"));
+    }
+
+    #[test]
+    fn test_compact_output_is_single_line() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join("app.py"), "def main():\n    run()\n").unwrap();
+        let (kb, _) = parse_directory(
+            dir.path().to_str().unwrap(),
+            FileSelection::Walk("all", &PathFilter::default()),
+            None,
+            false,
+            false,
+            SkipRules { max_file_size: 0, go_build_tags: &[] },
+            false,
+        )
+        .unwrap();
+
+        let compact_path = dir.path().join("compact.json");
+        write_kb(&kb, &compact_path, OutputFormat::Json, false, false).unwrap();
+        let compact = fs::read_to_string(&compact_path).unwrap();
+        assert!(!compact.contains('\n'));
+        assert!(compact.contains("},\"structure\":{"));
+
+        let pretty_path = dir.path().join("pretty.json");
+        write_kb(&kb, &pretty_path, OutputFormat::Json, false, true).unwrap();
+        let pretty = fs::read_to_string(&pretty_path).unwrap();
+        assert!(pretty.contains("\n  \"structure\": {"));
+        assert!(compact.len() < pretty.len());
+    }
+
+    #[test]
+    fn test_broken_file_is_failed_and_unsupported_is_not() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join("app.py"), "def main():\n    pass\n").unwrap();
+        // Not UTF-8, so the source can't even be read
+        fs::write(dir.path().join("broken.py"), b"def f():\n    return '\xff\xfe'\n").unwrap();
+        fs::write(dir.path().join("lib.rs"), "fn main() {}\n").unwrap();
+
+        let (kb, stats) = parse_directory(
+            dir.path().to_str().unwrap(),
+            FileSelection::Walk("all", &PathFilter::default()),
+            None,
+            false,
+            false,
+            SkipRules { max_file_size: 0, go_build_tags: &[] },
+            false,
+        )
+        .unwrap();
+
+        assert_eq!(kb.structure.keys().collect::<Vec<_>>(), vec!["app.py"]);
+        assert_eq!(stats.failed.len(), 1);
+        assert_eq!((stats.failed[0].path.as_str(), stats.failed[0].language.as_str()), ("broken.py", "python"));
+        assert_eq!(stats.unsupported.len(), 1);
+        assert_eq!((stats.unsupported[0].path.as_str(), stats.unsupported[0].language.as_str()), ("lib.rs", "rust"));
+
+        let path = dir.path().join("out/errors.json");
+        write_errors_json(&stats, &path, true).unwrap();
+        let report: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(report["failed"][0]["path"], "broken.py");
+        assert_eq!(report["failed"][0]["kind"], "io");
+        assert!(report["failed"][0]["error"].as_str().unwrap().contains("Failed to read file"));
+        assert_eq!(report["unsupported"][0]["kind"], "unsupported");
+        assert_eq!(report["unsupported"][0]["language"], "rust");
     }
 }
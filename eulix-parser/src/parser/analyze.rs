@@ -6,6 +6,14 @@ use std::collections::{HashMap, HashSet};
 /// Analyzes the knowledge base to extract high-level insights
 pub struct Analyzer;
 
+/// Dotted module path -> file for Python, and package directory -> files
+/// for Go, the two lookups import resolution works from
+#[derive(Default)]
+struct ProjectModules<'a> {
+    python: HashMap<String, &'a str>,
+    go: HashMap<&'a str, Vec<&'a str>>,
+}
+
 impl Analyzer {
     /// Generate complete knowledge base with indices and call graph
     pub fn analyze_and_build(mut kb: KnowledgeBase, verbose: bool) -> KnowledgeBase {
@@ -18,6 +26,18 @@ impl Analyzer {
             println!("   [!]  Enabling memory-efficient mode for {} files", file_count);
         }
 
+        // Single-file parsing can't tell local packages from third-party ones
+        if !is_large {
+            if verbose { println!("   → Resolving imports..."); }
+            Self::reclassify_imports(&mut kb);
+        }
+
+        // Point calls through function pointers at the function stored there
+        if !is_large {
+            if verbose { println!("   → Resolving function pointers..."); }
+            Self::resolve_indirect_calls(&mut kb);
+        }
+
         // Build call graph (skip for very large repos to save memory)
         if !is_large {
             if verbose { println!("   → Building call graph..."); }
@@ -26,16 +46,22 @@ impl Analyzer {
             println!("   [!]  Skipping call graph (too large, would use excessive memory)");
         }
 
-        // Build reverse call graph (populate called_by)
+        // Resolve function call locations
+        if !is_large {
+            if verbose { println!("   → Resolving call locations..."); }
+            Self::resolve_call_locations(&mut kb);
+        }
+
+        // Build reverse call graph (populate called_by) from the resolved calls
         if !is_large {
             if verbose { println!("   → Building reverse call graph..."); }
             Self::populate_called_by(&mut kb);
         }
 
-        // Resolve function call locations
+        // Exceptions that escape through uncaught calls
         if !is_large {
-            if verbose { println!("   → Resolving call locations..."); }
-            Self::resolve_call_locations(&mut kb);
+            if verbose { println!("   → Propagating exceptions..."); }
+            Self::propagate_exceptions(&mut kb);
         }
 
         // Build indices (always do this, it's useful)
@@ -50,15 +76,292 @@ impl Analyzer {
         if verbose { println!("   → Finding entry points..."); }
         kb.entry_points = Self::find_entry_points(&kb);
 
+        // Routes and CLI commands are only known now; flag their graph nodes too
+        let entry_nodes: HashSet<(&str, &str)> = kb.entry_points
+            .iter()
+            .map(|ep| (ep.file.as_str(), ep.function.as_str()))
+            .collect();
+        for node in &mut kb.call_graph.nodes {
+            if entry_nodes.contains(&(node.file.as_str(), node.name.as_str())) {
+                node.is_entry_point = true;
+            }
+        }
+
         // Analyze external dependencies (lightweight)
         if verbose { println!("   → Analyzing dependencies..."); }
         kb.external_dependencies = Self::analyze_external_deps(&kb);
 
+        // File-level import graph
+        if !is_large {
+            if verbose { println!("   → Building dependency graph..."); }
+            kb.dependency_graph = Self::build_dependency_graph(&kb);
+        }
+
         kb
     }
 
+    /// Mark Python and Go imports that resolve to a parsed file as
+    /// `internal`, whatever the parser guessed from the module name alone.
+    /// A Python import naming a directory of parsed modules (a namespace
+    /// package without `__init__.py`) counts as well.
+    fn reclassify_imports(kb: &mut KnowledgeBase) {
+        let mut files: Vec<&String> = kb.structure.keys().collect();
+        files.sort();
+        let ProjectModules { python: python_modules, go: go_packages } = Self::project_modules(&files, &kb.structure);
+
+        let resolves = |file: &str, language: &str, import: &Import| match language {
+            "python" => {
+                !Self::resolve_python_import(file, import, &python_modules).is_empty()
+                    || (!import.module.starts_with('.')
+                        && python_modules.keys().any(|m| m.starts_with(&format!("{}.", import.module))))
+            }
+            "go" => !Self::resolve_go_import(&import.module, &go_packages).is_empty(),
+            _ => false,
+        };
+
+        let internal: Vec<(String, usize)> = files
+            .iter()
+            .flat_map(|file| {
+                let filedata = &kb.structure[*file];
+                filedata.imports
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, import)| import.import_type != "internal" && import.import_type != "type")
+                    .filter(|(_, import)| resolves(file, &filedata.language, import))
+                    .map(|(i, _)| (file.to_string(), i))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+
+        for (file, i) in internal {
+            if let Some(filedata) = kb.structure.get_mut(&file) {
+                filedata.imports[i].import_type = "internal".to_string();
+            }
+        }
+    }
+
+    fn project_modules<'a>(files: &[&'a String], structure: &HashMap<String, FileData>) -> ProjectModules<'a> {
+        let mut modules = ProjectModules::default();
+        for file in files {
+            match structure[*file].language.as_str() {
+                "python" => {
+                    let module = Self::module_path(file);
+                    let module = module.strip_suffix(".__init__").unwrap_or(&module).to_string();
+                    modules.python.insert(module, file.as_str());
+                }
+                "go" => {
+                    let dir = file.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("");
+                    modules.go.entry(dir).or_default().push(file.as_str());
+                }
+                _ => {}
+            }
+        }
+        modules
+    }
+
+    /// Fill `exceptions.propagates` with what each function can surface
+    /// because a callee raises (or itself propagates) it and the function
+    /// doesn't catch it. Uses the resolved `defined_in`/`candidates` of each
+    /// call, so it must run after `resolve_call_locations`.
+    ///
+    /// Every round pushes exceptions one call level further up. Sets only
+    /// grow, so cycles settle on their own; the round cap just bounds the
+    /// work on very deep call chains.
+    fn propagate_exceptions(kb: &mut KnowledgeBase) {
+        const MAX_PROPAGATION_DEPTH: usize = 32;
+
+        // Flatten every function and method so callees can be referred to by index
+        let mut keys: HashMap<(String, String), usize> = HashMap::new();
+        let mut by_name: HashMap<(String, String), Vec<usize>> = HashMap::new();
+        let mut raises: Vec<Vec<String>> = Vec::new();
+        let mut handles: Vec<Vec<String>> = Vec::new();
+
+        for (filepath, filedata) in &kb.structure {
+            for (func, class) in Self::functions_with_class(filedata) {
+                let index = raises.len();
+                keys.insert((filepath.clone(), func.id.clone()), index);
+                by_name.entry((filepath.clone(), func.name.clone())).or_default().push(index);
+                if let Some(class) = class {
+                    by_name.entry((filepath.clone(), format!("{}.{}", class.name, func.name))).or_default().push(index);
+                }
+                raises.push(func.exceptions.raises.clone());
+                handles.push(func.exceptions.handles.clone());
+            }
+        }
+
+        let class_methods = Self::class_methods(kb);
+        let mut callees: Vec<Vec<usize>> = vec![Vec::new(); raises.len()];
+        for (filepath, filedata) in &kb.structure {
+            for (func, class) in Self::functions_with_class(filedata) {
+                let index = keys[&(filepath.clone(), func.id.clone())];
+                for call in &func.calls {
+                    let target_name = Self::call_target_name(call, func, class, &class_methods);
+                    for target_file in call.defined_in.iter().chain(call.candidates.iter()) {
+                        if let Some(targets) = by_name.get(&(target_file.clone(), target_name.clone())) {
+                            callees[index].extend(targets.iter().filter(|&&t| t != index));
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut propagates: Vec<Vec<String>> = vec![Vec::new(); raises.len()];
+        for _ in 0..MAX_PROPAGATION_DEPTH {
+            let mut changed = false;
+
+            for index in 0..propagates.len() {
+                let mut escaping: Vec<String> = callees[index]
+                    .iter()
+                    .flat_map(|&callee| raises[callee].iter().chain(propagates[callee].iter()))
+                    .filter(|exc| !Self::catches(&handles[index], exc))
+                    .cloned()
+                    .collect();
+                escaping.sort();
+                escaping.dedup();
+
+                if escaping != propagates[index] {
+                    propagates[index] = escaping;
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        for (filepath, filedata) in kb.structure.iter_mut() {
+            let funcs = filedata.functions.iter_mut()
+                .chain(filedata.classes.iter_mut().flat_map(|c| c.methods.iter_mut()));
+            for func in funcs {
+                if let Some(&index) = keys.get(&(filepath.clone(), func.id.clone())) {
+                    func.exceptions.propagates = std::mem::take(&mut propagates[index]);
+                }
+            }
+        }
+    }
+
+    /// Every function and method in a file, each with the class it belongs to
+    fn functions_with_class(filedata: &FileData) -> impl Iterator<Item = (&Function, Option<&Class>)> {
+        filedata.functions.iter().map(|f| (f, None)).chain(
+            filedata.classes.iter().flat_map(|c| c.methods.iter().map(move |m| (m, Some(c)))),
+        )
+    }
+
+    /// Whether `call` is `self.name()`/`cls.name()` on a method the calling
+    /// method's own class defines
+    fn is_own_method_call(call: &FunctionCall, own_methods: &HashSet<&str>) -> bool {
+        matches!(call.receiver.as_deref(), Some("self" | "cls")) && own_methods.contains(call.callee.as_str())
+    }
+
+    /// Method names of every class in the knowledge base, by class name
+    fn class_methods(kb: &KnowledgeBase) -> HashMap<String, HashSet<String>> {
+        let mut methods: HashMap<String, HashSet<String>> = HashMap::new();
+        for class in kb.structure.values().flat_map(|filedata| filedata.classes.iter()) {
+            methods.entry(class.name.clone()).or_default().extend(class.methods.iter().map(|m| m.name.clone()));
+        }
+        methods
+    }
+
+    /// Class whose method `call` invokes, when the receiver pins it down:
+    /// `self`/`cls` calling one of the caller's own methods, a class defining
+    /// the method (`Service.create()`, `Service().save()`), or a parameter
+    /// annotated with one (`def f(repo: Repo): repo.save()`)
+    fn receiver_class(
+        call: &FunctionCall,
+        caller: &Function,
+        caller_class: Option<&Class>,
+        class_methods: &HashMap<String, HashSet<String>>,
+    ) -> Option<String> {
+        if let Some(class) = caller_class {
+            let own_methods: HashSet<&str> = class.methods.iter().map(|m| m.name.as_str()).collect();
+            if Self::is_own_method_call(call, &own_methods) {
+                return Some(class.name.clone());
+            }
+        }
+
+        let receiver = call.receiver.as_deref()?;
+        let receiver = match receiver.strip_suffix(')') {
+            Some(constructed) => constructed.split('(').next().unwrap_or(""),
+            None => receiver,
+        };
+        let defines_callee = |class: &str| class_methods.get(class).is_some_and(|m| m.contains(&call.callee));
+        if defines_callee(receiver) {
+            return Some(receiver.to_string());
+        }
+        caller
+            .variables
+            .iter()
+            .find(|v| v.scope == "param" && v.name == receiver)
+            .and_then(|v| v.var_type.as_deref())
+            .filter(|ty| defines_callee(ty))
+            .map(str::to_string)
+    }
+
+    /// Name a call's target is looked up under: `Class.name` when the
+    /// receiver's class is known, so neither a same-named free function nor
+    /// another class's method is credited with it, otherwise the bare callee
+    /// name, which every same-named function and method answers to
+    fn call_target_name(
+        call: &FunctionCall,
+        caller: &Function,
+        caller_class: Option<&Class>,
+        class_methods: &HashMap<String, HashSet<String>>,
+    ) -> String {
+        match Self::receiver_class(call, caller, caller_class, class_methods) {
+            Some(class) => format!("{}.{}", class, call.callee),
+            None => call.callee.clone(),
+        }
+    }
+
+    /// Whether a handler list stops `exception`. Only exact names and the
+    /// catch-all bases are understood; subclass relationships aren't known.
+    fn catches(handles: &[String], exception: &str) -> bool {
+        handles.iter().any(|handled| {
+            handled == exception || matches!(handled.as_str(), "Exception" | "BaseException" | "Error")
+        })
+    }
+
+    /// Rename indirect calls after their target when the function pointer
+    /// only ever holds one defined function. Assignments in the caller's own
+    /// file are used when it has any for the pointer, else the whole
+    /// project's; several possible targets leave the call as it is.
+    fn resolve_indirect_calls(kb: &mut KnowledgeBase) {
+        let defined: HashSet<&str> = kb.structure
+            .values()
+            .flat_map(|filedata| Self::functions_with_class(filedata).map(|(f, _)| f.name.as_str()))
+            .collect();
+
+        let mut by_file: HashMap<String, HashMap<String, HashSet<String>>> = HashMap::new();
+        let mut project: HashMap<String, HashSet<String>> = HashMap::new();
+        for (filepath, filedata) in &kb.structure {
+            for pointer in &filedata.function_pointers {
+                if defined.contains(pointer.target.as_str()) {
+                    by_file.entry(filepath.clone()).or_default()
+                        .entry(pointer.name.clone()).or_default()
+                        .insert(pointer.target.clone());
+                    project.entry(pointer.name.clone()).or_default().insert(pointer.target.clone());
+                }
+            }
+        }
+
+        for (filepath, filedata) in kb.structure.iter_mut() {
+            let local = by_file.get(filepath);
+            let functions = filedata.functions.iter_mut()
+                .chain(filedata.classes.iter_mut().flat_map(|c| c.methods.iter_mut()));
+            for call in functions.flat_map(|f| f.calls.iter_mut()).filter(|c| c.is_indirect) {
+                let targets = local
+                    .and_then(|pointers| pointers.get(&call.callee))
+                    .or_else(|| project.get(&call.callee));
+                if let Some(targets) = targets.filter(|targets| targets.len() == 1) {
+                    call.callee = targets.iter().next().unwrap().clone();
+                }
+            }
+        }
+    }
+
     /// Build call graph from structure
-    fn build_call_graph(structure: &HashMap<String, FileData>) -> CallGraph {
+    pub fn build_call_graph(structure: &HashMap<String, FileData>) -> CallGraph {
         let mut nodes = Vec::new();
         let mut edges = Vec::new();
         let mut node_ids = HashSet::new();
@@ -69,6 +372,7 @@ impl Analyzer {
                 if !node_ids.contains(&func.id) {
                     nodes.push(CallGraphNode {
                         id: func.id.clone(),
+                        name: func.name.clone(),
                         node_type: if func.id.starts_with("method_") {
                             "method".to_string()
                         } else {
@@ -98,6 +402,7 @@ impl Analyzer {
                 if !node_ids.contains(&class.id) {
                     nodes.push(CallGraphNode {
                         id: class.id.clone(),
+                        name: class.name.clone(),
                         node_type: "class".to_string(),
                         file: filepath.clone(),
                         is_entry_point: false,
@@ -122,6 +427,7 @@ impl Analyzer {
                     if !node_ids.contains(&method.id) {
                         nodes.push(CallGraphNode {
                             id: method.id.clone(),
+                            name: method.name.clone(),
                             node_type: "method".to_string(),
                             file: filepath.clone(),
                             is_entry_point: false,
@@ -157,9 +463,15 @@ impl Analyzer {
     }
 
     /// Populate called_by fields in functions (reverse call graph) - OPTIMIZED WITH CHUNKING
+    ///
+    /// Runs after `resolve_call_locations`: a caller is attributed only to the
+    /// definition(s) its call resolved to, never to every same-named function.
+    /// A call whose receiver's class is known only counts for that class's
+    /// method (`Class.name`, see `call_target_name`).
     fn populate_called_by(kb: &mut KnowledgeBase) {
         const CHUNK_SIZE: usize = 1000;
 
+        let class_methods = Self::class_methods(kb);
         let structure_vec: Vec<_> = kb.structure.iter().collect();
         let chunks: Vec<_> = structure_vec.chunks(CHUNK_SIZE).collect();
 
@@ -170,26 +482,15 @@ impl Analyzer {
                 let mut local_calls = Vec::new();
 
                 for (filepath, filedata) in chunk.iter() {
-                    for func in &filedata.functions {
+                    for (func, class) in Self::functions_with_class(filedata) {
                         for call in &func.calls {
-                            local_calls.push((
-                                call.callee.clone(),
-                                CallerInfo {
-                                    function: func.id.clone(),
-                                    file: filepath.to_string(),
-                                    line: call.line,
-                                },
-                            ));
-                        }
-                    }
-
-                    for class in &filedata.classes {
-                        for method in &class.methods {
-                            for call in &method.calls {
+                            let target_name = Self::call_target_name(call, func, class, &class_methods);
+                            let targets = call.defined_in.iter().chain(call.candidates.iter());
+                            for target_file in targets {
                                 local_calls.push((
-                                    call.callee.clone(),
+                                    (target_file.clone(), target_name.clone()),
                                     CallerInfo {
-                                        function: method.id.clone(),
+                                        function: func.id.clone(),
                                         file: filepath.to_string(),
                                         line: call.line,
                                     },
@@ -203,27 +504,34 @@ impl Analyzer {
             })
             .collect();
 
-        // Build reverse mapping from collected data
-        let mut reverse_calls: HashMap<String, Vec<CallerInfo>> = HashMap::new();
-        for (callee, caller_info) in all_calls {
+        // Build reverse mapping from collected data, keyed by (file, name)
+        let mut reverse_calls: HashMap<(String, String), Vec<CallerInfo>> = HashMap::new();
+        for (target, caller_info) in all_calls {
             reverse_calls
-                .entry(callee)
+                .entry(target)
                 .or_insert_with(Vec::new)
                 .push(caller_info);
         }
 
         // Update called_by fields
-        for (_, filedata) in kb.structure.iter_mut() {
+        for (filepath, filedata) in kb.structure.iter_mut() {
             for func in &mut filedata.functions {
-                if let Some(callers) = reverse_calls.get(&func.name) {
+                if let Some(callers) = reverse_calls.get(&(filepath.clone(), func.name.clone())) {
                     func.called_by = callers.clone();
                 }
             }
 
             for class in &mut filedata.classes {
                 for method in &mut class.methods {
-                    if let Some(callers) = reverse_calls.get(&method.name) {
-                        method.called_by = callers.clone();
+                    let qualified = (filepath.clone(), format!("{}.{}", class.name, method.name));
+                    let callers: Vec<CallerInfo> = [(filepath.clone(), method.name.clone()), qualified]
+                        .iter()
+                        .filter_map(|key| reverse_calls.get(key))
+                        .flatten()
+                        .cloned()
+                        .collect();
+                    if !callers.is_empty() {
+                        method.called_by = callers;
                     }
                 }
             }
@@ -231,40 +539,134 @@ impl Analyzer {
     }
 
     /// Resolve where called functions are defined
+    ///
+    /// `self.name()`/`cls.name()` resolves to the calling method's own class
+    /// when it defines `name`. Otherwise a callee defined in the caller's own
+    /// file wins, then one reachable
+    /// through the caller's imports, then a globally unique name. Anything
+    /// still ambiguous leaves `defined_in` empty and lists every candidate,
+    /// including a call on a receiver of unknown class when its file has
+    /// more than one function or method by that name.
     fn resolve_call_locations(kb: &mut KnowledgeBase) {
-        // Build function name -> file location mapping
-        let mut func_locations: HashMap<String, String> = HashMap::new();
+        // Build function name -> file locations mapping
+        let mut func_locations: HashMap<String, Vec<String>> = HashMap::new();
+        let mut definitions: HashMap<(String, String), usize> = HashMap::new();
+        let class_methods = Self::class_methods(kb);
 
         for (filepath, filedata) in &kb.structure {
-            for func in &filedata.functions {
-                func_locations.insert(func.name.clone(), filepath.clone());
-            }
-
-            for class in &filedata.classes {
-                for method in &class.methods {
-                    func_locations.insert(method.name.clone(), filepath.clone());
+            let names = filedata.functions.iter()
+                .chain(filedata.classes.iter().flat_map(|c| c.methods.iter()))
+                .map(|f| f.name.clone());
+
+            for name in names {
+                *definitions.entry((filepath.clone(), name.clone())).or_default() += 1;
+                let files = func_locations.entry(name).or_default();
+                if !files.contains(filepath) {
+                    files.push(filepath.clone());
                 }
             }
         }
 
+        for files in func_locations.values_mut() {
+            files.sort();
+        }
+
         // Update defined_in fields
-        for (_, filedata) in kb.structure.iter_mut() {
-            for func in &mut filedata.functions {
-                for call in &mut func.calls {
-                    call.defined_in = func_locations.get(&call.callee).cloned();
-                }
-            }
+        for (filepath, filedata) in kb.structure.iter_mut() {
+            let FileData { imports, functions, classes, .. } = filedata;
+
+            let resolve = |func: &mut Function, own_methods: &HashSet<&str>| {
+                let class_known: Vec<bool> = func.calls
+                    .iter()
+                    .map(|call| Self::receiver_class(call, func, None, &class_methods).is_some())
+                    .collect();
+
+                for (call, class_known) in func.calls.iter_mut().zip(class_known) {
+                    // self.helper() inside a class that defines helper is that method
+                    if Self::is_own_method_call(call, own_methods) {
+                        call.defined_in = Some(filepath.clone());
+                        call.candidates = vec![];
+                        continue;
+                    }
 
-            for class in &mut filedata.classes {
-                for method in &mut class.methods {
-                    for call in &mut method.calls {
-                        call.defined_in = func_locations.get(&call.callee).cloned();
+                    let candidates = func_locations
+                        .get(&call.callee)
+                        .map(|files| Self::narrow_candidates(files, filepath, &call.callee, imports))
+                        .unwrap_or_default();
+                    // `item.save()` with two classes defining `save` in that file
+                    let ambiguous_in_file = call.receiver.is_some() && !class_known && candidates.len() == 1 &&
+                        definitions.get(&(candidates[0].clone(), call.callee.clone())).is_some_and(|&n| n > 1);
+
+                    if candidates.len() == 1 && !ambiguous_in_file {
+                        call.defined_in = candidates.into_iter().next();
+                        call.candidates = vec![];
+                    } else {
+                        call.defined_in = None;
+                        call.candidates = candidates;
                     }
                 }
+            };
+
+            for func in functions.iter_mut() {
+                resolve(func, &HashSet::new());
+            }
+            for class in classes.iter_mut() {
+                let names: Vec<String> = class.methods.iter().map(|m| m.name.clone()).collect();
+                let own_methods: HashSet<&str> = names.iter().map(String::as_str).collect();
+                for method in class.methods.iter_mut() {
+                    resolve(method, &own_methods);
+                }
             }
         }
     }
 
+    /// Narrow the files defining `callee` to the ones a call in `caller_file`
+    /// can actually reach
+    fn narrow_candidates(files: &[String], caller_file: &str, callee: &str, imports: &[Import]) -> Vec<String> {
+        if files.len() <= 1 {
+            return files.to_vec();
+        }
+
+        if files.iter().any(|f| f == caller_file) {
+            return vec![caller_file.to_string()];
+        }
+
+        let imported: Vec<String> = files
+            .iter()
+            .filter(|file| {
+                let file_module = Self::module_path(file);
+                imports.iter().any(|import| {
+                    let module = import.module
+                        .trim_start_matches("./")
+                        .trim_start_matches("../")
+                        .trim_start_matches('.')
+                        .replace('/', ".");
+                    let names_callee = import.items.is_empty() || import.is_wildcard ||
+                        import.items.iter().any(|item| item == callee);
+
+                    !module.is_empty() && names_callee &&
+                        (file_module == module || file_module.ends_with(&format!(".{}", module)))
+                })
+            })
+            .cloned()
+            .collect();
+
+        if imported.is_empty() {
+            files.to_vec()
+        } else {
+            imported
+        }
+    }
+
+    /// `pkg/auth/views.py` -> `pkg.auth.views`
+    fn module_path(file: &str) -> String {
+        let without_ext = match file.rsplit_once('.') {
+            Some((stem, ext)) if !ext.contains('/') => stem,
+            _ => file,
+        };
+        without_ext.replace(['/', '\\'], ".")
+    }
+
     /// Generate index for fast lookups - OPTIMIZED WITH CHUNKING
     fn generate_indices(kb: &KnowledgeBase) -> Indices {
         const CHUNK_SIZE: usize = 1000;
@@ -300,6 +702,17 @@ impl Analyzer {
                         }
                     }
 
+                    // Calls hidden behind C macros
+                    for mac in &filedata.macros {
+                        for callee in &mac.calls {
+                            local_fn_calling.push((callee.clone(), format!("macro_{}", mac.name)));
+                        }
+                    }
+
+                    for alias in &filedata.type_aliases {
+                        local_types.push((alias.name.clone(), format!("{}:{}", filepath, alias.line)));
+                    }
+
                     // Index classes
                     for class in &filedata.classes {
                         local_types.push((
@@ -317,6 +730,10 @@ impl Analyzer {
                             for tag in &method.tags {
                                 local_fn_by_tag.push((tag.clone(), method.id.clone()));
                             }
+
+                            for call in &method.calls {
+                                local_fn_calling.push((call.callee.clone(), method.id.clone()));
+                            }
                         }
                     }
                 }
@@ -351,7 +768,7 @@ impl Analyzer {
             functions_calling,
             functions_by_tag,
             types_by_name,
-            files_by_category: HashMap::new(),
+            files_by_category: Self::categorize_files(&kb.structure),
         }
     }
 
@@ -371,27 +788,31 @@ impl Analyzer {
                         file: filepath.clone(),
                         line: func.line_start,
                         methods: None,
+                        router: None,
                     });
                 }
 
-                // Check for API endpoints (Flask/FastAPI decorators)
+                // Check for API endpoints: `@<name>.route(...)` and the verb
+                // decorators, whether `<name>` is the app, a Flask blueprint
+                // or a FastAPI `APIRouter`
                 for decorator in &func.decorators {
-                    if decorator.contains("route") || decorator.contains("get") ||
-                       decorator.contains("post") || decorator.contains("api") {
-                        // Try to extract route path
-                        let route_path = Self::extract_route_path(decorator);
-                        let http_methods = Self::extract_http_methods(decorator);
-
-                        entry_points.push(EntryPoint {
-                            entry_type: "api_endpoint".to_string(),
-                            path: route_path,
-                            function: func.name.clone(),
-                            handler: func.name.clone(),
-                            file: filepath.clone(),
-                            line: func.line_start,
-                            methods: Some(http_methods),
-                        });
-                    }
+                    let Some(call) = DecoratorCall::parse(decorator) else {
+                        continue;
+                    };
+                    let Some(router) = Self::route_router(&call.callee) else {
+                        continue;
+                    };
+
+                    entry_points.push(EntryPoint {
+                        entry_type: "api_endpoint".to_string(),
+                        path: Self::extract_route_path(&call),
+                        function: func.name.clone(),
+                        handler: func.name.clone(),
+                        file: filepath.clone(),
+                        line: func.line_start,
+                        methods: Some(Self::extract_http_methods(&call)),
+                        router: Some(router.to_string()),
+                    });
                 }
 
                 // Check for CLI commands (click/argparse)
@@ -404,44 +825,111 @@ impl Analyzer {
                         file: filepath.clone(),
                         line: func.line_start,
                         methods: None,
+                        router: None,
                     });
                 }
             }
+
+            // Flask's non-decorator form, at module level or inside an app factory
+            let calls = filedata
+                .module_calls
+                .iter()
+                .chain(filedata.functions.iter().flat_map(|f| f.calls.iter()));
+            for call in calls.filter(|c| c.callee == "add_url_rule") {
+                if let Some(entry_point) = Self::url_rule_entry_point(call, filepath) {
+                    entry_points.push(entry_point);
+                }
+            }
+
+            // `go generate` runs these commands; they aren't functions, so
+            // the command itself is the path and the handler
+            for directive in filedata.directives.iter().filter(|d| d.name == "generate") {
+                entry_points.push(EntryPoint {
+                    entry_type: "go_generate".to_string(),
+                    path: Some(directive.args.clone()),
+                    function: "go:generate".to_string(),
+                    handler: directive.args.clone(),
+                    file: filepath.clone(),
+                    line: directive.line,
+                    methods: None,
+                    router: None,
+                });
+            }
         }
 
         entry_points
     }
 
-    fn extract_route_path(decorator: &str) -> Option<String> {
-        // Extract path from decorators like @app.route("/api/login")
-        let re = regex::Regex::new(r#"['"]([/\w-]+)['"]"#).ok()?;
-        re.captures(decorator)
-            .and_then(|caps| caps.get(1))
-            .map(|m| m.as_str().to_string())
+    /// `app` in `app.route`, `api.v1.router` in `api.v1.router.get`; `None`
+    /// when the callee isn't a route registration
+    fn route_router(callee: &str) -> Option<&str> {
+        let (router, verb) = callee.rsplit_once('.')?;
+        let is_route = matches!(verb, "route" | "get" | "post" | "put" | "delete" | "patch");
+        (is_route && !router.is_empty()).then_some(router)
     }
 
-    fn extract_http_methods(decorator: &str) -> Vec<String> {
-        let mut methods = Vec::new();
-        let dec_lower = decorator.to_lowercase();
+    /// `app.add_url_rule("/users", "users", list_users, methods=["GET"])`;
+    /// the view is the third positional argument or `view_func=`, and
+    /// without one the endpoint name stands in for the handler
+    fn url_rule_entry_point(call: &FunctionCall, file: &str) -> Option<EntryPoint> {
+        let receiver = call.receiver.as_deref()?;
+        let rule = DecoratorCall::parse(&format!("{}.add_url_rule({})", receiver, call.args.join(", ")))?;
+
+        // `view_func=index`, but not `"/a=b"` or `a == b`
+        let is_keyword = |arg: &str| match arg.split_once('=') {
+            Some((name, value)) => {
+                let name = name.trim();
+                !name.is_empty() && !value.starts_with('=') && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+            }
+            None => false,
+        };
+        let positional: Vec<&String> = call.args.iter().filter(|arg| !is_keyword(arg)).collect();
+        let handler = rule
+            .kwargs
+            .get("view_func")
+            .and_then(|v| v.first())
+            .or_else(|| positional.get(2).copied())
+            .or_else(|| rule.kwargs.get("endpoint").and_then(|v| v.first()))
+            .or_else(|| rule.args.get(1))?
+            .clone();
+        // `UserAPI.as_view("users")` is handled by the `UserAPI` class
+        let function = handler.split('(').next().unwrap_or(&handler).trim_end_matches(".as_view").to_string();
+
+        Some(EntryPoint {
+            entry_type: "api_endpoint".to_string(),
+            path: Self::extract_route_path(&rule),
+            function,
+            handler,
+            file: file.to_string(),
+            line: call.line,
+            methods: Some(Self::extract_http_methods(&rule)),
+            router: Some(receiver.to_string()),
+        })
+    }
 
-        if dec_lower.contains("get") {
-            methods.push("GET".to_string());
-        }
-        if dec_lower.contains("post") {
-            methods.push("POST".to_string());
-        }
-        if dec_lower.contains("put") {
-            methods.push("PUT".to_string());
-        }
-        if dec_lower.contains("delete") {
-            methods.push("DELETE".to_string());
-        }
+    fn extract_route_path(call: &DecoratorCall) -> Option<String> {
+        // Path is the first positional argument: @app.route("/users/<int:id>")
+        call.args
+            .first()
+            .or_else(|| call.kwargs.get("path").and_then(|v| v.first()))
+            .or_else(|| call.kwargs.get("rule").and_then(|v| v.first()))
+            .cloned()
+    }
 
-        if methods.is_empty() {
-            methods.push("GET".to_string()); // Default
+    fn extract_http_methods(call: &DecoratorCall) -> Vec<String> {
+        // Flask: methods=["POST", "PUT"]
+        if let Some(methods) = call.kwargs.get("methods") {
+            if !methods.is_empty() {
+                return methods.iter().map(|m| m.to_uppercase()).collect();
+            }
         }
 
-        methods
+        // FastAPI-style: @app.post("/items")
+        let verb = call.callee.rsplit('.').next().unwrap_or("").to_lowercase();
+        match verb.as_str() {
+            "get" | "post" | "put" | "delete" | "patch" => vec![verb.to_uppercase()],
+            _ => vec!["GET".to_string()], // Default
+        }
     }
 
     /// Analyze external dependencies - OPTIMIZED
@@ -555,6 +1043,655 @@ impl Analyzer {
         None
     }
 
+    /// Extract the call-graph neighborhood of a function as a standalone knowledge base.
+    /// Includes the function itself plus its callers and callees within `depth` hops.
+    /// `function` may be a function id (e.g. `func_login`) or a bare name.
+    pub fn extract_neighborhood(
+        kb: &KnowledgeBase,
+        function: &str,
+        depth: usize,
+    ) -> Result<KnowledgeBase, String> {
+        // Flatten functions and methods so they can be addressed by index
+        let mut units: Vec<(&str, &Function)> = Vec::new();
+        for (filepath, filedata) in &kb.structure {
+            for func in &filedata.functions {
+                units.push((filepath.as_str(), func));
+            }
+            for class in &filedata.classes {
+                for method in &class.methods {
+                    units.push((filepath.as_str(), method));
+                }
+            }
+        }
+
+        let mut units_by_name: HashMap<&str, Vec<usize>> = HashMap::new();
+        let mut callers_by_callee: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, (_, func)) in units.iter().enumerate() {
+            units_by_name.entry(func.name.as_str()).or_default().push(i);
+            for call in &func.calls {
+                callers_by_callee.entry(call.callee.as_str()).or_default().push(i);
+            }
+        }
+
+        let start: Vec<usize> = units
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, func))| func.id == function || func.name == function)
+            .map(|(i, _)| i)
+            .collect();
+
+        if start.is_empty() {
+            return Err(format!("Function '{}' not found in knowledge base", function));
+        }
+
+        // Walk callees and callers separately so siblings aren't pulled in
+        let callees = Self::walk_hops(&start, depth, |i| {
+            units[i]
+                .1
+                .calls
+                .iter()
+                .filter_map(|call| units_by_name.get(call.callee.as_str()))
+                .flatten()
+                .copied()
+                .collect()
+        });
+        let callers = Self::walk_hops(&start, depth, |i| {
+            callers_by_callee
+                .get(units[i].1.name.as_str())
+                .cloned()
+                .unwrap_or_default()
+        });
+
+        let selected: HashSet<(&str, &str, usize)> = callees
+            .union(&callers)
+            .map(|&i| (units[i].0, units[i].1.id.as_str(), units[i].1.line_start))
+            .collect();
+        let selected_names: HashSet<&str> = callees
+            .union(&callers)
+            .map(|&i| units[i].1.name.as_str())
+            .collect();
+        let is_selected = |filepath: &str, func: &Function| {
+            selected.contains(&(filepath, func.id.as_str(), func.line_start))
+        };
+
+        // Keep only the selected functions/methods and the files that contain them
+        let mut structure = HashMap::new();
+        for (filepath, filedata) in &kb.structure {
+            let functions: Vec<Function> = filedata
+                .functions
+                .iter()
+                .filter(|f| is_selected(filepath, f))
+                .cloned()
+                .collect();
+
+            let classes: Vec<Class> = filedata
+                .classes
+                .iter()
+                .filter_map(|class| {
+                    let methods: Vec<Function> = class
+                        .methods
+                        .iter()
+                        .filter(|m| is_selected(filepath, m))
+                        .cloned()
+                        .collect();
+                    if methods.is_empty() {
+                        None
+                    } else {
+                        Some(Class { methods, ..class.clone() })
+                    }
+                })
+                .collect();
+
+            if functions.is_empty() && classes.is_empty() {
+                continue;
+            }
+
+            structure.insert(
+                filepath.clone(),
+                FileData {
+                    functions,
+                    classes,
+                    ..filedata.clone()
+                },
+            );
+        }
+
+        // Only keep edges between members of the neighborhood
+        let mut call_graph = Self::build_call_graph(&structure);
+        call_graph
+            .edges
+            .retain(|edge| edge.edge_type == "calls" && selected_names.contains(edge.to.as_str()));
+
+        let entry_points = kb
+            .entry_points
+            .iter()
+            .filter(|ep| {
+                structure
+                    .get(&ep.file)
+                    .map(|data| {
+                        data.functions.iter().any(|f| f.name == ep.function)
+                            || data.classes.iter().any(|c| c.methods.iter().any(|m| m.name == ep.function))
+                    })
+                    .unwrap_or(false)
+            })
+            .cloned()
+            .collect();
+
+        let external_dependencies = kb
+            .external_dependencies
+            .iter()
+            .filter_map(|dep| {
+                let used_by: Vec<String> = dep
+                    .used_by
+                    .iter()
+                    .filter(|f| structure.contains_key(*f))
+                    .cloned()
+                    .collect();
+                if used_by.is_empty() {
+                    None
+                } else {
+                    Some(ExternalDependency {
+                        import_count: used_by.len(),
+                        used_by,
+                        ..dep.clone()
+                    })
+                }
+            })
+            .collect();
+
+        let metadata = Metadata {
+            schema_version: SCHEMA_VERSION,
+            total_files: structure.len(),
+            total_loc: structure.values().map(|d| d.loc).sum(),
+            total_functions: structure.values().map(|d| d.functions.len()).sum(),
+            total_classes: structure.values().map(|d| d.classes.len()).sum(),
+            total_methods: structure
+                .values()
+                .flat_map(|d| d.classes.iter())
+                .map(|c| c.methods.len())
+                .sum(),
+            ..kb.metadata.clone()
+        };
+
+        let mut subgraph = KnowledgeBase {
+            metadata,
+            structure,
+            call_graph,
+            dependency_graph: DependencyGraph::default(),
+            indices: Indices::default(),
+            entry_points,
+            external_dependencies,
+            patterns: kb.patterns.clone(),
+        };
+        subgraph.indices = Self::generate_indices(&subgraph);
+
+        Ok(subgraph)
+    }
+
+    /// Breadth-first walk up to `depth` hops, returning every visited index (including the start)
+    fn walk_hops<F>(start: &[usize], depth: usize, neighbors: F) -> HashSet<usize>
+    where
+        F: Fn(usize) -> Vec<usize>,
+    {
+        let mut visited: HashSet<usize> = start.iter().copied().collect();
+        let mut frontier: Vec<usize> = start.to_vec();
+
+        for _ in 0..depth {
+            let mut next = Vec::new();
+            for &i in &frontier {
+                for n in neighbors(i) {
+                    if visited.insert(n) {
+                        next.push(n);
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+
+        visited
+    }
+
+    /// Functions and methods nothing calls, excluding entry points, tests
+    /// and anything that may be called from outside the project (Go exported
+    /// identifiers, names in Python's `__all__`, dunder methods,
+    /// decorator-registered functions). Closures are skipped too: a wrapper
+    /// is returned or passed on rather than called by name.
+    ///
+    /// Relies on `called_by`, so it reports nothing when analysis skipped
+    /// the call graph.
+    pub fn find_dead_code(kb: &KnowledgeBase) -> Vec<DeadCodeEntry> {
+        if kb.call_graph.nodes.is_empty() {
+            return vec![];
+        }
+
+        let entry_points: HashSet<(&str, &str)> = kb.entry_points
+            .iter()
+            .map(|ep| (ep.file.as_str(), ep.function.as_str()))
+            .collect();
+
+        let mut dead = Vec::new();
+
+        for (filepath, filedata) in &kb.structure {
+            let is_go = filedata.language == "go";
+
+            let candidates: Vec<(&Function, Option<&str>)> = filedata.functions.iter().map(|f| (f, None)).chain(
+                filedata.classes.iter().flat_map(|c| c.methods.iter().map(move |m| (m, Some(c.name.as_str())))),
+            ).collect();
+            let is_closure = |func: &Function| {
+                candidates.iter().any(|(outer, _)| {
+                    outer.id != func.id && outer.line_start < func.line_start && func.line_end <= outer.line_end
+                })
+            };
+
+            for &(func, class_name) in &candidates {
+                let name = func.name.as_str();
+                let is_exported = class_name.is_none() &&
+                    filedata.exports.as_ref().is_some_and(|exports| exports.iter().any(|e| e == name));
+                let is_public_api = (is_go && name.starts_with(|c: char| c.is_uppercase())) ||
+                    (name.starts_with("__") && name.ends_with("__")) ||
+                    is_exported;
+                let is_entry_point = entry_points.contains(&(filepath.as_str(), name)) ||
+                    func.tags.iter().any(|t| t == "entry-point" || t == "test") ||
+                    (is_go && name == "init");
+                // Constructors run implicitly
+                let is_constructor = name == "constructor" || class_name == Some(name);
+
+                if func.called_by.is_empty() && func.decorators.is_empty() &&
+                    !is_public_api && !is_entry_point && !is_constructor && !is_closure(func) {
+                    dead.push(DeadCodeEntry {
+                        file: filepath.clone(),
+                        function: name.to_string(),
+                        line: func.line_start,
+                    });
+                }
+            }
+        }
+
+        dead.sort_by(|a, b| a.file.cmp(&b.file).then(a.line.cmp(&b.line)));
+        dead
+    }
+
+    /// The `top_n` functions and methods ranked by `(complexity +
+    /// cognitive_complexity) * fan_in`: complex code that many callers depend
+    /// on is the riskiest to change, and nesting makes it riskier still
+    pub fn generate_hotspots(kb: &KnowledgeBase, top_n: usize) -> Vec<Hotspot> {
+        let mut hotspots: Vec<Hotspot> = kb.structure
+            .iter()
+            .flat_map(|(filepath, filedata)| {
+                filedata.functions.iter()
+                    .chain(filedata.classes.iter().flat_map(|c| c.methods.iter()))
+                    .map(move |func| {
+                        let fan_in = func.called_by.len();
+                        let fan_out = func.calls.len();
+                        let instability = if fan_in + fan_out == 0 {
+                            0.0
+                        } else {
+                            fan_out as f32 / (fan_in + fan_out) as f32
+                        };
+
+                        let cognitive_complexity = func.control_flow.cognitive_complexity;
+                        Hotspot {
+                            id: func.id.clone(),
+                            name: func.name.clone(),
+                            file: filepath.clone(),
+                            line: func.line_start,
+                            complexity: func.complexity,
+                            cognitive_complexity,
+                            fan_in,
+                            fan_out,
+                            instability,
+                            score: (func.complexity + cognitive_complexity) * fan_in,
+                        }
+                    })
+            })
+            .collect();
+
+        hotspots.sort_by(|a, b| {
+            b.score.cmp(&a.score)
+                .then(b.fan_in.cmp(&a.fan_in))
+                .then_with(|| a.id.cmp(&b.id))
+        });
+        hotspots.truncate(top_n);
+        hotspots
+    }
+
+    /// The `top_n` functions and methods with the highest cyclomatic
+    /// complexity across the whole knowledge base
+    pub fn top_complexity(kb: &KnowledgeBase, top_n: usize) -> Vec<ComplexityEntry> {
+        let mut entries: Vec<ComplexityEntry> = kb.structure
+            .iter()
+            .flat_map(|(filepath, filedata)| {
+                filedata.functions.iter()
+                    .chain(filedata.classes.iter().flat_map(|c| c.methods.iter()))
+                    .map(move |func| ComplexityEntry {
+                        id: func.id.clone(),
+                        name: func.name.clone(),
+                        file: filepath.clone(),
+                        line: func.line_start,
+                        complexity: func.complexity,
+                        cognitive_complexity: func.control_flow.cognitive_complexity,
+                    })
+            })
+            .collect();
+
+        entries.sort_by(|a, b| {
+            b.complexity.cmp(&a.complexity)
+                .then(b.cognitive_complexity.cmp(&a.cognitive_complexity))
+                .then_with(|| a.file.cmp(&b.file))
+                .then(a.line.cmp(&b.line))
+        });
+        entries.truncate(top_n);
+        entries
+    }
+
+    /// Set `needs_refactor` on every function and method whose complexity is
+    /// above its language's threshold, returning how many were flagged
+    pub fn flag_needs_refactor(kb: &mut KnowledgeBase, thresholds: &ComplexityThresholds) -> usize {
+        let mut flagged = 0;
+        for filedata in kb.structure.values_mut() {
+            let threshold = thresholds.for_language(&filedata.language);
+            let methods = filedata.classes.iter_mut().flat_map(|c| c.methods.iter_mut());
+            for func in filedata.functions.iter_mut().chain(methods) {
+                func.needs_refactor = func.complexity > threshold;
+                flagged += usize::from(func.needs_refactor);
+            }
+        }
+        flagged
+    }
+
+    /// Groups of files that import each other in a loop (strongly connected
+    /// components of the file-level import graph with more than one file)
+    ///
+    /// Imports are resolved to project files as follows:
+    /// - Python absolute imports match the file whose dotted path is the
+    ///   module (`pkg.auth` -> `pkg/auth.py` or `pkg/auth/__init__.py`),
+    ///   falling back to a unique suffix match for `src/` layouts;
+    ///   `from pkg import views` also tries the submodule `pkg.views`
+    /// - Python relative imports start at the importing file's package and
+    ///   climb one level per extra dot, so `from . import x` in `pkg/a.py`
+    ///   resolves to `pkg/x.py`
+    /// - JS/TS relative specifiers resolve against the importing file's
+    ///   directory, trying the usual extensions and `index` files
+    /// - Go import paths name a package directory: every `.go` file in the
+    ///   project directory that the import path ends with
+    ///
+    /// Anything that doesn't resolve (stdlib, third-party) is ignored.
+    pub fn find_import_cycles(kb: &KnowledgeBase) -> Vec<Vec<String>> {
+        let files = Self::sorted_files(kb);
+        let graph: Vec<Vec<usize>> = Self::resolved_imports(kb, &files)
+            .into_iter()
+            .map(|imports| {
+                let mut targets: Vec<usize> = imports.into_iter().flat_map(|(_, targets)| targets).collect();
+                targets.sort_unstable();
+                targets.dedup();
+                targets
+            })
+            .collect();
+
+        let mut cycles: Vec<Vec<String>> = Self::strongly_connected(&graph)
+            .into_iter()
+            .filter(|component| component.len() > 1)
+            .map(|component| {
+                let mut paths: Vec<String> = component.into_iter().map(|i| files[i].clone()).collect();
+                paths.sort();
+                paths
+            })
+            .collect();
+
+        cycles.sort();
+        cycles
+    }
+
+    /// File-level dependency graph: a `file` node per parsed file with an
+    /// `imports` edge to every project file it imports (resolved as in
+    /// `find_import_cycles`), and a `module` node per third-party module with
+    /// a `depends_on` edge from each file importing it. Stdlib imports are left out.
+    pub fn build_dependency_graph(kb: &KnowledgeBase) -> DependencyGraph {
+        let files = Self::sorted_files(kb);
+        let file_id = |file: &str| format!("file_{}", file);
+        let mut graph = DependencyGraph::default();
+
+        for file in &files {
+            graph.nodes.push(GraphNode {
+                id: file_id(file),
+                node_type: "file".to_string(),
+                name: file.to_string(),
+            });
+        }
+
+        let mut modules: HashSet<&str> = HashSet::new();
+        let mut edges: HashSet<(String, String, &str)> = HashSet::new();
+        for (i, imports) in Self::resolved_imports(kb, &files).into_iter().enumerate() {
+            for (import, targets) in imports {
+                if !targets.is_empty() {
+                    for target in targets {
+                        edges.insert((file_id(files[i]), file_id(files[target]), "imports"));
+                    }
+                } else if import.import_type == "external" {
+                    modules.insert(&import.module);
+                    edges.insert((file_id(files[i]), format!("module_{}", import.module), "depends_on"));
+                }
+            }
+        }
+
+        let mut modules: Vec<&str> = modules.into_iter().collect();
+        modules.sort_unstable();
+        graph.nodes.extend(modules.into_iter().map(|module| GraphNode {
+            id: format!("module_{}", module),
+            node_type: "module".to_string(),
+            name: module.to_string(),
+        }));
+
+        let mut edges: Vec<_> = edges.into_iter().collect();
+        edges.sort();
+        graph.edges = edges
+            .into_iter()
+            .map(|(from, to, edge_type)| GraphEdge { from, to, edge_type: edge_type.to_string() })
+            .collect();
+        graph
+    }
+
+    fn sorted_files(kb: &KnowledgeBase) -> Vec<&String> {
+        let mut files: Vec<&String> = kb.structure.keys().collect();
+        files.sort();
+        files
+    }
+
+    /// For each of `files`, its non-stdlib imports paired with the positions
+    /// in `files` they resolve to (empty when an import isn't a project file);
+    /// a file importing itself is dropped
+    fn resolved_imports<'a>(kb: &'a KnowledgeBase, files: &[&'a String]) -> Vec<Vec<(&'a Import, Vec<usize>)>> {
+        let position: HashMap<&str, usize> = files
+            .iter()
+            .enumerate()
+            .map(|(i, f)| (f.as_str(), i))
+            .collect();
+        let ProjectModules { python: python_modules, go: go_packages } = Self::project_modules(files, &kb.structure);
+
+        files
+            .iter()
+            .map(|file| {
+                let filedata = &kb.structure[*file];
+                filedata.imports
+                    .iter()
+                    .filter(|import| import.import_type != "stdlib")
+                    .map(|import| {
+                        let targets = match filedata.language.as_str() {
+                            "python" => Self::resolve_python_import(file, import, &python_modules),
+                            "go" => Self::resolve_go_import(&import.module, &go_packages),
+                            "javascript" | "typescript" => {
+                                Self::resolve_js_import(file, &import.module, &position)
+                            }
+                            _ => vec![],
+                        };
+                        let targets = targets
+                            .into_iter()
+                            .filter_map(|target| position.get(target).copied())
+                            .filter(|&target| target != position[file.as_str()])
+                            .collect();
+                        (import, targets)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn resolve_python_import<'a>(
+        file: &str,
+        import: &Import,
+        modules: &HashMap<String, &'a str>,
+    ) -> Vec<&'a str> {
+        let module = if import.module.starts_with('.') {
+            let dots = import.module.len() - import.module.trim_start_matches('.').len();
+            let mut package: Vec<String> = Self::module_path(file)
+                .split('.')
+                .map(str::to_string)
+                .collect();
+            // Drop the module itself, then one package level per extra dot
+            for _ in 0..dots {
+                if package.pop().is_none() {
+                    return vec![];
+                }
+            }
+
+            let rest = &import.module[dots..];
+            if !rest.is_empty() {
+                package.push(rest.to_string());
+            }
+            package.join(".")
+        } else {
+            import.module.clone()
+        };
+
+        let lookup = |name: &str| -> Option<&'a str> {
+            if name.is_empty() {
+                return None;
+            }
+            if let Some(file) = modules.get(name) {
+                return Some(*file);
+            }
+            let suffix = format!(".{}", name);
+            let mut matches = modules.iter().filter(|(m, _)| m.ends_with(&suffix));
+            match (matches.next(), matches.next()) {
+                (Some((_, file)), None) => Some(*file),
+                _ => None,
+            }
+        };
+
+        // `from pkg import views` may name a submodule rather than an attribute
+        let submodules: Vec<&str> = import.items
+            .iter()
+            .filter_map(|item| {
+                let qualified = if module.is_empty() { item.clone() } else { format!("{}.{}", module, item) };
+                lookup(&qualified)
+            })
+            .collect();
+
+        if submodules.is_empty() {
+            lookup(&module).into_iter().collect()
+        } else {
+            submodules
+        }
+    }
+
+    fn resolve_go_import<'a>(path: &str, packages: &HashMap<&'a str, Vec<&'a str>>) -> Vec<&'a str> {
+        packages
+            .iter()
+            .filter(|(dir, _)| !dir.is_empty() && (path == **dir || path.ends_with(&format!("/{}", dir))))
+            .max_by_key(|(dir, _)| dir.len())
+            .map(|(_, files)| files.clone())
+            .unwrap_or_default()
+    }
+
+    fn resolve_js_import<'a>(file: &str, specifier: &str, files: &HashMap<&'a str, usize>) -> Vec<&'a str> {
+        if !specifier.starts_with('.') {
+            return vec![];
+        }
+
+        let mut parts: Vec<&str> = file.split('/').collect();
+        parts.pop();
+        for segment in specifier.split('/') {
+            match segment {
+                "." | "" => {}
+                ".." => {
+                    if parts.pop().is_none() {
+                        return vec![];
+                    }
+                }
+                _ => parts.push(segment),
+            }
+        }
+        let base = parts.join("/");
+
+        let candidates = ["", ".js", ".ts", ".jsx", ".tsx", ".mjs", "/index.js", "/index.ts"];
+        candidates
+            .iter()
+            .find_map(|ext| files.get_key_value(format!("{}{}", base, ext).as_str()))
+            .map(|(file, _)| vec![*file])
+            .unwrap_or_default()
+    }
+
+    /// Tarjan's algorithm, iterative so deep import chains can't overflow the stack
+    fn strongly_connected(graph: &[Vec<usize>]) -> Vec<Vec<usize>> {
+        const UNVISITED: usize = usize::MAX;
+
+        let mut index = vec![UNVISITED; graph.len()];
+        let mut lowlink = vec![0; graph.len()];
+        let mut on_stack = vec![false; graph.len()];
+        let mut stack = Vec::new();
+        let mut components = Vec::new();
+        let mut next_index = 0;
+
+        for start in 0..graph.len() {
+            if index[start] != UNVISITED {
+                continue;
+            }
+
+            // (node, next edge to explore)
+            let mut work = vec![(start, 0)];
+            while let Some((node, edge)) = work.pop() {
+                if edge == 0 {
+                    index[node] = next_index;
+                    lowlink[node] = next_index;
+                    next_index += 1;
+                    stack.push(node);
+                    on_stack[node] = true;
+                }
+
+                if let Some(&target) = graph[node].get(edge) {
+                    work.push((node, edge + 1));
+                    if index[target] == UNVISITED {
+                        work.push((target, 0));
+                    } else if on_stack[target] {
+                        lowlink[node] = lowlink[node].min(index[target]);
+                    }
+                    continue;
+                }
+
+                if let Some(&(parent, _)) = work.last() {
+                    lowlink[parent] = lowlink[parent].min(lowlink[node]);
+                }
+
+                if lowlink[node] == index[node] {
+                    let mut component = Vec::new();
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        component.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+
+        components
+    }
+
     /// Generate project summary
     pub fn generate_summary(kb: &KnowledgeBase) -> ProjectSummary {
         let mut summary = ProjectSummary::default();
@@ -564,7 +1701,12 @@ impl Analyzer {
         summary.total_loc = kb.metadata.total_loc;
         summary.languages = kb.metadata.languages.clone();
 
-        summary.categories = Self::categorize_files(&kb.structure);
+        // Same categories as the index file, unless indices were never generated
+        summary.categories = if kb.indices.files_by_category.is_empty() {
+            Self::categorize_files(&kb.structure)
+        } else {
+            kb.indices.files_by_category.clone()
+        };
         summary.key_features = Self::extract_key_features(kb);
         summary.entry_points = kb.entry_points.iter().map(|ep| {
             format!("{}:{}", ep.file, ep.line)
@@ -582,6 +1724,11 @@ impl Analyzer {
                 .collect(),
         };
         summary.patterns = kb.patterns.clone();
+            summary.call_graph_depth = kb.call_graph.max_depth_from_entry_points();
+        summary.needs_refactor = kb.structure.values()
+            .flat_map(|f| f.functions.iter().chain(f.classes.iter().flat_map(|c| c.methods.iter())))
+            .filter(|func| func.needs_refactor)
+            .count();
 
         summary
     }
@@ -606,6 +1753,10 @@ impl Analyzer {
                 .push(filepath.to_string());
         }
 
+        for files in categories.values_mut() {
+            files.sort();
+        }
+
         categories
     }
 
@@ -687,6 +1838,32 @@ pub struct ProjectSummary {
     pub entry_points: Vec<String>,
     pub dependencies: DependencyInfo,
     pub patterns: PatternInfo,
+    /// Longest call chain starting at an entry point
+    pub call_graph_depth: usize,
+    /// Functions and methods flagged `needs_refactor`
+    pub needs_refactor: usize,
+}
+
+/// Cyclomatic complexity above which a function is flagged `needs_refactor`.
+/// Idiomatic baselines differ (Go's `if err != nil` adds a branch per call),
+/// so each language can override the default.
+#[derive(Debug, Clone)]
+pub struct ComplexityThresholds {
+    pub default: usize,
+    /// Keyed by the language name stored on each file (`python`, `go`, ...)
+    pub per_language: HashMap<String, usize>,
+}
+
+impl Default for ComplexityThresholds {
+    fn default() -> Self {
+        Self { default: 10, per_language: HashMap::new() }
+    }
+}
+
+impl ComplexityThresholds {
+    pub fn for_language(&self, language: &str) -> usize {
+        self.per_language.get(language).copied().unwrap_or(self.default)
+    }
 }
 
 #[derive(Debug, Default, Serialize, Deserialize)]
@@ -694,3 +1871,687 @@ pub struct DependencyInfo {
     pub stdlib: Vec<String>,
     pub third_party: Vec<String>,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Hotspot {
+    pub id: String,
+    pub name: String,
+    pub file: String,
+    pub line: usize,
+    pub complexity: usize,
+    pub cognitive_complexity: usize,
+    pub fan_in: usize,  // Number of call sites calling this function
+    pub fan_out: usize, // Number of calls this function makes
+    pub instability: f32, // fan_out / (fan_in + fan_out), 0 = maximally stable
+    pub score: usize, // (complexity + cognitive_complexity) * fan_in
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComplexityEntry {
+    pub id: String,
+    pub name: String,
+    pub file: String,
+    pub line: usize,
+    pub complexity: usize,
+    pub cognitive_complexity: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadCodeEntry {
+    pub file: String,
+    pub function: String,
+    pub line: usize,
+}
+
+/// A decorator such as `@app.route("/users/<int:id>", methods=["POST"])`,
+/// parsed with the Python grammar so arguments are read structurally
+/// rather than by substring matching
+#[derive(Debug, Default)]
+struct DecoratorCall {
+    /// Dotted callee, e.g. `app.route`
+    callee: String,
+    /// Positional string-literal arguments
+    args: Vec<String>,
+    /// Keyword arguments; list and tuple values are flattened to their
+    /// string elements
+    kwargs: HashMap<String, Vec<String>>,
+}
+
+impl DecoratorCall {
+    fn parse(decorator: &str) -> Option<Self> {
+        let source = decorator.trim().trim_start_matches('@').trim();
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(tree_sitter_python::language()).ok()?;
+        let tree = parser.parse(source, None)?;
+
+        // module > expression_statement > call
+        let expr = tree.root_node().named_child(0)?.named_child(0)?;
+        let text = |node: &tree_sitter::Node| node.utf8_text(source.as_bytes()).unwrap_or("").to_string();
+
+        if expr.kind() != "call" {
+            // Bare decorator without arguments, e.g. @login_required
+            return Some(Self { callee: text(&expr), ..Default::default() });
+        }
+
+        let mut call = Self {
+            callee: expr.child_by_field_name("function").map(|f| text(&f)).unwrap_or_default(),
+            ..Default::default()
+        };
+
+        let Some(arguments) = expr.child_by_field_name("arguments") else {
+            return Some(call);
+        };
+
+        let mut cursor = arguments.walk();
+        for arg in arguments.named_children(&mut cursor) {
+            match arg.kind() {
+                "string" => call.args.push(Self::string_value(&text(&arg))),
+                "keyword_argument" => {
+                    let (Some(name), Some(value)) = (
+                        arg.child_by_field_name("name"),
+                        arg.child_by_field_name("value"),
+                    ) else {
+                        continue;
+                    };
+
+                    let values = match value.kind() {
+                        "list" | "tuple" | "set" => {
+                            let mut value_cursor = value.walk();
+                            let items = value
+                                .named_children(&mut value_cursor)
+                                .filter(|item| item.kind() == "string")
+                                .map(|item| Self::string_value(&text(&item)))
+                                .collect();
+                            items
+                        }
+                        "string" => vec![Self::string_value(&text(&value))],
+                        _ => vec![text(&value)],
+                    };
+
+                    call.kwargs.insert(text(&name), values);
+                }
+                _ => {}
+            }
+        }
+
+        Some(call)
+    }
+
+    /// Strip string prefixes and quotes: r"/a" -> /a
+    fn string_value(literal: &str) -> String {
+        literal
+            .trim_start_matches(|c: char| "rRbBuUfF".contains(c))
+            .trim_matches(|c| c == '"' || c == '\'')
+            .to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::c::CParser;
+    use crate::parser::python::PythonParser;
+
+    fn kb_from_python(files: &[(&str, &str)]) -> KnowledgeBase {
+        let structure: HashMap<String, FileData> = files
+            .iter()
+            .map(|(path, source)| {
+                let data = PythonParser::new(source.to_string()).parse().unwrap();
+                (path.to_string(), data)
+            })
+            .collect();
+        analyze(structure, "python")
+    }
+
+    fn kb_from_c(files: &[(&str, &str)]) -> KnowledgeBase {
+        let structure: HashMap<String, FileData> = files
+            .iter()
+            .map(|(path, source)| (path.to_string(), CParser::new(source.to_string()).parse().unwrap()))
+            .collect();
+        analyze(structure, "c")
+    }
+
+    fn analyze(structure: HashMap<String, FileData>, language: &str) -> KnowledgeBase {
+        let kb = KnowledgeBase {
+            metadata: Metadata {
+                schema_version: SCHEMA_VERSION,
+                project_name: "test".to_string(),
+                version: "1.0".to_string(),
+                parsed_at: String::new(),
+                languages: vec![language.to_string()],
+                total_files: structure.len(),
+                total_loc: 0,
+                total_functions: 0,
+                total_classes: 0,
+                total_methods: 0,
+                git_commit: None,
+                git_branch: None,
+                git_dirty: None,
+            },
+            structure,
+            call_graph: CallGraph::default(),
+            dependency_graph: DependencyGraph::default(),
+            indices: Indices::default(),
+            entry_points: vec![],
+            external_dependencies: vec![],
+            patterns: PatternInfo::default(),
+        };
+
+        Analyzer::analyze_and_build(kb, false)
+    }
+
+    #[test]
+    fn test_python_aliases_and_typed_dicts_indexed_as_types() {
+        let kb = kb_from_python(&[(
+            "models.py",
+            "UserId = int\n\nclass Movie(TypedDict):\n    title: str\n",
+        )]);
+
+        assert_eq!(kb.indices.types_by_name["UserId"], vec!["models.py:1"]);
+        assert_eq!(kb.indices.types_by_name["Movie"], vec!["models.py:3"]);
+    }
+
+    #[test]
+    fn test_indices_cover_methods_and_categories() {
+        let kb = kb_from_python(&[
+            ("auth/session.py", "class Session:\n    def refresh(self):\n        rotate_token()\n"),
+            ("tokens.py", "def rotate_token():\n    pass\n"),
+            ("tests/test_tokens.py", "def test_rotate():\n    rotate_token()\n"),
+        ]);
+
+        let mut callers = kb.indices.functions_calling["rotate_token"].clone();
+        callers.sort();
+        assert_eq!(callers, vec!["func_test_rotate", "method_Session_refresh"]);
+
+        let categories = &kb.indices.files_by_category;
+        assert_eq!(categories["Authentication"], vec!["auth/session.py"]);
+        assert_eq!(categories["Tests"], vec!["tests/test_tokens.py"]);
+        assert_eq!(categories["Other"], vec!["tokens.py"]);
+        assert_eq!(Analyzer::generate_summary(&kb).categories, *categories);
+    }
+
+    #[test]
+    fn test_exception_propagation() {
+        let kb = kb_from_python(&[
+            ("app.py", "def a():\n    b()\n\ndef safe():\n    try:\n        a()\n    except ValueError:\n        pass\n\ndef top():\n    a()\n"),
+            ("lib.py", "def b():\n    c()\n\ndef c():\n    raise ValueError(\"bad\")\n"),
+        ]);
+
+        let propagates = |name: &str| -> Vec<String> {
+            kb.structure
+                .values()
+                .flat_map(|d| d.functions.iter())
+                .find(|f| f.name == name)
+                .unwrap()
+                .exceptions
+                .propagates
+                .clone()
+        };
+
+        assert!(propagates("c").is_empty());
+        assert_eq!(propagates("b"), vec!["ValueError"]);
+        assert_eq!(propagates("a"), vec!["ValueError"]);
+        assert_eq!(propagates("top"), vec!["ValueError"]);
+        assert!(propagates("safe").is_empty());
+    }
+
+    #[test]
+    fn test_neighborhood_depth() {
+        let kb = kb_from_python(&[
+            ("app.py", "def handler():\n    login()\n\ndef unrelated():\n    pass\n"),
+            ("auth.py", "def login():\n    check()\n\ndef check():\n    hash_pw()\n\ndef hash_pw():\n    pass\n"),
+        ]);
+
+        let sub = Analyzer::extract_neighborhood(&kb, "func_login", 1).unwrap();
+        let mut names: Vec<&str> = sub
+            .structure
+            .values()
+            .flat_map(|d| d.functions.iter().map(|f| f.name.as_str()))
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["check", "handler", "login"]);
+        assert_eq!(sub.metadata.total_functions, 3);
+        assert!(sub.call_graph.edges.iter().all(|e| e.to != "hash_pw"));
+
+        let sub = Analyzer::extract_neighborhood(&kb, "login", 2).unwrap();
+        assert_eq!(sub.metadata.total_functions, 4);
+
+        assert!(Analyzer::extract_neighborhood(&kb, "func_missing", 2).is_err());
+    }
+
+    #[test]
+    fn test_route_decorator_arguments() {
+        let kb = kb_from_python(&[(
+            "app.py",
+            "@app.route(\"/users/<int:user_id>\", methods=[\"POST\", \"PUT\"])\n\
+             def update_user(user_id):\n    pass\n\n\
+             @app.route('/health')\ndef health():\n    pass\n\n\
+             @router.delete(\"/items/{item_id}\")\nasync def remove_item(item_id):\n    pass\n",
+        )]);
+
+        let endpoint = |name: &str| {
+            kb.entry_points
+                .iter()
+                .find(|e| e.function == name && e.entry_type == "api_endpoint")
+                .unwrap()
+        };
+
+        let update = endpoint("update_user");
+        assert_eq!(update.path.as_deref(), Some("/users/<int:user_id>"));
+        assert_eq!(update.methods, Some(vec!["POST".to_string(), "PUT".to_string()]));
+
+        let health = endpoint("health");
+        assert_eq!(health.path.as_deref(), Some("/health"));
+        assert_eq!(health.methods, Some(vec!["GET".to_string()]));
+
+        let remove = endpoint("remove_item");
+        assert_eq!(remove.path.as_deref(), Some("/items/{item_id}"));
+        assert_eq!(remove.methods, Some(vec!["DELETE".to_string()]));
+    }
+
+    #[test]
+    fn test_router_and_blueprint_routes() {
+        let kb = kb_from_python(&[
+            (
+                "api/items.py",
+                "router = APIRouter()\n\n@router.get(\"/items\")\nasync def list_items():\n    pass\n",
+            ),
+            (
+                "views/users.py",
+                "users = Blueprint(\"users\", __name__)\n\n\
+                 @users.route(\"/users\", methods=[\"POST\"])\ndef create_user():\n    pass\n\n\
+                 @budget_limited\ndef target():\n    pass\n\n\
+                 def index():\n    pass\n\n\
+                 users.add_url_rule(\"/\", view_func=index)\n",
+            ),
+            (
+                "app.py",
+                "def create_app():\n    app = Flask(__name__)\n    \
+                 app.add_url_rule(\"/health\", \"health\", health, methods=[\"GET\", \"HEAD\"])\n    return app\n",
+            ),
+        ]);
+
+        let endpoint = |name: &str| {
+            kb.entry_points
+                .iter()
+                .find(|e| e.function == name && e.entry_type == "api_endpoint")
+                .unwrap_or_else(|| panic!("no endpoint for {}", name))
+        };
+
+        let items = endpoint("list_items");
+        assert_eq!(items.path.as_deref(), Some("/items"));
+        assert_eq!(items.methods, Some(vec!["GET".to_string()]));
+        assert_eq!(items.router.as_deref(), Some("router"));
+
+        let create = endpoint("create_user");
+        assert_eq!(create.path.as_deref(), Some("/users"));
+        assert_eq!(create.methods, Some(vec!["POST".to_string()]));
+        assert_eq!(create.router.as_deref(), Some("users"));
+
+        let index = endpoint("index");
+        assert_eq!(index.path.as_deref(), Some("/"));
+        assert_eq!(index.router.as_deref(), Some("users"));
+        assert_eq!(index.file, "views/users.py");
+
+        let health = endpoint("health");
+        assert_eq!(health.path.as_deref(), Some("/health"));
+        assert_eq!(health.methods, Some(vec!["GET".to_string(), "HEAD".to_string()]));
+        assert_eq!(health.router.as_deref(), Some("app"));
+
+        assert!(!kb.entry_points.iter().any(|e| e.function == "target"));
+    }
+
+    #[test]
+    fn test_function_pointer_dispatch_table() {
+        let kb = kb_from_c(&[
+            (
+                "dev.c",
+                "struct dev_ops {\n    int (*open)(struct dev *d);\n    void (*close)(struct dev *d);\n};\n\n\
+                 static int dev_open(struct dev *d) { return 0; }\n\
+                 static void dev_close(struct dev *d) { }\n\
+                 static void log_line(int code) { }\n\
+                 static void do_work(int code) { }\n\n\
+                 static const struct dev_ops disk_ops = {\n    .open = dev_open,\n    .close = &dev_close,\n};\n\n\
+                 void run(struct dev *d, const struct dev_ops *ops, void (*callback)(int)) {\n\
+                 \x20   ops->open(d);\n\
+                 \x20   (*ops->close)(d);\n\
+                 \x20   void (*handler)(int);\n\
+                 \x20   handler = &do_work;\n\
+                 \x20   handler(1);\n\
+                 \x20   callback(2);\n\
+                 \x20   log_line(3);\n\
+                 }\n\n\
+                 void start(struct dev *d) {\n    run(d, &disk_ops, log_line);\n    run(d, &disk_ops, do_work);\n}\n",
+            ),
+        ]);
+
+        let file = &kb.structure["dev.c"];
+        let bindings: Vec<(&str, &str)> = file
+            .function_pointers
+            .iter()
+            .map(|p| (p.name.as_str(), p.target.as_str()))
+            .collect();
+        assert!(bindings.contains(&("open", "dev_open")));
+        assert!(bindings.contains(&("close", "dev_close")));
+        assert!(bindings.contains(&("handler", "do_work")));
+
+        let run = file.functions.iter().find(|f| f.name == "run").unwrap();
+        let call = |line: usize| run.calls.iter().find(|c| c.line == line).unwrap();
+        let (ops_open, ops_close, handler, callback, direct) = (call(17), call(18), call(21), call(22), call(23));
+
+        // Each table slot and the local pointer only ever hold one function
+        assert!(ops_open.is_indirect && ops_open.callee == "dev_open");
+        assert!(ops_close.is_indirect && ops_close.callee == "dev_close");
+        assert!(handler.is_indirect && handler.callee == "do_work");
+        assert_eq!(ops_open.defined_in.as_deref(), Some("dev.c"));
+
+        // A parameter is only known through its callers, so it stays unresolved
+        assert!(callback.is_indirect && callback.callee == "callback");
+        assert!(!direct.is_indirect && direct.callee == "log_line");
+
+        let dev_open = file.functions.iter().find(|f| f.name == "dev_open").unwrap();
+        assert!(dev_open.called_by.iter().any(|c| c.function == "func_run"));
+        assert!(kb.call_graph.edges.iter().any(|e| e.from == "func_run" && e.to == "dev_close"));
+    }
+
+    #[test]
+    fn test_self_calls_resolve_to_own_class_method() {
+        let kb = kb_from_python(&[
+            ("service.py", concat!(
+                "def helper(x):\n    return x\n\n",
+                "class Service:\n",
+                "    def run(self, x):\n        self.helper(x)\n        helper(x)\n\n",
+                "    def helper(self, x):\n        raise ValueError(x)\n",
+            )),
+            ("other.py", "def helper():\n    pass\n"),
+        ]);
+
+        let file = &kb.structure["service.py"];
+        let service = &file.classes[0];
+        let run = service.methods.iter().find(|m| m.name == "run").unwrap();
+        let self_call = run.calls.iter().find(|c| c.receiver.is_some()).unwrap();
+        assert_eq!(self_call.receiver.as_deref(), Some("self"));
+        assert_eq!(self_call.defined_in.as_deref(), Some("service.py"));
+        assert!(self_call.candidates.is_empty());
+
+        // The method gets both calls from run (the bare one resolves by name);
+        // the free function only gets the bare call
+        let method = service.methods.iter().find(|m| m.name == "helper").unwrap();
+        let lines = |callers: &[CallerInfo]| callers.iter().map(|c| c.line).collect::<Vec<_>>();
+        let mut method_lines = lines(&method.called_by);
+        method_lines.sort();
+        assert_eq!(method_lines, vec![6, 7]);
+        let free = file.functions.iter().find(|f| f.name == "helper").unwrap();
+        assert_eq!(lines(&free.called_by), vec![7]);
+
+        assert!(run.exceptions.propagates.contains(&"ValueError".to_string()));
+        assert!(kb.structure["other.py"].functions[0].called_by.is_empty());
+    }
+
+    #[test]
+    fn test_method_callers_are_keyed_by_class() {
+        let kb = kb_from_python(&[
+            ("models.py", concat!(
+                "class User:\n    def save(self):\n        pass\n\n",
+                "class Order:\n    def save(self):\n        pass\n\n",
+                "def save_user(user: User):\n    user.save()\n\n",
+                "def save_default():\n    User().save()\n\n",
+                "def save_any(item):\n    item.save()\n",
+            )),
+        ]);
+
+        let file = &kb.structure["models.py"];
+        let callers = |class: usize| {
+            let mut lines: Vec<usize> = file.classes[class].methods[0].called_by.iter().map(|c| c.line).collect();
+            lines.sort();
+            lines
+        };
+        assert_eq!(callers(0), vec![10, 13, 16]);
+        assert_eq!(callers(1), vec![16]);
+
+        // The receiver's class is unknown, so the call stays unresolved
+        let call_in = |func: &str| file.functions.iter().find(|f| f.name == func).unwrap().calls[0].clone();
+        assert_eq!(call_in("save_user").defined_in.as_deref(), Some("models.py"));
+        let any = call_in("save_any");
+        assert_eq!(any.defined_in, None);
+        assert_eq!(any.candidates, vec!["models.py"]);
+    }
+
+    #[test]
+    fn test_ambiguous_calls_keep_all_candidates() {
+        let kb = kb_from_python(&[
+            ("workers/images.py", "def process(img):\n    pass\n"),
+            ("workers/text.py", "def process(doc):\n    pass\n\ndef run_text(doc):\n    process(doc)\n"),
+            ("main.py", "from workers.images import process\n\ndef main():\n    process(1)\n"),
+            ("cli.py", "def dispatch():\n    process(2)\n"),
+        ]);
+
+        let call_in = |file: &str, func: &str| {
+            kb.structure[file].functions.iter().find(|f| f.name == func).unwrap().calls[0].clone()
+        };
+
+        // Local definition wins
+        let local = call_in("workers/text.py", "run_text");
+        assert_eq!(local.defined_in.as_deref(), Some("workers/text.py"));
+
+        // Import narrows to one file
+        let imported = call_in("main.py", "main");
+        assert_eq!(imported.defined_in.as_deref(), Some("workers/images.py"));
+        assert!(imported.candidates.is_empty());
+
+        // No way to tell: don't guess
+        let ambiguous = call_in("cli.py", "dispatch");
+        assert_eq!(ambiguous.defined_in, None);
+        assert_eq!(ambiguous.candidates, vec!["workers/images.py", "workers/text.py"]);
+
+        let callers = |file: &str| {
+            let mut ids: Vec<String> = kb.structure[file].functions[0].called_by
+                .iter()
+                .map(|c| c.function.clone())
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(callers("workers/images.py"), vec!["func_dispatch", "func_main"]);
+        assert_eq!(callers("workers/text.py"), vec!["func_dispatch", "func_run_text"]);
+    }
+
+    #[test]
+    fn test_find_dead_code() {
+        let kb = kb_from_python(&[
+            ("app.py", concat!(
+                "def main():\n    used()\n\n",
+                "def used():\n    pass\n\n",
+                "def orphan():\n    pass\n\n",
+                "@app.route('/ping')\ndef ping():\n    pass\n\n",
+                "class Service:\n    def __init__(self):\n        pass\n\n    def stale(self):\n        pass\n",
+            )),
+        ]);
+
+        let dead = Analyzer::find_dead_code(&kb);
+        let dead: Vec<(&str, usize)> = dead.iter().map(|d| (d.function.as_str(), d.line)).collect();
+        assert_eq!(dead, vec![("orphan", 7), ("stale", 18)]);
+
+        // Exported, test and closure functions aren't called by name either
+        let kb = kb_from_python(&[
+            ("lib.py", concat!(
+                "__all__ = ['public']\n\n",
+                "def public():\n    pass\n\n",
+                "def test_helper():\n    pass\n\n",
+                "def retry(f):\n    def wrapper():\n        return f()\n    return wrapper\n",
+            )),
+        ]);
+        let dead = Analyzer::find_dead_code(&kb);
+        let dead: Vec<&str> = dead.iter().map(|d| d.function.as_str()).collect();
+        assert_eq!(dead, vec!["retry"]);
+    }
+
+    #[test]
+    fn test_hotspots_rank_by_complexity_and_fan_in() {
+        let kb = kb_from_python(&[
+            ("core.py", concat!(
+                "def validate(x):\n    if x:\n        if x > 1:\n            return 1\n    return 0\n\n",
+                "def fmt(x):\n    return x\n\n",
+                "def a():\n    validate(1)\n    fmt(1)\n\n",
+                "def b():\n    validate(2)\n\n",
+                "def c():\n    validate(3)\n    fmt(3)\n",
+            )),
+        ]);
+
+        let hotspots = Analyzer::generate_hotspots(&kb, 2);
+        assert_eq!(hotspots.len(), 2);
+
+        let top = &hotspots[0];
+        assert_eq!(top.name, "validate");
+        assert_eq!((top.fan_in, top.fan_out), (3, 0));
+        assert_eq!(top.score, (top.complexity + top.cognitive_complexity) * 3);
+        assert_eq!(top.instability, 0.0);
+
+        assert_eq!(hotspots[1].name, "fmt");
+        assert_eq!(hotspots[1].fan_in, 2);
+
+        let a = Analyzer::generate_hotspots(&kb, usize::MAX)
+            .into_iter()
+            .find(|h| h.name == "a")
+            .unwrap();
+        assert_eq!((a.fan_in, a.fan_out, a.instability), (0, 2, 1.0));
+
+        // Same cyclomatic complexity and callers: the nested one ranks first
+        let kb = kb_from_python(&[
+            ("rules.py", concat!(
+                "def flat(x):\n    if x == 1:\n        return 1\n    elif x == 2:\n        return 2\n    return 0\n\n",
+                "def nested(x):\n    if x:\n        if x > 1:\n            return 1\n    return 0\n\n",
+                "def run():\n    flat(1)\n    nested(1)\n",
+            )),
+        ]);
+        let hotspots = Analyzer::generate_hotspots(&kb, 2);
+        assert_eq!(hotspots[0].complexity, hotspots[1].complexity);
+        assert_eq!(hotspots[0].name, "nested");
+        assert!(hotspots[0].cognitive_complexity > hotspots[1].cognitive_complexity);
+    }
+
+    #[test]
+    fn test_top_complexity_orders_functions_and_methods() {
+        let kb = kb_from_python(&[
+            ("a.py", concat!(
+                "def flat():\n    return 1\n\n",
+                "def branchy(x):\n    if x:\n        return 1\n    elif x > 2:\n        return 2\n    return 3\n",
+            )),
+            ("b.py", concat!(
+                "class Router:\n    def dispatch(self, x):\n",
+                "        for i in x:\n            if i:\n                while i:\n                    i -= 1\n",
+                "        return x\n",
+            )),
+        ]);
+
+        let all = Analyzer::top_complexity(&kb, usize::MAX);
+        let names: Vec<&str> = all.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["dispatch", "branchy", "flat"]);
+        assert!(all.windows(2).all(|w| w[0].complexity > w[1].complexity));
+        assert_eq!((all[0].file.as_str(), all[0].line), ("b.py", 2));
+
+        let top = Analyzer::top_complexity(&kb, 1);
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].name, "dispatch");
+    }
+
+    #[test]
+    fn test_needs_refactor_above_threshold() {
+        let mut kb = kb_from_python(&[(
+            "a.py",
+            "def flat():\n    return 1\n\n\
+             def branchy(x):\n    if x:\n        return 1\n    elif x > 2:\n        return 2\n    return 3\n",
+        )]);
+        let complexity = |kb: &KnowledgeBase, name: &str| {
+            let func = kb.structure["a.py"].functions.iter().find(|f| f.name == name).unwrap();
+            (func.complexity, func.needs_refactor)
+        };
+        let (branchy, _) = complexity(&kb, "branchy");
+        assert!(branchy > complexity(&kb, "flat").0);
+
+        // At the threshold is fine, only above it is flagged
+        let mut thresholds = ComplexityThresholds { default: branchy, ..Default::default() };
+        assert_eq!(Analyzer::flag_needs_refactor(&mut kb, &thresholds), 0);
+        assert_eq!(complexity(&kb, "branchy"), (branchy, false));
+
+        thresholds.per_language.insert("python".to_string(), branchy - 1);
+        assert_eq!(Analyzer::flag_needs_refactor(&mut kb, &thresholds), 1);
+        assert_eq!(complexity(&kb, "branchy"), (branchy, true));
+        assert!(!complexity(&kb, "flat").1);
+        assert_eq!(Analyzer::generate_summary(&kb).needs_refactor, 1);
+
+        thresholds.per_language.insert("go".to_string(), 1);
+        thresholds.per_language.remove("python");
+        assert_eq!(Analyzer::flag_needs_refactor(&mut kb, &thresholds), 0);
+        assert!(!complexity(&kb, "branchy").1);
+    }
+
+    #[test]
+    fn test_imports_of_parsed_modules_become_internal() {
+        let kb = kb_from_python(&[
+            ("app/__init__.py", ""),
+            ("app/auth.py", "def login():\n    pass\n"),
+            ("lib/jobs.py", "def push():\n    pass\n"),
+            (
+                "main.py",
+                "import os\nimport requests\nimport app\nimport lib\nimport json\nfrom app.auth import login\nfrom .settings import DEBUG\n",
+            ),
+        ]);
+
+        let types: HashMap<&str, &str> = kb.structure["main.py"].imports
+            .iter()
+            .map(|i| (i.module.as_str(), i.import_type.as_str()))
+            .collect();
+        assert_eq!(types["os"], "stdlib");
+        assert_eq!(types["requests"], "external");
+        assert_eq!(types["app"], "internal");
+        assert_eq!(types["lib"], "internal");
+        assert_eq!(types["app.auth"], "internal");
+        assert_eq!(types[".settings"], "internal");
+        assert_eq!(types["json"], "stdlib");
+
+        assert!(kb.external_dependencies.iter().all(|dep| dep.name != "app.auth"));
+    }
+
+    #[test]
+    fn test_import_cycles() {
+        let kb = kb_from_python(&[
+            ("orders.py", "from billing import charge\n\ndef place():\n    charge()\n"),
+            ("billing.py", "import orders\nimport os\n\ndef charge():\n    pass\n"),
+            ("pkg/__init__.py", ""),
+            ("pkg/a.py", "from . import b\n"),
+            ("pkg/b.py", "from .a import helper\n"),
+            ("pkg/c.py", "from .a import helper\n"),
+            ("report.py", "from orders import place\n"),
+        ]);
+
+        let cycles = Analyzer::find_import_cycles(&kb);
+        assert_eq!(cycles, vec![
+            vec!["billing.py".to_string(), "orders.py".to_string()],
+            vec!["pkg/a.py".to_string(), "pkg/b.py".to_string()],
+        ]);
+    }
+
+    #[test]
+    fn test_dependency_graph_exports_as_graphml() {
+        let kb = kb_from_python(&[
+            ("orders.py", "from billing import charge\nimport requests\n\ndef place():\n    charge()\n"),
+            ("billing.py", "import os\n\ndef charge():\n    pass\n"),
+        ]);
+
+        let edges: Vec<(&str, &str, &str)> = kb.dependency_graph.edges
+            .iter()
+            .map(|e| (e.from.as_str(), e.to.as_str(), e.edge_type.as_str()))
+            .collect();
+        assert_eq!(edges, vec![
+            ("file_orders.py", "file_billing.py", "imports"),
+            ("file_orders.py", "module_requests", "depends_on"),
+        ]);
+        assert_eq!(kb.dependency_graph.nodes.len(), 3);
+
+        let xml = kb.dependency_graph.to_graphml();
+        assert!(xml.contains("<node id=\"file_billing.py\">"));
+        assert!(xml.contains("<edge id=\"e0\" source=\"file_orders.py\" target=\"file_billing.py\">"));
+        assert!(!xml.contains("module_os"));
+    }
+}
@@ -1,4 +1,6 @@
 use crate::kb::types::*;
+use crate::parser::database::tag_database_usage;
+use crate::parser::error::ParseError;
 use regex::Regex;
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
@@ -8,43 +10,63 @@ use tree_sitter::{Node, Parser};
 
 pub struct CParser {
     source_code: String,
+    /// `obj.f()` is a method call in C++, not a call through a pointer field
+    cpp: bool,
 }
 
 impl CParser {
     pub fn new(source_code: String) -> Self {
         // let lines: Vec<String> = source_code.lines().map(|s| s.to_string()).collect();
-        Self { source_code }
+        Self { source_code, cpp: false }
     }
 
-    pub fn parse(&self) -> Result<FileData, String> {
+    /// The C parser as used by `CppParser` for function bodies
+    pub(super) fn for_cpp(source_code: String) -> Self {
+        Self { source_code, cpp: true }
+    }
+
+    pub fn parse(&self) -> Result<FileData, ParseError> {
         let mut parser = Parser::new();
         parser
             .set_language(tree_sitter_c::language())
-            .map_err(|e| format!("Failed to load C grammar: {}", e))?;
+            .map_err(|e| ParseError::GrammarLoad { language: "C", message: e.to_string() })?;
 
         let tree = parser
             .parse(&self.source_code, None)
-            .ok_or_else(|| "Failed to parse C file".to_string())?;
+            .ok_or(ParseError::TreeSitter { language: "C" })?;
 
         let root = tree.root_node();
 
+        let imports = self.extract_imports(&root);
+        let mut functions = self.extract_functions(&root);
+        let mut classes = self.extract_structs(&root);
+        let mut security_notes = self.detect_security_patterns();
+        security_notes.extend(tag_database_usage("c", &imports, &mut functions, &mut classes));
+
         Ok(FileData {
             language: "c".to_string(),
             loc: self.count_lines(),
-            imports: self.extract_imports(&root),
-            functions: self.extract_functions(&root),
-            classes: self.extract_structs(&root),
+            imports,
+            functions,
+            classes,
             global_vars: self.extract_global_vars(&root),
             todos: self.extract_todos(),
-            security_notes: self.detect_security_patterns(),
+            security_notes,
+            exports: None,
+            macros: self.extract_macros(&root),
+            build_tags: vec![],
+            directives: vec![],
+            type_aliases: vec![],
+            module_calls: vec![],
+            function_pointers: self.extract_function_pointers(&root),
         })
     }
 
-    fn count_lines(&self) -> usize {
+    pub(super) fn count_lines(&self) -> usize {
         self.source_code.lines().count()
     }
 
-    fn extract_imports(&self, root: &Node) -> Vec<Import> {
+    pub(super) fn extract_imports(&self, root: &Node) -> Vec<Import> {
         let mut imports = Vec::new();
         let mut cursor = root.walk();
 
@@ -64,6 +86,7 @@ impl CParser {
                         module: path.clone(),
                         items: vec![],
                         import_type: self.classify_import(&path, is_system),
+                        is_wildcard: false,
                     });
                 }
             }
@@ -107,14 +130,15 @@ impl CParser {
         functions
     }
 
-    fn parse_function(&self, node: &Node, struct_context: &str) -> Option<Function> {
+    pub(super) fn parse_function(&self, node: &Node, struct_context: &str) -> Option<Function> {
         let declarator = node.child_by_field_name("declarator")?;
         let name = self.extract_function_name(&declarator)?;
 
         let params = self.extract_parameters(&declarator);
+        // C++ constructors and destructors have no return type at all
         let return_type = node.child_by_field_name("type")
             .map(|t| self.get_node_text(&t))
-            .unwrap_or_else(|| "void".to_string());
+            .unwrap_or_else(|| if self.cpp { String::new() } else { "void".to_string() });
 
         let line_start = node.start_position().row + 1;
         let line_end = node.end_position().row + 1;
@@ -122,7 +146,10 @@ impl CParser {
         let signature = self.build_signature(&name, &params, &return_type);
 
         let body = node.child_by_field_name("body")?;
-        let calls = self.extract_function_calls_detailed(&body);
+        // Calling one of these can only go through a function pointer
+        let mut pointer_names = self.local_names(&body);
+        pointer_names.extend(params.iter().map(|p| p.name.clone()));
+        let calls = self.extract_function_calls_detailed(&body, &pointer_names);
         let variables = self.extract_variables(&body, &params);
         let control_flow = self.build_control_flow(&body);
         let exceptions = ExceptionInfo::default(); // C doesn't have exceptions
@@ -143,6 +170,7 @@ impl CParser {
             signature,
             params,
             return_type,
+            return_types: vec![],
             docstring,
             line_start,
             line_end,
@@ -153,9 +181,13 @@ impl CParser {
             exceptions,
             complexity,
             is_async: false,
+            needs_refactor: false,
+            spawns_goroutines: false,
+            method_kind: MethodKind::Instance,
             decorators: vec![],
             tags,
             importance_score,
+            source: None,
         })
     }
 
@@ -175,7 +207,24 @@ impl CParser {
                     None
                 }
             }
-            "identifier" => Some(self.get_node_text(declarator)),
+            // C++: `Shape::area`, in-class `area`, `~Shape`, `operator==`
+            "qualified_identifier" => {
+                if let Some(name) = declarator.child_by_field_name("name") {
+                    self.extract_function_name(&name)
+                } else {
+                    None
+                }
+            }
+            "reference_declarator" => {
+                if let Some(decl) = declarator.named_child(0) {
+                    self.extract_function_name(&decl)
+                } else {
+                    None
+                }
+            }
+            "identifier" | "field_identifier" | "destructor_name" | "operator_name" => {
+                Some(self.get_node_text(declarator))
+            }
             _ => None,
         }
     }
@@ -229,9 +278,9 @@ impl CParser {
         params
     }
 
-    fn extract_declarator_name(&self, declarator: &Node) -> String {
+    pub(super) fn extract_declarator_name(&self, declarator: &Node) -> String {
         match declarator.kind() {
-            "identifier" => self.get_node_text(declarator),
+            "identifier" | "field_identifier" => self.get_node_text(declarator),
             "pointer_declarator" | "array_declarator" | "function_declarator" => {
                 if let Some(decl) = declarator.child_by_field_name("declarator") {
                     self.extract_declarator_name(&decl)
@@ -239,6 +288,14 @@ impl CParser {
                     String::new()
                 }
             }
+            // C++ `T& name` has no declarator field, nor does `(*name)`
+            "reference_declarator" | "parenthesized_declarator" => {
+                if let Some(decl) = declarator.named_child(0) {
+                    self.extract_declarator_name(&decl)
+                } else {
+                    String::new()
+                }
+            }
             _ => String::new(),
         }
     }
@@ -256,17 +313,28 @@ impl CParser {
             .collect::<Vec<_>>()
             .join(", ");
 
-        format!("{} {}({})", return_type, name, param_str)
+        if return_type.is_empty() {
+            format!("{}({})", name, param_str)
+        } else {
+            format!("{} {}({})", return_type, name, param_str)
+        }
     }
 
-    fn extract_function_calls_detailed(&self, node: &Node) -> Vec<FunctionCall> {
+    fn extract_function_calls_detailed(&self, node: &Node, pointer_names: &HashSet<String>) -> Vec<FunctionCall> {
         let mut calls = Vec::new();
         let mut seen = HashSet::new();
-        self.find_calls_recursive(node, &mut calls, &mut seen, "unconditional");
+        self.find_calls_recursive(node, pointer_names, &mut calls, &mut seen, "unconditional");
         calls
     }
 
-    fn find_calls_recursive(&self, node: &Node, calls: &mut Vec<FunctionCall>, seen: &mut HashSet<String>, context: &str) {
+    fn find_calls_recursive(
+        &self,
+        node: &Node,
+        pointer_names: &HashSet<String>,
+        calls: &mut Vec<FunctionCall>,
+        seen: &mut HashSet<String>,
+        context: &str,
+    ) {
         let mut cursor = node.walk();
 
         let child_context = match node.kind() {
@@ -279,12 +347,22 @@ impl CParser {
         if node.kind() == "call_expression" {
             if let Some(func_node) = node.child_by_field_name("function") {
                 let call_text = self.get_node_text(&func_node);
-                let name = call_text
-                    .split(|c: char| c == '.' || c == '-' || c == '>')
-                    .last()
-                    .unwrap_or(&call_text)
-                    .trim()
-                    .to_string();
+                let is_indirect = match func_node.kind() {
+                    "field_expression" => !self.cpp,
+                    "parenthesized_expression" | "pointer_expression" | "subscript_expression" => true,
+                    "identifier" => pointer_names.contains(&call_text),
+                    _ => false,
+                };
+                let name = if is_indirect {
+                    self.pointer_slot(&func_node).unwrap_or_default()
+                } else {
+                    call_text
+                        .split(|c: char| c == '.' || c == '-' || c == '>' || c == ':')
+                        .last()
+                        .unwrap_or(&call_text)
+                        .trim()
+                        .to_string()
+                };
 
                 if !name.is_empty() {
                     let key = format!("{}:{}", name, node.start_position().row);
@@ -295,11 +373,14 @@ impl CParser {
 
                         calls.push(FunctionCall {
                             callee: name,
+                            receiver: None,
                             defined_in: None,
+                            candidates: vec![],
                             line: node.start_position().row + 1,
                             args,
                             is_conditional: context != "unconditional",
                             context: context.to_string(),
+                            is_indirect,
                         });
                     }
                 }
@@ -307,7 +388,7 @@ impl CParser {
         }
 
         for child in node.children(&mut cursor) {
-            self.find_calls_recursive(&child, calls, seen, child_context);
+            self.find_calls_recursive(&child, pointer_names, calls, seen, child_context);
         }
     }
 
@@ -326,6 +407,123 @@ impl CParser {
         args
     }
 
+    /// Names declared anywhere in a function body
+    fn local_names(&self, node: &Node) -> HashSet<String> {
+        let mut names = HashSet::new();
+        if node.kind() == "declaration" {
+            let mut cursor = node.walk();
+            for decl in node.children_by_field_name("declarator", &mut cursor) {
+                let decl = if decl.kind() == "init_declarator" {
+                    decl.child_by_field_name("declarator").unwrap_or(decl)
+                } else {
+                    decl
+                };
+                names.insert(self.extract_declarator_name(&decl));
+            }
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            names.extend(self.local_names(&child));
+        }
+        names.remove("");
+        names
+    }
+
+    /// Variable, field or array a function pointer is read from or stored
+    /// in: `fp` in `(*fp)`, `open` in `dev->ops->open`, `handlers` in `handlers[i]`
+    fn pointer_slot(&self, node: &Node) -> Option<String> {
+        match node.kind() {
+            "identifier" => Some(self.get_node_text(node)),
+            "field_expression" => node.child_by_field_name("field").map(|f| self.get_node_text(&f)),
+            "subscript_expression" | "pointer_expression" => self.pointer_slot(&node.child_by_field_name("argument")?),
+            "parenthesized_expression" => self.pointer_slot(&node.named_child(0)?),
+            _ => None,
+        }
+    }
+
+    /// Function named by an expression: `do_work`, `&do_work`, `(handler_t)do_work`
+    fn function_ref(&self, node: &Node) -> Option<String> {
+        match node.kind() {
+            "identifier" => Some(self.get_node_text(node)),
+            "pointer_expression" if node.child_by_field_name("operator").is_some_and(|op| op.kind() == "&") => {
+                self.function_ref(&node.child_by_field_name("argument")?)
+            }
+            "parenthesized_expression" => self.function_ref(&node.named_child(0)?),
+            "cast_expression" => self.function_ref(&node.child_by_field_name("value")?),
+            _ => None,
+        }
+    }
+
+    /// Every `slot = function` assignment and initializer in the file.
+    /// Non-function right-hand sides (`count = total`) are recorded too;
+    /// the analyzer only keeps targets that are defined functions.
+    fn extract_function_pointers(&self, root: &Node) -> Vec<FunctionPointer> {
+        let mut pointers = Vec::new();
+        self.collect_function_pointers(root, &mut pointers);
+        pointers
+    }
+
+    fn collect_function_pointers(&self, node: &Node, pointers: &mut Vec<FunctionPointer>) {
+        match node.kind() {
+            "assignment_expression" if node.child_by_field_name("operator").is_some_and(|op| op.kind() == "=") => {
+                if let (Some(left), Some(right)) = (node.child_by_field_name("left"), node.child_by_field_name("right")) {
+                    self.push_function_pointer(self.pointer_slot(&left), &right, pointers);
+                }
+            }
+            "init_declarator" => {
+                if let (Some(decl), Some(value)) = (node.child_by_field_name("declarator"), node.child_by_field_name("value")) {
+                    let name = self.extract_declarator_name(&decl);
+                    if value.kind() == "initializer_list" {
+                        self.initializer_pointers(&name, &value, pointers);
+                        return;
+                    }
+                    self.push_function_pointer(Some(name), &value, pointers);
+                }
+            }
+            _ => {}
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            self.collect_function_pointers(&child, pointers);
+        }
+    }
+
+    /// `{ .open = dev_open, .close = dev_close }` stores each function in its
+    /// field; a positional entry, as in `handler_t handlers[] = { a, b }`, in
+    /// `owner` itself
+    fn initializer_pointers(&self, owner: &str, list: &Node, pointers: &mut Vec<FunctionPointer>) {
+        let mut cursor = list.walk();
+        for item in list.named_children(&mut cursor) {
+            let (name, value) = if item.kind() == "initializer_pair" {
+                let mut designators = item.walk();
+                let field = item
+                    .children_by_field_name("designator", &mut designators)
+                    .filter(|d| d.kind() == "field_designator")
+                    .last()
+                    .and_then(|d| d.named_child(0))
+                    .map(|f| self.get_node_text(&f));
+                let Some(value) = item.child_by_field_name("value") else { continue };
+                (field.unwrap_or_else(|| owner.to_string()), value)
+            } else {
+                (owner.to_string(), item)
+            };
+
+            if value.kind() == "initializer_list" {
+                self.initializer_pointers(&name, &value, pointers);
+            } else {
+                self.push_function_pointer(Some(name), &value, pointers);
+            }
+        }
+    }
+
+    fn push_function_pointer(&self, name: Option<String>, value: &Node, pointers: &mut Vec<FunctionPointer>) {
+        if let (Some(name), Some(target)) = (name.filter(|n| !n.is_empty()), self.function_ref(value)) {
+            pointers.push(FunctionPointer { name, target, line: value.start_position().row + 1 });
+        }
+    }
+
     fn extract_variables(&self, node: &Node, params: &[Parameter]) -> Vec<Variable> {
         let mut variables: HashMap<String, Variable> = HashMap::new();
 
@@ -395,9 +593,11 @@ impl CParser {
     fn build_control_flow(&self, node: &Node) -> ControlFlow {
         let mut control_flow = ControlFlow {
             complexity: self.calculate_complexity(node),
+            cognitive_complexity: self.calculate_cognitive_complexity(node),
             branches: vec![],
             loops: vec![],
             try_blocks: vec![],
+            with_blocks: vec![],
         };
 
         self.extract_control_structures(node, &mut control_flow);
@@ -523,27 +723,101 @@ impl CParser {
             condition,
             line,
             calls,
+            is_async: false,
         })
     }
 
-    fn extract_structs(&self, root: &Node) -> Vec<Class> {
-        let mut structs = Vec::new();
+    /// Top-level items, looking inside `#ifdef`/`#if` blocks such as
+    /// header guards, which otherwise hide everything a header declares
+    fn top_level_items<'a>(&self, root: &Node<'a>) -> Vec<Node<'a>> {
+        let mut items = Vec::new();
         let mut cursor = root.walk();
 
         for child in root.children(&mut cursor) {
-            if child.kind() == "declaration" {
-                if let Some(struct_data) = self.parse_struct(&child) {
-                    structs.push(struct_data);
+            match child.kind() {
+                "preproc_ifdef" | "preproc_if" | "preproc_else" | "preproc_elif" => {
+                    items.extend(self.top_level_items(&child));
                 }
+                _ => items.push(child),
             }
         }
 
+        items
+    }
+
+    fn extract_structs(&self, root: &Node) -> Vec<Class> {
+        let mut structs = Vec::new();
+
+        for child in self.top_level_items(root) {
+            let parsed = match child.kind() {
+                "declaration" => child.child_by_field_name("type")
+                    .and_then(|type_node| self.parse_struct(&child, &type_node)),
+                // `struct Foo { ... };` with no declarator
+                "struct_specifier" | "union_specifier" => self.parse_struct(&child, &child),
+                "type_definition" => {
+                    structs.extend(self.parse_typedef(&child));
+                    None
+                }
+                _ => None,
+            };
+            structs.extend(parsed);
+        }
+
         structs
     }
 
-    fn parse_struct(&self, node: &Node) -> Option<Class> {
-        let type_node = node.child_by_field_name("type")?;
+    /// `typedef struct { ... } Foo;` becomes a struct named `Foo`; aliases
+    /// of existing types (`typedef int myint;`, `typedef struct Node Node;`)
+    /// become `typedef_<name>` entries with the aliased type as their base
+    fn parse_typedef(&self, node: &Node) -> Vec<Class> {
+        let Some(type_node) = node.child_by_field_name("type") else {
+            return vec![];
+        };
+        let has_body = matches!(type_node.kind(), "struct_specifier" | "union_specifier")
+            && type_node.child_by_field_name("body").is_some();
+
+        let mut cursor = node.walk();
+        let names: Vec<String> = node.children_by_field_name("declarator", &mut cursor)
+            .map(|decl| match decl.kind() {
+                "type_identifier" => self.get_node_text(&decl),
+                _ => self.typedef_declarator_name(&decl),
+            })
+            .filter(|name| !name.is_empty())
+            .collect();
+
+        names.into_iter()
+            .filter_map(|name| {
+                if has_body {
+                    return self.struct_class(node, &type_node, name);
+                }
+                Some(Class {
+                    id: format!("typedef_{}", name),
+                    name,
+                    bases: vec![self.get_node_text(&type_node)],
+                    docstring: self.extract_docstring(node),
+                    line_start: node.start_position().row + 1,
+                    line_end: node.end_position().row + 1,
+                    methods: vec![],
+                    attributes: vec![],
+                    decorators: vec![],
+                    type_params: vec![],
+                })
+            })
+            .collect()
+    }
 
+    /// Name in a typedef declarator like `*FooPtr` or `(*handler)(int)`
+    fn typedef_declarator_name(&self, declarator: &Node) -> String {
+        match declarator.kind() {
+            "type_identifier" => self.get_node_text(declarator),
+            _ => declarator.child_by_field_name("declarator")
+                .map(|decl| self.typedef_declarator_name(&decl))
+                .or_else(|| declarator.named_child(0).map(|decl| self.typedef_declarator_name(&decl)))
+                .unwrap_or_default(),
+        }
+    }
+
+    fn parse_struct(&self, node: &Node, type_node: &Node) -> Option<Class> {
         if type_node.kind() != "struct_specifier" && type_node.kind() != "union_specifier" {
             return None;
         }
@@ -551,6 +825,10 @@ impl CParser {
         let name = type_node.child_by_field_name("name")
             .map(|n| self.get_node_text(&n))?;
 
+        self.struct_class(node, type_node, name)
+    }
+
+    fn struct_class(&self, node: &Node, type_node: &Node, name: String) -> Option<Class> {
         let line_start = node.start_position().row + 1;
         let line_end = node.end_position().row + 1;
         let docstring = self.extract_docstring(node);
@@ -577,6 +855,7 @@ impl CParser {
             methods: vec![],
             attributes,
             decorators: vec![],
+            type_params: vec![],
         })
     }
 
@@ -606,7 +885,43 @@ impl CParser {
         fields
     }
 
-    fn extract_global_vars(&self, root: &Node) -> Vec<GlobalVar> {
+    /// `#define`s, including those inside header guards and other
+    /// conditional blocks
+    pub(super) fn extract_macros(&self, root: &Node) -> Vec<Macro> {
+        let mut macros = Vec::new();
+
+        for child in self.top_level_items(root) {
+            if child.kind() != "preproc_def" && child.kind() != "preproc_function_def" {
+                continue;
+            }
+            let Some(name) = child.child_by_field_name("name").map(|n| self.get_node_text(&n)) else {
+                continue;
+            };
+
+            let params = child.child_by_field_name("parameters").map(|params| {
+                let mut cursor = params.walk();
+                params.named_children(&mut cursor)
+                    .map(|param| self.get_node_text(&param))
+                    .collect::<Vec<_>>()
+            });
+            let value = child.child_by_field_name("value")
+                .map(|v| self.get_node_text(&v).trim().to_string())
+                .unwrap_or_default();
+            let calls = macro_calls(&value, params.as_deref().unwrap_or(&[]));
+
+            macros.push(Macro {
+                name,
+                params,
+                value,
+                line: child.start_position().row + 1,
+                calls,
+            });
+        }
+
+        macros
+    }
+
+    pub(super) fn extract_global_vars(&self, root: &Node) -> Vec<GlobalVar> {
         let mut vars = Vec::new();
         let mut cursor = root.walk();
 
@@ -653,7 +968,7 @@ impl CParser {
         })
     }
 
-    fn extract_docstring(&self, node: &Node) -> String {
+    pub(super) fn extract_docstring(&self, node: &Node) -> String {
         if let Some(prev) = node.prev_sibling() {
             if prev.kind() == "comment" {
                 let text = self.get_node_text(&prev);
@@ -695,7 +1010,82 @@ impl CParser {
         complexity
     }
 
-    fn extract_todos(&self) -> Vec<Todo> {
+    /// Cognitive complexity: like cyclomatic complexity, but nested control
+    /// structures cost more the deeper they sit
+    fn calculate_cognitive_complexity(&self, node: &Node) -> usize {
+        fn operator(node: &Node) -> Option<&'static str> {
+            node.child_by_field_name("operator").map(|op| op.kind())
+        }
+
+        fn continues_sequence(node: &Node) -> bool {
+            node.parent()
+                .map(|p| p.kind() == "binary_expression" && operator(&p) == operator(node))
+                .unwrap_or(false)
+        }
+
+        fn is_logical(node: &Node) -> bool {
+            matches!(operator(node), Some("&&") | Some("||"))
+        }
+
+        fn is_else_branch(node: &Node) -> bool {
+            node.parent()
+                .map(|p| p.kind() == "else_clause")
+                .unwrap_or(false)
+        }
+
+        fn walk(node: &Node, nesting: usize) -> usize {
+            let mut score = 0;
+            let mut child_nesting = nesting;
+
+            match node.kind() {
+                "if_statement" => {
+                    // `else if` is a flat increment, it doesn't pay for nesting again
+                    if is_else_branch(node) {
+                        score += 1;
+                    } else {
+                        score += 1 + nesting;
+                        child_nesting += 1;
+                    }
+                }
+                "for_statement" | "while_statement" | "do_statement" |
+                "switch_statement" | "conditional_expression" => {
+                    score += 1 + nesting;
+                    child_nesting += 1;
+                }
+                "else_clause" => {
+                    // `else if` is scored on the inner if_statement
+                    let is_else_if = node
+                        .named_child(0)
+                        .map(|c| c.kind() == "if_statement")
+                        .unwrap_or(false);
+                    if !is_else_if {
+                        score += 1;
+                    }
+                }
+                "goto_statement" => {
+                    score += 1;
+                }
+                // Each run of the same logical operator counts once
+                "binary_expression" if is_logical(node) && !continues_sequence(node) => {
+                    score += 1;
+                }
+                _ => {}
+            }
+
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                score += walk(&child, child_nesting);
+            }
+
+            score
+        }
+
+        // Start from the children so the function itself doesn't count as nesting
+        let mut cursor = node.walk();
+        node.children(&mut cursor).map(|child| walk(&child, 0)).sum()
+    }
+
+    pub(super) fn extract_todos(&self) -> Vec<Todo> {
         let re = Regex::new(r"(?://|/\*)\s*TODO:?\s*(.+?)(?:\*/|$)").unwrap();
 
         self.source_code
@@ -723,7 +1113,7 @@ impl CParser {
             .collect()
     }
 
-    fn detect_security_patterns(&self) -> Vec<SecurityNote> {
+    pub(super) fn detect_security_patterns(&self) -> Vec<SecurityNote> {
         let mut notes = Vec::new();
 
         let patterns = vec![
@@ -797,14 +1187,6 @@ impl CParser {
             tags.push("http-handler".to_string());
         }
 
-        // Database
-        if name_lower.contains("db") || name_lower.contains("database") ||
-            name_lower.contains("query") || name_lower.contains("select") ||
-            name_lower.contains("insert") || name_lower.contains("update") ||
-            name_lower.contains("delete") {
-            tags.push("database".to_string());
-        }
-
         // Validation
         if name_lower.contains("validate") || name_lower.contains("check") ||
             name_lower.contains("verify") || name_lower.contains("sanitize") {
@@ -929,16 +1311,39 @@ impl CParser {
         score.max(0.0).min(1.0)
     }
 
-    fn get_node_text(&self, node: &Node) -> String {
+    pub(super) fn get_node_text(&self, node: &Node) -> String {
         node.utf8_text(self.source_code.as_bytes())
             .unwrap_or("")
             .to_string()
     }
 }
 
-pub fn parse_file(path: &Path) -> Result<(String, FileData), String> {
+/// Names called in a macro body, skipping keywords, parameters and
+/// operators like `sizeof` that look like calls
+fn macro_calls(value: &str, params: &[String]) -> Vec<String> {
+    const NOT_CALLS: &[&str] = &[
+        "if", "while", "for", "switch", "return", "sizeof", "defined", "typeof",
+        "__typeof__", "_Generic", "_Alignof", "alignof", "do",
+    ];
+    let re = Regex::new(r"\b([A-Za-z_]\w*)\s*\(").unwrap();
+
+    let mut calls: Vec<String> = Vec::new();
+    for caps in re.captures_iter(value) {
+        let name = &caps[1];
+        if NOT_CALLS.contains(&name) || params.iter().any(|p| p == name) {
+            continue;
+        }
+        if !calls.iter().any(|c| c == name) {
+            calls.push(name.to_string());
+        }
+    }
+    calls
+}
+
+pub fn parse_file(path: &Path) -> Result<(String, FileData), ParseError> {
     let source_code = std::fs::read_to_string(path)
-        .map_err(|e| format!("Failed to read file {}: {}", path.display(), e))?;
+        .map_err(|e| ParseError::io(path, e))?
+        .replace("\r\n", "\n");
 
     let parser = CParser::new(source_code);
     let file_data = parser.parse()?;
@@ -947,3 +1352,58 @@ pub fn parse_file(path: &Path) -> Result<(String, FileData), String> {
 
     Ok((relative_path, file_data))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> FileData {
+        CParser::new(source.to_string()).parse().unwrap()
+    }
+
+    #[test]
+    fn test_macros() {
+        let data = parse(
+            "#ifndef UTIL_H\n#define UTIL_H\n\n\
+             #define MAX(a,b) ((a) > (b) ? (a) : (b))\n\
+             #define LOG(msg) log_write(stderr, msg)\n\
+             #define BUFFER_SIZE 4096\n\n\
+             #endif\n",
+        );
+        let find = |name: &str| data.macros.iter().find(|m| m.name == name).unwrap();
+
+        let max = find("MAX");
+        assert_eq!(max.params, Some(vec!["a".to_string(), "b".to_string()]));
+        assert_eq!(max.value, "((a) > (b) ? (a) : (b))");
+        assert_eq!(max.line, 4);
+        assert!(max.calls.is_empty());
+
+        assert_eq!(find("LOG").calls, vec!["log_write"]);
+
+        let size = find("BUFFER_SIZE");
+        assert_eq!(size.params, None);
+        assert_eq!(size.value, "4096");
+        assert!(data.macros.iter().any(|m| m.name == "UTIL_H"));
+    }
+
+    #[test]
+    fn test_typedefs_and_structs() {
+        let data = parse(
+            "typedef struct {\n    int x;\n    char *name;\n} Foo;\n\n\
+             typedef union { int i; float f; } Value;\n\
+             typedef unsigned long size_type;\n\
+             struct Bar { int y; };\n",
+        );
+        let find = |name: &str| data.classes.iter().find(|c| c.name == name).unwrap();
+
+        let foo = find("Foo");
+        assert_eq!(foo.id, "struct_Foo");
+        let fields: Vec<&str> = foo.attributes.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(fields, vec!["x", "name"]);
+
+        assert_eq!(find("Value").id, "union_Value");
+        assert_eq!(find("size_type").id, "typedef_size_type");
+        assert_eq!(find("size_type").bases, vec!["unsigned long"]);
+        assert_eq!(find("Bar").id, "struct_Bar");
+    }
+}
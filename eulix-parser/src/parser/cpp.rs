@@ -0,0 +1,392 @@
+use crate::kb::types::*;
+use crate::parser::c::CParser;
+use crate::parser::database::tag_database_usage;
+use crate::parser::error::ParseError;
+use std::path::Path;
+use tree_sitter::{Node, Parser};
+
+/// C++ parser. Function bodies, includes, globals, TODOs and security notes
+/// go through `CParser`; this layer adds classes, namespaces and templates.
+///
+/// Entities declared inside a namespace get the namespace path folded into
+/// their id (`func_geo_area`, `class_geo_Shape`, `method_geo_Shape_area`) so
+/// same-named declarations in different namespaces don't collide.
+pub struct CppParser {
+    c: CParser,
+    source_code: String,
+}
+
+impl CppParser {
+    pub fn new(source_code: String) -> Self {
+        Self {
+            c: CParser::for_cpp(source_code.clone()),
+            source_code,
+        }
+    }
+
+    pub fn parse(&self) -> Result<FileData, ParseError> {
+        let mut parser = Parser::new();
+        parser
+            .set_language(tree_sitter_cpp::language())
+            .map_err(|e| ParseError::GrammarLoad { language: "C++", message: e.to_string() })?;
+
+        let tree = parser
+            .parse(&self.source_code, None)
+            .ok_or(ParseError::TreeSitter { language: "C++" })?;
+
+        let root = tree.root_node();
+
+        let mut functions = Vec::new();
+        let mut classes = Vec::new();
+        let mut out_of_class = Vec::new();
+        self.walk_declarations(&root, &[], &mut functions, &mut classes, &mut out_of_class);
+
+        // Attach `Shape::area() { ... }` definitions to their class when it's in this file
+        for (qualified_class, method) in out_of_class {
+            let class_ids = [format!("class_{}", qualified_class), format!("struct_{}", qualified_class)];
+            match classes.iter_mut().find(|c| class_ids.contains(&c.id)) {
+                Some(class) => class.methods.push(method),
+                None => functions.push(method),
+            }
+        }
+
+        let imports = self.c.extract_imports(&root);
+        let mut security_notes = self.c.detect_security_patterns();
+        security_notes.extend(tag_database_usage("cpp", &imports, &mut functions, &mut classes));
+
+        Ok(FileData {
+            language: "cpp".to_string(),
+            loc: self.c.count_lines(),
+            imports,
+            functions,
+            classes,
+            global_vars: self.c.extract_global_vars(&root),
+            todos: self.c.extract_todos(),
+            security_notes,
+            exports: None,
+            macros: self.c.extract_macros(&root),
+            build_tags: vec![],
+            directives: vec![],
+            type_aliases: vec![],
+            module_calls: vec![],
+            function_pointers: vec![],
+        })
+    }
+
+    fn walk_declarations(
+        &self,
+        node: &Node,
+        scope: &[String],
+        functions: &mut Vec<Function>,
+        classes: &mut Vec<Class>,
+        out_of_class: &mut Vec<(String, Function)>,
+    ) {
+        let mut cursor = node.walk();
+
+        for child in node.children(&mut cursor) {
+            match child.kind() {
+                "namespace_definition" => {
+                    let mut inner_scope = scope.to_vec();
+                    // Anonymous namespaces don't add a path segment
+                    if let Some(name) = child.child_by_field_name("name") {
+                        inner_scope.push(self.c.get_node_text(&name).replace("::", "_"));
+                    }
+                    if let Some(body) = child.child_by_field_name("body") {
+                        self.walk_declarations(&body, &inner_scope, functions, classes, out_of_class);
+                    }
+                }
+                // extern "C" { ... }
+                "linkage_specification" => {
+                    if let Some(body) = child.child_by_field_name("body") {
+                        self.walk_declarations(&body, scope, functions, classes, out_of_class);
+                    }
+                }
+                "function_definition" => {
+                    self.add_function(&child, &child, scope, functions, out_of_class);
+                }
+                "class_specifier" | "struct_specifier" => {
+                    if let Some(class) = self.parse_class(&child, &child, scope) {
+                        classes.push(class);
+                    }
+                }
+                "template_declaration" => {
+                    let Some(inner) = self.template_target(&child) else {
+                        continue;
+                    };
+                    match inner.kind() {
+                        "function_definition" => {
+                            self.add_function(&inner, &child, scope, functions, out_of_class);
+                        }
+                        _ => {
+                            if let Some(class) = self.parse_class(&inner, &child, scope) {
+                                classes.push(class);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Free function or out-of-class method definition (`int Shape::area()`)
+    fn add_function(
+        &self,
+        node: &Node,
+        outer: &Node,
+        scope: &[String],
+        functions: &mut Vec<Function>,
+        out_of_class: &mut Vec<(String, Function)>,
+    ) {
+        match self.qualifying_scope(node) {
+            Some(class_name) => {
+                let qualified = self.qualify(scope, &class_name);
+                if let Some(method) = self.parse_function(node, outer, &qualified) {
+                    out_of_class.push((qualified, method));
+                }
+            }
+            None => {
+                if let Some(mut func) = self.parse_function(node, outer, "") {
+                    if !scope.is_empty() {
+                        func.id = format!("func_{}", self.qualify(scope, &func.name));
+                    }
+                    functions.push(func);
+                }
+            }
+        }
+    }
+
+    /// Run the C function extraction, then patch in what only the C++ wrapper
+    /// knows: template parameters and the enclosing template's position
+    fn parse_function(&self, node: &Node, outer: &Node, class_context: &str) -> Option<Function> {
+        let mut func = self.c.parse_function(node, class_context)?;
+
+        if outer.kind() == "template_declaration" {
+            if let Some(params) = outer.child_by_field_name("parameters") {
+                func.signature = format!("template{} {}", self.c.get_node_text(&params), func.signature);
+            }
+            func.line_start = outer.start_position().row + 1;
+            func.docstring = self.c.extract_docstring(outer);
+            func.tags.push("generic".to_string());
+            func.tags.sort();
+            func.tags.dedup();
+        }
+
+        Some(func)
+    }
+
+    /// Class name from a qualified definition like `Shape::area`
+    fn qualifying_scope(&self, node: &Node) -> Option<String> {
+        let mut declarator = node.child_by_field_name("declarator")?;
+
+        while declarator.kind() != "qualified_identifier" {
+            declarator = declarator
+                .child_by_field_name("declarator")
+                .or_else(|| match declarator.kind() {
+                    "reference_declarator" => declarator.named_child(0),
+                    _ => None,
+                })?;
+        }
+
+        declarator
+            .child_by_field_name("scope")
+            .map(|s| self.c.get_node_text(&s).replace("::", "_"))
+    }
+
+    fn template_target<'a>(&self, node: &Node<'a>) -> Option<Node<'a>> {
+        let mut cursor = node.walk();
+        let target = node.named_children(&mut cursor).find(|c| {
+            matches!(c.kind(), "function_definition" | "class_specifier" | "struct_specifier")
+        });
+        target
+    }
+
+    fn qualify(&self, scope: &[String], name: &str) -> String {
+        if scope.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}_{}", scope.join("_"), name)
+        }
+    }
+
+    fn parse_class(&self, node: &Node, outer: &Node, scope: &[String]) -> Option<Class> {
+        // Forward declarations (`class Foo;`) have no body
+        let body = node.child_by_field_name("body")?;
+        let name = self.c.get_node_text(&node.child_by_field_name("name")?);
+        let qualified = self.qualify(scope, &name);
+
+        let mut bases = Vec::new();
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            if child.kind() == "base_class_clause" {
+                let mut base_cursor = child.walk();
+                for base in child.named_children(&mut base_cursor) {
+                    if base.kind() != "access_specifier" && base.kind() != "virtual" {
+                        bases.push(self.c.get_node_text(&base));
+                    }
+                }
+            }
+        }
+
+        let mut methods = Vec::new();
+        let mut attributes = Vec::new();
+        let mut body_cursor = body.walk();
+
+        for member in body.children(&mut body_cursor) {
+            match member.kind() {
+                "function_definition" => {
+                    if let Some(method) = self.parse_function(&member, &member, &qualified) {
+                        methods.push(method);
+                    }
+                }
+                "template_declaration" => {
+                    if let Some(inner) = self.template_target(&member) {
+                        if inner.kind() == "function_definition" {
+                            if let Some(method) = self.parse_function(&inner, &member, &qualified) {
+                                methods.push(method);
+                            }
+                        }
+                    }
+                }
+                "field_declaration" => {
+                    let Some(decl) = member.child_by_field_name("declarator") else {
+                        continue;
+                    };
+                    // Method prototypes are declarations, not data members
+                    if decl.kind() == "function_declarator" {
+                        continue;
+                    }
+
+                    let name = self.c.extract_declarator_name(&decl);
+                    if !name.is_empty() {
+                        attributes.push(Attribute {
+                            name,
+                            type_annotation: member.child_by_field_name("type")
+                                .map(|t| self.c.get_node_text(&t))
+                                .unwrap_or_default(),
+                            value: member.child_by_field_name("default_value")
+                                .map(|v| self.c.get_node_text(&v)),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let kind = if node.kind() == "struct_specifier" { "struct" } else { "class" };
+
+        Some(Class {
+            id: format!("{}_{}", kind, qualified),
+            name,
+            bases,
+            docstring: self.c.extract_docstring(outer),
+            line_start: outer.start_position().row + 1,
+            line_end: outer.end_position().row + 1,
+            methods,
+            attributes,
+            decorators: vec![],
+            type_params: vec![],
+        })
+    }
+}
+
+pub fn parse_file(path: &Path) -> Result<(String, FileData), ParseError> {
+    let source_code = std::fs::read_to_string(path)
+        .map_err(|e| ParseError::io(path, e))?
+        .replace("\r\n", "\n");
+
+    let parser = CppParser::new(source_code);
+    let file_data = parser.parse()?;
+
+    let relative_path = path.to_string_lossy().to_string();
+
+    Ok((relative_path, file_data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> FileData {
+        CppParser::new(source.to_string()).parse().unwrap()
+    }
+
+    #[test]
+    fn test_class_and_out_of_class_method() {
+        let data = parse(
+            "#include <vector>\n\
+             // A drawable shape.\n\
+             class Shape : public Base {\n\
+             public:\n    int area() const { return w_ * 2; }\n    int perimeter();\n\
+             private:\n    int w_;\n};\n\n\
+             int Shape::perimeter() { return helper(w_); }\n",
+        );
+
+        assert_eq!(data.language, "cpp");
+        assert_eq!(data.imports[0].import_type, "stdlib");
+        assert!(data.functions.is_empty());
+
+        let class = &data.classes[0];
+        assert_eq!(class.id, "class_Shape");
+        assert_eq!(class.bases, vec!["Base"]);
+        assert_eq!(class.docstring, "A drawable shape.");
+        assert_eq!(class.attributes.len(), 1);
+        assert_eq!(class.attributes[0].name, "w_");
+
+        let methods: Vec<&str> = class.methods.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(methods, vec!["method_Shape_area", "method_Shape_perimeter"]);
+        assert_eq!(class.methods[1].calls[0].callee, "helper");
+    }
+
+    #[test]
+    fn test_namespaces_and_templates() {
+        let data = parse(
+            "namespace geo {\nnamespace detail {\nint area(int w) { return w * w; }\n}\n\
+             struct Point { int x; int y; };\n}\n\n\
+             template <typename T>\nT max_of(const T& a, const T& b) { return a > b ? a : b; }\n",
+        );
+
+        let ids: Vec<&str> = data.functions.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(ids, vec!["func_geo_detail_area", "func_max_of"]);
+
+        let max_of = &data.functions[1];
+        assert_eq!(max_of.signature, "template<typename T> T max_of(T a, T b)");
+        assert_eq!(max_of.params[0].name, "a");
+        assert_eq!(max_of.line_start, 8);
+        assert!(max_of.tags.contains(&"generic".to_string()));
+
+        assert_eq!(data.classes[0].id, "struct_geo_Point");
+        assert_eq!(data.classes[0].attributes.len(), 2);
+    }
+
+    #[test]
+    fn test_constructors_and_destructors_have_no_return_type() {
+        let data = parse(
+            "class Foo {
+public:
+    Foo(int size);
+    ~Foo() { release(); }
+private:
+    int size_;
+};
+
+\
+             Foo::Foo(int size) : size_(size) { allocate(size); }
+",
+        );
+
+        let methods = &data.classes[0].methods;
+        let ids: Vec<&str> = methods.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec!["method_Foo_~Foo", "method_Foo_Foo"]);
+
+        let destructor = &methods[0];
+        assert_eq!(destructor.return_type, "");
+        assert_eq!(destructor.signature, "~Foo()");
+
+        let constructor = &methods[1];
+        assert_eq!(constructor.return_type, "");
+        assert_eq!(constructor.signature, "Foo(int size)");
+        assert_eq!(constructor.line_start, 9);
+        assert_eq!(constructor.calls[0].callee, "allocate");
+    }
+}
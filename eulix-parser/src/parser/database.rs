@@ -0,0 +1,274 @@
+use crate::kb::types::{Class, Function, FunctionCall, Import, SecurityNote};
+
+/// Database APIs of one language
+struct DatabaseApis {
+    /// Driver and ORM modules; a module also covers its submodules
+    modules: &'static [&'static str],
+    /// Calls that touch the database once one of `modules` is imported;
+    /// Python goes by the receiver instead, see `is_python_database_call`
+    calls: &'static [&'static str],
+    /// Calls that run the SQL text they are given, checked for queries
+    /// built from strings
+    execute: &'static [&'static str],
+    /// Names from `calls` and `execute` common outside database code
+    /// (`http.Get`, `os.Create`, `child_process.exec`); these only count on
+    /// a receiver from `connections` or on the driver module itself
+    generic: &'static [&'static str],
+    /// Receivers that are a connection, session, transaction or cursor
+    connections: &'static [&'static str],
+    /// Name prefixes of C library functions, which need no import check
+    prefixes: &'static [&'static str],
+}
+
+const PYTHON: DatabaseApis = DatabaseApis {
+    modules: &[
+        "sqlalchemy", "flask_sqlalchemy", "sqlmodel", "django.db", "sqlite3", "psycopg2", "psycopg",
+        "pymysql", "MySQLdb", "mysql.connector", "asyncpg", "aiosqlite", "peewee", "pymongo", "motor",
+    ],
+    calls: &[],
+    execute: &["execute", "executemany", "executescript", "raw"],
+    generic: &[],
+    connections: &["session", "db", "engine", "conn", "connection", "cursor", "cur"],
+    prefixes: &[],
+};
+
+const GO: DatabaseApis = DatabaseApis {
+    modules: &[
+        "database/sql", "github.com/jmoiron/sqlx", "github.com/jackc/pgx", "gorm.io/gorm",
+        "go.mongodb.org/mongo-driver",
+    ],
+    calls: &["Begin", "BeginTx", "Find", "First", "Create", "Save", "Where", "Take", "Updates", "Scan"],
+    execute: &[
+        "Query", "QueryRow", "QueryContext", "QueryRowContext", "Exec", "ExecContext", "Prepare",
+        "PrepareContext", "Queryx", "QueryRowx", "Get", "Select", "NamedExec", "Raw",
+    ],
+    generic: &[
+        "Begin", "Find", "First", "Create", "Save", "Where", "Take", "Scan", "Query", "Exec", "Prepare", "Get",
+        "Select", "Raw",
+    ],
+    connections: &["db", "tx", "conn", "stmt", "rows", "row", "pool"],
+    prefixes: &[],
+};
+
+const JAVASCRIPT: DatabaseApis = DatabaseApis {
+    modules: &[
+        "pg", "mysql", "mysql2", "sqlite3", "better-sqlite3", "mongodb", "mongoose", "sequelize", "knex",
+        "typeorm", "@prisma/client",
+    ],
+    calls: &[
+        "findOne", "findMany", "findUnique", "findAll", "findById", "aggregate", "insertOne", "insertMany",
+        "updateOne", "updateMany", "deleteOne", "deleteMany", "transaction", "$transaction",
+    ],
+    execute: &["query", "execute", "exec", "prepare", "raw", "$queryRawUnsafe", "$executeRawUnsafe"],
+    generic: &["query", "execute", "exec", "prepare", "raw", "aggregate", "transaction"],
+    connections: &[
+        "db", "pool", "client", "conn", "connection", "trx", "tx", "knex", "prisma", "sequelize", "collection",
+    ],
+    prefixes: &[],
+};
+
+const C: DatabaseApis = DatabaseApis {
+    modules: &[],
+    calls: &[],
+    execute: &[],
+    generic: &[],
+    connections: &[],
+    prefixes: &["sqlite3_", "mysql_", "PQ"],
+};
+
+fn apis(language: &str) -> Option<&'static DatabaseApis> {
+    match language {
+        "python" => Some(&PYTHON),
+        "go" => Some(&GO),
+        "javascript" | "typescript" => Some(&JAVASCRIPT),
+        "c" | "cpp" => Some(&C),
+        _ => None,
+    }
+}
+
+/// Tag every function and method that calls a database API `database`, and
+/// return a `sql_injection_risk` note for each such call whose query is
+/// built by formatting or concatenating strings. Shared by every parser so
+/// the tag follows what a function imports and calls, not its name:
+/// `query_params` and `save_image` aren't database code.
+pub fn tag_database_usage(
+    language: &str,
+    imports: &[Import],
+    functions: &mut [Function],
+    classes: &mut [Class],
+) -> Vec<SecurityNote> {
+    let Some(apis) = apis(language) else {
+        return vec![];
+    };
+    let db_imports: Vec<&Import> = imports
+        .iter()
+        .filter(|import| apis.modules.iter().any(|module| within(&import.module, module)))
+        .collect();
+
+    let mut notes = Vec::new();
+    let methods = classes.iter_mut().flat_map(|class| class.methods.iter_mut());
+    for func in functions.iter_mut().chain(methods) {
+        let db_calls: Vec<&FunctionCall> = func
+            .calls
+            .iter()
+            .filter(|call| is_database_call(language, apis, &db_imports, call))
+            .collect();
+        if db_calls.is_empty() {
+            continue;
+        }
+
+        for call in db_calls {
+            if !apis.execute.contains(&call.callee.as_str()) {
+                continue;
+            }
+            if let Some(how) = call.args.iter().find_map(|arg| dynamic_query(language, arg)) {
+                notes.push(SecurityNote {
+                    note_type: "sql_injection_risk".to_string(),
+                    line: call.line,
+                    description: format!(
+                        "SQL built with {} passed to {}() in {}; use query parameters",
+                        how, call.callee, func.name
+                    ),
+                });
+            }
+        }
+
+        if !func.tags.iter().any(|tag| tag == "database") {
+            func.tags.push("database".to_string());
+            func.tags.sort();
+        }
+    }
+
+    notes
+}
+
+fn is_database_call(language: &str, apis: &DatabaseApis, db_imports: &[&Import], call: &FunctionCall) -> bool {
+    if language == "python" {
+        return is_python_database_call(call, db_imports);
+    }
+    let callee = call.callee.as_str();
+    if apis.prefixes.iter().any(|prefix| callee.starts_with(prefix)) {
+        return true;
+    }
+    if db_imports.is_empty() || !(apis.calls.contains(&callee) || apis.execute.contains(&callee)) {
+        return false;
+    }
+    !apis.generic.contains(&callee) || has_connection_receiver(apis, db_imports, call)
+}
+
+/// Whether the receiver of `call` is a connection value (`db`, `s.db`,
+/// `db.Where(...)`) or a driver module (`sqlx.Get`, `knex.raw`)
+fn has_connection_receiver(apis: &DatabaseApis, db_imports: &[&Import], call: &FunctionCall) -> bool {
+    let Some(receiver) = call.receiver.as_deref() else {
+        return false;
+    };
+    receiver.split('.').any(|segment| {
+        let segment = segment.split('(').next().unwrap_or("").trim().to_lowercase();
+        apis.connections.contains(&segment.as_str())
+            || db_imports.iter().any(|import| {
+                import.module.rsplit('/').next().is_some_and(|last| last.to_lowercase() == segment)
+                    || import.items.iter().any(|item| item.to_lowercase() == segment)
+            })
+    })
+}
+
+/// A raw `cursor.execute()` or a Django `User.objects.filter()` counts
+/// without any import. Once a driver or ORM is imported, so does any call on
+/// a session or connection, on the module itself (`sqlite3.connect()`), or
+/// to a name imported from it (`select()`).
+fn is_python_database_call(call: &FunctionCall, db_imports: &[&Import]) -> bool {
+    let receiver = call.receiver.as_deref().unwrap_or("");
+    let last = receiver.rsplit('.').next().unwrap_or("").to_lowercase();
+
+    if PYTHON.execute.contains(&call.callee.as_str()) && (last.contains("cursor") || last.contains("conn")) {
+        return true;
+    }
+    if last == "objects" {
+        return true;
+    }
+    if db_imports.is_empty() {
+        return false;
+    }
+
+    if receiver.is_empty() {
+        db_imports.iter().any(|import| import.items.contains(&call.callee))
+    } else {
+        PYTHON.connections.contains(&last.as_str())
+            || db_imports.iter().any(|import| import.module.split('.').next() == receiver.split('.').next())
+    }
+}
+
+/// How `arg` builds a query from strings, if it does
+fn dynamic_query(language: &str, arg: &str) -> Option<&'static str> {
+    let arg = arg.trim();
+    match language {
+        "python" => {
+            let prefix: String = arg.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+            let quoted = arg[prefix.len()..].starts_with(['"', '\'']);
+            if quoted && prefix.to_lowercase().contains('f') {
+                Some("an f-string")
+            } else if arg.contains(".format(") {
+                Some("str.format()")
+            } else if outside_literals(arg, '%') {
+                Some("% formatting")
+            } else if outside_literals(arg, '+') {
+                Some("string concatenation")
+            } else {
+                None
+            }
+        }
+        "go" => {
+            if arg.starts_with("fmt.Sprintf(") {
+                Some("fmt.Sprintf")
+            } else if outside_literals(arg, '+') {
+                Some("string concatenation")
+            } else {
+                None
+            }
+        }
+        "javascript" | "typescript" => {
+            if arg.starts_with('`') && arg.contains("${") {
+                Some("a template literal")
+            } else if outside_literals(arg, '+') {
+                Some("string concatenation")
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Whether `expr` has a string literal and `op` outside of it, as in
+/// `"SELECT ... " + name`
+fn outside_literals(expr: &str, op: char) -> bool {
+    let mut quote = None;
+    let mut has_literal = false;
+    let mut has_op = false;
+    let mut escaped = false;
+    for c in expr.chars() {
+        match quote {
+            Some(q) => {
+                if escaped {
+                    escaped = false;
+                } else if c == '\\' {
+                    escaped = true;
+                } else if c == q {
+                    quote = None;
+                }
+            }
+            None if matches!(c, '"' | '\'' | '`') => {
+                quote = Some(c);
+                has_literal = true;
+            }
+            None if c == op => has_op = true,
+            None => {}
+        }
+    }
+    has_literal && has_op
+}
+
+/// `module` is `root` or one of its submodules (`django.db.models`, `mysql2/promise`)
+fn within(module: &str, root: &str) -> bool {
+    module == root || module.strip_prefix(root).is_some_and(|rest| rest.starts_with(['.', '/']))
+}
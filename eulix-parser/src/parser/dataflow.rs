@@ -0,0 +1,101 @@
+use crate::kb::types::{FunctionCall, Variable};
+
+/// Fill `Variable::used_in` with the callees each variable is passed to,
+/// in call order. Works off the argument text already collected in
+/// `FunctionCall::args`, so it is shared by every language parser: a
+/// variable counts as used when it appears as an identifier in an argument
+/// (`save(data)`, `save(data.id)`, `save(*data)`), but not as an attribute
+/// name (`save(obj.data)`), a keyword name (`save(data=x)`) or inside a
+/// string literal.
+pub fn link_call_arguments(variables: &mut [Variable], calls: &[FunctionCall]) {
+    for call in calls {
+        for arg in &call.args {
+            for ident in argument_identifiers(arg) {
+                if let Some(var) = variables.iter_mut().find(|v| v.name == ident) {
+                    if !var.used_in.contains(&call.callee) {
+                        var.used_in.push(call.callee.clone());
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn argument_identifiers(arg: &str) -> Vec<&str> {
+    // Skip a keyword argument's name: `key=value` but not `a == b`
+    let value = match arg.split_once('=') {
+        Some((key, rest)) if !rest.starts_with('=') && is_identifier(key.trim()) => rest,
+        _ => arg,
+    };
+
+    let bytes = value.as_bytes();
+    let mut identifiers = Vec::new();
+    let mut quote: Option<u8> = None;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let b = bytes[i];
+        if let Some(q) = quote {
+            if b == b'\\' {
+                i += 1;
+            } else if b == q {
+                quote = None;
+            }
+            i += 1;
+        } else if matches!(b, b'"' | b'\'' | b'`') {
+            quote = Some(b);
+            i += 1;
+        } else if b == b'_' || b.is_ascii_alphabetic() {
+            let start = i;
+            while i < bytes.len() && (bytes[i] == b'_' || bytes[i].is_ascii_alphanumeric()) {
+                i += 1;
+            }
+            let after_dot = value[..start].trim_end().ends_with('.');
+            if !after_dot {
+                identifiers.push(&value[start..i]);
+            }
+        } else if b.is_ascii_digit() {
+            // Numeric literals like 1e5 or 0x1f aren't identifiers
+            while i < bytes.len() && (bytes[i] == b'_' || bytes[i].is_ascii_alphanumeric()) {
+                i += 1;
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    identifiers
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars.next().is_some_and(|c| c == '_' || c.is_ascii_alphabetic())
+        && chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::go::GoParser;
+    use crate::parser::python::PythonParser;
+
+    #[test]
+    fn test_variables_record_calls_they_are_passed_to() {
+        let source = "def handle(data, obj):\n    validate(data)\n    log(\"data\", obj.data, data=None)\n    \
+                      save(data.id, *rest)\n    return process(obj)\n";
+        let data = PythonParser::new(source.to_string()).parse().unwrap();
+        let vars = &data.functions[0].variables;
+        let used_in = |name: &str| vars.iter().find(|v| v.name == name).unwrap().used_in.clone();
+
+        assert_eq!(used_in("data"), vec!["validate", "save"]);
+        assert_eq!(used_in("obj"), vec!["log", "process"]);
+
+        let source = "package main\n\nfunc Handle(data []byte) error {\n\tbuf := decode(data)\n\
+                      \tif err := validate(data); err != nil {\n\t\treturn err\n\t}\n\treturn save(buf)\n}\n";
+        let data = GoParser::new(source.to_string()).parse().unwrap();
+        let vars = &data.functions[0].variables;
+        let used_in = |name: &str| vars.iter().find(|v| v.name == name).unwrap().used_in.clone();
+
+        assert_eq!(used_in("data"), vec!["decode", "validate"]);
+        assert_eq!(used_in("buf"), vec!["save"]);
+    }
+}
@@ -0,0 +1,53 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Why a file produced no `FileData`
+#[derive(Debug)]
+pub enum ParseError {
+    /// The source couldn't be read (missing, unreadable, not UTF-8)
+    Io { path: PathBuf, source: std::io::Error },
+    /// The tree-sitter grammar was rejected, e.g. an ABI version mismatch
+    GrammarLoad { language: &'static str, message: String },
+    /// tree-sitter returned no tree at all
+    TreeSitter { language: &'static str },
+    /// There is no parser for this language yet
+    Unsupported { language: &'static str },
+}
+
+impl ParseError {
+    pub fn io(path: &Path, source: std::io::Error) -> Self {
+        ParseError::Io { path: path.to_path_buf(), source }
+    }
+
+    /// Stable name for --errors-json
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ParseError::Io { .. } => "io",
+            ParseError::GrammarLoad { .. } => "grammar_load",
+            ParseError::TreeSitter { .. } => "tree_sitter",
+            ParseError::Unsupported { .. } => "unsupported",
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io { path, source } => write!(f, "Failed to read file {}: {}", path.display(), source),
+            ParseError::GrammarLoad { language, message } => {
+                write!(f, "Failed to load {} grammar: {}", language, message)
+            }
+            ParseError::TreeSitter { language } => write!(f, "Failed to parse {} file", language),
+            ParseError::Unsupported { language } => write!(f, "no {} parser yet", language),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
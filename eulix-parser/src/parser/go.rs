@@ -1,4 +1,7 @@
 use crate::kb::types::*;
+use crate::parser::database::tag_database_usage;
+use crate::parser::dataflow::link_call_arguments;
+use crate::parser::error::ParseError;
 use regex::Regex;
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
@@ -9,36 +12,74 @@ pub struct GoParser {
     source_code: String,
 }
 
+/// Concurrency constructs found in a function body
+#[derive(Default)]
+struct ConcurrencyOps {
+    goroutines: bool,
+    channel_ops: bool,
+}
+
 impl GoParser {
     pub fn new(source_code: String) -> Self {
         // let lines: Vec<String> = source_code.lines().map(|s| s.to_string()).collect();
         Self { source_code }
     }
 
-    pub fn parse(&self) -> Result<FileData, String> {
+    pub fn parse(&self) -> Result<FileData, ParseError> {
         let mut parser = Parser::new();
         parser
             .set_language(tree_sitter_go::language())
-            .map_err(|e| format!("Failed to load Go grammar: {}", e))?;
+            .map_err(|e| ParseError::GrammarLoad { language: "Go", message: e.to_string() })?;
 
         let tree = parser
             .parse(&self.source_code, None)
-            .ok_or_else(|| "Failed to parse Go file".to_string())?;
+            .ok_or(ParseError::TreeSitter { language: "Go" })?;
 
         let root = tree.root_node();
 
+        let imports = self.extract_imports(&root);
+        let mut functions = self.extract_functions(&root);
+        let mut classes = self.extract_structs(&root);
+        let mut security_notes = self.detect_security_patterns();
+        security_notes.extend(tag_database_usage("go", &imports, &mut functions, &mut classes));
+
         Ok(FileData {
             language: "go".to_string(),
             loc: self.count_lines(),
-            imports: self.extract_imports(&root),
-            functions: self.extract_functions(&root),
-            classes: self.extract_structs(&root),
+            imports,
+            functions,
+            classes,
             global_vars: self.extract_global_vars(&root),
             todos: self.extract_todos(),
-            security_notes: self.detect_security_patterns(),
+            security_notes,
+            exports: None,
+            macros: vec![],
+            build_tags: build_constraints(&self.source_code),
+            directives: self.extract_directives(),
+            type_aliases: vec![],
+            module_calls: vec![],
+            function_pointers: vec![],
         })
     }
 
+    /// `//go:` comments (no space after `//`, as the toolchain requires)
+    /// other than `go:build`, which is kept in `build_tags`
+    fn extract_directives(&self) -> Vec<Directive> {
+        self.source_code
+            .lines()
+            .enumerate()
+            .filter_map(|(idx, line)| {
+                let directive = line.trim_start().strip_prefix("//go:")?;
+                let (name, args) = directive.split_once(char::is_whitespace).unwrap_or((directive, ""));
+                (name != "build").then(|| Directive {
+                    name: name.to_string(),
+                    args: args.trim().to_string(),
+                    line: idx + 1,
+                })
+            })
+            .collect()
+    }
+
     fn count_lines(&self) -> usize {
         self.source_code.lines().count()
     }
@@ -69,6 +110,7 @@ impl GoParser {
                                         vec![]
                                     },
                                     import_type: self.classify_import(&path),
+                                    is_wildcard: false,
                                 });
                             }
                         } else if spec_node.kind() == "import_spec_list" {
@@ -84,6 +126,7 @@ impl GoParser {
                                             module: path.clone(),
                                             items: vec![],
                                             import_type: self.classify_import(&path),
+                                            is_wildcard: false,
                                         });
                                     }
                                 }
@@ -139,15 +182,18 @@ impl GoParser {
             .map(|r| self.get_node_text(&r));
 
         let params = self.extract_parameters(node);
-        let return_type = self.extract_return_type(node);
+        let (return_type, return_types) = self.extract_return_types(node);
         let line_start = node.start_position().row + 1;
         let line_end = node.end_position().row + 1;
         let docstring = self.extract_docstring(node);
-        let signature = self.build_signature(&name, &params, &return_type, receiver.as_deref());
+        let type_params = node.child_by_field_name("type_parameters")
+            .map(|tp| self.normalize_type(&tp))
+            .unwrap_or_default();
+        let signature = self.build_signature(&format!("{}{}", name, type_params), &params, &return_type, receiver.as_deref());
 
         let body = node.child_by_field_name("body")?;
         let calls = self.extract_function_calls_detailed(&body);
-        let variables = self.extract_variables(&body, &params);
+        let variables = self.extract_variables(&body, &params, &calls);
         let control_flow = self.build_control_flow(&body);
         let exceptions = self.extract_exception_info(&body);
         let complexity = self.calculate_complexity(&body);
@@ -158,8 +204,20 @@ impl GoParser {
             format!("method_{}_{}", struct_context, name)
         };
 
-        let tags = self.auto_tag_function(&name, &docstring, &calls);
-        let importance_score = self.estimate_importance(&name, receiver.is_some());
+        let mut concurrency = ConcurrencyOps::default();
+        self.find_concurrency(&body, &mut concurrency);
+
+        let mut tags = self.auto_tag_function(&name, &docstring, &calls, &concurrency);
+        if return_types.last().is_some_and(|t| t == "error") {
+            tags.push("returns-error".to_string());
+            tags.sort();
+        }
+        let is_test = receiver.is_none() && is_test_function(&name, &params);
+        if is_test {
+            tags.push("test".to_string());
+            tags.sort();
+        }
+        let importance_score = self.estimate_importance(&name, receiver.is_some(), is_test);
 
         Some(Function {
             id,
@@ -167,6 +225,7 @@ impl GoParser {
             signature,
             params,
             return_type,
+            return_types,
             docstring,
             line_start,
             line_end,
@@ -177,9 +236,13 @@ impl GoParser {
             exceptions,
             complexity,
             is_async: false,
+            needs_refactor: false,
+            spawns_goroutines: concurrency.goroutines,
+            method_kind: MethodKind::Instance,
             decorators: vec![],
             tags,
             importance_score,
+            source: None,
         })
     }
 
@@ -221,12 +284,62 @@ impl GoParser {
         params
     }
 
-    fn extract_return_type(&self, node: &Node) -> String {
-        if let Some(result) = node.child_by_field_name("result") {
-            self.get_node_text(&result)
-        } else {
-            String::new()
+    /// Rendered return type plus one type per returned value:
+    /// `int` -> ["int"], `(int, error)` -> ["int", "error"],
+    /// `(n, m int, err error)` -> ["int", "int", "error"]. The rendered form
+    /// keeps result names and collapses whitespace and line breaks.
+    fn extract_return_types(&self, node: &Node) -> (String, Vec<String>) {
+        let Some(result) = node.child_by_field_name("result") else {
+            return (String::new(), vec![]);
+        };
+
+        if result.kind() != "parameter_list" {
+            let ty = self.normalize_type(&result);
+            return (ty.clone(), vec![ty]);
         }
+
+        let mut rendered = Vec::new();
+        let mut types = Vec::new();
+        let mut cursor = result.walk();
+
+        for decl in result.named_children(&mut cursor) {
+            if !matches!(decl.kind(), "parameter_declaration" | "variadic_parameter_declaration") {
+                continue;
+            }
+            let Some(type_node) = decl.child_by_field_name("type") else {
+                continue;
+            };
+            let ty = self.normalize_type(&type_node);
+
+            let mut name_cursor = decl.walk();
+            let names: Vec<String> = decl
+                .children_by_field_name("name", &mut name_cursor)
+                .map(|n| self.get_node_text(&n))
+                .collect();
+
+            if names.is_empty() {
+                rendered.push(ty.clone());
+                types.push(ty);
+            } else {
+                for name in names {
+                    rendered.push(format!("{} {}", name, ty));
+                    types.push(ty.clone());
+                }
+            }
+        }
+
+        // `(error)` is legal but reads better without the parentheses
+        let rendered = if rendered.len() == 1 && types.len() == 1 && rendered[0] == types[0] {
+            rendered.remove(0)
+        } else {
+            format!("({})", rendered.join(", "))
+        };
+
+        (rendered, types)
+    }
+
+    fn normalize_type(&self, node: &Node) -> String {
+        self.get_node_text(node).split_whitespace().collect::<Vec<_>>().join(" ")
     }
 
     fn build_signature(&self, name: &str, params: &[Parameter], return_type: &str, receiver: Option<&str>) -> String {
@@ -265,6 +378,11 @@ impl GoParser {
         if node.kind() == "call_expression" {
             if let Some(func_node) = node.child_by_field_name("function") {
                 let call_name = self.get_node_text(&func_node);
+                // `db.Query(...)`, `s.db.Query(...)`; the package for `http.Get(...)`
+                let receiver = (func_node.kind() == "selector_expression")
+                    .then(|| func_node.child_by_field_name("operand"))
+                    .flatten()
+                    .map(|operand| self.get_node_text(&operand));
                 let name = call_name
                     .split('.')
                     .last()
@@ -281,11 +399,14 @@ impl GoParser {
 
                         calls.push(FunctionCall {
                             callee: name,
+                            receiver,
                             defined_in: None,
+                            candidates: vec![],
                             line: node.start_position().row + 1,
                             args,
                             is_conditional: context != "unconditional",
                             context: context.to_string(),
+                            is_indirect: false,
                         });
                     }
                 }
@@ -312,7 +433,7 @@ impl GoParser {
         args
     }
 
-    fn extract_variables(&self, node: &Node, params: &[Parameter]) -> Vec<Variable> {
+    fn extract_variables(&self, node: &Node, params: &[Parameter], calls: &[FunctionCall]) -> Vec<Variable> {
         let mut variables: HashMap<String, Variable> = HashMap::new();
 
         for param in params {
@@ -332,7 +453,10 @@ impl GoParser {
         }
 
         self.track_variable_usage(node, &mut variables);
-        variables.into_values().collect()
+
+        let mut variables: Vec<Variable> = variables.into_values().collect();
+        link_call_arguments(&mut variables, calls);
+        variables
     }
 
     fn track_variable_usage(&self, node: &Node, variables: &mut HashMap<String, Variable>) {
@@ -382,9 +506,11 @@ impl GoParser {
     fn build_control_flow(&self, node: &Node) -> ControlFlow {
         let mut control_flow = ControlFlow {
             complexity: self.calculate_complexity(node),
+            cognitive_complexity: self.calculate_cognitive_complexity(node),
             branches: vec![],
             loops: vec![],
             try_blocks: vec![],
+            with_blocks: vec![],
         };
 
         self.extract_control_structures(node, &mut control_flow);
@@ -499,6 +625,7 @@ impl GoParser {
             condition,
             line,
             calls,
+            is_async: false,
         })
     }
 
@@ -531,7 +658,12 @@ impl GoParser {
 
         for child in root.children(&mut cursor) {
             if child.kind() == "type_declaration" {
-                if let Some(spec) = child.child_by_field_name("spec") {
+                // `type ( A struct{}; B struct{} )` groups several specs
+                let mut spec_cursor = child.walk();
+                for spec in child.named_children(&mut spec_cursor) {
+                    if spec.kind() != "type_spec" {
+                        continue;
+                    }
                     if let Some(struct_data) = self.parse_struct(&spec) {
                         structs.push(struct_data);
                     }
@@ -544,12 +676,16 @@ impl GoParser {
         let mut cursor = root.walk();
 
         for child in root.children(&mut cursor) {
-            if child.kind() == "function_declaration" {
+            if child.kind() == "method_declaration" {
                 if let Some(receiver) = child.child_by_field_name("receiver") {
+                    // `(s *Stack[T])` belongs to `Stack`
                     let receiver_text = self.get_node_text(&receiver);
                     let type_name = receiver_text
                         .trim_start_matches('(')
                         .trim_end_matches(')')
+                        .split('[')
+                        .next()
+                        .unwrap_or("")
                         .split_whitespace()
                         .last()
                         .unwrap_or("")
@@ -580,6 +716,10 @@ impl GoParser {
         let line_end = node.end_position().row + 1;
         let docstring = self.extract_docstring(node);
 
+        let type_params = node.child_by_field_name("type_parameters")
+            .map(|tp| self.extract_type_params(&tp))
+            .unwrap_or_default();
+
         let attributes = if let Some(type_node) = node.child_by_field_name("type") {
             if type_node.kind() == "struct_type" {
                 self.extract_struct_fields(&type_node)
@@ -600,9 +740,31 @@ impl GoParser {
             methods: vec![],
             attributes,
             decorators: vec![],
+            type_params,
         })
     }
 
+    /// One `name constraint` entry per type parameter: `[K comparable, V any]`
+    /// and `[K, V any]` give `["K comparable", "V any"]` and `["K any", "V any"]`
+    fn extract_type_params(&self, list: &Node) -> Vec<String> {
+        let mut type_params = Vec::new();
+        let mut cursor = list.walk();
+
+        for decl in list.named_children(&mut cursor) {
+            let Some(constraint) = decl.child_by_field_name("type") else {
+                continue;
+            };
+            let constraint = self.normalize_type(&constraint);
+
+            let mut name_cursor = decl.walk();
+            for name in decl.children_by_field_name("name", &mut name_cursor) {
+                type_params.push(format!("{} {}", self.get_node_text(&name), constraint));
+            }
+        }
+
+        type_params
+    }
+
     fn extract_struct_fields(&self, struct_node: &Node) -> Vec<Attribute> {
         let mut fields = Vec::new();
 
@@ -716,6 +878,76 @@ impl GoParser {
         complexity
     }
 
+    /// Cognitive complexity: like cyclomatic complexity, but nested control
+    /// structures cost more the deeper they sit
+    fn calculate_cognitive_complexity(&self, node: &Node) -> usize {
+        fn operator(node: &Node) -> Option<&'static str> {
+            node.child_by_field_name("operator").map(|op| op.kind())
+        }
+
+        fn continues_sequence(node: &Node) -> bool {
+            node.parent()
+                .map(|p| p.kind() == "binary_expression" && operator(&p) == operator(node))
+                .unwrap_or(false)
+        }
+
+        fn is_logical(node: &Node) -> bool {
+            matches!(operator(node), Some("&&") | Some("||"))
+        }
+
+        fn is_else_branch(node: &Node) -> bool {
+            node.parent()
+                .filter(|p| p.kind() == "if_statement")
+                .and_then(|p| p.child_by_field_name("alternative"))
+                .map(|alt| alt.id() == node.id())
+                .unwrap_or(false)
+        }
+
+        fn walk(node: &Node, nesting: usize) -> usize {
+            let mut score = 0;
+            let mut child_nesting = nesting;
+
+            match node.kind() {
+                "if_statement" => {
+                    // `else if` is a flat increment, it doesn't pay for nesting again
+                    if is_else_branch(node) {
+                        score += 1;
+                    } else {
+                        score += 1 + nesting;
+                        child_nesting += 1;
+                    }
+                }
+                "for_statement" | "expression_switch_statement" |
+                "type_switch_statement" | "select_statement" => {
+                    score += 1 + nesting;
+                    child_nesting += 1;
+                }
+                "block" if is_else_branch(node) => {
+                    score += 1;
+                }
+                "func_literal" => {
+                    child_nesting += 1;
+                }
+                // Each run of the same logical operator counts once
+                "binary_expression" if is_logical(node) && !continues_sequence(node) => {
+                    score += 1;
+                }
+                _ => {}
+            }
+
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                score += walk(&child, child_nesting);
+            }
+
+            score
+        }
+
+        // Start from the children so the function itself doesn't count as nesting
+        let mut cursor = node.walk();
+        node.children(&mut cursor).map(|child| walk(&child, 0)).sum()
+    }
+
     fn extract_todos(&self) -> Vec<Todo> {
         let re = Regex::new(r"//\s*TODO:?\s*(.+)").unwrap();
 
@@ -772,7 +1004,25 @@ impl GoParser {
         notes
     }
 
-    fn auto_tag_function(&self, name: &str, docstring: &str, calls: &[FunctionCall]) -> Vec<String> {
+    /// Goroutine launches and channel sends/receives/selects anywhere in the
+    /// body, including inside closures
+    fn find_concurrency(&self, node: &Node, ops: &mut ConcurrencyOps) {
+        match node.kind() {
+            "go_statement" => ops.goroutines = true,
+            "send_statement" | "select_statement" => ops.channel_ops = true,
+            "unary_expression" if node.child_by_field_name("operator").is_some_and(|op| op.kind() == "<-") => {
+                ops.channel_ops = true;
+            }
+            _ => {}
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            self.find_concurrency(&child, ops);
+        }
+    }
+
+    fn auto_tag_function(&self, name: &str, docstring: &str, calls: &[FunctionCall], concurrency: &ConcurrencyOps) -> Vec<String> {
         let mut tags = Vec::new();
         let name_lower = name.to_lowercase();
         let doc_lower = docstring.to_lowercase();
@@ -817,15 +1067,6 @@ impl GoParser {
             tags.push("http-handler".to_string());
         }
 
-        // Database
-        if name_lower.contains("db") || name_lower.contains("database") ||
-            name_lower.contains("query") || name_lower.contains("select") ||
-            name_lower.contains("insert") || name_lower.contains("update") ||
-            name_lower.contains("delete") || name_lower.contains("save") ||
-            name_lower.contains("find") {
-            tags.push("database".to_string());
-        }
-
         // Validation
         if name_lower.contains("validate") || name_lower.contains("check") ||
             name_lower.contains("verify") || name_lower.contains("sanitize") {
@@ -885,14 +1126,13 @@ impl GoParser {
         }
 
         // Goroutines/Concurrency
-        if calls.iter().any(|c| c.callee.contains("Go") || c.callee == "go") {
+        if concurrency.goroutines {
             tags.push("concurrent".to_string());
             tags.push("goroutine".to_string());
         }
 
         // Channels
-        if calls.iter().any(|c| c.callee.contains("chan") || c.callee.contains("channel")) ||
-            name_lower.contains("channel") {
+        if concurrency.channel_ops || name_lower.contains("channel") {
             tags.push("channels".to_string());
             tags.push("concurrent".to_string());
         }
@@ -929,7 +1169,7 @@ impl GoParser {
         tags
     }
 
-    fn estimate_importance(&self, name: &str, is_method: bool) -> f32 {
+    fn estimate_importance(&self, name: &str, is_method: bool, is_test: bool) -> f32 {
         let mut score: f32 = 0.5;
 
         if name == "main" {
@@ -944,6 +1184,10 @@ impl GoParser {
             score += 0.1;
         }
 
+        if is_test {
+            score -= 0.3;
+        }
+
         score.max(0.0).min(1.0)
     }
 
@@ -954,9 +1198,129 @@ impl GoParser {
     }
 }
 
-pub fn parse_file(path: &Path) -> Result<(String, FileData), String> {
+/// What `go test` runs: `TestXxx(t *testing.T)` and `BenchmarkXxx(b *testing.B)`,
+/// where the suffix doesn't start with a lowercase letter
+fn is_test_function(name: &str, params: &[Parameter]) -> bool {
+    let harness = [("Test", "*testing.T"), ("Benchmark", "*testing.B")];
+    harness.iter().any(|(prefix, param_type)| {
+        name.strip_prefix(prefix)
+            .is_some_and(|rest| !rest.starts_with(|c: char| c.is_lowercase()))
+            && params.len() == 1
+            && params[0].type_annotation.replace(' ', "") == *param_type
+    })
+}
+
+/// Build constraints from the header comments above the `package` clause.
+/// A `//go:build` line wins; otherwise each legacy `// +build` line becomes
+/// one expression (spaces are OR, commas AND).
+pub fn build_constraints(source: &str) -> Vec<String> {
+    let mut go_build = Vec::new();
+    let mut plus_build = Vec::new();
+
+    for line in source.lines().map(str::trim) {
+        if line.starts_with("package ") {
+            break;
+        }
+        if let Some(expr) = line.strip_prefix("//go:build ") {
+            go_build.push(expr.trim().to_string());
+        } else if let Some(terms) = line.strip_prefix("// +build ") {
+            let any: Vec<&str> = terms.split_whitespace().collect();
+            let all = |term: &str| term.split(',').collect::<Vec<_>>().join(" && ");
+            plus_build.push(match any.as_slice() {
+                [single] => all(single),
+                _ => any
+                    .iter()
+                    .map(|term| if term.contains(',') { format!("({})", all(term)) } else { term.to_string() })
+                    .collect::<Vec<_>>()
+                    .join(" || "),
+            });
+        }
+    }
+
+    if go_build.is_empty() { plus_build } else { go_build }
+}
+
+/// Whether every constraint holds when exactly `tags` (plus the `go1.N`
+/// release tags) are set. Expressions that don't parse count as satisfied,
+/// so a file is never dropped because of syntax this doesn't understand.
+pub fn build_tags_satisfied(constraints: &[String], tags: &[String]) -> bool {
+    let is_set = |tag: &str| tag.starts_with("go1.") || tags.iter().any(|t| t == tag);
+    constraints
+        .iter()
+        .all(|expr| ConstraintExpr::new(expr).eval(&is_set).unwrap_or(true))
+}
+
+/// Recursive-descent evaluator for `//go:build` expressions:
+/// `||` binds loosest, then `&&`, then `!`, with parentheses for grouping
+struct ConstraintExpr<'a> {
+    tokens: Vec<&'a str>,
+    pos: usize,
+}
+
+impl<'a> ConstraintExpr<'a> {
+    fn new(expr: &'a str) -> Self {
+        let mut tokens = Vec::new();
+        let mut rest = expr.trim_start();
+        while !rest.is_empty() {
+            let len = if rest.starts_with("&&") || rest.starts_with("||") {
+                2
+            } else if rest.starts_with(['!', '(', ')']) {
+                1
+            } else {
+                rest.find(|c: char| c.is_whitespace() || "!()&|".contains(c)).unwrap_or(rest.len()).max(1)
+            };
+            tokens.push(&rest[..len]);
+            rest = rest[len..].trim_start();
+        }
+        Self { tokens, pos: 0 }
+    }
+
+    fn eval(mut self, is_set: &dyn Fn(&str) -> bool) -> Option<bool> {
+        let value = self.or(is_set)?;
+        (self.pos == self.tokens.len()).then_some(value)
+    }
+
+    fn next(&mut self) -> Option<&'a str> {
+        let token = self.tokens.get(self.pos).copied();
+        self.pos += 1;
+        token
+    }
+
+    fn or(&mut self, is_set: &dyn Fn(&str) -> bool) -> Option<bool> {
+        let mut value = self.and(is_set)?;
+        while self.tokens.get(self.pos) == Some(&"||") {
+            self.pos += 1;
+            value |= self.and(is_set)?;
+        }
+        Some(value)
+    }
+
+    fn and(&mut self, is_set: &dyn Fn(&str) -> bool) -> Option<bool> {
+        let mut value = self.unary(is_set)?;
+        while self.tokens.get(self.pos) == Some(&"&&") {
+            self.pos += 1;
+            value &= self.unary(is_set)?;
+        }
+        Some(value)
+    }
+
+    fn unary(&mut self, is_set: &dyn Fn(&str) -> bool) -> Option<bool> {
+        match self.next()? {
+            "!" => self.unary(is_set).map(|v| !v),
+            "(" => {
+                let value = self.or(is_set)?;
+                (self.next()? == ")").then_some(value)
+            }
+            tag if tag.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.') => Some(is_set(tag)),
+            _ => None,
+        }
+    }
+}
+
+pub fn parse_file(path: &Path) -> Result<(String, FileData), ParseError> {
     let source_code = std::fs::read_to_string(path)
-        .map_err(|e| format!("Failed to read file {}: {}", path.display(), e))?;
+        .map_err(|e| ParseError::io(path, e))?
+        .replace("\r\n", "\n");
 
     let parser = GoParser::new(source_code);
     let file_data = parser.parse()?;
@@ -965,3 +1329,187 @@ pub fn parse_file(path: &Path) -> Result<(String, FileData), String> {
 
     Ok((relative_path, file_data))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> FileData {
+        GoParser::new(source.to_string()).parse().unwrap()
+    }
+
+    fn function<'a>(data: &'a FileData, name: &str) -> &'a Function {
+        data.functions.iter().find(|f| f.name == name).unwrap()
+    }
+
+    #[test]
+    fn test_build_constraints_and_directives() {
+        let data = parse(
+            "//go:build ignore\n\n// Generates the lookup tables.\npackage main\n\n\
+             //go:generate stringer -type=Pill\n//go:embed templates/*.tmpl\nvar templates string\n\n\
+             func main() {}\n",
+        );
+
+        assert_eq!(data.build_tags, vec!["ignore"]);
+        let directives: Vec<(&str, &str, usize)> = data.directives
+            .iter()
+            .map(|d| (d.name.as_str(), d.args.as_str(), d.line))
+            .collect();
+        assert_eq!(directives, vec![("generate", "stringer -type=Pill", 6), ("embed", "templates/*.tmpl", 7)]);
+
+        let tags = |list: &[&str]| list.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+        assert!(!build_tags_satisfied(&data.build_tags, &tags(&["linux", "amd64"])));
+        assert!(build_tags_satisfied(&data.build_tags, &tags(&["ignore"])));
+
+        let constraints = tags(&["linux && (amd64 || arm64) && !cgo"]);
+        assert!(build_tags_satisfied(&constraints, &tags(&["linux", "arm64"])));
+        assert!(!build_tags_satisfied(&constraints, &tags(&["linux", "arm64", "cgo"])));
+        assert!(!build_tags_satisfied(&constraints, &tags(&["darwin", "amd64"])));
+        assert!(build_tags_satisfied(&tags(&["go1.21"]), &[]));
+
+        // Legacy lines: spaces are OR, commas AND
+        let legacy = build_constraints("// +build linux,amd64 darwin\n\npackage x\n");
+        assert_eq!(legacy, vec!["(linux && amd64) || darwin"]);
+        assert!(build_tags_satisfied(&legacy, &tags(&["darwin"])));
+    }
+
+    #[test]
+    fn test_single_return() {
+        let data = parse("package main\n\nfunc count(items []string) int {\n\treturn len(items)\n}\n");
+        let count = function(&data, "count");
+
+        assert_eq!(count.return_type, "int");
+        assert_eq!(count.return_types, vec!["int"]);
+        assert_eq!(count.signature, "func count(items []string) int");
+        assert!(!count.tags.contains(&"returns-error".to_string()));
+    }
+
+    #[test]
+    fn test_tuple_return() {
+        let data = parse(
+            "package main\n\nfunc load(path string) (*Config,\n\terror) {\n\treturn nil, nil\n}\n\n\
+             func check() (error) {\n\treturn nil\n}\n",
+        );
+
+        let load = function(&data, "load");
+        assert_eq!(load.return_type, "(*Config, error)");
+        assert_eq!(load.return_types, vec!["*Config", "error"]);
+        assert!(load.tags.contains(&"returns-error".to_string()));
+
+        let check = function(&data, "check");
+        assert_eq!(check.return_type, "error");
+        assert_eq!(check.return_types, vec!["error"]);
+    }
+
+    #[test]
+    fn test_named_results() {
+        let data = parse(
+            "package main\n\nfunc split(sum int) (x, y int, err error) {\n\treturn\n}\n",
+        );
+        let split = function(&data, "split");
+
+        assert_eq!(split.return_type, "(x int, y int, err error)");
+        assert_eq!(split.return_types, vec!["int", "int", "error"]);
+        assert_eq!(split.signature, "func split(sum int) (x int, y int, err error)");
+        assert!(split.tags.contains(&"returns-error".to_string()));
+    }
+
+    #[test]
+    fn test_goroutines_and_channels() {
+        let data = parse(
+            "package main\n\n\
+             func start(jobs chan int) {\n\tgo worker(jobs)\n\tjobs <- 1\n}\n\n\
+             func drain(results chan int) int {\n\treturn <-results\n}\n\n\
+             func GoHome() {\n\tGoAway()\n}\n",
+        );
+
+        let start = function(&data, "start");
+        assert!(start.spawns_goroutines);
+        assert!(start.tags.contains(&"concurrent".to_string()));
+        assert!(start.tags.contains(&"goroutine".to_string()));
+        assert!(start.tags.contains(&"channels".to_string()));
+
+        let drain = function(&data, "drain");
+        assert!(!drain.spawns_goroutines);
+        assert!(drain.tags.contains(&"channels".to_string()));
+        assert!(drain.tags.contains(&"concurrent".to_string()));
+
+        // Calls that merely contain "Go" aren't goroutines
+        let home = function(&data, "GoHome");
+        assert!(!home.spawns_goroutines);
+        assert!(!home.tags.contains(&"concurrent".to_string()));
+    }
+
+    #[test]
+    fn test_generic_type_parameters() {
+        let data = parse(
+            "package main\n\nfunc Map[T any, U any](items []T, f func(T) U) []U {\n\treturn nil\n}\n\n\
+             type Cache[K comparable, V any] struct {\n\titems map[K]V\n}\n\n\
+             type Pair[A, B any] struct{}\n\n\
+             func (c *Cache[K, V]) Get(key K) V {\n\treturn c.items[key]\n}\n",
+        );
+
+        assert_eq!(
+            function(&data, "Map").signature,
+            "func Map[T any, U any](items []T, f func(T) U) []U"
+        );
+
+        let class = |name: &str| data.classes.iter().find(|c| c.name == name).unwrap();
+        assert_eq!(class("Cache").type_params, vec!["K comparable", "V any"]);
+        assert_eq!(class("Pair").type_params, vec!["A any", "B any"]);
+        assert_eq!(class("Cache").methods[0].id, "method_Cache_Get");
+    }
+
+    #[test]
+    fn test_database_tag_needs_database_sql() {
+        let data = parse(
+            "package store\n\nimport \"database/sql\"\n\n\
+             func QueryParams(values map[string]string) string {\n\treturn values[\"q\"]\n}\n\n\
+             func FindUser(db *sql.DB, name string) error {\n\
+             \t_, err := db.Query(\"SELECT * FROM users WHERE name = '\" + name + \"'\")\n\treturn err\n}\n",
+        );
+
+        assert!(!function(&data, "QueryParams").tags.contains(&"database".to_string()));
+        assert!(function(&data, "FindUser").tags.contains(&"database".to_string()));
+        let risk = data.security_notes.iter().find(|n| n.note_type == "sql_injection_risk").unwrap();
+        assert_eq!(risk.line, 10);
+        assert!(risk.description.contains("concatenation"));
+
+        // Generic names need a connection receiver, even next to the import
+        let data = parse(
+            "package store\n\nimport (\n\t\"database/sql\"\n\t\"net/http\"\n)\n\n\
+             func Fetch(base string) {\n\thttp.Get(base + \"/users\")\n}\n\n\
+             func Count(s *Store) {\n\ts.db.QueryRow(\"SELECT count(*) FROM users\").Scan(&n)\n}\n",
+        );
+        assert!(!function(&data, "Fetch").tags.contains(&"database".to_string()));
+        assert!(function(&data, "Count").tags.contains(&"database".to_string()));
+        assert!(data.security_notes.iter().all(|n| n.note_type != "sql_injection_risk"));
+
+        // Without the import the same call is just a method named Query
+        let data = parse(
+            "package store\n\nfunc FindUser(c Client, name string) {\n\tc.Query(\"users\" + name)\n}\n",
+        );
+        assert!(!function(&data, "FindUser").tags.contains(&"database".to_string()));
+        assert!(data.security_notes.iter().all(|n| n.note_type != "sql_injection_risk"));
+    }
+
+    #[test]
+    fn test_test_functions_are_tagged() {
+        let data = parse(
+            "package auth\n\nimport \"testing\"\n\n\
+             func Login(user string) bool {\n\treturn user != \"\"\n}\n\n\
+             func TestLogin(t *testing.T) {\n\tLogin(\"a\")\n}\n\n\
+             func BenchmarkLogin(b *testing.B) {\n\tLogin(\"a\")\n}\n\n\
+             func Testimony(t *testing.T) {}\n\n\
+             func TestHelper(name string) {}\n",
+        );
+
+        let test = function(&data, "TestLogin");
+        assert!(test.tags.contains(&"test".to_string()));
+        assert!(test.importance_score < function(&data, "Login").importance_score);
+        assert!(function(&data, "BenchmarkLogin").tags.contains(&"test".to_string()));
+        assert!(!function(&data, "Login").tags.contains(&"test".to_string()));
+        assert!(!function(&data, "Testimony").tags.contains(&"test".to_string()));
+        assert!(!function(&data, "TestHelper").tags.contains(&"test".to_string()));
+    }
+}
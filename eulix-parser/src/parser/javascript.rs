@@ -0,0 +1,1527 @@
+use crate::kb::types::*;
+use crate::parser::database::tag_database_usage;
+use crate::parser::error::ParseError;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use tree_sitter::{Language, Node, Parser};
+
+/// Parser for JavaScript and, via `typescript.rs`, TypeScript.
+///
+/// The TypeScript grammar is a superset of the JavaScript one, so TS-only
+/// nodes (type annotations, interfaces, `import type`) are handled here and
+/// simply never show up in plain JavaScript trees.
+pub struct JavaScriptParser {
+    source_code: String,
+    grammar: Language,
+    language: &'static str,
+}
+
+impl JavaScriptParser {
+    pub fn new(source_code: String) -> Self {
+        Self::with_grammar(source_code, tree_sitter_javascript::language(), "javascript")
+    }
+
+    pub fn with_grammar(source_code: String, grammar: Language, language: &'static str) -> Self {
+        Self { source_code, grammar, language }
+    }
+
+    pub fn parse(&self) -> Result<FileData, ParseError> {
+        let mut parser = Parser::new();
+        parser
+            .set_language(self.grammar)
+            .map_err(|e| ParseError::GrammarLoad { language: self.language, message: e.to_string() })?;
+
+        let tree = parser
+            .parse(&self.source_code, None)
+            .ok_or(ParseError::TreeSitter { language: self.language })?;
+
+        let root = tree.root_node();
+
+        let imports = self.extract_imports(&root);
+        let mut functions = self.extract_functions(&root);
+        let mut classes = self.extract_classes(&root);
+        let mut security_notes = self.detect_security_patterns();
+        security_notes.extend(tag_database_usage(self.language, &imports, &mut functions, &mut classes));
+
+        Ok(FileData {
+            language: self.language.to_string(),
+            loc: self.count_lines(),
+            imports,
+            functions,
+            classes,
+            global_vars: self.extract_global_vars(&root),
+            todos: self.extract_todos(),
+            security_notes,
+            exports: None,
+            macros: vec![],
+            build_tags: vec![],
+            directives: vec![],
+            type_aliases: vec![],
+            module_calls: vec![],
+            function_pointers: vec![],
+        })
+    }
+
+    fn count_lines(&self) -> usize {
+        self.source_code.lines().count()
+    }
+
+    /// Top-level statements, looking through `export` wrappers
+    fn top_level_statements<'a>(&self, root: &Node<'a>) -> Vec<(Node<'a>, Node<'a>)> {
+        let mut statements = Vec::new();
+        let mut cursor = root.walk();
+
+        for child in root.children(&mut cursor) {
+            if child.kind() == "export_statement" {
+                if let Some(decl) = child.child_by_field_name("declaration") {
+                    statements.push((decl, child));
+                }
+            } else {
+                statements.push((child, child));
+            }
+        }
+
+        statements
+    }
+
+    fn extract_imports(&self, root: &Node) -> Vec<Import> {
+        let mut imports = Vec::new();
+        let mut cursor = root.walk();
+
+        for child in root.children(&mut cursor) {
+            match child.kind() {
+                "import_statement" => {
+                    if let Some(import) = self.parse_import_statement(&child) {
+                        imports.push(import);
+                    }
+                }
+                "lexical_declaration" | "variable_declaration" => {
+                    let mut decl_cursor = child.walk();
+                    for declarator in child.children(&mut decl_cursor) {
+                        if declarator.kind() != "variable_declarator" {
+                            continue;
+                        }
+                        let Some(value) = declarator.child_by_field_name("value") else {
+                            continue;
+                        };
+                        if let Some(module) = self.require_target(&value) {
+                            let items = declarator
+                                .child_by_field_name("name")
+                                .map(|n| self.pattern_names(&n))
+                                .unwrap_or_default();
+
+                            imports.push(Import {
+                                import_type: self.classify_import(&module),
+                                module,
+                                items,
+                                is_wildcard: false,
+                            });
+                        }
+                    }
+                }
+                "expression_statement" => {
+                    // Side-effect require: require('./polyfills')
+                    if let Some(expr) = child.named_child(0) {
+                        if let Some(module) = self.require_target(&expr) {
+                            imports.push(Import {
+                                import_type: self.classify_import(&module),
+                                module,
+                                items: vec![],
+                                is_wildcard: false,
+                            });
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        imports
+    }
+
+    fn parse_import_statement(&self, node: &Node) -> Option<Import> {
+        let source = node.child_by_field_name("source")?;
+        let module = self.string_value(&source);
+        let mut items = Vec::new();
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            if child.kind() != "import_clause" {
+                continue;
+            }
+
+            let mut clause_cursor = child.walk();
+            for part in child.children(&mut clause_cursor) {
+                match part.kind() {
+                    // Default import
+                    "identifier" => items.push(self.get_node_text(&part)),
+                    "named_imports" => {
+                        let mut named_cursor = part.walk();
+                        for spec in part.children(&mut named_cursor) {
+                            if spec.kind() == "import_specifier" {
+                                if let Some(name) = spec.child_by_field_name("name") {
+                                    items.push(self.string_value(&name));
+                                }
+                            }
+                        }
+                    }
+                    "namespace_import" => items.push("*".to_string()),
+                    _ => {}
+                }
+            }
+        }
+
+        // `import type { Foo }` is erased at compile time
+        let import_type = if self.has_child_kind(node, "type") {
+            "type".to_string()
+        } else {
+            self.classify_import(&module)
+        };
+
+        Some(Import {
+            import_type,
+            module,
+            items,
+            is_wildcard: false,
+        })
+    }
+
+    /// Module name if `node` is a `require('module')` call
+    fn require_target(&self, node: &Node) -> Option<String> {
+        if node.kind() != "call_expression" {
+            return None;
+        }
+
+        let func = node.child_by_field_name("function")?;
+        if self.get_node_text(&func) != "require" {
+            return None;
+        }
+
+        let args = node.child_by_field_name("arguments")?;
+        let first = args.named_child(0)?;
+        if first.kind() == "string" {
+            Some(self.string_value(&first))
+        } else {
+            None
+        }
+    }
+
+    /// Names bound by a declarator pattern: `x`, `{ a, b: c }`, `[a, b]`
+    fn pattern_names(&self, node: &Node) -> Vec<String> {
+        match node.kind() {
+            "identifier" => vec![self.get_node_text(node)],
+            "object_pattern" | "array_pattern" => {
+                let mut names = Vec::new();
+                let mut cursor = node.walk();
+                for child in node.named_children(&mut cursor) {
+                    match child.kind() {
+                        "shorthand_property_identifier_pattern" | "identifier" => {
+                            names.push(self.get_node_text(&child));
+                        }
+                        "pair_pattern" => {
+                            if let Some(key) = child.child_by_field_name("key") {
+                                names.push(self.get_node_text(&key));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                names
+            }
+            _ => vec![],
+        }
+    }
+
+    fn classify_import(&self, module: &str) -> String {
+        // Node.js builtin modules (common ones)
+        let stdlib = [
+            "fs", "path", "http", "https", "os", "crypto", "util", "events",
+            "stream", "child_process", "url", "querystring", "assert", "buffer",
+            "zlib", "net", "readline", "worker_threads", "cluster", "dns",
+        ];
+
+        let base = module.split('/').next().unwrap_or(module);
+        if module.starts_with("node:") || stdlib.contains(&base) {
+            "stdlib".to_string()
+        } else if module.starts_with('.') || module.starts_with('/') {
+            "internal".to_string()
+        } else {
+            "external".to_string()
+        }
+    }
+
+    fn extract_functions(&self, root: &Node) -> Vec<Function> {
+        let mut functions = Vec::new();
+
+        for (node, outer) in self.top_level_statements(root) {
+            match node.kind() {
+                "function_declaration" | "generator_function_declaration" => {
+                    if let Some(name_node) = node.child_by_field_name("name") {
+                        let name = self.get_node_text(&name_node);
+                        if let Some(func) = self.parse_function(&node, &name, "", &outer) {
+                            functions.push(func);
+                        }
+                    }
+                }
+                "lexical_declaration" | "variable_declaration" => {
+                    // const handler = async (req, res) => { ... }
+                    let mut cursor = node.walk();
+                    for declarator in node.children(&mut cursor) {
+                        if declarator.kind() != "variable_declarator" {
+                            continue;
+                        }
+                        let (Some(name_node), Some(value)) = (
+                            declarator.child_by_field_name("name"),
+                            declarator.child_by_field_name("value"),
+                        ) else {
+                            continue;
+                        };
+
+                        if name_node.kind() == "identifier" && self.is_function_value(&value) {
+                            let name = self.get_node_text(&name_node);
+                            if let Some(func) = self.parse_function(&value, &name, "", &outer) {
+                                functions.push(func);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        functions
+    }
+
+    fn is_function_value(&self, node: &Node) -> bool {
+        matches!(
+            node.kind(),
+            "arrow_function" | "function" | "function_expression" | "generator_function"
+        )
+    }
+
+    /// Parse a function-like node. `outer` is the statement that carries the
+    /// doc comment and line range (the declaration for arrow functions).
+    fn parse_function(&self, node: &Node, name: &str, class_context: &str, outer: &Node) -> Option<Function> {
+        if name.is_empty() {
+            return None;
+        }
+
+        let is_async = self.has_child_kind(node, "async");
+        let decorators = self.extract_decorators(node);
+
+        let params = self.extract_parameters(node);
+        let return_type = self.type_annotation(node, "return_type");
+        let line_start = outer.start_position().row + 1;
+        let line_end = outer.end_position().row + 1;
+        let docstring = self.extract_docstring(outer);
+        let signature = self.build_signature(name, &params, &return_type, is_async, !class_context.is_empty());
+
+        let body = node.child_by_field_name("body")?;
+        let calls = self.extract_function_calls_detailed(&body);
+        let variables = self.extract_variables(&body, &params);
+        let control_flow = self.build_control_flow(&body);
+        let exceptions = self.extract_exception_info(&body);
+        let complexity = self.calculate_complexity(&body);
+
+        let id = if class_context.is_empty() {
+            format!("func_{}", name)
+        } else {
+            format!("method_{}_{}", class_context, name)
+        };
+
+        let tags = self.auto_tag_function(name, &docstring, &calls, is_async);
+        let importance_score = self.estimate_importance(name, outer.kind() == "export_statement");
+
+        Some(Function {
+            id,
+            name: name.to_string(),
+            signature,
+            params,
+            return_type,
+            return_types: vec![],
+            docstring,
+            line_start,
+            line_end,
+            calls,
+            called_by: vec![], // Will be populated during post-processing
+            variables,
+            control_flow,
+            exceptions,
+            complexity,
+            is_async,
+            needs_refactor: false,
+            spawns_goroutines: false,
+            method_kind: MethodKind::Instance,
+            decorators,
+            tags,
+            importance_score,
+            source: None,
+        })
+    }
+
+    fn has_child_kind(&self, node: &Node, kind: &str) -> bool {
+        let mut cursor = node.walk();
+        let found = node.children(&mut cursor).any(|c| c.kind() == kind);
+        found
+    }
+
+    fn extract_decorators(&self, node: &Node) -> Vec<String> {
+        let mut decorators = Vec::new();
+        let mut cursor = node.walk();
+
+        for child in node.children(&mut cursor) {
+            if child.kind() == "decorator" {
+                decorators.push(self.get_node_text(&child));
+            }
+        }
+
+        decorators
+    }
+
+    fn extract_parameters(&self, node: &Node) -> Vec<Parameter> {
+        let mut params = Vec::new();
+
+        // Single unparenthesized arrow parameter: x => x * 2
+        if let Some(param) = node.child_by_field_name("parameter") {
+            params.push(Parameter {
+                name: self.get_node_text(&param),
+                type_annotation: String::new(),
+                default_value: None,
+            });
+            return params;
+        }
+
+        if let Some(param_list) = node.child_by_field_name("parameters") {
+            let mut cursor = param_list.walk();
+            for child in param_list.named_children(&mut cursor) {
+                match child.kind() {
+                    // TypeScript: `id: string`, `limit?: number = 10`
+                    "required_parameter" | "optional_parameter" => {
+                        let name = child.child_by_field_name("pattern")
+                            .or_else(|| child.child_by_field_name("name"))
+                            .map(|n| self.get_node_text(&n))
+                            .unwrap_or_default();
+
+                        params.push(Parameter {
+                            name,
+                            type_annotation: self.type_annotation(&child, "type"),
+                            default_value: child.child_by_field_name("value")
+                                .map(|n| self.get_node_text(&n)),
+                        });
+                    }
+                    "assignment_pattern" => {
+                        let name = child.child_by_field_name("left")
+                            .map(|n| self.get_node_text(&n))
+                            .unwrap_or_default();
+                        let default_value = child.child_by_field_name("right")
+                            .map(|n| self.get_node_text(&n));
+
+                        params.push(Parameter {
+                            name,
+                            type_annotation: String::new(),
+                            default_value,
+                        });
+                    }
+                    "comment" => {}
+                    _ => {
+                        params.push(Parameter {
+                            name: self.get_node_text(&child),
+                            type_annotation: String::new(),
+                            default_value: None,
+                        });
+                    }
+                }
+            }
+        }
+
+        params
+    }
+
+    /// Type text from a TS `type_annotation` field, without the leading colon
+    fn type_annotation(&self, node: &Node, field: &str) -> String {
+        node.child_by_field_name(field)
+            .map(|t| self.get_node_text(&t).trim_start_matches(':').trim().to_string())
+            .unwrap_or_default()
+    }
+
+    fn build_signature(&self, name: &str, params: &[Parameter], return_type: &str, is_async: bool, is_method: bool) -> String {
+        let async_prefix = if is_async { "async " } else { "" };
+        let param_str = params
+            .iter()
+            .map(|p| {
+                let mut param = p.name.clone();
+                if !p.type_annotation.is_empty() {
+                    param.push_str(&format!(": {}", p.type_annotation));
+                }
+                if let Some(default) = &p.default_value {
+                    param.push_str(&format!(" = {}", default));
+                }
+                param
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let return_str = if return_type.is_empty() {
+            String::new()
+        } else {
+            format!(": {}", return_type)
+        };
+
+        if is_method {
+            format!("{}{}({}){}", async_prefix, name, param_str, return_str)
+        } else {
+            format!("{}function {}({}){}", async_prefix, name, param_str, return_str)
+        }
+    }
+
+    fn extract_function_calls_detailed(&self, node: &Node) -> Vec<FunctionCall> {
+        let mut calls = Vec::new();
+        let mut seen = HashSet::new();
+        self.find_calls_recursive(node, &mut calls, &mut seen, "unconditional");
+        calls
+    }
+
+    fn find_calls_recursive(&self, node: &Node, calls: &mut Vec<FunctionCall>, seen: &mut HashSet<String>, context: &str) {
+        let mut cursor = node.walk();
+
+        let child_context = match node.kind() {
+            "if_statement" => "if",
+            "else_clause" => "else",
+            "for_statement" | "for_in_statement" | "while_statement" | "do_statement" => "loop",
+            "switch_statement" => "switch",
+            "try_statement" => "try",
+            "catch_clause" => "catch",
+            _ => context,
+        };
+
+        let callee_node = match node.kind() {
+            "call_expression" => node.child_by_field_name("function"),
+            "new_expression" => node.child_by_field_name("constructor"),
+            _ => None,
+        };
+
+        if let Some(func_node) = callee_node {
+            let name = self.callee_name(&func_node);
+            // `pool.query(...)`, `this.db.findOne(...)`
+            let receiver = (func_node.kind() == "member_expression")
+                .then(|| func_node.child_by_field_name("object"))
+                .flatten()
+                .map(|object| self.get_node_text(&object));
+
+            if !name.is_empty() {
+                let key = format!("{}:{}", name, node.start_position().row);
+                if !seen.contains(&key) {
+                    seen.insert(key);
+
+                    let args = self.extract_call_arguments(node);
+
+                    calls.push(FunctionCall {
+                        callee: name,
+                        receiver,
+                        defined_in: None, // Will be resolved in post-processing
+                        candidates: vec![],
+                        line: node.start_position().row + 1,
+                        args,
+                        is_conditional: context != "unconditional",
+                        context: context.to_string(),
+                        is_indirect: false,
+                    });
+                }
+            }
+        }
+
+        for child in node.children(&mut cursor) {
+            self.find_calls_recursive(&child, calls, seen, child_context);
+        }
+    }
+
+    /// `foo()` -> foo, `this.db.save()` -> save
+    fn callee_name(&self, func_node: &Node) -> String {
+        match func_node.kind() {
+            "member_expression" => func_node
+                .child_by_field_name("property")
+                .map(|p| self.get_node_text(&p))
+                .unwrap_or_default(),
+            "identifier" => self.get_node_text(func_node),
+            _ => String::new(),
+        }
+    }
+
+    fn extract_call_arguments(&self, call_node: &Node) -> Vec<String> {
+        let mut args = Vec::new();
+
+        if let Some(arg_list) = call_node.child_by_field_name("arguments") {
+            let mut cursor = arg_list.walk();
+            for child in arg_list.named_children(&mut cursor) {
+                // Concatenations and template literals are kept so queries built
+                // from strings can be flagged
+                if matches!(
+                    child.kind(),
+                    "identifier" | "string" | "number" | "member_expression" | "binary_expression" | "template_string"
+                ) {
+                    args.push(self.get_node_text(&child));
+                }
+            }
+        }
+
+        args
+    }
+
+    fn extract_variables(&self, node: &Node, params: &[Parameter]) -> Vec<Variable> {
+        let mut variables: HashMap<String, Variable> = HashMap::new();
+
+        for param in params {
+            variables.insert(param.name.clone(), Variable {
+                name: param.name.clone(),
+                var_type: None,
+                scope: "param".to_string(),
+                defined_at: None,
+                transformations: vec![],
+                used_in: vec![],
+                returned: false,
+            });
+        }
+
+        self.track_variable_usage(node, &mut variables);
+        variables.into_values().collect()
+    }
+
+    fn track_variable_usage(&self, node: &Node, variables: &mut HashMap<String, Variable>) {
+        let mut cursor = node.walk();
+
+        match node.kind() {
+            "variable_declarator" => {
+                if let Some(name_node) = node.child_by_field_name("name") {
+                    let line = node.start_position().row + 1;
+
+                    for var_name in self.pattern_names(&name_node) {
+                        let mut transformations = vec![];
+                        if let Some(value) = node.child_by_field_name("value") {
+                            if value.kind() == "call_expression" || value.kind() == "await_expression" {
+                                let via = self.find_first_callee(&value).unwrap_or_default();
+                                if !via.is_empty() {
+                                    transformations.push(VarTransformation {
+                                        line,
+                                        via,
+                                        becomes: var_name.clone(),
+                                    });
+                                }
+                            }
+                        }
+
+                        variables.entry(var_name.clone()).or_insert(Variable {
+                            name: var_name,
+                            var_type: None,
+                            scope: "local".to_string(),
+                            defined_at: Some(line),
+                            transformations,
+                            used_in: vec![],
+                            returned: false,
+                        });
+                    }
+                }
+            }
+            "return_statement" => {
+                let mut ret_cursor = node.walk();
+                for child in node.named_children(&mut ret_cursor) {
+                    if child.kind() == "identifier" {
+                        let var_name = self.get_node_text(&child);
+                        if let Some(var) = variables.get_mut(&var_name) {
+                            var.returned = true;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        for child in node.children(&mut cursor) {
+            self.track_variable_usage(&child, variables);
+        }
+    }
+
+    fn find_first_callee(&self, node: &Node) -> Option<String> {
+        if node.kind() == "call_expression" {
+            if let Some(func) = node.child_by_field_name("function") {
+                return Some(self.callee_name(&func));
+            }
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            if let Some(name) = self.find_first_callee(&child) {
+                return Some(name);
+            }
+        }
+
+        None
+    }
+
+    fn build_control_flow(&self, node: &Node) -> ControlFlow {
+        let mut control_flow = ControlFlow {
+            complexity: self.calculate_complexity(node),
+            cognitive_complexity: self.calculate_cognitive_complexity(node),
+            branches: vec![],
+            loops: vec![],
+            try_blocks: vec![],
+            with_blocks: vec![],
+        };
+
+        self.extract_control_structures(node, &mut control_flow);
+        control_flow
+    }
+
+    fn extract_control_structures(&self, node: &Node, cf: &mut ControlFlow) {
+        let mut cursor = node.walk();
+
+        match node.kind() {
+            "if_statement" => {
+                if let Some(branch) = self.parse_if_statement(node) {
+                    cf.branches.push(branch);
+                }
+            }
+            "for_statement" | "for_in_statement" | "while_statement" | "do_statement" => {
+                if let Some(loop_info) = self.parse_loop(node) {
+                    cf.loops.push(loop_info);
+                }
+            }
+            "try_statement" => {
+                if let Some(try_block) = self.parse_try_statement(node) {
+                    cf.try_blocks.push(try_block);
+                }
+            }
+            _ => {}
+        }
+
+        for child in node.children(&mut cursor) {
+            self.extract_control_structures(&child, cf);
+        }
+    }
+
+    fn parse_if_statement(&self, node: &Node) -> Option<Branch> {
+        let line = node.start_position().row + 1;
+        let condition = node.child_by_field_name("condition")
+            .map(|c| self.get_node_text(&c))
+            .unwrap_or_default();
+
+        let consequence = node.child_by_field_name("consequence")?;
+        let true_path = self.extract_execution_path(&consequence);
+
+        let false_path = node.child_by_field_name("alternative")
+            .map(|alt| self.extract_execution_path(&alt));
+
+        Some(Branch {
+            branch_type: "if".to_string(),
+            condition,
+            line,
+            true_path,
+            false_path,
+        })
+    }
+
+    fn extract_execution_path(&self, block: &Node) -> ExecutionPath {
+        ExecutionPath {
+            calls: self.extract_calls_from_block(block),
+            returns: self.find_statement_value(block, "return_statement"),
+            raises: self.find_statement_value(block, "throw_statement"),
+        }
+    }
+
+    fn extract_calls_from_block(&self, block: &Node) -> Vec<String> {
+        let mut calls = Vec::new();
+        let mut seen = HashSet::new();
+        self.find_call_names(block, &mut calls, &mut seen);
+        calls
+    }
+
+    fn find_call_names(&self, node: &Node, calls: &mut Vec<String>, seen: &mut HashSet<String>) {
+        let mut cursor = node.walk();
+
+        if node.kind() == "call_expression" {
+            if let Some(func_node) = node.child_by_field_name("function") {
+                let name = self.get_node_text(&func_node);
+                if !seen.contains(&name) {
+                    seen.insert(name.clone());
+                    calls.push(name);
+                }
+            }
+        }
+
+        for child in node.children(&mut cursor) {
+            self.find_call_names(&child, calls, seen);
+        }
+    }
+
+    /// Value of the first direct `return`/`throw` statement in a block
+    fn find_statement_value(&self, node: &Node, kind: &str) -> Option<String> {
+        if node.kind() == kind {
+            return node.named_child(0).map(|v| self.get_node_text(&v));
+        }
+
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            if child.kind() == kind {
+                return child.named_child(0).map(|v| self.get_node_text(&v));
+            }
+        }
+
+        None
+    }
+
+    fn parse_loop(&self, node: &Node) -> Option<Loop> {
+        let line = node.start_position().row + 1;
+        let (loop_type, condition) = match node.kind() {
+            "for_in_statement" => {
+                let right = node.child_by_field_name("right")
+                    .map(|r| self.get_node_text(&r))
+                    .unwrap_or_default();
+                ("for", right)
+            }
+            "for_statement" => (
+                "for",
+                node.child_by_field_name("condition")
+                    .map(|c| self.get_node_text(&c).trim_end_matches(';').to_string())
+                    .unwrap_or_default(),
+            ),
+            _ => (
+                "while",
+                node.child_by_field_name("condition")
+                    .map(|c| self.get_node_text(&c))
+                    .unwrap_or_default(),
+            ),
+        };
+
+        let calls = self.extract_calls_from_block(node);
+
+        Some(Loop {
+            loop_type: loop_type.to_string(),
+            condition,
+            line,
+            calls,
+            is_async: false,
+        })
+    }
+
+    fn parse_try_statement(&self, node: &Node) -> Option<TryBlock> {
+        let line = node.start_position().row + 1;
+        let body = node.child_by_field_name("body")?;
+        let try_calls = self.extract_calls_from_block(&body);
+
+        let except_clauses = node.child_by_field_name("handler")
+            .map(|handler| {
+                vec![ExceptClause {
+                    exception_type: "Error".to_string(),
+                    line: handler.start_position().row + 1,
+                    calls: self.extract_calls_from_block(&handler),
+                }]
+            })
+            .unwrap_or_default();
+
+        let finally_calls = node.child_by_field_name("finalizer")
+            .map(|f| self.extract_calls_from_block(&f))
+            .unwrap_or_default();
+
+        Some(TryBlock {
+            line,
+            try_calls,
+            except_clauses,
+            finally_calls,
+        })
+    }
+
+    fn extract_exception_info(&self, node: &Node) -> ExceptionInfo {
+        let mut info = ExceptionInfo::default();
+        self.find_exceptions(node, &mut info);
+        info
+    }
+
+    fn find_exceptions(&self, node: &Node, info: &mut ExceptionInfo) {
+        let mut cursor = node.walk();
+
+        match node.kind() {
+            "throw_statement" => {
+                if let Some(value) = node.named_child(0) {
+                    // throw new ValidationError(...) -> ValidationError
+                    let exc_type = if value.kind() == "new_expression" {
+                        value.child_by_field_name("constructor")
+                            .map(|c| self.get_node_text(&c))
+                            .unwrap_or_default()
+                    } else {
+                        self.get_node_text(&value)
+                    };
+
+                    if !exc_type.is_empty() && !info.raises.contains(&exc_type) {
+                        info.raises.push(exc_type);
+                    }
+                }
+            }
+            "catch_clause" => {
+                // JavaScript catch clauses aren't typed
+                let exc_type = "Error".to_string();
+                if !info.handles.contains(&exc_type) {
+                    info.handles.push(exc_type);
+                }
+            }
+            _ => {}
+        }
+
+        for child in node.children(&mut cursor) {
+            self.find_exceptions(&child, info);
+        }
+    }
+
+    fn extract_classes(&self, root: &Node) -> Vec<Class> {
+        let mut classes = Vec::new();
+
+        for (node, outer) in self.top_level_statements(root) {
+            let class = match node.kind() {
+                "class_declaration" | "abstract_class_declaration" => self.parse_class(&node, &outer),
+                "interface_declaration" => self.parse_interface(&node, &outer),
+                _ => None,
+            };
+
+            if let Some(class) = class {
+                classes.push(class);
+            }
+        }
+
+        classes
+    }
+
+    fn parse_class(&self, node: &Node, outer: &Node) -> Option<Class> {
+        let name_node = node.child_by_field_name("name")?;
+        let name = self.get_node_text(&name_node);
+
+        let mut bases = Vec::new();
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            if child.kind() == "class_heritage" {
+                bases.extend(self.heritage_bases(&child));
+            }
+        }
+
+        let mut decorators = self.extract_decorators(node);
+        decorators.extend(self.extract_decorators(outer));
+        decorators.dedup();
+
+        let (methods, attributes) = node.child_by_field_name("body")
+            .map(|body| self.parse_class_body(&body, &name))
+            .unwrap_or_default();
+
+        Some(Class {
+            id: format!("class_{}", name),
+            docstring: self.extract_docstring(outer),
+            line_start: outer.start_position().row + 1,
+            line_end: outer.end_position().row + 1,
+            name,
+            bases,
+            methods,
+            attributes,
+            decorators,
+            type_params: vec![],
+        })
+    }
+
+    /// Base classes from `extends X`; TypeScript also nests `implements A, B`
+    fn heritage_bases(&self, heritage: &Node) -> Vec<String> {
+        let mut bases = Vec::new();
+        let mut cursor = heritage.walk();
+
+        for clause in heritage.named_children(&mut cursor) {
+            match clause.kind() {
+                "extends_clause" => {
+                    if let Some(value) = clause.child_by_field_name("value") {
+                        bases.push(self.get_node_text(&value));
+                    }
+                }
+                "implements_clause" => {
+                    let mut impl_cursor = clause.walk();
+                    for iface in clause.named_children(&mut impl_cursor) {
+                        bases.push(self.get_node_text(&iface));
+                    }
+                }
+                "comment" => {}
+                // Plain JavaScript: `extends Base` is the heritage's only child
+                _ => {
+                    bases.push(self.get_node_text(&clause));
+                    break;
+                }
+            }
+        }
+
+        bases
+    }
+
+    /// TypeScript interfaces become method-less classes whose attributes are
+    /// the interface members
+    fn parse_interface(&self, node: &Node, outer: &Node) -> Option<Class> {
+        let name = self.get_node_text(&node.child_by_field_name("name")?);
+
+        let mut bases = Vec::new();
+        let mut attributes = Vec::new();
+        let mut cursor = node.walk();
+
+        for child in node.children(&mut cursor) {
+            if child.kind() == "extends_type_clause" {
+                let mut ext_cursor = child.walk();
+                for base in child.named_children(&mut ext_cursor) {
+                    bases.push(self.get_node_text(&base));
+                }
+            }
+        }
+
+        if let Some(body) = node.child_by_field_name("body") {
+            let mut body_cursor = body.walk();
+            for member in body.named_children(&mut body_cursor) {
+                let Some(member_name) = member.child_by_field_name("name") else {
+                    continue;
+                };
+
+                let type_annotation = match member.kind() {
+                    "property_signature" => self.type_annotation(&member, "type"),
+                    // Methods keep their full call signature as the type
+                    "method_signature" => {
+                        let params = member.child_by_field_name("parameters")
+                            .map(|p| self.get_node_text(&p))
+                            .unwrap_or_else(|| "()".to_string());
+                        let return_type = self.type_annotation(&member, "return_type");
+                        if return_type.is_empty() {
+                            params
+                        } else {
+                            format!("{} => {}", params, return_type)
+                        }
+                    }
+                    _ => continue,
+                };
+
+                attributes.push(Attribute {
+                    name: self.get_node_text(&member_name),
+                    type_annotation,
+                    value: None,
+                });
+            }
+        }
+
+        Some(Class {
+            id: format!("interface_{}", name),
+            name,
+            bases,
+            docstring: self.extract_docstring(outer),
+            line_start: outer.start_position().row + 1,
+            line_end: outer.end_position().row + 1,
+            methods: vec![],
+            attributes,
+            decorators: vec![],
+            type_params: vec![],
+        })
+    }
+
+    fn parse_class_body(&self, node: &Node, class_name: &str) -> (Vec<Function>, Vec<Attribute>) {
+        let mut methods = Vec::new();
+        let mut attributes = Vec::new();
+        let mut cursor = node.walk();
+
+        for child in node.children(&mut cursor) {
+            match child.kind() {
+                "method_definition" => {
+                    if let Some(name_node) = child.child_by_field_name("name") {
+                        let name = self.get_node_text(&name_node);
+                        if let Some(method) = self.parse_function(&child, &name, class_name, &child) {
+                            methods.push(method);
+                        }
+                    }
+                }
+                "field_definition" | "public_field_definition" => {
+                    let prop = child.child_by_field_name("property")
+                        .or_else(|| child.child_by_field_name("name"));
+                    if let Some(prop) = prop {
+                        attributes.push(Attribute {
+                            name: self.get_node_text(&prop),
+                            type_annotation: self.type_annotation(&child, "type"),
+                            value: child.child_by_field_name("value").map(|v| self.get_node_text(&v)),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        (methods, attributes)
+    }
+
+    fn extract_global_vars(&self, root: &Node) -> Vec<GlobalVar> {
+        let mut vars = Vec::new();
+
+        for (node, _) in self.top_level_statements(root) {
+            if node.kind() != "lexical_declaration" && node.kind() != "variable_declaration" {
+                continue;
+            }
+
+            let mut cursor = node.walk();
+            for declarator in node.children(&mut cursor) {
+                if declarator.kind() != "variable_declarator" {
+                    continue;
+                }
+                let Some(name_node) = declarator.child_by_field_name("name") else {
+                    continue;
+                };
+                let value = declarator.child_by_field_name("value");
+
+                // Functions and requires are reported elsewhere
+                if let Some(ref v) = value {
+                    if self.is_function_value(v) || self.require_target(v).is_some() {
+                        continue;
+                    }
+                }
+
+                vars.push(GlobalVar {
+                    name: self.get_node_text(&name_node),
+                    type_annotation: self.type_annotation(&declarator, "type"),
+                    value: value.map(|v| self.get_node_text(&v)),
+                    line: declarator.start_position().row + 1,
+                });
+            }
+        }
+
+        vars
+    }
+
+    /// JSDoc (`/** ... */`) or line comment immediately preceding a declaration
+    fn extract_docstring(&self, node: &Node) -> String {
+        let Some(prev) = node.prev_sibling() else {
+            return String::new();
+        };
+        if prev.kind() != "comment" {
+            return String::new();
+        }
+
+        let text = self.get_node_text(&prev);
+        if text.starts_with("/*") {
+            text.trim_start_matches("/**")
+                .trim_start_matches("/*")
+                .trim_end_matches("*/")
+                .lines()
+                .map(|line| line.trim().trim_start_matches('*').trim())
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join("\n")
+        } else {
+            text.trim_start_matches("//").trim().to_string()
+        }
+    }
+
+    fn calculate_complexity(&self, node: &Node) -> usize {
+        let mut complexity = 1;
+
+        fn count_complexity_nodes(node: &Node) -> usize {
+            let mut count = 0;
+            let mut cursor = node.walk();
+
+            match node.kind() {
+                "if_statement" | "for_statement" | "for_in_statement" |
+                "while_statement" | "do_statement" | "switch_case" |
+                "catch_clause" | "ternary_expression" | "&&" | "||" | "??" => {
+                    count += 1;
+                }
+                _ => {}
+            }
+
+            for child in node.children(&mut cursor) {
+                count += count_complexity_nodes(&child);
+            }
+
+            count
+        }
+
+        complexity += count_complexity_nodes(node);
+        complexity
+    }
+
+    /// Cognitive complexity: like cyclomatic complexity, but nested control
+    /// structures cost more the deeper they sit
+    fn calculate_cognitive_complexity(&self, node: &Node) -> usize {
+        fn operator(node: &Node) -> Option<&'static str> {
+            node.child_by_field_name("operator").map(|op| op.kind())
+        }
+
+        fn continues_sequence(node: &Node) -> bool {
+            node.parent()
+                .map(|p| p.kind() == "binary_expression" && operator(&p) == operator(node))
+                .unwrap_or(false)
+        }
+
+        fn is_logical(node: &Node) -> bool {
+            matches!(operator(node), Some("&&") | Some("||") | Some("??"))
+        }
+
+        fn is_else_branch(node: &Node) -> bool {
+            node.parent()
+                .map(|p| p.kind() == "else_clause")
+                .unwrap_or(false)
+        }
+
+        fn walk(node: &Node, nesting: usize) -> usize {
+            let mut score = 0;
+            let mut child_nesting = nesting;
+
+            match node.kind() {
+                "if_statement" => {
+                    if is_else_branch(node) {
+                        score += 1;
+                    } else {
+                        score += 1 + nesting;
+                        child_nesting += 1;
+                    }
+                }
+                "for_statement" | "for_in_statement" | "while_statement" |
+                "do_statement" | "switch_statement" | "catch_clause" |
+                "ternary_expression" => {
+                    score += 1 + nesting;
+                    child_nesting += 1;
+                }
+                "else_clause" => {
+                    let is_else_if = node
+                        .named_child(0)
+                        .map(|c| c.kind() == "if_statement")
+                        .unwrap_or(false);
+                    if !is_else_if {
+                        score += 1;
+                    }
+                }
+                "arrow_function" | "function" | "function_expression" => {
+                    child_nesting += 1;
+                }
+                "binary_expression" if is_logical(node) && !continues_sequence(node) => {
+                    score += 1;
+                }
+                _ => {}
+            }
+
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                score += walk(&child, child_nesting);
+            }
+
+            score
+        }
+
+        let mut cursor = node.walk();
+        node.children(&mut cursor).map(|child| walk(&child, 0)).sum()
+    }
+
+    fn extract_todos(&self) -> Vec<Todo> {
+        let re = Regex::new(r"(?://|/\*)\s*TODO:?\s*(.+?)(?:\*/|$)").unwrap();
+
+        self.source_code
+            .lines()
+            .enumerate()
+            .filter_map(|(idx, line)| {
+                re.captures(line).map(|caps| {
+                    let text = caps.get(1).unwrap().as_str().trim().to_string();
+                    let priority = if text.to_lowercase().contains("critical") ||
+                                      text.to_lowercase().contains("urgent") {
+                        "high"
+                    } else if text.to_lowercase().contains("minor") {
+                        "low"
+                    } else {
+                        "medium"
+                    };
+
+                    Todo {
+                        line: idx + 1,
+                        text,
+                        priority: priority.to_string(),
+                    }
+                })
+            })
+            .collect()
+    }
+
+    fn detect_security_patterns(&self) -> Vec<SecurityNote> {
+        let mut notes = Vec::new();
+
+        let patterns = vec![
+            (r"password|secret|token|apikey|api_key", "sensitive_data", "Handles sensitive data"),
+            (r"eval\(|new function\(", "code_execution", "Dynamic code execution"),
+            (r"child_process|execsync\(|spawn\(", "command_execution", "System command execution"),
+            (r"innerhtml|dangerouslysetinnerhtml|document\.write", "xss", "Writes raw HTML - potential XSS"),
+        ];
+
+        for (pattern, note_type, description) in patterns {
+            if let Ok(re) = Regex::new(pattern) {
+                for (idx, line) in self.source_code.lines().enumerate() {
+                    if re.is_match(&line.to_lowercase()) {
+                        notes.push(SecurityNote {
+                            note_type: note_type.to_string(),
+                            line: idx + 1,
+                            description: description.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+
+        notes
+    }
+
+    fn auto_tag_function(&self, name: &str, docstring: &str, calls: &[FunctionCall], is_async: bool) -> Vec<String> {
+        let mut tags = Vec::new();
+        let name_lower = name.to_lowercase();
+        let doc_lower = docstring.to_lowercase();
+
+        // Entry point
+        if name == "main" || name == "run" || name == "start" {
+            tags.push("entry-point".to_string());
+        }
+
+        // Initialization
+        if name_lower.contains("init") || name_lower.contains("setup") ||
+            name_lower.contains("bootstrap") || name == "constructor" {
+            tags.push("initialization".to_string());
+        }
+
+        // Authentication & Security
+        if name_lower.contains("auth") || name_lower.contains("login") ||
+            name_lower.contains("logout") || name_lower.contains("password") ||
+            name_lower.contains("token") || doc_lower.contains("authentication") {
+            tags.push("authentication".to_string());
+            tags.push("security".to_string());
+        }
+
+        // API & HTTP (express-style handlers and fetch clients)
+        if name_lower.contains("api") || name_lower.contains("endpoint") ||
+            name_lower.contains("route") || name_lower.contains("handler") ||
+            doc_lower.contains("endpoint") ||
+            calls.iter().any(|c| c.callee == "fetch" || c.callee == "json" || c.callee == "send") {
+            tags.push("api".to_string());
+        }
+
+        // Event handlers
+        if (name.starts_with("on") && name.chars().nth(2).is_some_and(|c| c.is_uppercase())) ||
+            name_lower.starts_with("handle") ||
+            calls.iter().any(|c| c.callee == "addEventListener") {
+            tags.push("event-handler".to_string());
+        }
+
+        // Async
+        if is_async || calls.iter().any(|c| c.callee == "then" || c.callee == "Promise") {
+            tags.push("async".to_string());
+        }
+
+        // Validation
+        if name_lower.contains("validate") || name_lower.contains("check") ||
+            name_lower.contains("verify") || name_lower.contains("sanitize") {
+            tags.push("validation".to_string());
+        }
+
+        // Error handling
+        if name_lower.contains("error") || calls.iter().any(|c| c.callee == "catch") {
+            tags.push("error-handling".to_string());
+        }
+
+        // Utilities
+        if name_lower.contains("util") || name_lower.contains("helper") {
+            tags.push("utility".to_string());
+        }
+
+        // Testing
+        if name_lower.starts_with("test") ||
+            calls.iter().any(|c| c.callee == "describe" || c.callee == "it" || c.callee == "expect") {
+            tags.push("testing".to_string());
+        }
+
+        // File I/O
+        if name_lower.contains("read") || name_lower.contains("write") ||
+            name_lower.contains("file") || name_lower.contains("load") {
+            tags.push("file-io".to_string());
+        }
+
+        // UI (React components and hooks)
+        if name.chars().next().is_some_and(|c| c.is_uppercase()) && !name.contains('_') {
+            tags.push("component".to_string());
+        }
+        if name.starts_with("use") && name.chars().nth(3).is_some_and(|c| c.is_uppercase()) {
+            tags.push("hook".to_string());
+        }
+
+        // Configuration
+        if name_lower.contains("config") || name_lower.contains("setting") ||
+            name_lower.contains("option") {
+            tags.push("configuration".to_string());
+        }
+
+        // Logging
+        if name_lower.contains("log") || calls.iter().any(|c| c.callee == "log" && name_lower != "log") {
+            tags.push("logging".to_string());
+        }
+
+        // Parsing / Serialization
+        if name_lower.contains("parse") || name_lower.contains("decode") {
+            tags.push("parsing".to_string());
+        }
+        if name_lower.contains("serialize") || name_lower.contains("encode") ||
+            name_lower.contains("stringify") {
+            tags.push("serialization".to_string());
+        }
+
+        // Middleware
+        if name_lower.contains("middleware") || calls.iter().any(|c| c.callee == "next") {
+            tags.push("middleware".to_string());
+        }
+
+        // Remove duplicates and sort
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    fn estimate_importance(&self, name: &str, is_exported: bool) -> f32 {
+        let mut score: f32 = 0.5;
+
+        if name == "main" || name == "run" || name == "start" {
+            score += 0.3;
+        }
+
+        if is_exported {
+            score += 0.1;
+        }
+
+        if name.to_lowercase().contains("auth") || name.to_lowercase().contains("login") {
+            score += 0.2;
+        }
+
+        // Conventionally private
+        if name.starts_with('_') || name.starts_with('#') {
+            score -= 0.2;
+        }
+
+        score.clamp(0.0, 1.0)
+    }
+
+    /// String literal contents without quotes
+    fn string_value(&self, node: &Node) -> String {
+        self.get_node_text(node)
+            .trim_matches(|c| c == '"' || c == '\'' || c == '`')
+            .to_string()
+    }
+
+    fn get_node_text(&self, node: &Node) -> String {
+        node.utf8_text(self.source_code.as_bytes())
+            .unwrap_or("")
+            .to_string()
+    }
+}
+
+pub fn parse_file(path: &Path) -> Result<(String, FileData), ParseError> {
+    let source_code = std::fs::read_to_string(path)
+        .map_err(|e| ParseError::io(path, e))?
+        .replace("\r\n", "\n");
+
+    let parser = JavaScriptParser::new(source_code);
+    let file_data = parser.parse()?;
+
+    let relative_path = path.to_string_lossy().to_string();
+
+    Ok((relative_path, file_data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> FileData {
+        JavaScriptParser::new(source.to_string()).parse().unwrap()
+    }
+
+    #[test]
+    fn test_functions_and_arrows() {
+        let data = parse(
+            "/** Log a user in. */\n\
+             export async function login(user, remember = false) {\n  return checkPassword(user);\n}\n\n\
+             const double = x => x * 2;\n\
+             let handler = (req, res) => {\n  res.send(double(2));\n};\n",
+        );
+
+        let names: Vec<&str> = data.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["login", "double", "handler"]);
+
+        let login = &data.functions[0];
+        assert_eq!(login.id, "func_login");
+        assert!(login.is_async);
+        assert_eq!(login.docstring, "Log a user in.");
+        assert_eq!(login.params[1].default_value.as_deref(), Some("false"));
+        assert_eq!(login.calls[0].callee, "checkPassword");
+
+        let handler = &data.functions[2];
+        assert_eq!(handler.params.len(), 2);
+        assert!(handler.calls.iter().any(|c| c.callee == "send"));
+        assert!(handler.calls.iter().any(|c| c.callee == "double"));
+    }
+
+    #[test]
+    fn test_class_with_methods() {
+        let data = parse(
+            "class UserService extends BaseService {\n  cache = new Map();\n\n\
+             constructor(db) {\n    super();\n    this.db = db;\n  }\n\n\
+             async find(id) {\n    return this.db.findOne(id);\n  }\n}\n",
+        );
+
+        assert_eq!(data.classes.len(), 1);
+        let class = &data.classes[0];
+        assert_eq!(class.id, "class_UserService");
+        assert_eq!(class.bases, vec!["BaseService"]);
+        assert_eq!(class.attributes[0].name, "cache");
+
+        let methods: Vec<&str> = class.methods.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(methods, vec!["method_UserService_constructor", "method_UserService_find"]);
+        assert!(class.methods[1].is_async);
+    }
+
+    #[test]
+    fn test_database_tag_needs_connection_receiver() {
+        let data = parse(
+            "const { Pool } = require('pg');\n\
+             const child_process = require('child_process');\n\n\
+             function clean(dir) {\n  child_process.exec('rm -rf ' + dir);\n}\n\n\
+             function findUser(pool, name) {\n  return pool.query('SELECT * FROM users WHERE name = ' + name);\n}\n",
+        );
+
+        let tagged = |name: &str| {
+            data.functions.iter().find(|f| f.name == name).unwrap().tags.contains(&"database".to_string())
+        };
+        assert!(!tagged("clean"));
+        assert!(tagged("findUser"));
+
+        let risks: Vec<usize> = data
+            .security_notes
+            .iter()
+            .filter(|n| n.note_type == "sql_injection_risk")
+            .map(|n| n.line)
+            .collect();
+        assert_eq!(risks, vec![9]);
+    }
+
+    #[test]
+    fn test_imports_and_requires() {
+        let data = parse(
+            "import React, { useState } from 'react';\n\
+             import * as utils from './utils';\n\
+             const fs = require('fs');\n\
+             const { join, resolve } = require('path');\n\
+             require('./polyfills');\n",
+        );
+
+        assert_eq!(data.imports.len(), 5);
+        assert_eq!(data.imports[0].module, "react");
+        assert_eq!(data.imports[0].items, vec!["React", "useState"]);
+        assert_eq!(data.imports[0].import_type, "external");
+        assert_eq!(data.imports[1].import_type, "internal");
+        assert_eq!(data.imports[2].module, "fs");
+        assert_eq!(data.imports[2].import_type, "stdlib");
+        assert_eq!(data.imports[3].items, vec!["join", "resolve"]);
+        assert_eq!(data.imports[4].module, "./polyfills");
+
+        // Requires aren't reported as globals
+        assert!(data.global_vars.is_empty());
+    }
+}
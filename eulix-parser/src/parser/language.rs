@@ -14,6 +14,73 @@ pub enum Language {
 }
 
 impl Language {
+    /// Names `--languages` accepts besides `all` (aliases: py, js, ts, golang, rs, c++, cxx)
+    pub const FILTER_NAMES: [&'static str; 7] = ["c", "cpp", "python", "javascript", "typescript", "go", "rust"];
+
+    /// Canonical name, as listed in `FILTER_NAMES` and stored on each file
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::Python => "python",
+            Language::JavaScript => "javascript",
+            Language::TypeScript => "typescript",
+            Language::Go => "go",
+            Language::Rust => "rust",
+            Language::C => "c",
+            Language::Cpp => "cpp",
+            Language::Unknown => "unknown",
+        }
+    }
+
+    /// Whether a tree-sitter parser exists yet; files in other languages are
+    /// reported as unsupported rather than failed
+    pub fn has_parser(self) -> bool {
+        !matches!(self, Language::Rust | Language::Unknown)
+    }
+
+    /// Parse a `--languages` value: `all` or a comma-separated list of names.
+    /// Unknown names are an error rather than silently matching no files.
+    pub fn parse_filter(languages: &str) -> Result<Vec<Self>, String> {
+        if languages.trim().eq_ignore_ascii_case("all") {
+            return Ok(vec![
+                Language::C,
+                Language::Cpp,
+                Language::Python,
+                Language::JavaScript,
+                Language::TypeScript,
+                Language::Go,
+                Language::Rust,
+            ]);
+        }
+
+        let mut filters = Vec::new();
+        for name in languages.split(',').map(str::trim).filter(|name| !name.is_empty()) {
+            let lang = match name.to_lowercase().as_str() {
+                "c" => Language::C,
+                "cpp" | "c++" | "cxx" => Language::Cpp,
+                "python" | "py" => Language::Python,
+                "javascript" | "js" => Language::JavaScript,
+                "typescript" | "ts" => Language::TypeScript,
+                "go" | "golang" => Language::Go,
+                "rust" | "rs" => Language::Rust,
+                _ => {
+                    return Err(format!(
+                        "unknown language '{}' in --languages (valid: all, {})",
+                        name,
+                        Self::FILTER_NAMES.join(", ")
+                    ))
+                }
+            };
+            if !filters.contains(&lang) {
+                filters.push(lang);
+            }
+        }
+
+        if filters.is_empty() {
+            return Err(format!("--languages is empty (valid: all, {})", Self::FILTER_NAMES.join(", ")));
+        }
+        Ok(filters)
+    }
+
     /// Detect language from file path and optionally content
     pub fn detect(path: &Path) -> Self {
         // 1. Try extension first (fastest)
@@ -186,6 +253,20 @@ mod tests {
         assert_eq!(Language::from_extension("rs"), Some(Language::Rust));
     }
 
+    #[test]
+    fn test_language_filter() {
+        assert_eq!(
+            Language::parse_filter("py, golang,rs,ts,js").unwrap(),
+            vec![Language::Python, Language::Go, Language::Rust, Language::TypeScript, Language::JavaScript]
+        );
+        assert_eq!(Language::parse_filter("all").unwrap().len(), Language::FILTER_NAMES.len());
+
+        let err = Language::parse_filter("python,pyhton").unwrap_err();
+        assert!(err.contains("pyhton"));
+        assert!(err.contains("python, javascript"));
+        assert!(Language::parse_filter(" , ").is_err());
+    }
+
     #[test]
     fn test_shebang_detection() {
         let python_content = "#!/usr/bin/env python3\nprint('hello')";
@@ -1,6 +1,13 @@
 // parser/src/parser/mod.rs
 pub mod python;
+pub mod javascript;
+pub mod typescript;
 pub mod go;
 pub mod c;
+pub mod cpp;
 pub mod language;
 pub mod analyze;
+pub mod source;
+pub mod dataflow;
+pub mod database;
+pub mod error;
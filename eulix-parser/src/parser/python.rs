@@ -1,4 +1,7 @@
 use crate::kb::types::*;
+use crate::parser::dataflow::link_call_arguments;
+use crate::parser::database::tag_database_usage;
+use crate::parser::error::ParseError;
 use regex::Regex;
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
@@ -14,27 +17,45 @@ impl PythonParser {
         Self { source_code }
     }
 
-    pub fn parse(&self) -> Result<FileData, String> {
+    pub fn parse(&self) -> Result<FileData, ParseError> {
         let mut parser = Parser::new();
         parser
             .set_language(tree_sitter_python::language())
-            .map_err(|e| format!("Failed to load Python grammar: {}", e))?;
+            .map_err(|e| ParseError::GrammarLoad { language: "Python", message: e.to_string() })?;
 
         let tree = parser
             .parse(&self.source_code, None)
-            .ok_or_else(|| "Failed to parse Python file".to_string())?;
+            .ok_or(ParseError::TreeSitter { language: "Python" })?;
 
         let root = tree.root_node();
+        let type_aliases = self.extract_type_aliases(&root);
+        let global_vars = self.extract_global_vars(&root)
+            .into_iter()
+            .filter(|var| !type_aliases.iter().any(|alias| alias.name == var.name && alias.line == var.line))
+            .collect();
+
+        let imports = self.extract_imports(&root);
+        let mut functions = self.extract_functions(&root);
+        let mut classes = self.extract_classes(&root);
+        let mut security_notes = self.detect_security_patterns();
+        security_notes.extend(tag_database_usage("python", &imports, &mut functions, &mut classes));
 
         Ok(FileData {
             language: "python".to_string(),
             loc: self.count_lines(),
-            imports: self.extract_imports(&root),
-            functions: self.extract_functions(&root),
-            classes: self.extract_classes(&root),
-            global_vars: self.extract_global_vars(&root),
+            imports,
+            functions,
+            classes,
+            global_vars,
             todos: self.extract_todos(),
-            security_notes: self.detect_security_patterns(),
+            security_notes,
+            exports: self.extract_exports(&root),
+            macros: vec![],
+            build_tags: vec![],
+            directives: vec![],
+            type_aliases,
+            module_calls: self.extract_module_calls(&root),
+            function_pointers: vec![],
         })
     }
 
@@ -49,21 +70,23 @@ impl PythonParser {
         for child in root.children(&mut cursor) {
             match child.kind() {
                 "import_statement" => {
-                    let module = self.get_node_text(&child);
-                    let cleaned = module
-                        .replace("import ", "")
-                        .split(',')
-                        .next()
-                        .unwrap_or("")
-                        .trim()
-                        .to_string();
-
-                    if !cleaned.is_empty() {
-                        imports.push(Import {
-                            module: cleaned.clone(),
-                            items: vec![],
-                            import_type: self.classify_import(&cleaned),
-                        });
+                    // `import os, sys as system` -> one Import per module
+                    let text = self.get_node_text(&child);
+                    for module in text.trim_start_matches("import").split(',') {
+                        let cleaned = module
+                            .split_whitespace()
+                            .next()
+                            .unwrap_or("")
+                            .to_string();
+
+                        if !cleaned.is_empty() {
+                            imports.push(Import {
+                                module: cleaned.clone(),
+                                items: vec![],
+                                import_type: self.classify_import(&cleaned),
+                                is_wildcard: false,
+                            });
+                        }
                     }
                 }
                 "import_from_statement" => {
@@ -102,17 +125,23 @@ impl PythonParser {
         if let Some(caps) = re.captures(&text) {
             let module = caps.get(1)?.as_str().to_string();
             let items_str = caps.get(2)?.as_str();
-            let items: Vec<String> = items_str
-                .split(',')
-                .map(|s| s.trim().split_whitespace().next().unwrap_or(s.trim()))
-                .map(|s| s.to_string())
-                .filter(|s| !s.is_empty())
-                .collect();
+            let is_wildcard = items_str.trim() == "*";
+            let items: Vec<String> = if is_wildcard {
+                vec!["*".to_string()]
+            } else {
+                items_str
+                    .split(',')
+                    .map(|s| s.trim().split_whitespace().next().unwrap_or(s.trim()))
+                    .map(|s| s.to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            };
 
             return Some(Import {
                 module: module.clone(),
                 items,
                 import_type: self.classify_import(&module),
+                is_wildcard,
             });
         }
 
@@ -124,37 +153,120 @@ impl PythonParser {
         let mut cursor = root.walk();
 
         for child in root.children(&mut cursor) {
+            let child = self.unwrap_decorated(child);
             if child.kind() == "function_definition" {
                 if let Some(func) = self.parse_function(&child, "") {
+                    let scope = func.id.clone();
                     functions.push(func);
+                    self.extract_nested_functions(&child, &scope, &mut functions);
                 }
+            } else if child.kind() == "class_definition" {
+                self.extract_method_closures(&child, &mut functions);
             }
         }
 
         functions
     }
 
-    fn parse_function(&self, node: &Node, class_context: &str) -> Option<Function> {
-        let mut cursor = node.walk();
-        let mut name = String::new();
-        let mut is_async = false;
+    /// Closures defined inside the methods of a top-level class, with ids
+    /// like `method_Class_method_inner`
+    fn extract_method_closures(&self, class: &Node, functions: &mut Vec<Function>) {
+        let (Some(class_name), Some(body)) = (class.child_by_field_name("name"), class.child_by_field_name("body")) else {
+            return;
+        };
+        let class_name = self.get_node_text(&class_name);
 
-        // Check for async
-        if let Some(prev) = node.prev_sibling() {
-            if prev.kind() == "async" {
-                is_async = true;
+        let mut cursor = body.walk();
+        for child in body.children(&mut cursor) {
+            let method = self.unwrap_decorated(child);
+            if method.kind() != "function_definition" {
+                continue;
+            }
+            if let Some(name) = method.child_by_field_name("name") {
+                let scope = format!("method_{}_{}", class_name, self.get_node_text(&name));
+                self.extract_nested_functions(&method, &scope, functions);
             }
         }
+    }
+
+    /// `@decorator`-wrapped definitions hold the function or class in their
+    /// `definition` field, with the decorators as its preceding siblings
+    fn unwrap_decorated<'a>(&self, node: Node<'a>) -> Node<'a> {
+        if node.kind() == "decorated_definition" {
+            node.child_by_field_name("definition").unwrap_or(node)
+        } else {
+            node
+        }
+    }
 
-        // Extract decorators
+    /// The run of `decorator` nodes directly above a definition, top to
+    /// bottom. Comments inside the run are skipped; anything else ends it.
+    fn collect_decorators(&self, node: &Node) -> Vec<String> {
         let mut decorators = Vec::new();
         let mut current = node.prev_sibling();
         while let Some(sibling) = current {
-            if sibling.kind() == "decorator" {
-                decorators.insert(0, self.get_node_text(&sibling));
+            match sibling.kind() {
+                "decorator" => decorators.push(Self::logical_line(&self.get_node_text(&sibling))),
+                "comment" => {}
+                _ => break,
             }
             current = sibling.prev_sibling();
         }
+        decorators.reverse();
+        decorators
+    }
+
+    /// Join a decorator whose arguments span several lines into one line:
+    /// `@app.route(\n    "/x",\n)` becomes `@app.route("/x")`
+    fn logical_line(text: &str) -> String {
+        let mut line = String::new();
+        for part in text.lines().map(str::trim) {
+            if part.is_empty() || part.starts_with('#') {
+                continue;
+            }
+            if part.starts_with([')', ']', '}']) {
+                if line.ends_with(',') {
+                    line.pop();
+                }
+            } else if !line.is_empty() && !line.ends_with(['(', '[', '{']) {
+                line.push(' ');
+            }
+            line.push_str(part);
+        }
+        line
+    }
+
+    /// Closures and decorator wrappers defined inside a function body. Ids are
+    /// prefixed with the enclosing function's id (`func_outer_inner`) so a
+    /// common helper name like `wrapper` doesn't collide across functions.
+    fn extract_nested_functions(&self, node: &Node, scope: &str, functions: &mut Vec<Function>) {
+        let mut cursor = node.walk();
+
+        for child in node.children(&mut cursor) {
+            match child.kind() {
+                "function_definition" => {
+                    if let Some(mut func) = self.parse_function(&child, "") {
+                        func.id = format!("{}_{}", scope, func.name);
+                        let inner_scope = func.id.clone();
+                        functions.push(func);
+                        self.extract_nested_functions(&child, &inner_scope, functions);
+                    }
+                }
+                // Methods of locally defined classes aren't module functions
+                "class_definition" => {}
+                _ => self.extract_nested_functions(&child, scope, functions),
+            }
+        }
+    }
+
+    fn parse_function(&self, node: &Node, class_context: &str) -> Option<Function> {
+        let mut cursor = node.walk();
+        let mut name = String::new();
+
+        // `async` is the function_definition's own first token, not a sibling
+        let is_async = node.child(0).map(|c| c.kind() == "async").unwrap_or(false);
+
+        let decorators = self.collect_decorators(node);
 
         for child in node.children(&mut cursor) {
             if child.kind() == "identifier" && name.is_empty() {
@@ -178,7 +290,7 @@ impl PythonParser {
         let calls = self.extract_function_calls_detailed(node);
 
         // Extract variables and data flow
-        let variables = self.extract_variables(node, &params);
+        let variables = self.extract_variables(node, &params, &calls);
 
         // Build control flow
         let control_flow = self.build_control_flow(node);
@@ -194,11 +306,26 @@ impl PythonParser {
             format!("method_{}_{}", class_context, name)
         };
 
-        // Auto-tag functions
-        let tags = self.auto_tag_function(&name, &docstring, &calls);
+        // Auto-tag functions; `async with`/`async for` make a coroutine even
+        // when nothing in it is awaited directly
+        let uses_async = is_async
+            || control_flow.loops.iter().any(|l| l.is_async)
+            || control_flow.with_blocks.iter().any(|w| w.is_async);
+        let mut tags = self.auto_tag_function(&name, &docstring, &calls, uses_async);
+        let is_test = Self::is_test_function(&name, &decorators);
+        if is_test {
+            tags.push("test".to_string());
+            tags.sort();
+        }
 
         // Calculate importance (placeholder, will be refined later)
-        let importance_score = self.estimate_importance(&name, &decorators);
+        let importance_score = self.estimate_importance(&name, &decorators, is_test);
+
+        let method_kind = if class_context.is_empty() {
+            MethodKind::Instance
+        } else {
+            self.method_kind(&decorators)
+        };
 
         Some(Function {
             id,
@@ -206,6 +333,7 @@ impl PythonParser {
             signature,
             params,
             return_type,
+            return_types: vec![],
             docstring,
             line_start,
             line_end,
@@ -216,9 +344,13 @@ impl PythonParser {
             exceptions,
             complexity,
             is_async,
+            needs_refactor: false,
+            spawns_goroutines: false,
+            method_kind,
             decorators,
             tags,
             importance_score,
+            source: None,
         })
     }
 
@@ -320,6 +452,26 @@ impl PythonParser {
         }
     }
 
+    /// Calls made while the module is imported, skipping definitions and
+    /// imports; bodies of functions and classes are covered per function
+    fn extract_module_calls(&self, root: &Node) -> Vec<FunctionCall> {
+        let mut calls = Vec::new();
+        let mut seen = HashSet::new();
+        let mut cursor = root.walk();
+
+        for child in root.children(&mut cursor) {
+            if !matches!(
+                child.kind(),
+                "function_definition" | "class_definition" | "decorated_definition"
+                    | "import_statement" | "import_from_statement"
+            ) {
+                self.find_calls_recursive(&child, &mut calls, &mut seen, "unconditional");
+            }
+        }
+
+        calls
+    }
+
     // Extract function calls with detailed context
     fn extract_function_calls_detailed(&self, node: &Node) -> Vec<FunctionCall> {
         let mut calls = Vec::new();
@@ -340,18 +492,23 @@ impl PythonParser {
             "while_statement" | "for_statement" => "loop",
             "try_statement" => "try",
             "except_clause" => "except",
+            // Comprehension bodies run once per item (possibly never), lambda
+            // bodies only when the lambda is eventually invoked
+            "list_comprehension" | "set_comprehension" | "dictionary_comprehension" | "generator_expression" => {
+                "comprehension"
+            }
+            "if_clause" => "if",
+            "lambda" => "lambda",
             _ => context,
         };
 
         if node.kind() == "call" {
             if let Some(func_node) = node.child_by_field_name("function") {
                 if let Ok(call_name) = func_node.utf8_text(self.source_code.as_bytes()) {
-                    let name = call_name
-                        .split('.')
-                        .last()
-                        .unwrap_or(call_name)
-                        .trim()
-                        .to_string();
+                    let (receiver, name) = match call_name.rsplit_once('.') {
+                        Some((receiver, name)) => (Some(receiver.trim().to_string()), name.trim().to_string()),
+                        None => (None, call_name.trim().to_string()),
+                    };
 
                     if !name.is_empty() {
                         let key = format!("{}:{}", name, node.start_position().row);
@@ -363,11 +520,14 @@ impl PythonParser {
 
                             calls.push(FunctionCall {
                                 callee: name,
+                                receiver,
                                 defined_in: None, // Will be resolved in post-processing
+                                candidates: vec![],
                                 line: node.start_position().row + 1,
                                 args,
                                 is_conditional: context != "unconditional",
                                 context: context.to_string(),
+                                is_indirect: false,
                             });
                         }
                     }
@@ -380,23 +540,32 @@ impl PythonParser {
         }
     }
 
+    /// Source text of each argument, one per position: `foo(a, b=c, *d)`
+    /// gives `["a", "b=c", "*d"]`. A bare generator argument
+    /// (`sum(x for x in xs)`) is a single argument.
     fn extract_call_arguments(&self, call_node: &Node) -> Vec<String> {
-        let mut args = Vec::new();
+        let Some(arg_list) = call_node.child_by_field_name("arguments") else {
+            return vec![];
+        };
 
-        if let Some(arg_list) = call_node.child_by_field_name("arguments") {
-            let mut cursor = arg_list.walk();
-            for child in arg_list.children(&mut cursor) {
-                if child.kind() == "identifier" || child.kind() == "string" || child.kind() == "integer" {
-                    args.push(self.get_node_text(&child));
-                }
-            }
+        // The generator's parentheses double as the call's
+        if arg_list.kind() == "generator_expression" {
+            let text = self.get_node_text(&arg_list);
+            let inner = text.strip_prefix('(').and_then(|t| t.strip_suffix(')')).unwrap_or(&text);
+            return vec![inner.to_string()];
         }
 
+        let mut cursor = arg_list.walk();
+        let args = arg_list
+            .named_children(&mut cursor)
+            .filter(|child| child.kind() != "comment")
+            .map(|child| self.get_node_text(&child))
+            .collect();
         args
     }
 
     // Extract variables and track transformations
-    fn extract_variables(&self, node: &Node, params: &[Parameter]) -> Vec<Variable> {
+    fn extract_variables(&self, node: &Node, params: &[Parameter], calls: &[FunctionCall]) -> Vec<Variable> {
         let mut variables: HashMap<String, Variable> = HashMap::new();
 
         // Add parameters as variables
@@ -419,7 +588,9 @@ impl PythonParser {
         // Track assignments and usage
         self.track_variable_usage(node, &mut variables);
 
-        variables.into_values().collect()
+        let mut variables: Vec<Variable> = variables.into_values().collect();
+        link_call_arguments(&mut variables, calls);
+        variables
     }
 
     fn track_variable_usage(&self, node: &Node, variables: &mut HashMap<String, Variable>) {
@@ -494,9 +665,11 @@ impl PythonParser {
     fn build_control_flow(&self, node: &Node) -> ControlFlow {
         let mut control_flow = ControlFlow {
             complexity: self.calculate_complexity(node),
+            cognitive_complexity: self.calculate_cognitive_complexity(node),
             branches: vec![],
             loops: vec![],
             try_blocks: vec![],
+            with_blocks: vec![],
         };
 
         self.extract_control_structures(node, &mut control_flow);
@@ -512,6 +685,9 @@ impl PythonParser {
                     cf.branches.push(branch);
                 }
             }
+            "match_statement" => {
+                cf.branches.extend(self.parse_match_statement(node));
+            }
             "while_statement" | "for_statement" => {
                 if let Some(loop_info) = self.parse_loop(node) {
                     cf.loops.push(loop_info);
@@ -522,6 +698,9 @@ impl PythonParser {
                     cf.try_blocks.push(try_block);
                 }
             }
+            "with_statement" => {
+                cf.with_blocks.push(self.parse_with_statement(node));
+            }
             _ => {}
         }
 
@@ -546,6 +725,49 @@ impl PythonParser {
         })
     }
 
+    /// One "match" branch per `case`, conditioned on the subject and pattern
+    fn parse_match_statement(&self, node: &Node) -> Vec<Branch> {
+        let mut branches = Vec::new();
+        let subject = node.child_by_field_name("subject")
+            .map(|n| self.get_node_text(&n))
+            .unwrap_or_default();
+
+        let Some(body) = node.child_by_field_name("body") else {
+            return branches;
+        };
+
+        let mut cursor = body.walk();
+        for case in body.named_children(&mut cursor) {
+            if case.kind() != "case_clause" {
+                continue;
+            }
+
+            let mut pattern_cursor = case.walk();
+            let mut pattern = case
+                .named_children(&mut pattern_cursor)
+                .filter(|c| c.kind() == "case_pattern")
+                .map(|c| self.get_node_text(&c))
+                .collect::<Vec<_>>()
+                .join(", ");
+            if let Some(guard) = case.child_by_field_name("guard") {
+                pattern.push(' ');
+                pattern.push_str(&self.get_node_text(&guard));
+            }
+
+            if let Some(true_path) = self.extract_execution_path(&case, "consequence") {
+                branches.push(Branch {
+                    branch_type: "match".to_string(),
+                    condition: format!("{} case {}", subject, pattern),
+                    line: case.start_position().row + 1,
+                    true_path,
+                    false_path: None,
+                });
+            }
+        }
+
+        branches
+    }
+
     fn extract_condition(&self, node: &Node) -> Option<String> {
         if let Some(cond_node) = node.child_by_field_name("condition") {
             Some(self.get_node_text(&cond_node))
@@ -627,6 +849,7 @@ impl PythonParser {
             condition,
             line,
             calls,
+            is_async: node.child(0).is_some_and(|c| c.kind() == "async"),
         })
     }
 
@@ -644,6 +867,41 @@ impl PythonParser {
         })
     }
 
+    fn parse_with_statement(&self, node: &Node) -> WithBlock {
+        let mut managers = Vec::new();
+        let mut cursor = node.walk();
+
+        for clause in node.named_children(&mut cursor).filter(|c| c.kind() == "with_clause") {
+            let mut item_cursor = clause.walk();
+            for item in clause.named_children(&mut item_cursor) {
+                let Some(value) = item.child_by_field_name("value") else {
+                    continue;
+                };
+
+                // `open(f) as fh` parses as an as_pattern wrapping the manager
+                let manager = if value.kind() == "as_pattern" {
+                    let alias = value.child_by_field_name("alias").map(|a| self.get_node_text(&a));
+                    let expression = value.named_child(0).map(|e| self.get_node_text(&e)).unwrap_or_default();
+                    ContextManager { expression, alias }
+                } else {
+                    ContextManager { expression: self.get_node_text(&value), alias: None }
+                };
+                managers.push(manager);
+            }
+        }
+
+        let calls = node.child_by_field_name("body")
+            .map(|body| self.extract_calls_from_block(&body))
+            .unwrap_or_default();
+
+        WithBlock {
+            line: node.start_position().row + 1,
+            managers,
+            calls,
+            is_async: node.child(0).is_some_and(|c| c.kind() == "async"),
+        }
+    }
+
     fn extract_except_clauses(&self, node: &Node) -> Vec<ExceptClause> {
         let mut clauses = Vec::new();
         let mut cursor = node.walk();
@@ -692,7 +950,12 @@ impl PythonParser {
 
         match node.kind() {
             "raise_statement" => {
-                if let Some(exc) = node.child(1) {
+                // raise ValueError("bad") -> ValueError; a bare re-raise has no value
+                if let Some(exc) = node.named_child(0) {
+                    let exc = match exc.kind() {
+                        "call" => exc.child_by_field_name("function").unwrap_or(exc),
+                        _ => exc,
+                    };
                     let exc_type = self.get_node_text(&exc);
                     if !info.raises.contains(&exc_type) {
                         info.raises.push(exc_type);
@@ -700,8 +963,13 @@ impl PythonParser {
                 }
             }
             "except_clause" => {
-                if let Some(exc) = node.child(1) {
-                    let exc_type = self.get_node_text(&exc);
+                let caught = node.named_child(0).filter(|c| c.kind() != "block");
+                let exc_types = match caught {
+                    Some(exc) => self.caught_exception_types(&exc),
+                    // A bare `except:` catches everything
+                    None => vec!["BaseException".to_string()],
+                };
+                for exc_type in exc_types {
                     if !info.handles.contains(&exc_type) {
                         info.handles.push(exc_type);
                     }
@@ -715,11 +983,29 @@ impl PythonParser {
         }
     }
 
+    /// `ValueError as e` -> [ValueError], `(KeyError, TypeError)` -> both
+    fn caught_exception_types(&self, node: &Node) -> Vec<String> {
+        match node.kind() {
+            "as_pattern" => node
+                .named_child(0)
+                .map(|exc| self.caught_exception_types(&exc))
+                .unwrap_or_default(),
+            "tuple" | "parenthesized_expression" => {
+                let mut cursor = node.walk();
+                node.named_children(&mut cursor)
+                    .flat_map(|exc| self.caught_exception_types(&exc))
+                    .collect()
+            }
+            _ => vec![self.get_node_text(node)],
+        }
+    }
+
     fn extract_classes(&self, root: &Node) -> Vec<Class> {
         let mut classes = Vec::new();
         let mut cursor = root.walk();
 
         for child in root.children(&mut cursor) {
+            let child = self.unwrap_decorated(child);
             if child.kind() == "class_definition" {
                 if let Some(class) = self.parse_class(&child) {
                     classes.push(class);
@@ -736,16 +1022,7 @@ impl PythonParser {
         let mut bases = Vec::new();
         let mut methods = Vec::new();
         let mut attributes = Vec::new();
-        let mut decorators = Vec::new();
-
-        // Extract decorators
-        let mut current = node.prev_sibling();
-        while let Some(sibling) = current {
-            if sibling.kind() == "decorator" {
-                decorators.insert(0, self.get_node_text(&sibling));
-            }
-            current = sibling.prev_sibling();
-        }
+        let decorators = self.collect_decorators(node);
 
         for child in node.children(&mut cursor) {
             match child.kind() {
@@ -784,24 +1061,53 @@ impl PythonParser {
             methods,
             attributes,
             decorators,
+            type_params: vec![],
         })
     }
 
+    /// Positional arguments of the class's argument list; keywords such as
+    /// `metaclass=ABCMeta` or TypedDict's `total=False` aren't bases
     fn extract_base_classes(&self, node: &Node) -> Vec<String> {
-        let text = self.get_node_text(node);
-        text.trim_matches(|c| c == '(' || c == ')')
-            .split(',')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
+        let mut cursor = node.walk();
+        node.named_children(&mut cursor)
+            .filter(|arg| !matches!(arg.kind(), "keyword_argument" | "comment"))
+            .map(|arg| self.get_node_text(&arg))
             .collect()
     }
 
+    /// `@property`, `@cached_property` and `@x.setter` make a property,
+    /// `@staticmethod`/`@classmethod` change the binding; anything else is
+    /// an ordinary instance method
+    fn method_kind(&self, decorators: &[String]) -> MethodKind {
+        for decorator in decorators {
+            let callee = decorator
+                .trim_start_matches('@')
+                .split('(')
+                .next()
+                .unwrap_or("")
+                .trim();
+            let last = callee.rsplit('.').next().unwrap_or(callee);
+
+            match last {
+                "staticmethod" => return MethodKind::Static,
+                "classmethod" => return MethodKind::Class,
+                "property" | "cached_property" | "setter" | "getter" | "deleter" => {
+                    return MethodKind::Property;
+                }
+                _ => {}
+            }
+        }
+
+        MethodKind::Instance
+    }
+
     fn parse_class_body(&self, node: &Node, class_name: &str) -> (Vec<Function>, Vec<Attribute>) {
         let mut methods = Vec::new();
         let mut attributes = Vec::new();
         let mut cursor = node.walk();
 
         for child in node.children(&mut cursor) {
+            let child = self.unwrap_decorated(child);
             match child.kind() {
                 "function_definition" => {
                     if let Some(method) = self.parse_function(&child, class_name) {
@@ -837,6 +1143,50 @@ impl PythonParser {
         None
     }
 
+    /// The module's public surface from `__all__ = [...]` (or a tuple),
+    /// including later `__all__ += [...]` extensions. Only string literals
+    /// are collected; computed entries can't be resolved statically.
+    fn extract_exports(&self, root: &Node) -> Option<Vec<String>> {
+        let mut exports: Option<Vec<String>> = None;
+        let mut cursor = root.walk();
+
+        for child in root.children(&mut cursor) {
+            if child.kind() != "expression_statement" {
+                continue;
+            }
+            let Some(assignment) = child.named_child(0) else {
+                continue;
+            };
+            if !matches!(assignment.kind(), "assignment" | "augmented_assignment") {
+                continue;
+            }
+            let is_all = assignment.child_by_field_name("left")
+                .is_some_and(|left| self.get_node_text(&left) == "__all__");
+            let Some(right) = assignment.child_by_field_name("right").filter(|_| is_all) else {
+                continue;
+            };
+            if !matches!(right.kind(), "list" | "tuple") {
+                continue;
+            }
+
+            let names = exports.get_or_insert_with(Vec::new);
+            if assignment.kind() == "assignment" {
+                names.clear();
+            }
+            let mut list_cursor = right.walk();
+            for item in right.named_children(&mut list_cursor) {
+                if item.kind() == "string" {
+                    let name = self.get_node_text(&item).trim_matches(|c| c == '"' || c == '\'').to_string();
+                    if !name.is_empty() && !names.contains(&name) {
+                        names.push(name);
+                    }
+                }
+            }
+        }
+
+        exports
+    }
+
     fn extract_global_vars(&self, root: &Node) -> Vec<GlobalVar> {
         let mut vars = Vec::new();
         let mut cursor = root.walk();
@@ -852,6 +1202,118 @@ impl PythonParser {
         vars
     }
 
+    /// `UserId = int`, `Vec = List[float]`, `Handler: TypeAlias = ...`,
+    /// `type Pair = tuple[int, int]`, and `NewType`, `NamedTuple` and
+    /// `TypedDict` calls. A bare assignment only counts when the name is
+    /// capitalized and the value is built from builtin or `typing` types, so
+    /// `Config = load()` stays a global var.
+    fn extract_type_aliases(&self, root: &Node) -> Vec<TypeAlias> {
+        let mut aliases = Vec::new();
+        let mut cursor = root.walk();
+
+        for child in root.children(&mut cursor) {
+            let line = child.start_position().row + 1;
+            if child.kind() == "type_alias_statement" {
+                let mut parts = child.walk();
+                let types: Vec<Node> = child.named_children(&mut parts).collect();
+                if let [name, target] = types.as_slice() {
+                    let name = self.get_node_text(name);
+                    aliases.push(TypeAlias {
+                        name: name.split('[').next().unwrap_or(&name).trim().to_string(),
+                        target: self.get_node_text(target),
+                        kind: "alias".to_string(),
+                        line,
+                    });
+                }
+                continue;
+            }
+
+            let Some(assignment) = child.named_child(0).filter(|_| child.kind() == "expression_statement") else {
+                continue;
+            };
+            let (Some(left), Some(right)) = (
+                assignment.child_by_field_name("left").filter(|l| l.kind() == "identifier"),
+                assignment.child_by_field_name("right"),
+            ) else {
+                continue;
+            };
+            if assignment.kind() != "assignment" {
+                continue;
+            }
+            let name = self.get_node_text(&left);
+
+            let kind = match assignment.child_by_field_name("type") {
+                Some(annotation) => {
+                    let annotation = self.get_node_text(&annotation);
+                    (Self::last_segment(&annotation) == "TypeAlias").then_some("alias")
+                }
+                None if right.kind() == "call" => right
+                    .child_by_field_name("function")
+                    .and_then(|f| match Self::last_segment(&self.get_node_text(&f)) {
+                        "NewType" => Some("newtype"),
+                        "NamedTuple" | "namedtuple" => Some("namedtuple"),
+                        "TypedDict" => Some("typeddict"),
+                        _ => None,
+                    }),
+                None => {
+                    let capitalized = name.starts_with(|c: char| c.is_ascii_uppercase())
+                        && name.chars().any(|c| c.is_ascii_lowercase());
+                    (capitalized && self.is_type_expression(&right, false)).then_some("alias")
+                }
+            };
+
+            if let Some(kind) = kind {
+                aliases.push(TypeAlias {
+                    name,
+                    target: self.get_node_text(&right),
+                    kind: kind.to_string(),
+                    line,
+                });
+            }
+        }
+
+        aliases
+    }
+
+    /// Whether an expression only names types: `int`, `typing.Any`,
+    /// `Optional[str]`, `dict[str, list[int]]`, `int | None`
+    fn is_type_expression(&self, node: &Node, in_union: bool) -> bool {
+        const BUILTIN_TYPES: &[&str] = &[
+            "int", "str", "float", "bool", "bytes", "complex", "object", "list", "dict", "set",
+            "frozenset", "tuple", "type", "bytearray",
+        ];
+        const TYPING_NAMES: &[&str] = &[
+            "Any", "List", "Dict", "Set", "FrozenSet", "Tuple", "Type", "Optional", "Union",
+            "Callable", "Literal", "Annotated", "Iterable", "Iterator", "Sequence", "Mapping",
+            "MutableMapping", "MutableSequence", "Generator", "AsyncIterator", "Awaitable",
+            "Coroutine", "DefaultDict", "OrderedDict", "Counter", "Deque", "ClassVar", "Final",
+        ];
+
+        match node.kind() {
+            "identifier" => {
+                let name = self.get_node_text(node);
+                BUILTIN_TYPES.contains(&name.as_str()) || TYPING_NAMES.contains(&name.as_str())
+            }
+            "attribute" => TYPING_NAMES.contains(&Self::last_segment(&self.get_node_text(node))),
+            "none" => in_union,
+            "subscript" => node.child_by_field_name("value").is_some_and(|value| {
+                let value = self.get_node_text(&value);
+                let value = Self::last_segment(&value);
+                BUILTIN_TYPES.contains(&value) || TYPING_NAMES.contains(&value)
+            }),
+            "binary_operator" => {
+                let is_union = node.child_by_field_name("operator").is_some_and(|op| op.kind() == "|");
+                let side = |field| node.child_by_field_name(field).is_some_and(|n| self.is_type_expression(&n, true));
+                is_union && side("left") && side("right")
+            }
+            _ => false,
+        }
+    }
+
+    fn last_segment(path: &str) -> &str {
+        path.rsplit('.').next().unwrap_or(path).trim()
+    }
+
     fn parse_global_var(&self, node: &Node) -> Option<GlobalVar> {
         let text = self.get_node_text(node);
         let line = node.start_position().row + 1;
@@ -933,7 +1395,7 @@ impl PythonParser {
             match node.kind() {
                 "if_statement" | "elif_clause" | "while_statement" |
                 "for_statement" | "except_clause" | "with_statement" |
-                "and" | "or" => {
+                "match_statement" | "case_clause" | "and" | "or" => {
                     count += 1;
                 }
                 _ => {}
@@ -950,6 +1412,55 @@ impl PythonParser {
         complexity
     }
 
+    /// Cognitive complexity: like cyclomatic complexity, but nested control
+    /// structures cost more the deeper they sit
+    fn calculate_cognitive_complexity(&self, node: &Node) -> usize {
+        fn operator(node: &Node) -> Option<&'static str> {
+            node.child_by_field_name("operator").map(|op| op.kind())
+        }
+
+        fn continues_sequence(node: &Node) -> bool {
+            node.parent()
+                .map(|p| p.kind() == "boolean_operator" && operator(&p) == operator(node))
+                .unwrap_or(false)
+        }
+
+        fn walk(node: &Node, nesting: usize) -> usize {
+            let mut score = 0;
+            let mut child_nesting = nesting;
+
+            match node.kind() {
+                "if_statement" | "for_statement" | "while_statement" |
+                "except_clause" | "match_statement" | "conditional_expression" => {
+                    score += 1 + nesting;
+                    child_nesting += 1;
+                }
+                "elif_clause" | "else_clause" => {
+                    score += 1;
+                }
+                "function_definition" | "lambda" => {
+                    child_nesting += 1;
+                }
+                // Each run of the same operator counts once: `a and b and c` is +1
+                "boolean_operator" if !continues_sequence(node) => {
+                    score += 1;
+                }
+                _ => {}
+            }
+
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                score += walk(&child, child_nesting);
+            }
+
+            score
+        }
+
+        // Start from the children so the function itself doesn't count as nesting
+        let mut cursor = node.walk();
+        node.children(&mut cursor).map(|child| walk(&child, 0)).sum()
+    }
+
     fn extract_todos(&self) -> Vec<Todo> {
         let re = Regex::new(r"#\s*TODO:?\s*(.+)").unwrap();
 
@@ -1010,7 +1521,7 @@ impl PythonParser {
     }
 
     // Auto-tag functions based on name and behavior
-    fn auto_tag_function(&self, name: &str, docstring: &str, calls: &[FunctionCall]) -> Vec<String> {
+    fn auto_tag_function(&self, name: &str, docstring: &str, calls: &[FunctionCall], is_async: bool) -> Vec<String> {
         let mut tags = Vec::new();
         let name_lower = name.to_lowercase();
         let doc_lower = docstring.to_lowercase();
@@ -1055,15 +1566,6 @@ impl PythonParser {
             tags.push("http-handler".to_string());
         }
 
-        // Database
-        if name_lower.contains("db") || name_lower.contains("database") ||
-            name_lower.contains("query") || name_lower.contains("select") ||
-            name_lower.contains("insert") || name_lower.contains("update") ||
-            name_lower.contains("delete") || name_lower.contains("save") ||
-            name_lower.contains("fetch") || name_lower.contains("find") {
-            tags.push("database".to_string());
-        }
-
         // Validation
         if name_lower.contains("validate") || name_lower.contains("check") ||
             name_lower.contains("verify") || name_lower.contains("sanitize") {
@@ -1121,7 +1623,7 @@ impl PythonParser {
         }
 
         // Async/Await
-        if calls.iter().any(|c| c.callee.contains("await") || c.callee.contains("async")) ||
+        if is_async || calls.iter().any(|c| c.callee.contains("await") || c.callee.contains("async")) ||
             name_lower.contains("async") {
             tags.push("async".to_string());
             tags.push("coroutine".to_string());
@@ -1190,8 +1692,15 @@ impl PythonParser {
         tags
     }
 
+    /// pytest collects `test_*` functions; `@pytest.*` decorators (fixtures,
+    /// marks) also only appear on test code
+    fn is_test_function(name: &str, decorators: &[String]) -> bool {
+        name.starts_with("test_")
+            || decorators.iter().any(|d| d.trim_start_matches('@').trim_start().starts_with("pytest."))
+    }
+
     // Estimate function importance
-    fn estimate_importance(&self, name: &str, decorators: &[String]) -> f32 {
+    fn estimate_importance(&self, name: &str, decorators: &[String], is_test: bool) -> f32 {
         let mut score: f32 = 0.5; // Base score
 
         // Entry points are important
@@ -1214,6 +1723,11 @@ impl PythonParser {
             score -= 0.2;
         }
 
+        // Tests rarely answer questions about how the code works
+        if is_test {
+            score -= 0.3;
+        }
+
         score.max(0.0).min(1.0)
     }
 
@@ -1224,9 +1738,11 @@ impl PythonParser {
     }
 }
 
-pub fn parse_file(path: &Path) -> Result<(String, FileData), String> {
+pub fn parse_file(path: &Path) -> Result<(String, FileData), ParseError> {
+    // Normalize CRLF so captured text (docstrings, signatures, TODOs) has no stray '\r'
     let source_code = std::fs::read_to_string(path)
-        .map_err(|e| format!("Failed to read file {}: {}", path.display(), e))?;
+        .map_err(|e| ParseError::io(path, e))?
+        .replace("\r\n", "\n");
 
     let parser = PythonParser::new(source_code);
     let file_data = parser.parse()?;
@@ -1235,3 +1751,389 @@ pub fn parse_file(path: &Path) -> Result<(String, FileData), String> {
 
     Ok((relative_path, file_data))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> FileData {
+        PythonParser::new(source.to_string()).parse().unwrap()
+    }
+
+    #[test]
+    fn test_cognitive_complexity_weights_nesting() {
+        let data = parse(
+            "def flat(a, b, c):\n    if a:\n        pass\n    if b:\n        pass\n    if c and a:\n        pass\n\n\
+             def nested(items):\n    for x in items:\n        if x:\n            if x > 1 and x < 5:\n                pass\n",
+        );
+
+        let flat = &data.functions[0];
+        let nested = &data.functions[1];
+        assert_eq!(flat.complexity, nested.complexity);
+        assert_eq!(flat.control_flow.cognitive_complexity, 4);
+        // for (+1) + if (+2) + if (+3) + boolean sequence (+1)
+        assert_eq!(nested.control_flow.cognitive_complexity, 7);
+    }
+
+    #[test]
+    fn test_star_import_and_all_exports() {
+        let data = parse(
+            "from .utils import *\nfrom os.path import join, exists\n\n\
+             __all__ = [\"foo\", 'bar']\n\n\
+             def foo():\n    pass\n\n\
+             def bar():\n    pass\n\n\
+             def _helper():\n    pass\n",
+        );
+
+        let star = &data.imports[0];
+        assert_eq!(star.module, ".utils");
+        assert_eq!(star.items, vec!["*"]);
+        assert!(star.is_wildcard);
+        assert_eq!(data.imports[1].items, vec!["join", "exists"]);
+        assert!(!data.imports[1].is_wildcard);
+
+        assert_eq!(data.exports, Some(vec!["foo".to_string(), "bar".to_string()]));
+        assert_eq!(parse("def foo():\n    pass\n").exports, None);
+    }
+
+    #[test]
+    fn test_test_functions_are_tagged() {
+        let data = parse(
+            "def login(user):\n    return user\n\n\
+             def test_login():\n    assert login('a')\n\n\
+             @pytest.fixture\ndef client():\n    return None\n\n\
+             def testimony():\n    pass\n",
+        );
+        let function = |name: &str| data.functions.iter().find(|f| f.name == name).unwrap();
+
+        let test = function("test_login");
+        assert!(test.tags.contains(&"test".to_string()));
+        assert!(test.importance_score < function("login").importance_score);
+        assert!(function("client").tags.contains(&"test".to_string()));
+        assert!(!function("login").tags.contains(&"test".to_string()));
+        assert!(!function("testimony").tags.contains(&"test".to_string()));
+    }
+
+    #[test]
+    fn test_stacked_and_multiline_decorators() {
+        let data = parse(
+            "@login_required\n# only admins\n@app.route(\n    \"/users/<id>\",\n    methods=[\n        \"GET\",\n        \"POST\",\n    ],\n)\n\
+             def user(id):\n    pass\n\n\
+             x = 1\n\n\
+             def plain():\n    pass\n",
+        );
+
+        let user = &data.functions[0];
+        assert_eq!(
+            user.decorators,
+            vec!["@login_required", "@app.route(\"/users/<id>\", methods=[\"GET\", \"POST\"])"]
+        );
+        assert!(data.functions[1].decorators.is_empty());
+    }
+
+    #[test]
+    fn test_type_aliases_leave_global_vars() {
+        let data = parse(
+            "from typing import List, NewType, TypeAlias, TypedDict\n\n\
+             UserId = int\nVec = List[float]\nMaybeName = str | None\n\
+             Handler: TypeAlias = \"Callable[[int], None]\"\nOrderId = NewType(\"OrderId\", int)\n\
+             type Pair[T] = tuple[T, T]\nMAX_SIZE = 10\nConfig = load()\nDefault = None\n",
+        );
+
+        let aliases: Vec<(&str, &str, &str)> = data.type_aliases
+            .iter()
+            .map(|a| (a.name.as_str(), a.kind.as_str(), a.target.as_str()))
+            .collect();
+        assert_eq!(aliases, vec![
+            ("UserId", "alias", "int"),
+            ("Vec", "alias", "List[float]"),
+            ("MaybeName", "alias", "str | None"),
+            ("Handler", "alias", "\"Callable[[int], None]\""),
+            ("OrderId", "newtype", "NewType(\"OrderId\", int)"),
+            ("Pair", "alias", "tuple[T, T]"),
+        ]);
+
+        let globals: Vec<&str> = data.global_vars.iter().map(|v| v.name.as_str()).collect();
+        assert_eq!(globals, vec!["MAX_SIZE", "Config", "Default"]);
+    }
+
+    #[test]
+    fn test_typed_dict_and_named_tuple_classes() {
+        let data = parse(
+            "class Movie(TypedDict, total=False):\n    title: str\n    year: int\n\n\
+             class Point(NamedTuple):\n    x: float\n    y: float = 0.0\n\n\
+             class Cache(Dict[str, int], metaclass=ABCMeta):\n    pass\n",
+        );
+
+        let movie = &data.classes[0];
+        assert_eq!(movie.bases, vec!["TypedDict"]);
+        let fields: Vec<(&str, &str)> = movie.attributes
+            .iter()
+            .map(|a| (a.name.as_str(), a.type_annotation.as_str()))
+            .collect();
+        assert_eq!(fields, vec![("title", "str"), ("year", "int")]);
+
+        assert_eq!(data.classes[1].bases, vec!["NamedTuple"]);
+        assert_eq!(data.classes[1].attributes[1].value.as_deref(), Some("0.0"));
+        assert_eq!(data.classes[2].bases, vec!["Dict[str, int]"]);
+    }
+
+    #[test]
+    fn test_crlf_source_is_normalized() {
+        let path = std::env::temp_dir().join(format!("eulix_crlf_{}.py", std::process::id()));
+        std::fs::write(
+            &path,
+            "# TODO: clean up\r\ndef greet(name):\r\n    \"\"\"Say hello.\"\"\"\r\n    return name\r\n",
+        )
+        .unwrap();
+
+        let result = parse_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        let (_, data) = result.unwrap();
+
+        let func = &data.functions[0];
+        assert_eq!(func.docstring, "Say hello.");
+        assert!(!func.signature.contains('\r'));
+        assert_eq!(data.todos[0].text, "clean up");
+        assert_eq!(data.loc, 4);
+    }
+
+    #[test]
+    fn test_async_function_detected() {
+        let data = parse("async def fetch(url):\n    return await get(url)\n\ndef sync():\n    pass\n");
+
+        assert!(data.functions[0].is_async);
+        assert!(data.functions[0].signature.starts_with("async def"));
+        assert!(data.functions[0].tags.contains(&"async".to_string()));
+        assert!(!data.functions[1].is_async);
+    }
+
+    #[test]
+    fn test_multi_module_import() {
+        let data = parse("import os, sys, collections\nimport numpy as np, requests\n");
+
+        let modules: Vec<(&str, &str)> = data.imports
+            .iter()
+            .map(|i| (i.module.as_str(), i.import_type.as_str()))
+            .collect();
+        assert_eq!(modules, vec![
+            ("os", "stdlib"),
+            ("sys", "stdlib"),
+            ("collections", "stdlib"),
+            ("numpy", "external"),
+            ("requests", "external"),
+        ]);
+    }
+
+    #[test]
+    fn test_nested_functions() {
+        let data = parse(
+            "def outer(items):\n    def inner(x):\n        return transform(x)\n\n\
+             \x20   if items:\n        def deepest():\n            pass\n    return [inner(i) for i in items]\n",
+        );
+
+        let ids: Vec<&str> = data.functions.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(ids, vec!["func_outer", "func_outer_inner", "func_outer_deepest"]);
+
+        let inner = &data.functions[1];
+        assert_eq!(inner.name, "inner");
+        assert_eq!(inner.line_start, 2);
+        assert!(inner.calls.iter().any(|c| c.callee == "transform"));
+    }
+
+    #[test]
+    fn test_nested_functions_in_methods() {
+        let source = "class Cache:\n    @staticmethod\n    def memoize(fn):\n        def wrapper(*args):\n            return fn(*args)\n\
+             \x20       return wrapper\n\n    def clear(self):\n        pass\n";
+        let mut parser = Parser::new();
+        parser.set_language(tree_sitter_python::language()).unwrap();
+        assert!(!parser.parse(source, None).unwrap().root_node().has_error());
+        let data = parse(source);
+
+        let ids: Vec<&str> = data.functions.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(ids, vec!["method_Cache_memoize_wrapper"]);
+        assert_eq!(data.functions[0].line_start, 4);
+
+        // The closure isn't a method of the class
+        let methods: Vec<&str> = data.classes[0].methods.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(methods, vec!["memoize", "clear"]);
+    }
+
+    #[test]
+    fn test_match_statement_complexity() {
+        let data = parse(
+            "def plain(command):\n    return run(command)\n\n\
+             def dispatch(command):\n    match command:\n        case \"start\":\n            start()\n\
+             \x20       case \"stop\" | \"quit\":\n            stop()\n        case _:\n            raise ValueError(command)\n",
+        );
+
+        let plain = &data.functions[0];
+        let dispatch = &data.functions[1];
+        // match_statement + three case_clauses
+        assert_eq!(dispatch.complexity, plain.complexity + 4);
+
+        let branches = &dispatch.control_flow.branches;
+        assert_eq!(branches.len(), 3);
+        assert!(branches.iter().all(|b| b.branch_type == "match"));
+        assert_eq!(branches[0].condition, "command case \"start\"");
+        assert_eq!(branches[0].true_path.calls, vec!["start"]);
+        assert_eq!(branches[2].true_path.raises.as_deref(), Some("ValueError(command)"));
+    }
+
+    #[test]
+    fn test_method_kinds() {
+        let data = parse(
+            "class Account:\n\
+             \x20   def deposit(self, amount):\n        pass\n\n\
+             \x20   @staticmethod\n    def validate(amount):\n        pass\n\n\
+             \x20   @classmethod\n    def open(cls):\n        pass\n\n\
+             \x20   @property\n    def balance(self):\n        pass\n\n\
+             \x20   @balance.setter\n    def balance(self, value):\n        pass\n\n\
+             \x20   @functools.cached_property\n    def history(self):\n        pass\n\n\
+             def helper():\n    pass\n",
+        );
+
+        let kinds: Vec<(&str, MethodKind)> = data.classes[0].methods
+            .iter()
+            .map(|m| (m.name.as_str(), m.method_kind))
+            .collect();
+        assert_eq!(kinds, vec![
+            ("deposit", MethodKind::Instance),
+            ("validate", MethodKind::Static),
+            ("open", MethodKind::Class),
+            ("balance", MethodKind::Property),
+            ("balance", MethodKind::Property),
+            ("history", MethodKind::Property),
+        ]);
+        assert_eq!(data.functions[0].method_kind, MethodKind::Instance);
+    }
+
+    #[test]
+    fn test_call_arguments_keep_source_text() {
+        let data = parse(
+            "def run():\n    foo(a, b=c, d.e)\n    bar(load(x), [1, 2], *rest, **opts)\n    total(n for n in items)\n",
+        );
+
+        let calls = &data.functions[0].calls;
+        let args = |callee: &str| calls.iter().find(|c| c.callee == callee).unwrap().args.clone();
+
+        assert_eq!(args("foo"), vec!["a", "b=c", "d.e"]);
+        assert_eq!(args("bar"), vec!["load(x)", "[1, 2]", "*rest", "**opts"]);
+        assert_eq!(args("total"), vec!["n for n in items"]);
+    }
+
+    #[test]
+    fn test_calls_inside_comprehensions_and_lambdas() {
+        let data = parse(
+            "def run(items):\n    names = [f(x) for x in items if keep(x)]\n    lookup = {k: g(k) for k in items}\n    items.sort(key=lambda u: normalize(u))\n    done()\n",
+        );
+
+        let calls = &data.functions[0].calls;
+        let call = |callee: &str| calls.iter().find(|c| c.callee == callee).unwrap();
+
+        assert_eq!(call("f").context, "comprehension");
+        assert!(call("f").is_conditional);
+        assert_eq!(call("keep").context, "if");
+        assert_eq!(call("g").context, "comprehension");
+        assert_eq!(call("normalize").context, "lambda");
+        assert!(call("normalize").is_conditional);
+        assert_eq!(call("sort").context, "unconditional");
+        assert!(!call("done").is_conditional);
+    }
+
+    #[test]
+    fn test_with_blocks_record_context_managers() {
+        let data = parse(
+            "async def copy(f, db):\n    with open(f) as fh, lock:\n        data = fh.read()\n\
+             \n    async with db.transaction():\n        db.save(data)\n",
+        );
+
+        let blocks = &data.functions[0].control_flow.with_blocks;
+        assert_eq!(blocks.len(), 2);
+
+        let file = &blocks[0];
+        assert_eq!(file.line, 2);
+        assert_eq!(file.managers[0].expression, "open(f)");
+        assert_eq!(file.managers[0].alias.as_deref(), Some("fh"));
+        assert_eq!(file.managers[1].expression, "lock");
+        assert_eq!(file.managers[1].alias, None);
+        assert_eq!(file.calls, vec!["fh.read"]);
+        assert!(!file.is_async);
+
+        let tx = &blocks[1];
+        assert_eq!(tx.managers[0].expression, "db.transaction()");
+        assert_eq!(tx.calls, vec!["db.save"]);
+        assert!(tx.is_async);
+    }
+
+    #[test]
+    fn test_async_for_and_async_with_session_get() {
+        let data = parse(
+            "async def stream(session, url):\n    async with session.get(url) as resp:\n\
+             \x20       async for line in resp.content:\n            handle(line)\n\
+             \x20   for retry in range(3):\n        log(retry)\n",
+        );
+
+        let func = &data.functions[0];
+        assert!(func.tags.contains(&"async".to_string()));
+
+        let with_block = &func.control_flow.with_blocks[0];
+        assert!(with_block.is_async);
+        assert_eq!(with_block.managers[0].expression, "session.get(url)");
+        assert_eq!(with_block.managers[0].alias.as_deref(), Some("resp"));
+        assert!(with_block.calls.contains(&"handle".to_string()));
+
+        let loops = &func.control_flow.loops;
+        assert_eq!(loops.len(), 2);
+        assert_eq!(loops[0].loop_type, "for");
+        assert_eq!(loops[0].line, 3);
+        assert!(loops[0].is_async);
+        assert!(!loops[1].is_async);
+    }
+
+    #[test]
+    fn test_database_tag_needs_database_calls() {
+        let data = parse(
+            "def query_params(request):\n    return request.args.get(\"q\")\n\n\
+             def save_image(img, path):\n    img.save(path)\n\n\
+             def find_user(cursor, name):\n\
+             \x20   cursor.execute(f\"SELECT * FROM users WHERE name = '{name}'\")\n\
+             \x20   return cursor.fetchone()\n\n\
+             def find_user_safe(cursor, name):\n\
+             \x20   cursor.execute(\"SELECT * FROM users WHERE name = %s\", (name,))\n\n\
+             def active_users():\n    return User.objects.filter(active=True)\n",
+        );
+        let tagged = |name: &str| {
+            let func = data.functions.iter().find(|f| f.name == name).unwrap();
+            func.tags.contains(&"database".to_string())
+        };
+
+        assert!(!tagged("query_params"));
+        assert!(!tagged("save_image"));
+        assert!(tagged("find_user"));
+        assert!(tagged("find_user_safe"));
+        assert!(tagged("active_users"));
+
+        let risks: Vec<&SecurityNote> = data
+            .security_notes
+            .iter()
+            .filter(|n| n.note_type == "sql_injection_risk")
+            .collect();
+        assert_eq!(risks.len(), 1);
+        assert_eq!(risks[0].line, 8);
+        assert!(risks[0].description.contains("f-string") && risks[0].description.contains("find_user"));
+    }
+
+    #[test]
+    fn test_exception_types_are_normalized() {
+        let data = parse(
+            "def load():\n    try:\n        read()\n\
+             \x20   except (KeyError, TypeError) as e:\n        raise LoadError(\"bad\") from e\n\
+             \x20   except ValueError:\n        raise\n    except:\n        pass\n",
+        );
+
+        let exceptions = &data.functions[0].exceptions;
+        assert_eq!(exceptions.raises, vec!["LoadError"]);
+        assert_eq!(exceptions.handles, vec!["KeyError", "TypeError", "ValueError", "BaseException"]);
+    }
+}
@@ -0,0 +1,44 @@
+use crate::kb::types::FileData;
+
+/// Fill `Function::source` for every function and method from the file's
+/// source, using each definition's line range. Runs after the language
+/// parser, so it works the same for every language; for decorated Python
+/// functions the range (and so the text) includes the decorators.
+pub fn attach_sources(file_data: &mut FileData, source: &str) {
+    let lines: Vec<&str> = source.lines().collect();
+
+    let functions = file_data.functions.iter_mut()
+        .chain(file_data.classes.iter_mut().flat_map(|c| c.methods.iter_mut()));
+
+    for func in functions {
+        let start = func.line_start.saturating_sub(1);
+        let end = func.line_end.min(lines.len());
+        if start < end {
+            func.source = Some(lines[start..end].join("\n"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::python::PythonParser;
+
+    #[test]
+    fn test_attach_sources() {
+        let source = "import os\n\ndef load(path):\n    return open(path).read()\n\n\
+                      class Store:\n    def save(self):\n        pass\n";
+        let mut data = PythonParser::new(source.to_string()).parse().unwrap();
+        assert!(data.functions[0].source.is_none());
+
+        attach_sources(&mut data, source);
+        assert_eq!(
+            data.functions[0].source.as_deref(),
+            Some("def load(path):\n    return open(path).read()")
+        );
+        assert_eq!(
+            data.classes[0].methods[0].source.as_deref(),
+            Some("    def save(self):\n        pass")
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use crate::kb::types::*;
+use crate::parser::javascript::JavaScriptParser;
+use crate::parser::error::ParseError;
+use std::path::Path;
+
+/// TypeScript parser. Shares its extraction logic with `JavaScriptParser`
+/// and only swaps in the TypeScript (or TSX) grammar.
+pub struct TypeScriptParser {
+    inner: JavaScriptParser,
+}
+
+impl TypeScriptParser {
+    pub fn new(source_code: String, is_tsx: bool) -> Self {
+        let grammar = if is_tsx {
+            tree_sitter_typescript::language_tsx()
+        } else {
+            tree_sitter_typescript::language_typescript()
+        };
+
+        Self {
+            inner: JavaScriptParser::with_grammar(source_code, grammar, "typescript"),
+        }
+    }
+
+    pub fn parse(&self) -> Result<FileData, ParseError> {
+        self.inner.parse()
+    }
+}
+
+pub fn parse_file(path: &Path) -> Result<(String, FileData), ParseError> {
+    let source_code = std::fs::read_to_string(path)
+        .map_err(|e| ParseError::io(path, e))?
+        .replace("\r\n", "\n");
+
+    let is_tsx = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("tsx"))
+        .unwrap_or(false);
+
+    let parser = TypeScriptParser::new(source_code, is_tsx);
+    let file_data = parser.parse()?;
+
+    let relative_path = path.to_string_lossy().to_string();
+
+    Ok((relative_path, file_data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> FileData {
+        TypeScriptParser::new(source.to_string(), false).parse().unwrap()
+    }
+
+    #[test]
+    fn test_type_annotations() {
+        let data = parse(
+            "export async function fetchUser(id: string, retries: number = 3): Promise<User> {\n\
+             \x20 return api.get(id);\n}\n\n\
+             const limit: number = 10;\n",
+        );
+
+        let func = &data.functions[0];
+        assert_eq!(data.language, "typescript");
+        assert_eq!(func.params[0].name, "id");
+        assert_eq!(func.params[0].type_annotation, "string");
+        assert_eq!(func.params[1].default_value.as_deref(), Some("3"));
+        assert_eq!(func.return_type, "Promise<User>");
+        assert_eq!(
+            func.signature,
+            "async function fetchUser(id: string, retries: number = 3): Promise<User>"
+        );
+        assert_eq!(data.global_vars[0].type_annotation, "number");
+    }
+
+    #[test]
+    fn test_interface_and_class() {
+        let data = parse(
+            "interface User extends Entity {\n  name: string;\n  greet(other: User): string;\n}\n\n\
+             class Admin extends Base implements User {\n  name: string = 'root';\n\
+             \x20 greet(other: User): string {\n    return other.name;\n  }\n}\n",
+        );
+
+        let iface = &data.classes[0];
+        assert_eq!(iface.id, "interface_User");
+        assert_eq!(iface.bases, vec!["Entity"]);
+        assert!(iface.methods.is_empty());
+        assert_eq!(iface.attributes[0].name, "name");
+        assert_eq!(iface.attributes[0].type_annotation, "string");
+        assert_eq!(iface.attributes[1].type_annotation, "(other: User) => string");
+
+        let class = &data.classes[1];
+        assert_eq!(class.bases, vec!["Base", "User"]);
+        assert_eq!(class.attributes[0].type_annotation, "string");
+        assert_eq!(class.methods[0].return_type, "string");
+    }
+
+    #[test]
+    fn test_import_type() {
+        let data = parse(
+            "import type { User } from './models';\nimport { Router } from 'express';\n",
+        );
+
+        assert_eq!(data.imports[0].import_type, "type");
+        assert_eq!(data.imports[0].items, vec!["User"]);
+        assert_eq!(data.imports[1].import_type, "external");
+    }
+
+    #[test]
+    fn test_tsx_grammar() {
+        let source = "export const App = (props: Props) => <div>{props.title}</div>;\n";
+        let data = TypeScriptParser::new(source.to_string(), true).parse().unwrap();
+        assert_eq!(data.functions[0].name, "App");
+        assert_eq!(data.functions[0].params[0].type_annotation, "Props");
+    }
+}
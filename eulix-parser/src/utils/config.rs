@@ -0,0 +1,105 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `[parser]` section of `eulix.toml`, the project config file shared with
+/// the eulix CLI and eulix_embed. Keys are named after the long CLI flags.
+/// Precedence is CLI flag > config file > built-in default; keys this
+/// binary doesn't know, and the other sections, are ignored.
+#[derive(Debug, Default, Deserialize)]
+pub struct ParserConfig {
+    pub output: Option<String>,
+    pub output_format: Option<String>,
+    pub gzip: Option<bool>,
+    pub pretty: Option<bool>,
+    pub threads: Option<usize>,
+    pub languages: Option<String>,
+    pub no_analyze: Option<bool>,
+    pub euignore: Option<String>,
+    pub max_file_size: Option<u64>,
+    pub no_cache: Option<bool>,
+    pub include_source: Option<bool>,
+    pub no_callgraph_file: Option<bool>,
+    pub no_indices_file: Option<bool>,
+    pub no_summary_file: Option<bool>,
+    pub no_dead_code_file: Option<bool>,
+    pub no_import_cycles_file: Option<bool>,
+    pub no_hotspots_file: Option<bool>,
+    pub call_graph_format: Option<String>,
+    pub emit_graphml: Option<bool>,
+    pub hotspot_limit: Option<usize>,
+    pub report_complexity: Option<usize>,
+    pub complexity_threshold: Option<usize>,
+    /// A table such as `{ go = 15, python = 8 }`
+    pub language_complexity_threshold: Option<HashMap<String, usize>>,
+    pub fail_on_complexity: Option<usize>,
+    pub fail_on_parse_error: Option<bool>,
+    pub errors_json: Option<String>,
+    pub go_build_tags: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    parser: ParserConfig,
+}
+
+impl ParserConfig {
+    pub const FILE_NAME: &'static str = "eulix.toml";
+
+    /// Load `explicit` (which must exist) or else `<root>/eulix.toml` if there
+    /// is one. No config file means every option keeps its built-in default.
+    pub fn discover(explicit: Option<&str>, root: &str) -> Result<Self, String> {
+        let path = match explicit {
+            Some(path) => PathBuf::from(path),
+            None => {
+                let path = Path::new(root).join(Self::FILE_NAME);
+                if !path.is_file() {
+                    return Ok(Self::default());
+                }
+                path
+            }
+        };
+
+        let text = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read config {}: {}", path.display(), e))?;
+        Self::from_toml(&text).map_err(|e| format!("Invalid config {}: {}", path.display(), e))
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str::<ConfigFile>(text).map(|file| file.parser)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_parser_section_of_shared_config() {
+        let config = ParserConfig::from_toml(
+            "[project]\npath = \".\"\n\n[parser]\nthreads = 8\nlanguages = \"python,go\"\ngzip = true\n\n\
+             [embeddings]\nmodel = \"BAAI/bge-small-en-v1.5\"\n\n[cache.redis]\nenabled = false\n",
+        )
+        .unwrap();
+
+        assert_eq!(config.threads, Some(8));
+        assert_eq!(config.languages.as_deref(), Some("python,go"));
+        assert_eq!(config.gzip, Some(true));
+        assert_eq!(config.output, None);
+
+        let config = ParserConfig::from_toml(
+            "[parser]\ncomplexity_threshold = 12\nlanguage_complexity_threshold = { go = 15 }\n",
+        )
+        .unwrap();
+        assert_eq!(config.complexity_threshold, Some(12));
+        assert_eq!(config.language_complexity_threshold.unwrap()["go"], 15);
+
+        let config = ParserConfig::from_toml("[parser]\ngo_build_tags = [\"linux\", \"amd64\"]\n").unwrap();
+        assert_eq!(config.go_build_tags.unwrap(), vec!["linux", "amd64"]);
+
+        assert!(ParserConfig::from_toml("[parser]\nthreads = \"many\"\n").is_err());
+        assert_eq!(ParserConfig::from_toml("").unwrap().threads, None);
+    }
+}
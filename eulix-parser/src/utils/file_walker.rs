@@ -2,13 +2,31 @@ use anyhow::Result;
 use ignore::WalkBuilder;
 use std::path::{Path, PathBuf};
 
+use super::ignore::{IgnoreFilter, PathFilter};
+
 pub struct FileWalker {
     root: PathBuf,
+    path_filter: PathFilter,
+    ignore_filter: IgnoreFilter,
 }
 
 impl FileWalker {
     pub fn new(root: PathBuf) -> Self {
-        Self { root }
+        let ignore_filter = IgnoreFilter::new(&root);
+        Self { root, path_filter: PathFilter::default(), ignore_filter }
+    }
+
+    /// Read ignore patterns from `path` (`--euignore`) instead of the
+    /// project's .euignore
+    pub fn with_euignore(mut self, path: &Path) -> Self {
+        self.ignore_filter = IgnoreFilter::from_file(&self.root, path);
+        self
+    }
+
+    /// Only return files `filter` allows, after .euignore has been applied
+    pub fn with_path_filter(mut self, filter: PathFilter) -> Self {
+        self.path_filter = filter;
+        self
     }
 
     /// Generic walker that respects .euignore for any file extension
@@ -18,10 +36,7 @@ impl FileWalker {
     {
         let mut builder = WalkBuilder::new(&self.root);
 
-        // Only use .euignore, completely ignore .gitignore
-        builder.add_custom_ignore_filename(".euignore");
-
-        // Disable all gitignore support
+        // .euignore is matched by `IgnoreFilter`; disable all gitignore support
         builder.git_ignore(false);
         builder.git_global(false);
         builder.git_exclude(false);
@@ -35,6 +50,7 @@ impl FileWalker {
             ".eggs", ".ipynb_checkpoints", "target"
         ];
 
+        let ignore_filter = self.ignore_filter.clone();
         builder.filter_entry(move |entry| {
             let path = entry.path();
             let name = path.file_name()
@@ -54,7 +70,8 @@ impl FileWalker {
                 }
             }
 
-            true
+            // Skipping an ignored directory skips everything below it
+            !ignore_filter.should_ignore(path)
         });
 
         let files: Vec<PathBuf> = builder
@@ -63,6 +80,10 @@ impl FileWalker {
             .filter(|entry| {
                 entry.file_type().map(|ft| ft.is_file()).unwrap_or(false)
             })
+            .filter(|entry| {
+                let rel_path = entry.path().strip_prefix(&self.root).unwrap_or(entry.path());
+                self.path_filter.allows(&rel_path.to_string_lossy().replace('\\', "/"))
+            })
             .filter(|entry| filter(entry.path()))
             .map(|entry| entry.path().to_path_buf())
             .collect();
@@ -91,6 +112,8 @@ mod tests {
         fs::write(root.join("src/main.py"), "# main")?;
         fs::write(root.join("tests/test_main.py"), "# test")?;
         fs::write(root.join("docs/guide.py"), "# docs")?;
+        fs::create_dir_all(root.join("docs/sub"))?;
+        fs::write(root.join("docs/sub/guide.py"), "# nested docs")?;
 
         // Create .euignore
         fs::write(
@@ -99,11 +122,126 @@ mod tests {
         )?;
 
         let walker = FileWalker::new(root.to_path_buf());
+        let files = walker.walk_files(|p| p.extension().is_some_and(|e| e == "py"))?;
 
         // Should only find src/main.py
         assert_eq!(files.len(), 1);
         assert!(files[0].ends_with("src/main.py"));
 
+        // docs/ also excludes everything nested beneath it
+        assert!(!files.iter().any(|f| f.ends_with("docs/sub/guide.py")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_euignore_globs_and_negation() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        for dir in ["src/a/vendor", "web/node_modules/react", "other/vendor"] {
+            fs::create_dir_all(root.join(dir))?;
+        }
+        for file in [
+            "main.py", "drop.tmp", "keep.tmp", "src/a/vendor/lib.py", "web/node_modules/react/index.py",
+            "other/vendor/lib.py", "api.generated.py",
+        ] {
+            fs::write(root.join(file), "# file")?;
+        }
+        fs::write(root.join(".euignore"), "*.tmp
+!keep.tmp
+src/**/vendor
+**/node_modules
+*.generated.py
+")?;
+
+        let mut files: Vec<String> = FileWalker::new(root.to_path_buf())
+            .walk_files(|p| p.extension().is_some_and(|e| e == "py" || e == "tmp"))?
+            .iter()
+            .map(|f| f.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["keep.tmp", "main.py", "other/vendor/lib.py"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_euignore_path() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path().join("project");
+        fs::create_dir_all(root.join("docs"))?;
+        fs::write(root.join("main.py"), "# main")?;
+        fs::write(root.join("docs/guide.py"), "# docs")?;
+        fs::write(root.join(".euignore"), "main.py
+")?;
+        let custom = temp_dir.path().join("custom.euignore");
+        fs::write(&custom, "/docs/
+")?;
+
+        let files = FileWalker::new(root.clone())
+            .with_euignore(&custom)
+            .walk_files(|p| p.extension().is_some_and(|e| e == "py"))?;
+
+        // The custom file replaces the project's .euignore
+        assert_eq!(files.len(), 1);
+        assert!(files[0].ends_with("main.py"));
+
+        Ok(())
+    }
+
+    fn api_project() -> Result<TempDir> {
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path();
+        fs::create_dir_all(root.join("src/api/v1"))?;
+        fs::create_dir_all(root.join("src/core"))?;
+        fs::create_dir_all(root.join("vendor"))?;
+        fs::write(root.join("src/api/routes.py"), "# routes")?;
+        fs::write(root.join("src/api/v1/users.py"), "# users")?;
+        fs::write(root.join("src/api/test_routes.py"), "# test")?;
+        fs::write(root.join("src/core/db.py"), "# db")?;
+        fs::write(root.join("vendor/lib.py"), "# vendored")?;
+        fs::write(root.join(".euignore"), "vendor/\n")?;
+        Ok(temp_dir)
+    }
+
+    fn walk_relative(root: &Path, filter: PathFilter) -> Result<Vec<String>> {
+        let walker = FileWalker::new(root.to_path_buf()).with_path_filter(filter);
+        let mut files: Vec<String> = walker
+            .walk_files(|p| p.extension().is_some_and(|e| e == "py"))?
+            .iter()
+            .map(|f| f.strip_prefix(root).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+
+    #[test]
+    fn test_include_only_parses_matching_files() -> Result<()> {
+        let temp_dir = api_project()?;
+
+        let files = walk_relative(temp_dir.path(), PathFilter::new(&["src/api/**".to_string()], &[]))?;
+        assert_eq!(files, vec!["src/api/routes.py", "src/api/test_routes.py", "src/api/v1/users.py"]);
+
+        // .euignore still wins over an include that names the directory
+        let files = walk_relative(temp_dir.path(), PathFilter::new(&["vendor/".to_string(), "db.py".to_string()], &[]))?;
+        assert_eq!(files, vec!["src/core/db.py"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_exclude_removes_matching_files() -> Result<()> {
+        let temp_dir = api_project()?;
+
+        let files = walk_relative(temp_dir.path(), PathFilter::new(&[], &["test_*.py".to_string(), "src/api/v1/".to_string()]))?;
+        assert_eq!(files, vec!["src/api/routes.py", "src/core/db.py"]);
+
+        let files = walk_relative(
+            temp_dir.path(),
+            PathFilter::new(&["src/api/**".to_string()], &["test_*.py".to_string()]),
+        )?;
+        assert_eq!(files, vec!["src/api/routes.py", "src/api/v1/users.py"]);
+
         Ok(())
     }
 }
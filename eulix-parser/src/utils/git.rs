@@ -0,0 +1,122 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Which revision of the source a knowledge base was built from. Every
+/// field is `None` outside a git checkout or when it can't be determined.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GitInfo {
+    pub commit: Option<String>,
+    /// `None` on a detached HEAD
+    pub branch: Option<String>,
+    /// Uncommitted changes, untracked files included. Needs the `git`
+    /// binary; commit and branch are read from `.git` directly.
+    pub dirty: Option<bool>,
+}
+
+impl GitInfo {
+    /// Inspect the repository containing `root`, which may be a
+    /// subdirectory of the checkout
+    pub fn detect(root: &Path) -> Self {
+        let Some(git_dir) = root.ancestors().find_map(git_dir_of) else {
+            return Self::default();
+        };
+        let Ok(head) = fs::read_to_string(git_dir.join("HEAD")) else {
+            return Self::default();
+        };
+
+        let (commit, branch) = match head.trim().strip_prefix("ref: ") {
+            Some(reference) => (
+                resolve_ref(&git_dir, reference),
+                reference.strip_prefix("refs/heads/").map(str::to_string),
+            ),
+            None => (Some(head.trim().to_string()), None),
+        };
+
+        Self { commit, branch, dirty: is_dirty(root) }
+    }
+}
+
+/// `dir/.git` as a directory, or the `gitdir:` it points to when it's a
+/// file (worktrees and submodules)
+fn git_dir_of(dir: &Path) -> Option<PathBuf> {
+    let dot_git = dir.join(".git");
+    if dot_git.is_dir() {
+        return Some(dot_git);
+    }
+    let pointer = fs::read_to_string(&dot_git).ok()?;
+    let target = Path::new(pointer.trim().strip_prefix("gitdir:")?.trim());
+    Some(if target.is_absolute() { target.to_path_buf() } else { dir.join(target) })
+}
+
+/// A loose ref file, else its line in `packed-refs`. Linked worktrees keep
+/// branch refs in the main repository, named by their `commondir` file.
+fn resolve_ref(git_dir: &Path, reference: &str) -> Option<String> {
+    let common_dir = fs::read_to_string(git_dir.join("commondir"))
+        .map(|dir| git_dir.join(dir.trim()))
+        .unwrap_or_else(|_| git_dir.to_path_buf());
+
+    for dir in [git_dir, common_dir.as_path()] {
+        if let Ok(hash) = fs::read_to_string(dir.join(reference)) {
+            return Some(hash.trim().to_string());
+        }
+    }
+
+    let packed = fs::read_to_string(common_dir.join("packed-refs")).ok()?;
+    packed.lines().find_map(|line| {
+        let (hash, name) = line.split_once(' ')?;
+        (name == reference).then(|| hash.to_string())
+    })
+}
+
+fn is_dirty(root: &Path) -> Option<bool> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["status", "--porcelain"])
+        .output()
+        .ok()?;
+    output.status.success().then_some(!output.stdout.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_non_git_directory_has_no_git_info() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join("app.py"), "def main():\n    pass\n").unwrap();
+
+        assert_eq!(GitInfo::detect(dir.path()), GitInfo::default());
+    }
+
+    #[test]
+    fn test_branch_and_commit_read_from_refs() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let git_dir = dir.path().join(".git");
+        fs::create_dir_all(git_dir.join("refs/heads")).unwrap();
+        fs::write(git_dir.join("HEAD"), "ref: refs/heads/main\n").unwrap();
+        fs::write(
+            git_dir.join("packed-refs"),
+            "# pack-refs with: peeled fully-peeled sorted\n\
+             1111111111111111111111111111111111111111 refs/heads/main\n",
+        )
+        .unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+
+        let info = GitInfo::detect(&dir.path().join("src"));
+        assert_eq!(info.branch.as_deref(), Some("main"));
+        assert_eq!(info.commit.as_deref(), Some("1111111111111111111111111111111111111111"));
+
+        // A loose ref wins over the packed one
+        fs::write(git_dir.join("refs/heads/main"), "2222222222222222222222222222222222222222\n").unwrap();
+        let info = GitInfo::detect(dir.path());
+        assert_eq!(info.commit.as_deref(), Some("2222222222222222222222222222222222222222"));
+
+        fs::write(git_dir.join("HEAD"), "3333333333333333333333333333333333333333\n").unwrap();
+        let detached = GitInfo::detect(dir.path());
+        assert_eq!(detached.branch, None);
+        assert_eq!(detached.commit.as_deref(), Some("3333333333333333333333333333333333333333"));
+    }
+}
@@ -1,7 +1,9 @@
+use globset::{GlobBuilder, GlobMatcher};
 use std::fs;
 use std::path::{Path, PathBuf};
 
 /// Manages .euignore patterns (similar to .gitignore)
+#[derive(Debug, Clone)]
 pub struct IgnoreFilter {
     patterns: Vec<IgnorePattern>,
     base_path: PathBuf,
@@ -9,10 +11,14 @@ pub struct IgnoreFilter {
 
 #[derive(Debug, Clone)]
 struct IgnorePattern {
+    // `pattern` and `is_anchored` are folded into `matcher`; kept for Debug output
+    #[allow(dead_code)]
     pattern: String,
     is_directory: bool,      // Ends with /
+    #[allow(dead_code)]
     is_anchored: bool,       // Starts with /
     is_negation: bool,       // Starts with !
+    matcher: GlobMatcher,
 }
 
 impl IgnorePattern {
@@ -33,52 +39,82 @@ impl IgnorePattern {
             pattern = pattern[..pattern.len()-1].to_string();
         }
 
+        // As in gitignore, a slash anywhere but the end ties the pattern to
+        // the root; otherwise it may match at any depth
+        let glob = if is_anchored || pattern.contains('/') {
+            pattern.clone()
+        } else {
+            format!("**/{}", pattern)
+        };
+        let matcher = GlobBuilder::new(&glob)
+            .literal_separator(true)
+            .build()
+            .or_else(|_| GlobBuilder::new(&globset::escape(&glob)).build())
+            .expect("escaped glob is always valid")
+            .compile_matcher();
+
         Self {
             pattern,
             is_directory,
             is_anchored,
             is_negation,
+            matcher,
         }
     }
 
+    /// A pattern matches a path when it matches the path itself or any of its
+    /// parent directories, so ignoring `build/` also ignores `build/out.py`
     fn matches(&self, path_str: &str, is_dir: bool) -> bool {
-        // If pattern is for directories only, skip non-directories
-        if self.is_directory && !is_dir {
-            return false;
-        }
+        let components: Vec<&str> = path_str.split('/').collect();
 
-        if self.is_anchored {
-            // Anchored patterns match from root
-            if self.is_directory {
-                // For directory patterns, check if path starts with pattern
-                path_str.starts_with(&self.pattern)
-                    || path_str == self.pattern
-            } else {
-                // Exact match or as a component
-                path_str == self.pattern
-                    || path_str.starts_with(&format!("{}/", self.pattern))
-            }
-        } else {
-            // Non-anchored patterns match anywhere
-            let components: Vec<&str> = path_str.split('/').collect();
-
-            if self.is_directory {
-                // Match directory name anywhere in path
-                components.iter().any(|&comp| comp == self.pattern)
-            } else {
-                // Match component or full path
-                components.contains(&self.pattern.as_str())
-                    || path_str.ends_with(&self.pattern)
+        (1..=components.len()).any(|depth| {
+            // Every prefix but the full path is a directory
+            if self.is_directory && depth == components.len() && !is_dir {
+                return false;
             }
-        }
+            self.matcher.is_match(components[..depth].join("/"))
+        })
     }
 }
-#[allow(dead_code)]
+/// `--include`/`--exclude` globs, checked after .euignore with the same
+/// pattern syntax. With no includes every file is a candidate; excludes
+/// then remove files either way.
+#[derive(Debug, Clone, Default)]
+pub struct PathFilter {
+    include: Vec<IgnorePattern>,
+    exclude: Vec<IgnorePattern>,
+}
+
+impl PathFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Self {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|p| p.trim())
+                .filter(|p| !p.is_empty())
+                .map(IgnorePattern::from_str)
+                .collect()
+        };
+        Self { include: compile(include), exclude: compile(exclude) }
+    }
+
+    /// `rel_path` is the file's path below the project root, `/`-separated
+    pub fn allows(&self, rel_path: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| p.matches(rel_path, false)))
+            && !self.exclude.iter().any(|p| p.matches(rel_path, false))
+    }
+}
+
 impl IgnoreFilter {
     /// Load .euignore from project root
     pub fn new(project_root: &Path) -> Self {
-        let ignore_path = project_root.join(".euignore");
-        let patterns = Self::load_patterns(&ignore_path);
+        Self::from_file(project_root, &project_root.join(".euignore"))
+    }
+
+    /// Load patterns from `ignore_path` (`--euignore`) instead of the
+    /// project's own .euignore; paths still match relative to `project_root`
+    pub fn from_file(project_root: &Path, ignore_path: &Path) -> Self {
+        let patterns = Self::load_patterns(ignore_path);
 
         Self {
             patterns,
@@ -158,28 +194,6 @@ impl IgnoreFilter {
 
         ignored
     }
-
-    /// Check if directory should be ignored (including subdirectories)
-    pub fn should_ignore_dir(&self, dir_path: &Path) -> bool {
-        if self.should_ignore(dir_path) {
-            return true;
-        }
-
-        // Check if any parent directory is ignored
-        let mut current = dir_path;
-        while let Some(parent) = current.parent() {
-            if parent == self.base_path {
-                break;
-            }
-
-            if self.should_ignore(parent) {
-                return true;
-            }
-            current = parent;
-        }
-
-        false
-    }
 }
 
 #[cfg(test)]
@@ -189,7 +203,12 @@ mod tests {
 
     #[test]
     fn test_default_ignores() {
-        let base = PathBuf::from("/tmp/test-project");
+        // Directory patterns only match paths that are directories on disk
+        let dir = tempfile::TempDir::new().unwrap();
+        let base = dir.path().to_path_buf();
+        for name in ["node_modules", "__pycache__", ".git"] {
+            fs::create_dir(base.join(name)).unwrap();
+        }
         let filter = IgnoreFilter::new(&base);
 
         assert!(filter.should_ignore(&base.join("node_modules")));
@@ -209,4 +228,50 @@ mod tests {
         assert!(pattern2.is_directory);
         assert_eq!(pattern2.pattern, "test");
     }
+
+    fn filter_with(name: &str, euignore: &str) -> (PathBuf, IgnoreFilter) {
+        let base = std::env::temp_dir().join(format!("eulix_ignore_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&base).unwrap();
+        fs::write(base.join(".euignore"), euignore).unwrap();
+        let filter = IgnoreFilter::new(&base);
+        fs::remove_dir_all(&base).unwrap();
+        (base, filter)
+    }
+
+    #[test]
+    fn test_glob_wildcards() {
+        let (base, filter) = filter_with("wildcards", "*.tmp\nsrc/**/vendor\ncache_?.py\n");
+
+        assert!(filter.should_ignore(&base.join("scratch.tmp")));
+        assert!(filter.should_ignore(&base.join("deep/nested/scratch.tmp")));
+        assert!(!filter.should_ignore(&base.join("scratch.tmp.py")));
+
+        assert!(filter.should_ignore(&base.join("src/vendor/lib.py")));
+        assert!(filter.should_ignore(&base.join("src/a/b/vendor/lib.py")));
+        assert!(!filter.should_ignore(&base.join("other/vendor/lib.py")));
+
+        assert!(filter.should_ignore(&base.join("cache_1.py")));
+        assert!(!filter.should_ignore(&base.join("cache_10.py")));
+    }
+
+    #[test]
+    fn test_double_star_prefix() {
+        let (base, filter) = filter_with("double_star", "**/node_modules\n/generated\n");
+
+        assert!(filter.should_ignore(&base.join("node_modules/react/index.js")));
+        assert!(filter.should_ignore(&base.join("web/app/node_modules/react/index.js")));
+
+        // Anchored patterns only match at the root
+        assert!(filter.should_ignore(&base.join("generated/api.py")));
+        assert!(!filter.should_ignore(&base.join("src/generated/api.py")));
+    }
+
+    #[test]
+    fn test_negation() {
+        let (base, filter) = filter_with("negation", "*.tmp\n!keep.tmp\n");
+
+        assert!(filter.should_ignore(&base.join("drop.tmp")));
+        assert!(!filter.should_ignore(&base.join("keep.tmp")));
+        assert!(!filter.should_ignore(&base.join("nested/keep.tmp")));
+    }
 }
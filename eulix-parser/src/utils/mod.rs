@@ -1,3 +1,5 @@
 // src/utils/mod.rs
 pub mod file_walker;
 pub mod ignore;
+pub mod config;
+pub mod git;
@@ -21,7 +21,7 @@ import (
 const (
 	BinaryVersion = uint32(2)
 	MagicBytes    = "EULX"
-	VectorVersion = uint32(1)
+	VectorVersion = uint32(2)
 )
 
 func ContextWindowCreator(eulixDir string, cfg *config.Config, llmClient *llm.Client) (*ContextBuilder, error) {
@@ -194,8 +194,8 @@ func (cb *ContextBuilder) loadVectorMap() error {
 	// Read version
 	version := binary.LittleEndian.Uint32(data[offset : offset+4])
 	offset += 4
-	if version != VectorVersion {
-		return fmt.Errorf("vectors.bin version mismatch: expected %d, got %d", VectorVersion, version)
+	if version < 1 || version > VectorVersion {
+		return fmt.Errorf("vectors.bin version mismatch: expected 1 to %d, got %d", VectorVersion, version)
 	}
 
 	// Read count
@@ -206,6 +206,15 @@ func (cb *ContextBuilder) loadVectorMap() error {
 	dimension := binary.LittleEndian.Uint32(data[offset : offset+4])
 	offset += 4
 
+	// Version 2 adds a JSON header (model and embedding settings) before the vectors
+	if version >= 2 {
+		if offset+4 > len(data) {
+			return fmt.Errorf("unexpected EOF reading vectors.bin header length")
+		}
+		headerLen := binary.LittleEndian.Uint32(data[offset : offset+4])
+		offset += 4 + int(headerLen)
+	}
+
 	// Read each ID and build map
 	for i := 0; i < int(count); i++ {
 		// Read ID length